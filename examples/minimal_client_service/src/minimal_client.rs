@@ -12,9 +12,7 @@ fn main() -> Result<(), Error> {
 
     println!("Starting client");
 
-    while !client.service_is_ready()? {
-        std::thread::sleep(std::time::Duration::from_millis(10));
-    }
+    client.wait_for_service(None)?;
 
     client.async_send_request_with_callback(
         &request,
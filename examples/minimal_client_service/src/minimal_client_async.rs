@@ -11,9 +11,11 @@ async fn main() -> Result<(), Error> {
 
     println!("Starting client");
 
-    while !client.service_is_ready()? {
-        std::thread::sleep(std::time::Duration::from_millis(10));
-    }
+    // The executor notices when the service becomes available, so it needs to spin
+    // while waiting for the service.
+    let rclrs_spin = tokio::task::spawn_blocking(move || executor.spin(SpinOptions::default()));
+
+    client.wait_for_service_async(None).await?;
 
     let request = example_interfaces::srv::AddTwoInts_Request { a: 41, b: 1 };
 
@@ -21,8 +23,6 @@ async fn main() -> Result<(), Error> {
 
     println!("Waiting for response");
 
-    let rclrs_spin = tokio::task::spawn_blocking(move || executor.spin(SpinOptions::default()));
-
     let response = future.await?;
     println!(
         "Result of {} + {} is: {}",
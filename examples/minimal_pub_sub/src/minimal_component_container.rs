@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use anyhow::{Error, Result};
+use rclrs::*;
+
+/// Publishes a greeting twice per second.
+struct Talker {
+    node: Node,
+    _timer: Timer,
+}
+
+impl Component for Talker {
+    const NODE_NAME: &'static str = "talker";
+
+    fn new(spawner: &NodeSpawner, options: NodeOptions) -> Result<Self, RclrsError> {
+        let node = spawner.create_node(options)?;
+        let publisher = node.create_publisher::<std_msgs::msg::String>("topic")?;
+        let mut publish_count: u32 = 1;
+        let _timer = node.create_timer(Duration::from_millis(500), move |_| {
+            let message = std_msgs::msg::String {
+                data: format!("Hello, world! {}", publish_count),
+            };
+            println!("Publishing: [{}]", message.data);
+            if let Err(err) = publisher.publish(&message) {
+                eprintln!("Failed to publish: {err}");
+            }
+            publish_count += 1;
+        })?;
+        Ok(Self { node, _timer })
+    }
+
+    fn node(&self) -> &Node {
+        &self.node
+    }
+}
+
+/// Prints the greetings of the talker.
+struct Listener {
+    node: Node,
+    _subscription: Subscription<std_msgs::msg::String>,
+}
+
+impl Component for Listener {
+    const NODE_NAME: &'static str = "listener";
+
+    fn new(spawner: &NodeSpawner, options: NodeOptions) -> Result<Self, RclrsError> {
+        let node = spawner.create_node(options)?;
+        let name = node.name();
+        let _subscription = node.create_subscription::<std_msgs::msg::String, _>(
+            "topic",
+            move |msg: std_msgs::msg::String| {
+                println!("[{}] I heard: '{}'", name, msg.data);
+            },
+        )?;
+        Ok(Self {
+            node,
+            _subscription,
+        })
+    }
+
+    fn node(&self) -> &Node {
+        &self.node
+    }
+}
+
+/// A container that can load the talker and listener, e.g. with
+///
+/// ```sh
+/// ros2 component load /ComponentManager \
+///     examples_rclrs_minimal_pub_sub examples_rclrs_minimal_pub_sub::Talker
+/// ```
+fn main() -> Result<(), Error> {
+    let mut executor = Context::default_from_env()?.create_basic_executor();
+
+    let mut registry = ComponentRegistry::new();
+    register_components!(registry, "examples_rclrs_minimal_pub_sub", Talker, Listener);
+
+    let _container = executor.create_component_container("ComponentManager", registry)?;
+
+    executor
+        .spin(SpinOptions::default())
+        .first_error()
+        .map_err(|err| err.into())
+}
@@ -0,0 +1,380 @@
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hasher},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{rcl_bindings::*, Time};
+
+mod client;
+mod client_goal_handle;
+mod server;
+mod server_goal_handle;
+pub use client::*;
+pub use client_goal_handle::*;
+pub use server::*;
+pub use server_goal_handle::*;
+
+/// A unique identifier for a goal request.
+///
+/// The identifier is chosen by the [`ActionClient`] that sends the goal, and is
+/// used by both the client and the [`ActionServer`] to refer to the goal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GoalUuid(pub [u8; 16]);
+
+impl GoalUuid {
+    /// Creates a new, random goal identifier, in the format of a version 4 UUID.
+    pub fn new_random() -> Self {
+        // A counter ensures that identifiers created in quick succession differ even
+        // if the hasher happens to be seeded identically.
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let mut bytes = [0u8; 16];
+        for chunk in bytes.chunks_mut(8) {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+            if let Ok(since_epoch) = std::time::UNIX_EPOCH.elapsed() {
+                hasher.write_u128(since_epoch.as_nanos());
+            }
+            chunk.copy_from_slice(&hasher.finish().to_le_bytes());
+        }
+        // Set the version and variant bits of the UUID.
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        Self(bytes)
+    }
+}
+
+impl fmt::Display for GoalUuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                write!(f, "-")?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// The status of a goal, as tracked by the [`ActionServer`] and reported to
+/// [`ActionClient`]s.
+///
+/// This corresponds to the `STATUS_*` constants of the `action_msgs/msg/GoalStatus`
+/// message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(i8)]
+pub enum GoalStatusCode {
+    /// The status of the goal is not known, e.g. because the action server does not know it.
+    Unknown = 0,
+    /// The goal has been accepted and is awaiting execution.
+    Accepted = 1,
+    /// The goal is currently being executed by the action server.
+    Executing = 2,
+    /// The client has requested that the goal be canceled, and the action server has
+    /// accepted the cancel request.
+    Canceling = 3,
+    /// The goal was achieved successfully by the action server.
+    Succeeded = 4,
+    /// The goal was canceled after an external request from an action client.
+    Canceled = 5,
+    /// The goal was terminated by the action server without an external request.
+    Aborted = 6,
+}
+
+impl GoalStatusCode {
+    /// Returns true if the goal has reached a terminal state, i.e. it has succeeded,
+    /// been canceled, or been aborted.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Succeeded | Self::Canceled | Self::Aborted)
+    }
+}
+
+impl From<i8> for GoalStatusCode {
+    fn from(status: i8) -> Self {
+        match status {
+            1 => Self::Accepted,
+            2 => Self::Executing,
+            3 => Self::Canceling,
+            4 => Self::Succeeded,
+            5 => Self::Canceled,
+            6 => Self::Aborted,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// The outcome of a cancel request, as reported to the [`ActionClient`].
+///
+/// This corresponds to the `ERROR_*` constants of the `action_msgs/srv/CancelGoal`
+/// service response.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(i8)]
+pub enum CancelResponseCode {
+    /// The cancel request was accepted.
+    Accept = 0,
+    /// The cancel request was rejected.
+    Reject = 1,
+    /// The requested goal is not known to the action server.
+    UnknownGoalId = 2,
+    /// The requested goal has already reached a terminal state.
+    GoalTerminated = 3,
+}
+
+impl From<i8> for CancelResponseCode {
+    fn from(code: i8) -> Self {
+        match code {
+            0 => Self::Accept,
+            2 => Self::UnknownGoalId,
+            3 => Self::GoalTerminated,
+            _ => Self::Reject,
+        }
+    }
+}
+
+/// The response of an [`ActionServer`]'s goal callback to a new goal request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GoalResponse {
+    /// The goal is rejected and will not be executed.
+    Reject,
+    /// The goal is accepted and will start executing right away.
+    AcceptAndExecute,
+    /// The goal is accepted, but will only start executing once
+    /// [`ServerGoalHandle::execute`] is called.
+    AcceptAndDefer,
+}
+
+/// The response of an [`ActionServer`]'s cancel callback to a cancel request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CancelResponse {
+    /// The goal will not be canceled.
+    Reject,
+    /// The goal will be canceled. The server must then finish the goal by calling
+    /// [`ServerGoalHandle::canceled`].
+    Accept,
+}
+
+/// Converts a [`Time`] into the `(sec, nanosec)` representation used by
+/// `builtin_interfaces/msg/Time`.
+pub(crate) fn time_to_stamp(time: &Time) -> (i32, u32) {
+    let sec = time.nsec.div_euclid(1_000_000_000);
+    let nanosec = time.nsec.rem_euclid(1_000_000_000);
+    (sec as i32, nanosec as u32)
+}
+
+/// Creates an `rcl_action_goal_info_t` for the given goal and stamp.
+pub(crate) fn create_goal_info(goal_id: &GoalUuid, stamp: (i32, u32)) -> rcl_action_goal_info_t {
+    // SAFETY: Getting a zero-initialized value is always safe.
+    let mut goal_info = unsafe { rcl_action_get_zero_initialized_goal_info() };
+    goal_info.goal_id.uuid = goal_id.0;
+    goal_info.stamp.sec = stamp.0;
+    goal_info.stamp.nanosec = stamp.1;
+    goal_info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        time::{Duration, Instant},
+    };
+    use test_msgs::action::{Fibonacci, Fibonacci_Feedback, Fibonacci_Goal, Fibonacci_Result};
+
+    #[test]
+    fn goal_uuid_is_random_and_formatted() {
+        let a = GoalUuid::new_random();
+        let b = GoalUuid::new_random();
+        assert_ne!(a, b);
+        let formatted = a.to_string();
+        assert_eq!(formatted.len(), 36);
+        assert_eq!(&formatted[14..15], "4");
+    }
+
+    #[test]
+    fn goal_status_code_round_trip() {
+        for status in [
+            GoalStatusCode::Unknown,
+            GoalStatusCode::Accepted,
+            GoalStatusCode::Executing,
+            GoalStatusCode::Canceling,
+            GoalStatusCode::Succeeded,
+            GoalStatusCode::Canceled,
+            GoalStatusCode::Aborted,
+        ] {
+            assert_eq!(GoalStatusCode::from(status as i8), status);
+        }
+        assert!(GoalStatusCode::Aborted.is_terminal());
+        assert!(!GoalStatusCode::Executing.is_terminal());
+    }
+
+    fn spin_until(executor: &mut Executor, mut condition: impl FnMut() -> bool) {
+        let start_time = Instant::now();
+        while !condition() {
+            // rcl_wait times out whenever nothing becomes ready within 10ms, e.g. during discovery.
+            for err in executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10))) {
+                assert!(err.is_timeout(), "{err}");
+            }
+            assert!(start_time.elapsed() < Duration::from_secs(10));
+        }
+    }
+
+    #[test]
+    fn test_action_goal_feedback_and_result() -> Result<(), RclrsError> {
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_action_goal_feedback_{}", line!()))?;
+
+        let _server = node.create_action_server::<Fibonacci, _, _, _>(
+            "test_action_fibonacci",
+            |_uuid, goal: &Fibonacci_Goal| {
+                if goal.order < 0 {
+                    GoalResponse::Reject
+                } else {
+                    GoalResponse::AcceptAndExecute
+                }
+            },
+            |_goal_handle| CancelResponse::Accept,
+            |goal_handle: Arc<ServerGoalHandle<Fibonacci>>| {
+                let mut sequence = vec![0, 1];
+                for i in 1..goal_handle.goal().order {
+                    sequence.push(sequence[i as usize] + sequence[i as usize - 1]);
+                    goal_handle
+                        .publish_feedback(Fibonacci_Feedback {
+                            sequence: sequence.clone(),
+                        })
+                        .unwrap();
+                }
+                goal_handle.succeed(Fibonacci_Result { sequence }).unwrap();
+            },
+        )?;
+
+        let client = node.create_action_client::<Fibonacci>("test_action_fibonacci")?;
+        spin_until(&mut executor, || client.server_is_ready().unwrap());
+
+        let feedback_received = Arc::new(AtomicBool::new(false));
+        let feedback_received_inner = Arc::clone(&feedback_received);
+        let goal_handle = Arc::new(Mutex::new(None));
+        let goal_handle_inner = Arc::clone(&goal_handle);
+        let send_goal = client.send_goal_with_feedback(
+            Fibonacci_Goal { order: 5 },
+            move |_feedback: Fibonacci_Feedback| {
+                feedback_received_inner.store(true, Ordering::Release);
+            },
+        );
+        std::thread::spawn(move || {
+            let handle = futures::executor::block_on(send_goal).unwrap();
+            *goal_handle_inner.lock().unwrap() = Some(handle);
+        });
+        spin_until(&mut executor, || goal_handle.lock().unwrap().is_some());
+
+        let goal_handle = goal_handle.lock().unwrap().take().unwrap();
+        let goal_handle = goal_handle.expect("The goal should have been accepted");
+
+        let result = Arc::new(Mutex::new(None));
+        let result_inner = Arc::clone(&result);
+        let get_result = goal_handle.get_result();
+        std::thread::spawn(move || {
+            *result_inner.lock().unwrap() = Some(futures::executor::block_on(get_result));
+        });
+        spin_until(&mut executor, || result.lock().unwrap().is_some());
+
+        let (status, result) = result.lock().unwrap().take().unwrap()?;
+        assert_eq!(status, GoalStatusCode::Succeeded);
+        assert_eq!(result.sequence, vec![0, 1, 1, 2, 3, 5]);
+        assert!(feedback_received.load(Ordering::Acquire));
+
+        spin_until(&mut executor, || {
+            goal_handle.status() == GoalStatusCode::Succeeded
+        });
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_action_goal_rejected() -> Result<(), RclrsError> {
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_action_goal_rejected_{}", line!()))?;
+
+        let _server = node.create_action_server::<Fibonacci, _, _, _>(
+            "test_action_rejected",
+            |_uuid, _goal: &Fibonacci_Goal| GoalResponse::Reject,
+            |_goal_handle| CancelResponse::Reject,
+            |_goal_handle: Arc<ServerGoalHandle<Fibonacci>>| {
+                panic!("A rejected goal must not be executed");
+            },
+        )?;
+
+        let client = node.create_action_client::<Fibonacci>("test_action_rejected")?;
+        spin_until(&mut executor, || client.server_is_ready().unwrap());
+
+        let response = Arc::new(Mutex::new(None));
+        let response_inner = Arc::clone(&response);
+        let send_goal = client.send_goal(Fibonacci_Goal { order: 3 });
+        std::thread::spawn(move || {
+            *response_inner.lock().unwrap() = Some(futures::executor::block_on(send_goal));
+        });
+        spin_until(&mut executor, || response.lock().unwrap().is_some());
+
+        let goal_handle = response.lock().unwrap().take().unwrap()?;
+        assert!(goal_handle.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_action_cancel() -> Result<(), RclrsError> {
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_action_cancel_{}", line!()))?;
+
+        let server_goal_handle = Arc::new(Mutex::new(None));
+        let server_goal_handle_inner = Arc::clone(&server_goal_handle);
+        let _server = node.create_action_server::<Fibonacci, _, _, _>(
+            "test_action_cancel",
+            |_uuid, _goal: &Fibonacci_Goal| GoalResponse::AcceptAndExecute,
+            |_goal_handle| CancelResponse::Accept,
+            move |goal_handle: Arc<ServerGoalHandle<Fibonacci>>| {
+                *server_goal_handle_inner.lock().unwrap() = Some(goal_handle);
+            },
+        )?;
+
+        let client = node.create_action_client::<Fibonacci>("test_action_cancel")?;
+        spin_until(&mut executor, || client.server_is_ready().unwrap());
+
+        let goal_handle = Arc::new(Mutex::new(None));
+        let goal_handle_inner = Arc::clone(&goal_handle);
+        let send_goal = client.send_goal(Fibonacci_Goal { order: 10 });
+        std::thread::spawn(move || {
+            let handle = futures::executor::block_on(send_goal).unwrap().unwrap();
+            *goal_handle_inner.lock().unwrap() = Some(handle);
+        });
+        spin_until(&mut executor, || goal_handle.lock().unwrap().is_some());
+        let goal_handle = goal_handle.lock().unwrap().take().unwrap();
+
+        let cancel_response = Arc::new(Mutex::new(None));
+        let cancel_response_inner = Arc::clone(&cancel_response);
+        let cancel = goal_handle.cancel();
+        std::thread::spawn(move || {
+            *cancel_response_inner.lock().unwrap() = Some(futures::executor::block_on(cancel));
+        });
+        spin_until(&mut executor, || cancel_response.lock().unwrap().is_some());
+        assert_eq!(
+            cancel_response.lock().unwrap().take().unwrap()?,
+            CancelResponseCode::Accept
+        );
+
+        let server_goal_handle = server_goal_handle.lock().unwrap().take().unwrap();
+        assert!(server_goal_handle.is_canceling());
+        server_goal_handle.canceled(Fibonacci_Result::default())?;
+        assert_eq!(server_goal_handle.status(), GoalStatusCode::Canceled);
+
+        spin_until(&mut executor, || {
+            goal_handle.status() == GoalStatusCode::Canceled
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,590 @@
+use std::{
+    collections::HashMap,
+    ffi::CString,
+    future::Future,
+    sync::{atomic::AtomicBool, Arc, Mutex, MutexGuard},
+};
+
+use futures::channel::oneshot;
+use rosidl_runtime_rs::{Action, ActionImpl, Message};
+
+use super::{
+    server::{
+        into_rmw, RmwFeedbackMessage, RmwGoalRequest, RmwGoalResponse, RmwResultRequest,
+        RmwResultResponse,
+    },
+    ClientGoalHandle, GoalStatusCode, GoalUuid,
+};
+use crate::{
+    error::ToResult,
+    rcl_bindings::*,
+    vendor::action_msgs::{
+        msg::rmw::GoalStatusArray,
+        srv::rmw::{CancelGoal_Request, CancelGoal_Response},
+    },
+    CallbackGroup, IntoPrimitiveOptions, Node, NodeHandle, QoSProfile, RclrsError,
+    ENTITY_LIFECYCLE_MUTEX,
+};
+
+// SAFETY: The functions accessing this type, including drop(), shouldn't care about the thread
+// they are running in. Therefore, this type can be safely sent to another thread.
+unsafe impl Send for rcl_action_client_t {}
+
+/// Manage the lifecycle of an `rcl_action_client_t`, including managing its dependencies
+/// on `rcl_node_t` and `rcl_context_t` by ensuring that these dependencies are
+/// [dropped after][1] the `rcl_action_client_t`.
+///
+/// [1]: <https://doc.rust-lang.org/reference/destructors.html>
+pub struct ActionClientHandle {
+    rcl_action_client: Mutex<rcl_action_client_t>,
+    node_handle: Arc<NodeHandle>,
+    pub(crate) in_use_by_wait_set: Arc<AtomicBool>,
+}
+
+impl ActionClientHandle {
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_action_client_t> {
+        self.rcl_action_client.lock().unwrap()
+    }
+}
+
+impl Drop for ActionClientHandle {
+    fn drop(&mut self) {
+        let rcl_action_client = self.rcl_action_client.get_mut().unwrap();
+        let mut rcl_node = self.node_handle.rcl_node.lock().unwrap();
+        let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+        // SAFETY: The entity lifecycle mutex is locked to protect against the risk of
+        // global variables in the rmw implementation being unsafely modified during cleanup.
+        unsafe {
+            rcl_action_client_fini(rcl_action_client, &mut *rcl_node);
+        }
+    }
+}
+
+/// Indicates which parts of an action client are ready, as determined by a wait set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ActionClientReady {
+    /// New feedback has been received.
+    pub feedback: bool,
+    /// A new status message has been received.
+    pub status: bool,
+    /// A response to a goal request has been received.
+    pub goal_response: bool,
+    /// A response to a cancel request has been received.
+    pub cancel_response: bool,
+    /// A response to a result request has been received.
+    pub result_response: bool,
+}
+
+impl ActionClientReady {
+    /// Returns true if any part of the action client is ready.
+    pub fn any(&self) -> bool {
+        self.feedback
+            || self.status
+            || self.goal_response
+            || self.cancel_response
+            || self.result_response
+    }
+}
+
+/// Trait to be implemented by concrete [`ActionClient`]s.
+pub trait ActionClientBase: Send + Sync {
+    /// Internal function to get a reference to the `rcl` handle.
+    fn handle(&self) -> &ActionClientHandle;
+    /// Returns the callback group that the callbacks belong to.
+    fn callback_group(&self) -> &CallbackGroup;
+    /// Processes the messages and responses that are ready and runs the corresponding
+    /// callbacks or completes the corresponding futures.
+    fn execute(&self, ready: ActionClientReady) -> Result<(), RclrsError>;
+}
+
+type FeedbackCallback<A> = Box<dyn FnMut(<A as Action>::Feedback) + 'static + Send>;
+
+type RequestId = i64;
+
+/// Bookkeeping for a goal that was accepted and has not reached a terminal state yet.
+struct ActiveGoal<A: ActionImpl> {
+    status: Arc<Mutex<GoalStatusCode>>,
+    feedback_callback: Option<FeedbackCallback<A>>,
+}
+
+/// Main class responsible for sending goals to a ROS action server.
+///
+/// Create an action client using [`Node::create_action_client`][1].
+///
+/// Goals are sent with [`ActionClientState::send_goal`], which returns a [`ClientGoalHandle`]
+/// once the action server has accepted the goal. The goal handle can then be used to
+/// request the result of the goal, or to cancel it.
+///
+/// Receiving responses, feedback and status updates requires the node's executor to [spin][2].
+///
+/// [1]: crate::NodeState::create_action_client
+/// [2]: crate::Executor::spin
+pub type ActionClient<A> = Arc<ActionClientState<A>>;
+
+/// The inner state of an [`ActionClient`].
+///
+/// This is public so that you can choose to create a [`Weak`][1] reference to it
+/// if you want to be able to refer to an [`ActionClient`] in a non-owning way. It is
+/// generally recommended to manage the `ActionClientState` inside of an [`Arc`],
+/// and [`ActionClient`] is provided as a convenience alias for that.
+///
+/// The public API of the [`ActionClient`] type is implemented via `ActionClientState`.
+///
+/// [1]: std::sync::Weak
+pub struct ActionClientState<A>
+where
+    A: ActionImpl,
+{
+    pub(crate) handle: Arc<ActionClientHandle>,
+    goal_requests: Mutex<HashMap<RequestId, oneshot::Sender<RmwGoalResponse<A>>>>,
+    result_requests: Mutex<HashMap<RequestId, oneshot::Sender<RmwResultResponse<A>>>>,
+    cancel_requests: Mutex<HashMap<RequestId, oneshot::Sender<CancelGoal_Response>>>,
+    active_goals: Mutex<HashMap<GoalUuid, ActiveGoal<A>>>,
+    callback_group: CallbackGroup,
+    /// Ensure the parent node remains alive as long as the action client is held.
+    /// This implementation will change in the future.
+    #[allow(unused)]
+    node: Node,
+}
+
+impl<A> ActionClientState<A>
+where
+    A: ActionImpl,
+{
+    /// Creates a new action client.
+    pub(crate) fn new<'a>(
+        node: &Node,
+        options: impl Into<ActionClientOptions<'a>>,
+    ) -> Result<Self, RclrsError> {
+        // This uses pub(crate) visibility to avoid instantiating this struct outside
+        // [`Node::create_action_client`], see the struct's documentation for the rationale
+        let ActionClientOptions {
+            action_name,
+            goal_service_qos,
+            result_service_qos,
+            cancel_service_qos,
+            feedback_topic_qos,
+            callback_group,
+        } = options.into();
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut rcl_action_client = unsafe { rcl_action_get_zero_initialized_client() };
+        let type_support = <A as Action>::get_type_support() as *const rosidl_action_type_support_t;
+        let action_name_c_string =
+            CString::new(action_name).map_err(|err| RclrsError::StringContainsNul {
+                err,
+                s: action_name.into(),
+            })?;
+
+        // SAFETY: No preconditions for this function.
+        let mut action_client_options = unsafe { rcl_action_client_get_default_options() };
+        action_client_options.goal_service_qos = goal_service_qos.into();
+        action_client_options.result_service_qos = result_service_qos.into();
+        action_client_options.cancel_service_qos = cancel_service_qos.into();
+        action_client_options.feedback_topic_qos = feedback_topic_qos.into();
+
+        {
+            let mut rcl_node = node.handle.rcl_node.lock().unwrap();
+            let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+            unsafe {
+                // SAFETY:
+                // * The rcl_action_client is zero-initialized as mandated by this function.
+                // * The rcl_node is kept alive by the NodeHandle because it is a dependency of the action client.
+                // * The action name and the options are copied by this function, so they can be dropped
+                //   afterwards.
+                // * The entity lifecycle mutex is locked to protect against the risk of global
+                //   variables in the rmw implementation being unsafely modified during initialization.
+                rcl_action_client_init(
+                    &mut rcl_action_client,
+                    &mut *rcl_node,
+                    type_support,
+                    action_name_c_string.as_ptr(),
+                    &action_client_options,
+                )
+                .ok()?;
+            }
+        }
+
+        let handle = Arc::new(ActionClientHandle {
+            rcl_action_client: Mutex::new(rcl_action_client),
+            node_handle: Arc::clone(&node.handle),
+            in_use_by_wait_set: Arc::new(AtomicBool::new(false)),
+        });
+
+        Ok(Self {
+            handle,
+            goal_requests: Mutex::new(HashMap::new()),
+            result_requests: Mutex::new(HashMap::new()),
+            cancel_requests: Mutex::new(HashMap::new()),
+            active_goals: Mutex::new(HashMap::new()),
+            callback_group: callback_group
+                .unwrap_or_else(|| Arc::clone(node.default_callback_group())),
+            node: Arc::clone(node),
+        })
+    }
+
+    /// Check if an action server is available for this action client.
+    pub fn server_is_ready(&self) -> Result<bool, RclrsError> {
+        let mut is_ready = false;
+        let rcl_action_client = &*self.handle.lock();
+        let rcl_node = &*self.handle.node_handle.rcl_node.lock().unwrap();
+        unsafe {
+            // SAFETY: Both the node and the action client are valid, and the action client
+            // was created with this node.
+            rcl_action_server_is_available(rcl_node, rcl_action_client, &mut is_ready)
+        }
+        .ok()?;
+        Ok(is_ready)
+    }
+
+    /// Sends a goal to the action server.
+    ///
+    /// The goal request is sent right away, and the returned future resolves once the
+    /// action server has responded to it. If the goal was accepted, the future resolves to
+    /// a [`ClientGoalHandle`] for the goal, otherwise it resolves to `None`.
+    pub fn send_goal(
+        self: &Arc<Self>,
+        goal: A::Goal,
+    ) -> impl Future<Output = Result<Option<ClientGoalHandle<A>>, RclrsError>> + Send + 'static
+    {
+        self.send_goal_inner(goal, None)
+    }
+
+    /// Sends a goal to the action server, and runs the callback whenever feedback for the
+    /// goal is received.
+    ///
+    /// See [`ActionClientState::send_goal`] for details.
+    pub fn send_goal_with_feedback<F>(
+        self: &Arc<Self>,
+        goal: A::Goal,
+        feedback_callback: F,
+    ) -> impl Future<Output = Result<Option<ClientGoalHandle<A>>, RclrsError>> + Send + 'static
+    where
+        F: FnMut(A::Feedback) + 'static + Send,
+    {
+        self.send_goal_inner(goal, Some(Box::new(feedback_callback)))
+    }
+
+    fn send_goal_inner(
+        self: &Arc<Self>,
+        goal: A::Goal,
+        feedback_callback: Option<FeedbackCallback<A>>,
+    ) -> impl Future<Output = Result<Option<ClientGoalHandle<A>>, RclrsError>> + Send + 'static
+    {
+        let goal_id = GoalUuid::new_random();
+        let status = Arc::new(Mutex::new(GoalStatusCode::Unknown));
+        // Register the goal before sending the request, so that no feedback is missed.
+        self.active_goals.lock().unwrap().insert(
+            goal_id,
+            ActiveGoal {
+                status: Arc::clone(&status),
+                feedback_callback,
+            },
+        );
+        let request = <A as ActionImpl>::create_goal_request(&goal_id.0, into_rmw(goal));
+        let receiver = self.send_request(
+            &self.goal_requests,
+            |rcl_action_client, sequence_number| unsafe {
+                // SAFETY: The request type is guaranteed to match the action client type by
+                // the type system.
+                rcl_action_send_goal_request(
+                    rcl_action_client,
+                    &request as *const RmwGoalRequest<A> as *const _,
+                    sequence_number,
+                )
+            },
+        );
+
+        let client = Arc::clone(self);
+        async move {
+            let receiver = match receiver {
+                Ok(receiver) => receiver,
+                Err(err) => {
+                    client.active_goals.lock().unwrap().remove(&goal_id);
+                    return Err(err);
+                }
+            };
+            // It is safe to call unwrap() here since the `Canceled` error will only happen when the
+            // `Sender` is dropped, and the action client, which owns it, is kept alive by this future.
+            let response = receiver.await.unwrap();
+            if !<A as ActionImpl>::get_goal_response_accepted(&response) {
+                client.active_goals.lock().unwrap().remove(&goal_id);
+                return Ok(None);
+            }
+            {
+                // The status might already have been updated by a status message.
+                let mut status = status.lock().unwrap();
+                if *status == GoalStatusCode::Unknown {
+                    *status = GoalStatusCode::Accepted;
+                }
+            }
+            let stamp = <A as ActionImpl>::get_goal_response_stamp(&response);
+            Ok(Some(ClientGoalHandle::new(client, goal_id, stamp, status)))
+        }
+    }
+
+    /// Sends a request for the result of a goal.
+    pub(super) fn request_result(
+        &self,
+        goal_id: GoalUuid,
+    ) -> Result<oneshot::Receiver<RmwResultResponse<A>>, RclrsError> {
+        let request = <A as ActionImpl>::create_result_request(&goal_id.0);
+        self.send_request(
+            &self.result_requests,
+            |rcl_action_client, sequence_number| unsafe {
+                // SAFETY: The request type is guaranteed to match the action client type by
+                // the type system.
+                rcl_action_send_result_request(
+                    rcl_action_client,
+                    &request as *const RmwResultRequest<A> as *const _,
+                    sequence_number,
+                )
+            },
+        )
+    }
+
+    /// Sends a request to cancel a goal.
+    pub(super) fn request_cancel(
+        &self,
+        goal_id: GoalUuid,
+    ) -> Result<oneshot::Receiver<CancelGoal_Response>, RclrsError> {
+        let mut request = CancelGoal_Request::default();
+        request.goal_info.goal_id.uuid = goal_id.0;
+        self.send_request(
+            &self.cancel_requests,
+            |rcl_action_client, sequence_number| unsafe {
+                // SAFETY: The vendored CancelGoal_Request is the request type of the cancel service.
+                rcl_action_send_cancel_request(
+                    rcl_action_client,
+                    &request as *const CancelGoal_Request as *const _,
+                    sequence_number,
+                )
+            },
+        )
+    }
+
+    /// Sends a request with the given function and registers a channel for the response.
+    fn send_request<Response>(
+        &self,
+        requests: &Mutex<HashMap<RequestId, oneshot::Sender<Response>>>,
+        send: impl FnOnce(*const rcl_action_client_t, *mut i64) -> rcl_ret_t,
+    ) -> Result<oneshot::Receiver<Response>, RclrsError> {
+        let (sender, receiver) = oneshot::channel();
+        // Keep the requests locked while sending, so that the response cannot be
+        // processed before the sender is registered.
+        let mut requests = requests.lock().unwrap();
+        let mut sequence_number = -1;
+        send(&*self.handle.lock() as *const _, &mut sequence_number).ok()?;
+        requests.insert(sequence_number, sender);
+        Ok(receiver)
+    }
+
+    /// Takes a response with the given function and completes the matching request.
+    fn take_response<Response: Default>(
+        &self,
+        requests: &Mutex<HashMap<RequestId, oneshot::Sender<Response>>>,
+        take: impl FnOnce(*const rcl_action_client_t, *mut rmw_request_id_t, *mut Response) -> rcl_ret_t,
+    ) -> Result<(), RclrsError> {
+        let mut request_id = rmw_request_id_t {
+            writer_guid: [0; 16],
+            sequence_number: 0,
+        };
+        let mut response = Response::default();
+        let take_result = take(
+            &*self.handle.lock() as *const _,
+            &mut request_id,
+            &mut response,
+        )
+        .ok();
+        match take_result {
+            Ok(()) => {}
+            Err(err) if err.is_take_failed() => {
+                // Spurious wakeup – this may happen even when a waitset indicated that this
+                // action client was ready, so it shouldn't be an error.
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        }
+        if let Some(sender) = requests.lock().unwrap().remove(&request_id.sequence_number) {
+            let _ = sender.send(response);
+        }
+        Ok(())
+    }
+
+    fn execute_feedback(&self) -> Result<(), RclrsError> {
+        let mut feedback_message = RmwFeedbackMessage::<A>::default();
+        let take_result = unsafe {
+            // SAFETY: The feedback message type is guaranteed to match the action client type
+            // by the type system.
+            rcl_action_take_feedback(
+                &*self.handle.lock(),
+                &mut feedback_message as *mut RmwFeedbackMessage<A> as *mut _,
+            )
+            .ok()
+        };
+        match take_result {
+            Ok(()) => {}
+            Err(err) if err.is_take_failed() => return Ok(()),
+            Err(err) => return Err(err),
+        }
+        let (uuid, feedback) = <A as ActionImpl>::split_feedback_message(feedback_message);
+        let goal_id = GoalUuid(uuid);
+        // Take the callback out of the map while running it, so that the callback is
+        // free to use this action client.
+        let callback = self
+            .active_goals
+            .lock()
+            .unwrap()
+            .get_mut(&goal_id)
+            .and_then(|goal| goal.feedback_callback.take());
+        if let Some(mut callback) = callback {
+            callback(<A::Feedback as Message>::from_rmw_message(feedback));
+            if let Some(goal) = self.active_goals.lock().unwrap().get_mut(&goal_id) {
+                goal.feedback_callback = Some(callback);
+            }
+        }
+        Ok(())
+    }
+
+    fn execute_status(&self) -> Result<(), RclrsError> {
+        let mut status_array = GoalStatusArray::default();
+        let take_result = unsafe {
+            // SAFETY: The vendored GoalStatusArray is the message type of the status topic.
+            rcl_action_take_status(
+                &*self.handle.lock(),
+                &mut status_array as *mut GoalStatusArray as *mut _,
+            )
+            .ok()
+        };
+        match take_result {
+            Ok(()) => {}
+            Err(err) if err.is_take_failed() => return Ok(()),
+            Err(err) => return Err(err),
+        }
+        let mut active_goals = self.active_goals.lock().unwrap();
+        for goal_status in status_array.status_list.iter() {
+            let goal_id = GoalUuid(goal_status.goal_info.goal_id.uuid);
+            let status = GoalStatusCode::from(goal_status.status);
+            if let Some(goal) = active_goals.get(&goal_id) {
+                *goal.status.lock().unwrap() = status;
+                if status.is_terminal() {
+                    active_goals.remove(&goal_id);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `ActionClientOptions` are used by [`Node::create_action_client`][1] to initialize an
+/// [`ActionClient`].
+///
+/// [1]: crate::NodeState::create_action_client
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ActionClientOptions<'a> {
+    /// The name of the action that this client will send goals to.
+    pub action_name: &'a str,
+    /// The quality of service profile for the goal service.
+    pub goal_service_qos: QoSProfile,
+    /// The quality of service profile for the result service.
+    pub result_service_qos: QoSProfile,
+    /// The quality of service profile for the cancel service.
+    pub cancel_service_qos: QoSProfile,
+    /// The quality of service profile for the feedback topic.
+    pub feedback_topic_qos: QoSProfile,
+    /// The callback group of the action client. If this is `None`, the default callback
+    /// group of the node is used.
+    pub callback_group: Option<CallbackGroup>,
+}
+
+impl<'a> ActionClientOptions<'a> {
+    /// Initialize a new [`ActionClientOptions`] with default settings.
+    pub fn new(action_name: &'a str) -> Self {
+        Self {
+            action_name,
+            goal_service_qos: QoSProfile::services_default(),
+            result_service_qos: QoSProfile::services_default(),
+            cancel_service_qos: QoSProfile::services_default(),
+            feedback_topic_qos: QoSProfile::topics_default(),
+            callback_group: None,
+        }
+    }
+}
+
+/// The quality of service settings of the [`IntoPrimitiveOptions`] are applied to the
+/// services and the feedback topic of the action client. The status topic always uses
+/// its default settings.
+impl<'a, T: IntoPrimitiveOptions<'a>> From<T> for ActionClientOptions<'a> {
+    fn from(value: T) -> Self {
+        let primitive = value.into_primitive_options();
+        let mut options = Self::new(primitive.name);
+        primitive.apply_to(&mut options.goal_service_qos);
+        primitive.apply_to(&mut options.result_service_qos);
+        primitive.apply_to(&mut options.cancel_service_qos);
+        primitive.apply_to(&mut options.feedback_topic_qos);
+        options.callback_group = primitive.callback_group.cloned();
+        options
+    }
+}
+
+impl<A> ActionClientBase for ActionClientState<A>
+where
+    A: ActionImpl,
+{
+    fn handle(&self) -> &ActionClientHandle {
+        &self.handle
+    }
+
+    fn callback_group(&self) -> &CallbackGroup {
+        &self.callback_group
+    }
+
+    fn execute(&self, ready: ActionClientReady) -> Result<(), RclrsError> {
+        if ready.feedback {
+            self.execute_feedback()?;
+        }
+        if ready.status {
+            self.execute_status()?;
+        }
+        if ready.goal_response {
+            self.take_response(&self.goal_requests, |client, request_id, response| unsafe {
+                // SAFETY: The response type is guaranteed to match the action client type by
+                // the type system.
+                rcl_action_take_goal_response(client, request_id, response as *mut _)
+            })?;
+        }
+        if ready.cancel_response {
+            self.take_response(
+                &self.cancel_requests,
+                |client, request_id, response| unsafe {
+                    // SAFETY: The vendored CancelGoal_Response is the response type of the cancel service.
+                    rcl_action_take_cancel_response(client, request_id, response as *mut _)
+                },
+            )?;
+        }
+        if ready.result_response {
+            self.take_response(
+                &self.result_requests,
+                |client, request_id, response| unsafe {
+                    // SAFETY: The response type is guaranteed to match the action client type by
+                    // the type system.
+                    rcl_action_take_result_response(client, request_id, response as *mut _)
+                },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+    use test_msgs::action::Fibonacci;
+
+    #[test]
+    fn traits() {
+        assert_send::<ActionClientState<Fibonacci>>();
+        assert_sync::<ActionClientState<Fibonacci>>();
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use rosidl_runtime_rs::{Action, ActionImpl, Message};
+
+use super::{ActionClient, CancelResponseCode, GoalStatusCode, GoalUuid};
+use crate::RclrsError;
+
+/// Handle to a goal that has been accepted by an action server.
+///
+/// This is obtained from [`ActionClientState::send_goal`][1]. It can be used to get the
+/// result of the goal, to cancel it, and to observe its status.
+///
+/// [1]: crate::ActionClientState::send_goal
+pub struct ClientGoalHandle<A>
+where
+    A: ActionImpl,
+{
+    client: ActionClient<A>,
+    goal_id: GoalUuid,
+    stamp: (i32, u32),
+    status: Arc<Mutex<GoalStatusCode>>,
+}
+
+impl<A> ClientGoalHandle<A>
+where
+    A: ActionImpl,
+{
+    pub(super) fn new(
+        client: ActionClient<A>,
+        goal_id: GoalUuid,
+        stamp: (i32, u32),
+        status: Arc<Mutex<GoalStatusCode>>,
+    ) -> Self {
+        Self {
+            client,
+            goal_id,
+            stamp,
+            status,
+        }
+    }
+
+    /// Returns the unique identifier of the goal.
+    pub fn goal_id(&self) -> GoalUuid {
+        self.goal_id
+    }
+
+    /// Returns the time at which the action server accepted the goal, as `(sec, nanosec)`.
+    pub fn stamp(&self) -> (i32, u32) {
+        self.stamp
+    }
+
+    /// Returns the last known status of the goal.
+    ///
+    /// The status is updated from the status messages of the action server, which
+    /// requires the node's executor to spin.
+    pub fn status(&self) -> GoalStatusCode {
+        *self.status.lock().unwrap()
+    }
+
+    /// Requests the result of the goal.
+    ///
+    /// The returned future resolves once the goal has reached a terminal state, with the
+    /// final status and the result of the goal.
+    pub fn get_result(
+        &self,
+    ) -> impl Future<Output = Result<(GoalStatusCode, <A as Action>::Result), RclrsError>> + Send + 'static
+    {
+        let receiver = self.client.request_result(self.goal_id);
+        let status = Arc::clone(&self.status);
+        let client = Arc::clone(&self.client);
+        async move {
+            // It is safe to call unwrap() on the received value since the `Canceled` error
+            // will only happen when the `Sender` is dropped, and the action client, which
+            // owns it, is kept alive by this future.
+            let _client = client;
+            let response = receiver?.await.unwrap();
+            let (status_code, result) = <A as ActionImpl>::split_result_response(response);
+            let status_code = GoalStatusCode::from(status_code);
+            if status_code.is_terminal() {
+                *status.lock().unwrap() = status_code;
+            }
+            Ok((
+                status_code,
+                <<A as Action>::Result as Message>::from_rmw_message(result),
+            ))
+        }
+    }
+
+    /// Requests the goal to be canceled.
+    ///
+    /// The returned future resolves once the action server has responded to the request.
+    pub fn cancel(
+        &self,
+    ) -> impl Future<Output = Result<CancelResponseCode, RclrsError>> + Send + 'static {
+        let receiver = self.client.request_cancel(self.goal_id);
+        let client = Arc::clone(&self.client);
+        async move {
+            // See get_result() for why this unwrap() is safe.
+            let _client = client;
+            let response = receiver?.await.unwrap();
+            Ok(CancelResponseCode::from(response.return_code))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+    use test_msgs::action::Fibonacci;
+
+    #[test]
+    fn traits() {
+        assert_send::<ClientGoalHandle<Fibonacci>>();
+        assert_sync::<ClientGoalHandle<Fibonacci>>();
+    }
+}
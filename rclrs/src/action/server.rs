@@ -0,0 +1,726 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    ffi::CString,
+    sync::{atomic::AtomicBool, Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use rosidl_runtime_rs::{Action, ActionImpl, Message, Sequence, Service};
+
+use super::{
+    create_goal_info, time_to_stamp, CancelResponse, GoalResponse, GoalUuid, ServerGoalHandle,
+};
+use crate::{
+    error::{to_rclrs_result, RclReturnCode, ToResult},
+    rcl_bindings::*,
+    vendor::action_msgs::{
+        msg::GoalStatus,
+        srv::rmw::{CancelGoal_Request, CancelGoal_Response},
+    },
+    CallbackGroup, Clock, IntoPrimitiveOptions, Node, NodeHandle, QoSProfile, RclrsError,
+    ENTITY_LIFECYCLE_MUTEX,
+};
+
+// SAFETY: The functions accessing this type, including drop(), shouldn't care about the thread
+// they are running in. Therefore, this type can be safely sent to another thread.
+unsafe impl Send for rcl_action_server_t {}
+
+pub(super) type RmwGoalRequest<A> =
+    <<<A as ActionImpl>::SendGoalService as Service>::Request as Message>::RmwMsg;
+pub(super) type RmwGoalResponse<A> =
+    <<<A as ActionImpl>::SendGoalService as Service>::Response as Message>::RmwMsg;
+pub(super) type RmwFeedbackMessage<A> = <<A as ActionImpl>::FeedbackMessage as Message>::RmwMsg;
+pub(super) type RmwResultRequest<A> =
+    <<<A as ActionImpl>::GetResultService as Service>::Request as Message>::RmwMsg;
+pub(super) type RmwResultResponse<A> =
+    <<<A as ActionImpl>::GetResultService as Service>::Response as Message>::RmwMsg;
+
+/// Manage the lifecycle of an `rcl_action_server_t`, including managing its dependencies
+/// on `rcl_node_t`, `rcl_clock_t` and `rcl_context_t` by ensuring that these dependencies
+/// are [dropped after][1] the `rcl_action_server_t`.
+///
+/// [1]: <https://doc.rust-lang.org/reference/destructors.html>
+pub struct ActionServerHandle {
+    rcl_action_server: Mutex<rcl_action_server_t>,
+    clock: Clock,
+    node_handle: Arc<NodeHandle>,
+    pub(crate) in_use_by_wait_set: Arc<AtomicBool>,
+}
+
+impl ActionServerHandle {
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_action_server_t> {
+        self.rcl_action_server.lock().unwrap()
+    }
+
+    /// Publishes the current status of all goals of the action server.
+    pub(super) fn publish_status(&self) -> Result<(), RclrsError> {
+        let rcl_action_server = &*self.lock();
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut status_array = unsafe { rcl_action_get_zero_initialized_goal_status_array() };
+        unsafe {
+            // SAFETY: The action server is valid and the status array is zero-initialized
+            // as mandated by this function.
+            rcl_action_get_goal_status_array(rcl_action_server, &mut status_array).ok()?;
+        }
+        let result = unsafe {
+            // SAFETY: The status array has been initialized by rcl and contains a valid
+            // action_msgs/msg/GoalStatusArray message.
+            rcl_action_publish_status(
+                rcl_action_server,
+                &status_array.msg as *const _ as *const std::os::raw::c_void,
+            )
+            .ok()
+        };
+        unsafe {
+            // SAFETY: The status array was initialized above and is not used afterwards.
+            rcl_action_goal_status_array_fini(&mut status_array).ok()?;
+        }
+        result
+    }
+
+    /// Lets the action server know that a goal has reached a terminal state, so that
+    /// it can start the timer for expiring the goal result.
+    pub(super) fn notify_goal_done(&self) -> Result<(), RclrsError> {
+        unsafe {
+            // SAFETY: The action server is valid.
+            rcl_action_notify_goal_done(&*self.lock()).ok()
+        }
+    }
+}
+
+impl Drop for ActionServerHandle {
+    fn drop(&mut self) {
+        let rcl_action_server = self.rcl_action_server.get_mut().unwrap();
+        let mut rcl_node = self.node_handle.rcl_node.lock().unwrap();
+        let _clock_lock = self.clock.get_rcl_clock().lock().unwrap();
+        let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+        // SAFETY: The entity lifecycle mutex is locked to protect against the risk of
+        // global variables in the rmw implementation being unsafely modified during cleanup.
+        unsafe {
+            rcl_action_server_fini(rcl_action_server, &mut *rcl_node);
+        }
+    }
+}
+
+/// Indicates which parts of an action server are ready, as determined by a wait set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ActionServerReady {
+    /// A new goal request has been received.
+    pub goal_request: bool,
+    /// A new cancel request has been received.
+    pub cancel_request: bool,
+    /// A new result request has been received.
+    pub result_request: bool,
+    /// The result of one or more goals has expired.
+    pub goal_expired: bool,
+}
+
+impl ActionServerReady {
+    /// Returns true if any part of the action server is ready.
+    pub fn any(&self) -> bool {
+        self.goal_request || self.cancel_request || self.result_request || self.goal_expired
+    }
+}
+
+/// Trait to be implemented by concrete [`ActionServer`]s.
+pub trait ActionServerBase: Send + Sync {
+    /// Internal function to get a reference to the `rcl` handle.
+    fn handle(&self) -> &ActionServerHandle;
+    /// Returns the callback group that the callbacks belong to.
+    fn callback_group(&self) -> &CallbackGroup;
+    /// Processes the requests that are ready and runs the corresponding callbacks.
+    fn execute(&self, ready: ActionServerReady) -> Result<(), RclrsError>;
+}
+
+type GoalCallback<A> = Box<dyn Fn(GoalUuid, &<A as Action>::Goal) -> GoalResponse + 'static + Send>;
+type CancelCallback<A> = Box<dyn Fn(Arc<ServerGoalHandle<A>>) -> CancelResponse + 'static + Send>;
+type AcceptedCallback<A> = Box<dyn Fn(Arc<ServerGoalHandle<A>>) + 'static + Send>;
+
+/// The results of goals that have reached a terminal state, and the result requests that are
+/// still waiting for a goal to reach a terminal state.
+///
+/// This is shared between the action server and its goal handles.
+pub(super) struct GoalResults<A: ActionImpl> {
+    results: HashMap<GoalUuid, RmwResultResponse<A>>,
+    pending_requests: HashMap<GoalUuid, Vec<rmw_request_id_t>>,
+}
+
+impl<A: ActionImpl> GoalResults<A> {
+    /// Stores the result of a goal and sends it to all clients that already requested it.
+    pub(super) fn set_result(
+        &mut self,
+        handle: &ActionServerHandle,
+        goal_id: GoalUuid,
+        response: RmwResultResponse<A>,
+    ) -> Result<(), RclrsError> {
+        let mut result = Ok(());
+        for mut request_id in self.pending_requests.remove(&goal_id).unwrap_or_default() {
+            // Keep sending the result to the remaining clients even if one of them fails.
+            let send_result = send_result_response::<A>(handle, &mut request_id, &response);
+            result = result.and(send_result);
+        }
+        self.results.insert(goal_id, response);
+        result
+    }
+}
+
+fn send_result_response<A: ActionImpl>(
+    handle: &ActionServerHandle,
+    request_id: &mut rmw_request_id_t,
+    response: &RmwResultResponse<A>,
+) -> Result<(), RclrsError> {
+    unsafe {
+        // SAFETY: The response type is guaranteed to match the action server type by the type system.
+        rcl_action_send_result_response(
+            &*handle.lock(),
+            request_id,
+            response as *const RmwResultResponse<A> as *mut _,
+        )
+        .ok()
+    }
+}
+
+/// Provide an action that can be used by ROS action clients to run long-running goals.
+///
+/// Create an action server using [`Node::create_action_server`][1].
+///
+/// An action server receives goal requests from [`ActionClient`][2]s, and decides whether
+/// to accept them. While an accepted goal is executing, the server can publish feedback
+/// for it through its [`ServerGoalHandle`], and eventually finishes it with a result.
+/// Clients may also request goals to be canceled.
+///
+/// ROS only supports having one action server for any given fully-qualified
+/// action name.
+///
+/// Responding to requests requires the node's executor to [spin][3].
+///
+/// [1]: crate::NodeState::create_action_server
+/// [2]: crate::ActionClient
+/// [3]: crate::Executor::spin
+pub type ActionServer<A> = Arc<ActionServerState<A>>;
+
+/// The inner state of an [`ActionServer`].
+///
+/// This is public so that you can choose to create a [`Weak`][1] reference to it
+/// if you want to be able to refer to an [`ActionServer`] in a non-owning way. It is
+/// generally recommended to manage the `ActionServerState` inside of an [`Arc`],
+/// and [`ActionServer`] is provided as a convenience alias for that.
+///
+/// The public API of the [`ActionServer`] type is implemented via `ActionServerState`.
+///
+/// [1]: std::sync::Weak
+pub struct ActionServerState<A>
+where
+    A: ActionImpl,
+{
+    pub(crate) handle: Arc<ActionServerHandle>,
+    goal_callback: Mutex<GoalCallback<A>>,
+    cancel_callback: Mutex<CancelCallback<A>>,
+    accepted_callback: Mutex<AcceptedCallback<A>>,
+    goal_handles: Mutex<HashMap<GoalUuid, Arc<ServerGoalHandle<A>>>>,
+    goal_results: Arc<Mutex<GoalResults<A>>>,
+    callback_group: CallbackGroup,
+    /// Ensure the parent node remains alive as long as the action server is held.
+    /// This implementation will change in the future.
+    #[allow(unused)]
+    node: Node,
+}
+
+impl<A> ActionServerState<A>
+where
+    A: ActionImpl,
+{
+    /// Creates a new action server.
+    pub(crate) fn new<'a, G, C, Acc>(
+        node: &Node,
+        options: impl Into<ActionServerOptions<'a>>,
+        goal_callback: G,
+        cancel_callback: C,
+        accepted_callback: Acc,
+    ) -> Result<Self, RclrsError>
+    // This uses pub(crate) visibility to avoid instantiating this struct outside
+    // [`Node::create_action_server`], see the struct's documentation for the rationale
+    where
+        G: Fn(GoalUuid, &A::Goal) -> GoalResponse + 'static + Send,
+        C: Fn(Arc<ServerGoalHandle<A>>) -> CancelResponse + 'static + Send,
+        Acc: Fn(Arc<ServerGoalHandle<A>>) + 'static + Send,
+    {
+        let ActionServerOptions {
+            action_name,
+            goal_service_qos,
+            result_service_qos,
+            cancel_service_qos,
+            feedback_topic_qos,
+            result_timeout,
+            callback_group,
+        } = options.into();
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut rcl_action_server = unsafe { rcl_action_get_zero_initialized_server() };
+        let type_support = <A as Action>::get_type_support() as *const rosidl_action_type_support_t;
+        let action_name_c_string =
+            CString::new(action_name).map_err(|err| RclrsError::StringContainsNul {
+                err,
+                s: action_name.into(),
+            })?;
+
+        // SAFETY: No preconditions for this function.
+        let mut action_server_options = unsafe { rcl_action_server_get_default_options() };
+        action_server_options.goal_service_qos = goal_service_qos.into();
+        action_server_options.result_service_qos = result_service_qos.into();
+        action_server_options.cancel_service_qos = cancel_service_qos.into();
+        action_server_options.feedback_topic_qos = feedback_topic_qos.into();
+        action_server_options.result_timeout.nanoseconds = i64::try_from(result_timeout.as_nanos())
+            .map_err(|_| RclrsError::RclError {
+                code: RclReturnCode::InvalidArgument,
+                msg: None,
+            })?;
+
+        let clock = node.get_clock();
+        {
+            let mut rcl_node = node.handle.rcl_node.lock().unwrap();
+            let mut rcl_clock = clock.get_rcl_clock().lock().unwrap();
+            let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+            unsafe {
+                // SAFETY:
+                // * The rcl_action_server is zero-initialized as mandated by this function.
+                // * The rcl_node is kept alive by the NodeHandle because it is a dependency of the action server.
+                // * The rcl_clock is kept alive by the ActionServerHandle because it is a dependency of the
+                //   action server. It is stored behind an Arc, so its address does not change.
+                // * The action name and the options are copied by this function, so they can be dropped
+                //   afterwards.
+                // * The entity lifecycle mutex is locked to protect against the risk of global
+                //   variables in the rmw implementation being unsafely modified during initialization.
+                rcl_action_server_init(
+                    &mut rcl_action_server,
+                    &mut *rcl_node,
+                    &mut *rcl_clock,
+                    type_support,
+                    action_name_c_string.as_ptr(),
+                    &action_server_options,
+                )
+                .ok()?;
+            }
+        }
+
+        let handle = Arc::new(ActionServerHandle {
+            rcl_action_server: Mutex::new(rcl_action_server),
+            clock,
+            node_handle: Arc::clone(&node.handle),
+            in_use_by_wait_set: Arc::new(AtomicBool::new(false)),
+        });
+
+        Ok(Self {
+            handle,
+            goal_callback: Mutex::new(Box::new(goal_callback)),
+            cancel_callback: Mutex::new(Box::new(cancel_callback)),
+            accepted_callback: Mutex::new(Box::new(accepted_callback)),
+            goal_handles: Mutex::new(HashMap::new()),
+            goal_results: Arc::new(Mutex::new(GoalResults {
+                results: HashMap::new(),
+                pending_requests: HashMap::new(),
+            })),
+            callback_group: callback_group
+                .unwrap_or_else(|| Arc::clone(node.default_callback_group())),
+            node: Arc::clone(node),
+        })
+    }
+
+    /// Returns the goal handles of all goals that are currently known to the action server.
+    ///
+    /// This includes goals that have already reached a terminal state, until their
+    /// result expires.
+    pub fn goal_handles(&self) -> Vec<Arc<ServerGoalHandle<A>>> {
+        self.goal_handles
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    fn take_goal_request(&self) -> Result<(RmwGoalRequest<A>, rmw_request_id_t), RclrsError> {
+        let mut request_id = rmw_request_id_t {
+            writer_guid: [0; 16],
+            sequence_number: 0,
+        };
+        let mut request = RmwGoalRequest::<A>::default();
+        unsafe {
+            // SAFETY: The three pointers are valid/initialized, and the request type is
+            // guaranteed to match the action server type by the type system.
+            rcl_action_take_goal_request(
+                &*self.handle.lock(),
+                &mut request_id,
+                &mut request as *mut RmwGoalRequest<A> as *mut _,
+            )
+            .ok()?;
+        }
+        Ok((request, request_id))
+    }
+
+    fn execute_goal_request(&self) -> Result<(), RclrsError> {
+        let (request, mut request_id) = match self.take_goal_request() {
+            Ok(taken) => taken,
+            Err(err) if err.is_take_failed() => {
+                // Spurious wakeup – this may happen even when a waitset indicated that this
+                // action server was ready, so it shouldn't be an error.
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+        let (uuid, goal) = <A as ActionImpl>::split_goal_request(request);
+        let goal_id = GoalUuid(uuid);
+        let goal = <A::Goal as Message>::from_rmw_message(goal);
+
+        let response = (*self.goal_callback.lock().unwrap())(goal_id, &goal);
+        let accepted = response != GoalResponse::Reject;
+        let stamp = time_to_stamp(&self.handle.clock.now());
+
+        let goal_handle = if accepted {
+            let goal_info = create_goal_info(&goal_id, stamp);
+            let rcl_goal_handle = unsafe {
+                // SAFETY: The action server and goal info are valid. The returned goal handle
+                // is owned by the action server.
+                rcl_action_accept_new_goal(&mut *self.handle.lock(), &goal_info)
+            };
+            if rcl_goal_handle.is_null() {
+                // The error message has been set by rcl, so pick it up here.
+                return to_rclrs_result(RclReturnCode::Error as i32);
+            }
+            let goal_handle = Arc::new(ServerGoalHandle::new(
+                rcl_goal_handle,
+                Arc::clone(&self.handle),
+                Arc::clone(&self.goal_results),
+                goal_id,
+                goal,
+            ));
+            if response == GoalResponse::AcceptAndExecute {
+                goal_handle.update_state(rcl_action_goal_event_t::GOAL_EVENT_EXECUTE)?;
+            }
+            self.goal_handles
+                .lock()
+                .unwrap()
+                .insert(goal_id, Arc::clone(&goal_handle));
+            Some(goal_handle)
+        } else {
+            None
+        };
+
+        let response = <A as ActionImpl>::create_goal_response(accepted, stamp);
+        unsafe {
+            // SAFETY: The response type is guaranteed to match the action server type by the type system.
+            rcl_action_send_goal_response(
+                &*self.handle.lock(),
+                &mut request_id,
+                &response as *const RmwGoalResponse<A> as *mut _,
+            )
+            .ok()?;
+        }
+
+        if let Some(goal_handle) = goal_handle {
+            self.handle.publish_status()?;
+            (*self.accepted_callback.lock().unwrap())(goal_handle);
+        }
+        Ok(())
+    }
+
+    fn execute_cancel_request(&self) -> Result<(), RclrsError> {
+        let mut request_id = rmw_request_id_t {
+            writer_guid: [0; 16],
+            sequence_number: 0,
+        };
+        let mut request = CancelGoal_Request::default();
+        let take_result = unsafe {
+            // SAFETY: The three pointers are valid/initialized.
+            rcl_action_take_cancel_request(
+                &*self.handle.lock(),
+                &mut request_id,
+                &mut request as *mut CancelGoal_Request as *mut _,
+            )
+            .ok()
+        };
+        match take_result {
+            Ok(()) => {}
+            Err(err) if err.is_take_failed() => {
+                // Spurious wakeup – this may happen even when a waitset indicated that this
+                // action server was ready, so it shouldn't be an error.
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        }
+
+        // Let rcl determine which goals the request refers to.
+        let (mut return_code, candidates) = {
+            // SAFETY: Getting a zero-initialized value is always safe.
+            let mut rcl_cancel_response =
+                unsafe { rcl_action_get_zero_initialized_cancel_response() };
+            unsafe {
+                // SAFETY: The vendored CancelGoal_Request has the same layout as
+                // rcl_action_cancel_request_t, and the response is zero-initialized.
+                rcl_action_process_cancel_request(
+                    &*self.handle.lock(),
+                    &request as *const CancelGoal_Request as *const rcl_action_cancel_request_t,
+                    &mut rcl_cancel_response,
+                )
+                .ok()?;
+            }
+            let goals_canceling = &rcl_cancel_response.msg.goals_canceling;
+            let candidates: Vec<(GoalUuid, (i32, u32))> = if goals_canceling.size == 0 {
+                Vec::new()
+            } else {
+                // SAFETY: rcl allocated `size` elements in the sequence.
+                unsafe { std::slice::from_raw_parts(goals_canceling.data, goals_canceling.size) }
+                    .iter()
+                    .map(|info| {
+                        (
+                            GoalUuid(info.goal_id.uuid),
+                            (info.stamp.sec, info.stamp.nanosec),
+                        )
+                    })
+                    .collect()
+            };
+            let return_code = rcl_cancel_response.msg.return_code;
+            unsafe {
+                // SAFETY: The response was initialized above and is not used afterwards.
+                rcl_action_cancel_response_fini(&mut rcl_cancel_response).ok()?;
+            }
+            (return_code, candidates)
+        };
+
+        let mut goals_canceling = Vec::new();
+        for (goal_id, stamp) in candidates {
+            let Some(goal_handle) = self.goal_handles.lock().unwrap().get(&goal_id).cloned() else {
+                continue;
+            };
+            if (*self.cancel_callback.lock().unwrap())(Arc::clone(&goal_handle))
+                == CancelResponse::Accept
+                && goal_handle
+                    .update_state(rcl_action_goal_event_t::GOAL_EVENT_CANCEL_GOAL)
+                    .is_ok()
+            {
+                goals_canceling.push((goal_id, stamp));
+            }
+        }
+
+        if return_code == CancelGoal_Response::ERROR_NONE && goals_canceling.is_empty() {
+            return_code = CancelGoal_Response::ERROR_REJECTED;
+        }
+
+        let mut response = CancelGoal_Response {
+            return_code,
+            goals_canceling: Sequence::new(goals_canceling.len()),
+        };
+        for (info, (goal_id, stamp)) in response
+            .goals_canceling
+            .iter_mut()
+            .zip(goals_canceling.iter())
+        {
+            info.goal_id.uuid = goal_id.0;
+            info.stamp.sec = stamp.0;
+            info.stamp.nanosec = stamp.1;
+        }
+
+        unsafe {
+            // SAFETY: The vendored CancelGoal_Response is the response type of the cancel service.
+            rcl_action_send_cancel_response(
+                &*self.handle.lock(),
+                &mut request_id,
+                &mut response as *mut CancelGoal_Response as *mut _,
+            )
+            .ok()?;
+        }
+
+        if !goals_canceling.is_empty() {
+            self.handle.publish_status()?;
+        }
+        Ok(())
+    }
+
+    fn execute_result_request(&self) -> Result<(), RclrsError> {
+        let mut request_id = rmw_request_id_t {
+            writer_guid: [0; 16],
+            sequence_number: 0,
+        };
+        let mut request = RmwResultRequest::<A>::default();
+        let take_result = unsafe {
+            // SAFETY: The three pointers are valid/initialized, and the request type is
+            // guaranteed to match the action server type by the type system.
+            rcl_action_take_result_request(
+                &*self.handle.lock(),
+                &mut request_id,
+                &mut request as *mut RmwResultRequest<A> as *mut _,
+            )
+            .ok()
+        };
+        match take_result {
+            Ok(()) => {}
+            Err(err) if err.is_take_failed() => {
+                // Spurious wakeup – this may happen even when a waitset indicated that this
+                // action server was ready, so it shouldn't be an error.
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        }
+
+        let goal_id = GoalUuid(*<A as ActionImpl>::get_result_request_uuid(&request));
+        let goal_is_known = self.goal_handles.lock().unwrap().contains_key(&goal_id);
+        let mut goal_results = self.goal_results.lock().unwrap();
+        if let Some(response) = goal_results.results.get(&goal_id) {
+            send_result_response::<A>(&self.handle, &mut request_id, response)
+        } else if goal_is_known {
+            // The goal has not reached a terminal state yet, so the response is sent once
+            // its result is set.
+            goal_results
+                .pending_requests
+                .entry(goal_id)
+                .or_default()
+                .push(request_id);
+            Ok(())
+        } else {
+            let response = <A as ActionImpl>::create_result_response(
+                GoalStatus::STATUS_UNKNOWN,
+                Default::default(),
+            );
+            send_result_response::<A>(&self.handle, &mut request_id, &response)
+        }
+    }
+
+    fn execute_goal_expired(&self) -> Result<(), RclrsError> {
+        loop {
+            // SAFETY: Getting a zero-initialized value is always safe.
+            let mut expired_goal = unsafe { rcl_action_get_zero_initialized_goal_info() };
+            let mut num_expired = 0;
+            let mut goal_handles = self.goal_handles.lock().unwrap();
+            {
+                let rcl_action_server = &*self.handle.lock();
+                unsafe {
+                    // SAFETY: The action server is valid, and there is room for one expired goal.
+                    rcl_action_expire_goals(
+                        rcl_action_server,
+                        &mut expired_goal,
+                        1,
+                        &mut num_expired,
+                    )
+                    .ok()?;
+                }
+                if num_expired == 0 {
+                    return Ok(());
+                }
+                let goal_id = GoalUuid(expired_goal.goal_id.uuid);
+                if let Some(goal_handle) = goal_handles.remove(&goal_id) {
+                    // The rcl goal handle has been finalized by rcl, so it must not be used
+                    // anymore. This happens while the action server is locked, which the
+                    // goal handle also locks before accessing its rcl goal handle.
+                    goal_handle.invalidate();
+                }
+            }
+            self.goal_results
+                .lock()
+                .unwrap()
+                .results
+                .remove(&GoalUuid(expired_goal.goal_id.uuid));
+        }
+    }
+}
+
+/// `ActionServerOptions` are used by [`Node::create_action_server`][1] to initialize an
+/// [`ActionServer`].
+///
+/// [1]: crate::NodeState::create_action_server
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ActionServerOptions<'a> {
+    /// The name of the action.
+    pub action_name: &'a str,
+    /// The quality of service profile for the goal service.
+    pub goal_service_qos: QoSProfile,
+    /// The quality of service profile for the result service.
+    pub result_service_qos: QoSProfile,
+    /// The quality of service profile for the cancel service.
+    pub cancel_service_qos: QoSProfile,
+    /// The quality of service profile for the feedback topic.
+    pub feedback_topic_qos: QoSProfile,
+    /// How long the result of a finished goal is kept around for clients to request it.
+    pub result_timeout: Duration,
+    /// The callback group of the action server. If this is `None`, the default callback
+    /// group of the node is used.
+    pub callback_group: Option<CallbackGroup>,
+}
+
+impl<'a> ActionServerOptions<'a> {
+    /// Initialize a new [`ActionServerOptions`] with default settings.
+    pub fn new(action_name: &'a str) -> Self {
+        Self {
+            action_name,
+            goal_service_qos: QoSProfile::services_default(),
+            result_service_qos: QoSProfile::services_default(),
+            cancel_service_qos: QoSProfile::services_default(),
+            feedback_topic_qos: QoSProfile::topics_default(),
+            result_timeout: Duration::from_secs(15 * 60),
+            callback_group: None,
+        }
+    }
+}
+
+/// The quality of service settings of the [`IntoPrimitiveOptions`] are applied to the
+/// services and the feedback topic of the action server. The status topic always uses
+/// its default settings.
+impl<'a, T: IntoPrimitiveOptions<'a>> From<T> for ActionServerOptions<'a> {
+    fn from(value: T) -> Self {
+        let primitive = value.into_primitive_options();
+        let mut options = Self::new(primitive.name);
+        primitive.apply_to(&mut options.goal_service_qos);
+        primitive.apply_to(&mut options.result_service_qos);
+        primitive.apply_to(&mut options.cancel_service_qos);
+        primitive.apply_to(&mut options.feedback_topic_qos);
+        options.callback_group = primitive.callback_group.cloned();
+        options
+    }
+}
+
+impl<A> ActionServerBase for ActionServerState<A>
+where
+    A: ActionImpl,
+{
+    fn handle(&self) -> &ActionServerHandle {
+        &self.handle
+    }
+
+    fn callback_group(&self) -> &CallbackGroup {
+        &self.callback_group
+    }
+
+    fn execute(&self, ready: ActionServerReady) -> Result<(), RclrsError> {
+        if ready.goal_request {
+            self.execute_goal_request()?;
+        }
+        if ready.cancel_request {
+            self.execute_cancel_request()?;
+        }
+        if ready.result_request {
+            self.execute_result_request()?;
+        }
+        if ready.goal_expired {
+            self.execute_goal_expired()?;
+        }
+        Ok(())
+    }
+}
+
+/// Converts an idiomatic message into its `rmw` representation.
+pub(super) fn into_rmw<T: Message>(message: T) -> T::RmwMsg {
+    T::into_rmw_message(Cow::Owned(message)).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+    use test_msgs::action::Fibonacci;
+
+    #[test]
+    fn traits() {
+        assert_send::<ActionServerState<Fibonacci>>();
+        assert_sync::<ActionServerState<Fibonacci>>();
+    }
+}
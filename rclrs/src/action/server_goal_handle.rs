@@ -0,0 +1,213 @@
+use std::sync::{Arc, Mutex};
+
+use rosidl_runtime_rs::{Action, ActionImpl};
+
+use super::{
+    server::{into_rmw, ActionServerHandle, GoalResults},
+    GoalStatusCode, GoalUuid,
+};
+use crate::{
+    error::{RclReturnCode, ToResult},
+    rcl_bindings::*,
+    RclrsError,
+};
+
+/// Wrapper around the `rcl_action_goal_handle_t` pointer that is owned by the action server.
+struct RclGoalHandle(*mut rcl_action_goal_handle_t);
+
+// SAFETY: The goal handle is only accessed while the action server is locked, which
+// doesn't depend on the thread that this is running in.
+unsafe impl Send for RclGoalHandle {}
+
+/// Handle to a goal that has been accepted by an [`ActionServer`][1].
+///
+/// The goal handle is passed to the callbacks of the action server. It is used to
+/// publish feedback for the goal, and to eventually finish the goal with a result by
+/// calling one of [`succeed`][2], [`abort`][3] or [`canceled`][4].
+///
+/// [1]: crate::ActionServer
+/// [2]: ServerGoalHandle::succeed
+/// [3]: ServerGoalHandle::abort
+/// [4]: ServerGoalHandle::canceled
+pub struct ServerGoalHandle<A>
+where
+    A: ActionImpl,
+{
+    /// The rcl goal handle is owned by the action server, which finalizes it once the
+    /// goal result expires. At that point, the pointer is set to null.
+    rcl_goal_handle: Mutex<RclGoalHandle>,
+    action_server: Arc<ActionServerHandle>,
+    goal_results: Arc<Mutex<GoalResults<A>>>,
+    goal_id: GoalUuid,
+    goal: A::Goal,
+}
+
+impl<A> ServerGoalHandle<A>
+where
+    A: ActionImpl,
+{
+    pub(super) fn new(
+        rcl_goal_handle: *mut rcl_action_goal_handle_t,
+        action_server: Arc<ActionServerHandle>,
+        goal_results: Arc<Mutex<GoalResults<A>>>,
+        goal_id: GoalUuid,
+        goal: A::Goal,
+    ) -> Self {
+        Self {
+            rcl_goal_handle: Mutex::new(RclGoalHandle(rcl_goal_handle)),
+            action_server,
+            goal_results,
+            goal_id,
+            goal,
+        }
+    }
+
+    /// Returns the unique identifier of the goal.
+    pub fn goal_id(&self) -> GoalUuid {
+        self.goal_id
+    }
+
+    /// Returns the goal that was requested by the client.
+    pub fn goal(&self) -> &A::Goal {
+        &self.goal
+    }
+
+    /// Returns the current status of the goal.
+    ///
+    /// Once the result of the goal has expired, the status is [`GoalStatusCode::Unknown`].
+    pub fn status(&self) -> GoalStatusCode {
+        let _server_lock = self.action_server.lock();
+        let rcl_goal_handle = self.rcl_goal_handle.lock().unwrap();
+        if rcl_goal_handle.0.is_null() {
+            return GoalStatusCode::Unknown;
+        }
+        let mut status = GoalStatusCode::Unknown as i8;
+        unsafe {
+            // SAFETY: The goal handle is valid while the action server is locked and the
+            // pointer is not null.
+            rcl_action_goal_handle_get_status(rcl_goal_handle.0, &mut status);
+        }
+        GoalStatusCode::from(status)
+    }
+
+    /// Returns true if the goal is accepted, executing or canceling.
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self.status(),
+            GoalStatusCode::Accepted | GoalStatusCode::Executing | GoalStatusCode::Canceling
+        )
+    }
+
+    /// Returns true if the goal is executing.
+    pub fn is_executing(&self) -> bool {
+        self.status() == GoalStatusCode::Executing
+    }
+
+    /// Returns true if a client requested the goal to be canceled and the cancel
+    /// request was accepted.
+    ///
+    /// The goal should then be finished as soon as possible by calling
+    /// [`ServerGoalHandle::canceled`].
+    pub fn is_canceling(&self) -> bool {
+        self.status() == GoalStatusCode::Canceling
+    }
+
+    /// Starts executing the goal.
+    ///
+    /// This only needs to be called if the goal was accepted with
+    /// [`GoalResponse::AcceptAndDefer`][1].
+    ///
+    /// [1]: crate::GoalResponse::AcceptAndDefer
+    pub fn execute(&self) -> Result<(), RclrsError> {
+        self.update_state(rcl_action_goal_event_t::GOAL_EVENT_EXECUTE)?;
+        self.action_server.publish_status()
+    }
+
+    /// Publishes feedback about the progress of the goal to the action client.
+    pub fn publish_feedback(&self, feedback: A::Feedback) -> Result<(), RclrsError> {
+        let feedback_message =
+            <A as ActionImpl>::create_feedback_message(&self.goal_id.0, into_rmw(feedback));
+        unsafe {
+            // SAFETY: The feedback message type is guaranteed to match the action server type
+            // by the type system.
+            rcl_action_publish_feedback(
+                &*self.action_server.lock(),
+                &feedback_message as *const _ as *mut _,
+            )
+            .ok()
+        }
+    }
+
+    /// Finishes the goal successfully with the given result.
+    pub fn succeed(&self, result: <A as Action>::Result) -> Result<(), RclrsError> {
+        self.finish(rcl_action_goal_event_t::GOAL_EVENT_SUCCEED, result)
+    }
+
+    /// Finishes the goal unsuccessfully with the given result, without a client having
+    /// requested it.
+    pub fn abort(&self, result: <A as Action>::Result) -> Result<(), RclrsError> {
+        self.finish(rcl_action_goal_event_t::GOAL_EVENT_ABORT, result)
+    }
+
+    /// Finishes the goal with the given result after it has been canceled.
+    ///
+    /// This may only be called when the goal [is canceling][1].
+    ///
+    /// [1]: ServerGoalHandle::is_canceling
+    pub fn canceled(&self, result: <A as Action>::Result) -> Result<(), RclrsError> {
+        self.finish(rcl_action_goal_event_t::GOAL_EVENT_CANCELED, result)
+    }
+
+    fn finish(
+        &self,
+        event: rcl_action_goal_event_t,
+        result: <A as Action>::Result,
+    ) -> Result<(), RclrsError> {
+        self.update_state(event)?;
+        let response =
+            <A as ActionImpl>::create_result_response(self.status() as i8, into_rmw(result));
+        self.goal_results.lock().unwrap().set_result(
+            &self.action_server,
+            self.goal_id,
+            response,
+        )?;
+        self.action_server.publish_status()?;
+        self.action_server.notify_goal_done()
+    }
+
+    pub(super) fn update_state(&self, event: rcl_action_goal_event_t) -> Result<(), RclrsError> {
+        let _server_lock = self.action_server.lock();
+        let rcl_goal_handle = self.rcl_goal_handle.lock().unwrap();
+        if rcl_goal_handle.0.is_null() {
+            return Err(RclrsError::RclError {
+                code: RclReturnCode::ActionGoalHandleInvalid,
+                msg: None,
+            });
+        }
+        unsafe {
+            // SAFETY: The goal handle is valid while the action server is locked and the
+            // pointer is not null. Invalid transitions are reported as an error by rcl.
+            rcl_action_update_goal_state(rcl_goal_handle.0, event).ok()
+        }
+    }
+
+    /// Marks the goal handle as finalized by the action server.
+    ///
+    /// Must only be called while the action server is locked.
+    pub(super) fn invalidate(&self) {
+        self.rcl_goal_handle.lock().unwrap().0 = std::ptr::null_mut();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+    use test_msgs::action::Fibonacci;
+
+    #[test]
+    fn traits() {
+        assert_send::<ServerGoalHandle<Fibonacci>>();
+        assert_sync::<ServerGoalHandle<Fibonacci>>();
+    }
+}
@@ -0,0 +1,90 @@
+//! Recording and playing back topics in rosbag2-compatible MCAP files.
+//!
+//! A [`Recorder`] subscribes to topics without knowing their types at compile time and
+//! writes the serialized messages to an [MCAP][1] file, together with the message
+//! definitions and QoS profiles in the format used by rosbag2. A [`Player`] publishes the
+//! messages of such a file again, optionally driving the `/clock` topic so that nodes
+//! using sim time follow the time of the recording.
+//!
+//! Files written by the recorder can be read with `ros2 bag` using the `mcap` storage plugin.
+//! The player can read files written by rosbag2 as long as their chunks are not compressed,
+//! e.g. when recorded with `--storage-preset-profile fastwrite`. The SQLite storage format
+//! is not supported.
+//!
+//! [1]: https://mcap.dev
+
+use std::{error::Error, fmt, io};
+
+use crate::{dynamic_message::DynamicMessageError, RclrsError};
+
+mod mcap;
+mod message_definition;
+mod player;
+mod qos_metadata;
+mod recorder;
+pub use player::*;
+pub use recorder::*;
+
+/// The profile of MCAP files that contain ROS 2 messages.
+const MCAP_PROFILE: &str = "ros2";
+/// The encoding of the messages in a bag.
+const MESSAGE_ENCODING: &str = "cdr";
+/// The encoding of the message definitions in a bag.
+const SCHEMA_ENCODING: &str = "ros2msg";
+
+/// An error while recording or playing back a bag.
+#[derive(Debug)]
+pub enum BagError {
+    /// Reading or writing the file failed.
+    Io(io::Error),
+    /// The file is not a valid MCAP file, or uses features that are not supported.
+    InvalidFile(String),
+    /// The type of a topic could not be loaded.
+    DynamicMessageError(DynamicMessageError),
+    /// Creating a publisher or subscription, or publishing a message, failed.
+    RclError(RclrsError),
+    /// The options of a player or recorder are not valid.
+    InvalidOptions(String),
+}
+
+impl fmt::Display for BagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(_) => write!(f, "Reading or writing the bag failed"),
+            Self::InvalidFile(reason) => write!(f, "The bag is not valid: {}", reason),
+            Self::DynamicMessageError(err) => write!(f, "{}", err),
+            Self::RclError(err) => write!(f, "{}", err),
+            Self::InvalidOptions(reason) => write!(f, "The options are not valid: {}", reason),
+        }
+    }
+}
+
+impl Error for BagError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::InvalidFile(_) => None,
+            Self::DynamicMessageError(err) => err.source(),
+            Self::RclError(err) => err.source(),
+            Self::InvalidOptions(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for BagError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<DynamicMessageError> for BagError {
+    fn from(err: DynamicMessageError) -> Self {
+        Self::DynamicMessageError(err)
+    }
+}
+
+impl From<RclrsError> for BagError {
+    fn from(err: RclrsError) -> Self {
+        Self::RclError(err)
+    }
+}
@@ -0,0 +1,409 @@
+//! A minimal implementation of the [MCAP][1] file format.
+//!
+//! The writer produces unchunked, unindexed files, which every MCAP reader supports.
+//! The reader reads records sequentially, including the records in uncompressed chunks.
+//!
+//! [1]: https://mcap.dev/spec
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io::{self, Read, Write},
+};
+
+use super::BagError;
+
+const MAGIC: &[u8; 8] = b"\x89MCAP0\r\n";
+
+const OP_HEADER: u8 = 0x01;
+const OP_FOOTER: u8 = 0x02;
+const OP_SCHEMA: u8 = 0x03;
+const OP_CHANNEL: u8 = 0x04;
+const OP_MESSAGE: u8 = 0x05;
+const OP_CHUNK: u8 = 0x06;
+const OP_DATA_END: u8 = 0x0F;
+
+/// A message type, as stored in an MCAP file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Schema {
+    pub(crate) id: u16,
+    pub(crate) name: String,
+    pub(crate) encoding: String,
+    pub(crate) data: Vec<u8>,
+}
+
+/// A topic, as stored in an MCAP file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Channel {
+    pub(crate) id: u16,
+    pub(crate) schema_id: u16,
+    pub(crate) topic: String,
+    pub(crate) message_encoding: String,
+    pub(crate) metadata: BTreeMap<String, String>,
+}
+
+/// A single message on a channel, as stored in an MCAP file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct McapMessage {
+    pub(crate) channel_id: u16,
+    pub(crate) sequence: u32,
+    /// The time at which the message was recorded, in nanoseconds.
+    pub(crate) log_time: u64,
+    /// The time at which the message was published, in nanoseconds.
+    pub(crate) publish_time: u64,
+    pub(crate) data: Vec<u8>,
+}
+
+/// The records of an MCAP file that are relevant for playing back messages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Record {
+    Schema(Schema),
+    Channel(Channel),
+    Message(McapMessage),
+}
+
+/// Writes an MCAP file.
+pub(crate) struct McapWriter<W: Write> {
+    writer: W,
+    finished: bool,
+}
+
+impl<W: Write> McapWriter<W> {
+    /// Writes the magic bytes and the header of the file.
+    pub(crate) fn new(mut writer: W, profile: &str, library: &str) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        let mut header = Vec::new();
+        put_string(&mut header, profile);
+        put_string(&mut header, library);
+        write_record(&mut writer, OP_HEADER, &header)?;
+        Ok(Self {
+            writer,
+            finished: false,
+        })
+    }
+
+    pub(crate) fn write_schema(&mut self, schema: &Schema) -> io::Result<()> {
+        let mut content = Vec::new();
+        content.extend_from_slice(&schema.id.to_le_bytes());
+        put_string(&mut content, &schema.name);
+        put_string(&mut content, &schema.encoding);
+        put_bytes(&mut content, &schema.data);
+        write_record(&mut self.writer, OP_SCHEMA, &content)
+    }
+
+    pub(crate) fn write_channel(&mut self, channel: &Channel) -> io::Result<()> {
+        let mut content = Vec::new();
+        content.extend_from_slice(&channel.id.to_le_bytes());
+        content.extend_from_slice(&channel.schema_id.to_le_bytes());
+        put_string(&mut content, &channel.topic);
+        put_string(&mut content, &channel.message_encoding);
+        let mut metadata = Vec::new();
+        for (key, value) in &channel.metadata {
+            put_string(&mut metadata, key);
+            put_string(&mut metadata, value);
+        }
+        put_bytes(&mut content, &metadata);
+        write_record(&mut self.writer, OP_CHANNEL, &content)
+    }
+
+    pub(crate) fn write_message(&mut self, message: &McapMessage) -> io::Result<()> {
+        let mut content = Vec::with_capacity(22 + message.data.len());
+        content.extend_from_slice(&message.channel_id.to_le_bytes());
+        content.extend_from_slice(&message.sequence.to_le_bytes());
+        content.extend_from_slice(&message.log_time.to_le_bytes());
+        content.extend_from_slice(&message.publish_time.to_le_bytes());
+        content.extend_from_slice(&message.data);
+        write_record(&mut self.writer, OP_MESSAGE, &content)
+    }
+
+    /// Writes the end of the file, without a summary section.
+    ///
+    /// Nothing may be written afterwards.
+    pub(crate) fn finish(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        // A CRC of zero means that no CRC was computed.
+        write_record(&mut self.writer, OP_DATA_END, &0u32.to_le_bytes())?;
+        let mut footer = Vec::new();
+        footer.extend_from_slice(&0u64.to_le_bytes());
+        footer.extend_from_slice(&0u64.to_le_bytes());
+        footer.extend_from_slice(&0u32.to_le_bytes());
+        write_record(&mut self.writer, OP_FOOTER, &footer)?;
+        self.writer.write_all(MAGIC)?;
+        self.writer.flush()
+    }
+}
+
+/// Reads the records of an MCAP file in the order in which they are stored.
+pub(crate) struct McapReader<R: Read> {
+    reader: R,
+    // Records of a chunk that have not been returned yet.
+    pending: VecDeque<Record>,
+    done: bool,
+}
+
+impl<R: Read> McapReader<R> {
+    /// Checks the magic bytes at the start of the file.
+    pub(crate) fn new(mut reader: R) -> Result<Self, BagError> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(BagError::InvalidFile("not an MCAP file".into()));
+        }
+        Ok(Self {
+            reader,
+            pending: VecDeque::new(),
+            done: false,
+        })
+    }
+
+    /// Returns the next schema, channel or message, or `None` at the end of the data section.
+    pub(crate) fn next_record(&mut self) -> Result<Option<Record>, BagError> {
+        loop {
+            if let Some(record) = self.pending.pop_front() {
+                return Ok(Some(record));
+            }
+            if self.done {
+                return Ok(None);
+            }
+            let mut opcode = [0; 1];
+            self.reader.read_exact(&mut opcode)?;
+            let mut length = [0; 8];
+            self.reader.read_exact(&mut length)?;
+            let length = u64::from_le_bytes(length);
+            let mut content = Vec::new();
+            (&mut self.reader).take(length).read_to_end(&mut content)?;
+            if content.len() as u64 != length {
+                return Err(BagError::InvalidFile("truncated record".into()));
+            }
+            match opcode[0] {
+                OP_CHUNK => self.read_chunk(&content)?,
+                // Only the summary section and the footer follow the data section.
+                OP_DATA_END | OP_FOOTER => self.done = true,
+                opcode => {
+                    if let Some(record) = parse_record(opcode, &content)? {
+                        return Ok(Some(record));
+                    }
+                }
+            }
+        }
+    }
+
+    fn read_chunk(&mut self, content: &[u8]) -> Result<(), BagError> {
+        let mut cursor = Cursor::new(content);
+        // Skip the start time, end time, uncompressed size and uncompressed CRC.
+        cursor.skip(8 + 8 + 8 + 4)?;
+        let compression = cursor.string()?;
+        if !compression.is_empty() {
+            return Err(BagError::InvalidFile(format!(
+                "chunks with '{}' compression are not supported",
+                compression
+            )));
+        }
+        let records_length = cursor.u64()? as usize;
+        let mut records = Cursor::new(cursor.take(records_length)?);
+        while !records.is_empty() {
+            let opcode = records.u8()?;
+            let length = records.u64()? as usize;
+            let content = records.take(length)?;
+            if let Some(record) = parse_record(opcode, content)? {
+                self.pending.push_back(record);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_record(opcode: u8, content: &[u8]) -> Result<Option<Record>, BagError> {
+    let mut cursor = Cursor::new(content);
+    let record = match opcode {
+        OP_SCHEMA => Record::Schema(Schema {
+            id: cursor.u16()?,
+            name: cursor.string()?,
+            encoding: cursor.string()?,
+            data: cursor.bytes()?.to_vec(),
+        }),
+        OP_CHANNEL => Record::Channel(Channel {
+            id: cursor.u16()?,
+            schema_id: cursor.u16()?,
+            topic: cursor.string()?,
+            message_encoding: cursor.string()?,
+            metadata: {
+                let mut map = Cursor::new(cursor.bytes()?);
+                let mut metadata = BTreeMap::new();
+                while !map.is_empty() {
+                    metadata.insert(map.string()?, map.string()?);
+                }
+                metadata
+            },
+        }),
+        OP_MESSAGE => Record::Message(McapMessage {
+            channel_id: cursor.u16()?,
+            sequence: cursor.u32()?,
+            log_time: cursor.u64()?,
+            publish_time: cursor.u64()?,
+            data: cursor.rest().to_vec(),
+        }),
+        // Other records, e.g. attachments and the header, are not needed for playback.
+        _ => return Ok(None),
+    };
+    Ok(Some(record))
+}
+
+fn write_record(writer: &mut impl Write, opcode: u8, content: &[u8]) -> io::Result<()> {
+    writer.write_all(&[opcode])?;
+    writer.write_all(&(content.len() as u64).to_le_bytes())?;
+    writer.write_all(content)
+}
+
+fn put_string(buffer: &mut Vec<u8>, value: &str) {
+    put_bytes(buffer, value.as_bytes());
+}
+
+fn put_bytes(buffer: &mut Vec<u8>, value: &[u8]) {
+    buffer.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(value);
+}
+
+/// Reads little-endian values from the content of a record.
+struct Cursor<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Cursor<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BagError> {
+        if len > self.data.len() {
+            return Err(BagError::InvalidFile("truncated record".into()));
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), BagError> {
+        self.take(len).map(|_| ())
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.data)
+    }
+
+    fn u8(&mut self) -> Result<u8, BagError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, BagError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, BagError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, BagError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], BagError> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Result<String, BagError> {
+        String::from_utf8(self.bytes()?.to_vec())
+            .map_err(|_| BagError::InvalidFile("invalid string".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mcap_round_trip() {
+        let schema = Schema {
+            id: 1,
+            name: "std_msgs/msg/String".into(),
+            encoding: "ros2msg".into(),
+            data: b"string data".to_vec(),
+        };
+        let channel = Channel {
+            id: 1,
+            schema_id: 1,
+            topic: "/chatter".into(),
+            message_encoding: "cdr".into(),
+            metadata: [("offered_qos_profiles".to_owned(), "".to_owned())].into(),
+        };
+        let message = McapMessage {
+            channel_id: 1,
+            sequence: 0,
+            log_time: 2_000,
+            publish_time: 1_000,
+            data: vec![0, 1, 0, 0, 3, 0, 0, 0, b'h', b'i', 0],
+        };
+
+        let mut writer = McapWriter::new(Vec::new(), "ros2", "rclrs").unwrap();
+        writer.write_schema(&schema).unwrap();
+        writer.write_channel(&channel).unwrap();
+        writer.write_message(&message).unwrap();
+        writer.finish().unwrap();
+        let bytes = writer.writer;
+        assert!(bytes.ends_with(MAGIC));
+
+        let mut reader = McapReader::new(bytes.as_slice()).unwrap();
+        assert_eq!(reader.next_record().unwrap(), Some(Record::Schema(schema)));
+        assert_eq!(
+            reader.next_record().unwrap(),
+            Some(Record::Channel(channel))
+        );
+        assert_eq!(
+            reader.next_record().unwrap(),
+            Some(Record::Message(message))
+        );
+        assert_eq!(reader.next_record().unwrap(), None);
+    }
+
+    #[test]
+    fn mcap_reads_uncompressed_chunks() {
+        let mut records = Vec::new();
+        let mut content = Vec::new();
+        content.extend_from_slice(&7u16.to_le_bytes());
+        content.extend_from_slice(&3u32.to_le_bytes());
+        content.extend_from_slice(&5u64.to_le_bytes());
+        content.extend_from_slice(&4u64.to_le_bytes());
+        content.extend_from_slice(b"data");
+        write_record(&mut records, OP_MESSAGE, &content).unwrap();
+        let mut chunk = vec![0; 8 + 8 + 8 + 4];
+        put_string(&mut chunk, "");
+        chunk.extend_from_slice(&(records.len() as u64).to_le_bytes());
+        chunk.extend_from_slice(&records);
+
+        let mut bytes = MAGIC.to_vec();
+        write_record(&mut bytes, OP_CHUNK, &chunk).unwrap();
+        write_record(&mut bytes, OP_DATA_END, &0u32.to_le_bytes()).unwrap();
+
+        let mut reader = McapReader::new(bytes.as_slice()).unwrap();
+        assert_eq!(
+            reader.next_record().unwrap(),
+            Some(Record::Message(McapMessage {
+                channel_id: 7,
+                sequence: 3,
+                log_time: 5,
+                publish_time: 4,
+                data: b"data".to_vec(),
+            }))
+        );
+        assert_eq!(reader.next_record().unwrap(), None);
+
+        assert!(McapReader::new(&b"not an mcap file"[..]).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
+/// The separator between the definitions of a message and its dependencies, as used by rosbag2.
+const SEPARATOR: &str =
+    "================================================================================";
+
+const PRIMITIVE_TYPES: &[&str] = &[
+    "bool", "byte", "char", "float32", "float64", "int8", "uint8", "int16", "uint16", "int32",
+    "uint32", "int64", "uint64", "string", "wstring",
+];
+
+/// Returns the full definition of a message type in the `ros2msg` encoding, or `None` if the
+/// `.msg` file of the type or one of its dependencies was not found.
+///
+/// This has the same format as the definitions that rosbag2 stores in a bag: the `.msg` file
+/// of the type, followed by the `.msg` files of all the message types that it uses.
+pub(crate) fn message_definition(topic_type: &str) -> Option<String> {
+    let ament = ament_rs::Ament::new().ok()?;
+    let (package, type_name) = split_type(topic_type, "")?;
+    let mut definition = read_definition(&ament, &package, &type_name)?;
+    let mut visited = BTreeSet::new();
+    let mut queue = dependencies(&definition, &package);
+    while let Some((package, type_name)) = queue.pop() {
+        if !visited.insert((package.clone(), type_name.clone())) {
+            continue;
+        }
+        let dependency = read_definition(&ament, &package, &type_name)?;
+        definition.push_str(&format!(
+            "\n{}\nMSG: {}/{}\n{}",
+            SEPARATOR, package, type_name, dependency
+        ));
+        queue.extend(dependencies(&dependency, &package));
+    }
+    Some(definition)
+}
+
+fn read_definition(ament: &ament_rs::Ament, package: &str, type_name: &str) -> Option<String> {
+    let prefix = PathBuf::from(ament.find_package(package)?);
+    let path = prefix
+        .join("share")
+        .join(package)
+        .join("msg")
+        .join(format!("{}.msg", type_name));
+    std::fs::read_to_string(path).ok()
+}
+
+/// Splits a type like `std_msgs/msg/Header`, `std_msgs/Header` or `Header` into its package
+/// and type name. Types without a package belong to the `current_package`.
+fn split_type(field_type: &str, current_package: &str) -> Option<(String, String)> {
+    let parts: Vec<&str> = field_type.split('/').collect();
+    match parts.as_slice() {
+        [type_name] if !current_package.is_empty() => {
+            Some((current_package.to_owned(), (*type_name).to_owned()))
+        }
+        [package, type_name] | [package, "msg", type_name] => {
+            Some(((*package).to_owned(), (*type_name).to_owned()))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the message types used by the fields of a message definition.
+fn dependencies(definition: &str, package: &str) -> Vec<(String, String)> {
+    definition
+        .lines()
+        .filter_map(|line| {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let field_type = line.split_whitespace().next()?;
+            // Remove array and string bounds, e.g. `int32[<=3]` or `string<=5`.
+            let field_type = field_type.split(['[', '<']).next().unwrap_or_default();
+            if field_type.is_empty() || PRIMITIVE_TYPES.contains(&field_type) {
+                return None;
+            }
+            split_type(field_type, package)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_definition_dependencies() {
+        let definition = "\
+            # A comment\n\
+            std_msgs/Header header\n\
+            int32[<=3] values\n\
+            string<=5 name\n\
+            Point[] points  # In the same package\n\
+            uint8 CONSTANT=1\n";
+        assert_eq!(
+            dependencies(definition, "geometry_msgs"),
+            vec![
+                ("std_msgs".to_owned(), "Header".to_owned()),
+                ("geometry_msgs".to_owned(), "Point".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn message_definition_of_installed_type() {
+        let definition = message_definition("test_msgs/msg/Nested").unwrap();
+        assert!(definition.contains("BasicTypes basic_types_value"));
+        assert!(definition.contains("MSG: test_msgs/BasicTypes"));
+        assert!(message_definition("test_msgs/msg/NoSuchType").is_none());
+    }
+}
@@ -0,0 +1,523 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{
+    mcap::{McapMessage, McapReader, Record},
+    qos_metadata::{qos_profile_from_yaml, OFFERED_QOS_PROFILES},
+    BagError,
+};
+use crate::{
+    dynamic_message::GenericPublisher,
+    vendor::{builtin_interfaces::msg::Time as TimeMsg, rosgraph_msgs::msg::Clock as ClockMsg},
+    ContextHandle, IntoPrimitiveOptions, Node, Publisher, QoSProfile, QOS_PROFILE_CLOCK,
+};
+
+/// The longest time that the player sleeps before checking whether the context is still valid.
+const MAX_SLEEP: Duration = Duration::from_millis(100);
+
+/// Options for a [`Player`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct PlayerOptions {
+    /// The factor by which playback is sped up, e.g. `2.0` plays twice as fast.
+    ///
+    /// This must be positive.
+    pub rate: f64,
+    /// Whether to start over at the beginning of the bag once all messages were played.
+    pub loop_playback: bool,
+    /// Whether to publish the time of the recording on the `/clock` topic.
+    ///
+    /// Nodes that use sim time then follow the time at which the messages were recorded.
+    pub publish_clock: bool,
+    /// How often the time is published on the `/clock` topic, independently of the
+    /// messages of the bag.
+    ///
+    /// The default is 25 ms, which is the default rate of 40 Hz of `ros2 bag play --clock`.
+    pub clock_period: Duration,
+    /// How long to wait before playing the messages, each time the bag is played.
+    ///
+    /// This gives subscriptions time to discover the publishers of the player, since
+    /// messages that are published before that are lost.
+    pub delay: Duration,
+    /// The fully qualified names of the topics to play, e.g. `/chatter`.
+    ///
+    /// If this is empty, all topics are played.
+    pub topics: Vec<String>,
+}
+
+impl Default for PlayerOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlayerOptions {
+    /// Creates options that play all topics once at the recorded rate.
+    pub fn new() -> Self {
+        Self {
+            rate: 1.0,
+            loop_playback: false,
+            publish_clock: false,
+            clock_period: Duration::from_millis(25),
+            delay: Duration::ZERO,
+            topics: Vec::new(),
+        }
+    }
+
+    /// Sets the factor by which playback is sped up.
+    pub fn rate(mut self, rate: f64) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Sets whether to start over once all messages were played.
+    pub fn loop_playback(mut self, loop_playback: bool) -> Self {
+        self.loop_playback = loop_playback;
+        self
+    }
+
+    /// Sets whether to publish the time of the recording on the `/clock` topic.
+    pub fn publish_clock(mut self, publish_clock: bool) -> Self {
+        self.publish_clock = publish_clock;
+        self
+    }
+
+    /// Sets how often the time is published on the `/clock` topic.
+    pub fn clock_period(mut self, clock_period: Duration) -> Self {
+        self.clock_period = clock_period;
+        self
+    }
+
+    /// Sets how long to wait before playing the messages.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Sets the topics to play.
+    pub fn topics(mut self, topics: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.topics = topics.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+/// Publishes the messages of an MCAP bag with their original timing.
+///
+/// The publishers are created when the player is created, with the QoS profiles stored in
+/// the bag, so that subscriptions can discover them before [`Player::play`] is called.
+/// Their types are loaded at runtime, so the message packages of the bag must be installed.
+///
+/// ```no_run
+/// # use rclrs::{*, bag::*};
+/// # let executor = Context::default().create_basic_executor();
+/// # let node = executor.create_node("player").unwrap();
+/// let player = Player::new(&node, "my_bag.mcap", PlayerOptions::new().rate(2.0))?;
+/// player.play()?;
+/// # Ok::<(), BagError>(())
+/// ```
+pub struct Player {
+    path: PathBuf,
+    options: PlayerOptions,
+    // The publishers by the ID of their channel in the bag.
+    publishers: HashMap<u16, GenericPublisher>,
+    clock_publisher: Option<Publisher<ClockMsg>>,
+    // The log time of the first message that is played, which is where playback starts.
+    first_log_time: u64,
+    context: Arc<ContextHandle>,
+}
+
+impl Player {
+    /// Opens the bag at the given path and creates a publisher for each of its topics.
+    ///
+    /// This reads the whole file once to find the topics of the bag. An error is returned
+    /// if the playback rate or the clock period are not positive.
+    pub fn new(
+        node: &Node,
+        path: impl AsRef<Path>,
+        options: PlayerOptions,
+    ) -> Result<Self, BagError> {
+        if options.rate.is_nan() || options.rate <= 0.0 {
+            return Err(BagError::InvalidOptions(
+                "the playback rate must be positive".to_owned(),
+            ));
+        }
+        if options.clock_period.is_zero() {
+            return Err(BagError::InvalidOptions(
+                "the clock period must be positive".to_owned(),
+            ));
+        }
+        let path = path.as_ref().to_owned();
+        let mut reader = McapReader::new(BufReader::new(File::open(&path)?))?;
+        let mut schemas = HashMap::new();
+        let mut publishers = HashMap::new();
+        let mut first_log_time = None;
+        while let Some(record) = reader.next_record()? {
+            match record {
+                Record::Schema(schema) => {
+                    schemas.insert(schema.id, schema.name);
+                }
+                Record::Channel(channel) => {
+                    if !options.topics.is_empty() && !options.topics.contains(&channel.topic) {
+                        continue;
+                    }
+                    let topic_type = schemas.get(&channel.schema_id).ok_or_else(|| {
+                        BagError::InvalidFile(format!(
+                            "the type of topic '{}' is unknown",
+                            channel.topic
+                        ))
+                    })?;
+                    let qos = channel
+                        .metadata
+                        .get(OFFERED_QOS_PROFILES)
+                        .map(|yaml| qos_profile_from_yaml(yaml, QoSProfile::topics_default()))
+                        .unwrap_or_else(QoSProfile::topics_default);
+                    let publisher =
+                        node.create_generic_publisher(topic_type, channel.topic.as_str().qos(qos))?;
+                    publishers.insert(channel.id, publisher);
+                }
+                Record::Message(message) => {
+                    if publishers.contains_key(&message.channel_id) {
+                        let first_log_time = first_log_time.get_or_insert(message.log_time);
+                        *first_log_time = message.log_time.min(*first_log_time);
+                    }
+                }
+            }
+        }
+        let clock_publisher = if options.publish_clock {
+            Some(node.create_publisher("/clock".qos(QOS_PROFILE_CLOCK))?)
+        } else {
+            None
+        };
+        Ok(Self {
+            path,
+            options,
+            publishers,
+            clock_publisher,
+            first_log_time: first_log_time.unwrap_or_default(),
+            context: Arc::clone(&node.handle.context_handle),
+        })
+    }
+
+    /// Returns the topics that are played, in alphabetical order.
+    pub fn topics(&self) -> Vec<String> {
+        let mut topics: Vec<_> = self
+            .publishers
+            .values()
+            .map(|publisher| publisher.topic_name())
+            .collect();
+        topics.sort();
+        topics.dedup();
+        topics
+    }
+
+    /// Plays the bag, blocking until all messages were published.
+    ///
+    /// When [looping][1], this only returns once the context is shut down or an error occurs.
+    ///
+    /// [1]: PlayerOptions::loop_playback
+    pub fn play(&self) -> Result<(), BagError> {
+        loop {
+            self.play_once()?;
+            if !self.options.loop_playback || !self.context.ok() {
+                return Ok(());
+            }
+        }
+    }
+
+    fn play_once(&self) -> Result<(), BagError> {
+        let mut reader = McapReader::new(BufReader::new(File::open(&self.path)?))?;
+        if !self.sleep_until(Instant::now() + self.options.delay) {
+            return Ok(());
+        }
+        let start = Instant::now();
+        let done = AtomicBool::new(false);
+        thread::scope(|scope| {
+            // The clock is published from its own loop, so that it keeps advancing when
+            // there are long gaps between the messages.
+            let clock_loop = self.clock_publisher.as_ref().map(|clock_publisher| {
+                let done = &done;
+                scope.spawn(move || self.publish_clock(clock_publisher, start, done))
+            });
+            let result = self.publish_messages(&mut reader, start);
+            done.store(true, Ordering::Release);
+            let clock_result = match clock_loop {
+                Some(clock_loop) => clock_loop.join().unwrap(),
+                None => Ok(()),
+            };
+            result.and(clock_result)
+        })
+    }
+
+    fn publish_messages(
+        &self,
+        reader: &mut McapReader<BufReader<File>>,
+        start: Instant,
+    ) -> Result<(), BagError> {
+        while let Some(record) = reader.next_record()? {
+            let Record::Message(message) = record else {
+                continue;
+            };
+            let Some(publisher) = self.publishers.get(&message.channel_id) else {
+                continue;
+            };
+            if !self.sleep_until(start + self.playback_offset(&message)) {
+                return Ok(());
+            }
+            publisher.publish(&message.data)?;
+        }
+        Ok(())
+    }
+
+    /// Publishes the time of the recording on `/clock` with a fixed period, until `done`
+    /// is set or the context is shut down.
+    fn publish_clock(
+        &self,
+        clock_publisher: &Publisher<ClockMsg>,
+        start: Instant,
+        done: &AtomicBool,
+    ) -> Result<(), BagError> {
+        let mut next = start;
+        loop {
+            let now = Instant::now();
+            if now >= next {
+                let bag_time = self.first_log_time
+                    + ((now - start).as_nanos() as f64 * self.options.rate) as u64;
+                clock_publisher.publish(ClockMsg {
+                    clock: TimeMsg {
+                        sec: (bag_time / 1_000_000_000) as i32,
+                        nanosec: (bag_time % 1_000_000_000) as u32,
+                    },
+                })?;
+                // Skip the periods that were missed instead of catching up.
+                next = (next + self.options.clock_period).max(now);
+                continue;
+            }
+            if done.load(Ordering::Acquire) || !self.context.ok() {
+                return Ok(());
+            }
+            thread::sleep((next - now).min(MAX_SLEEP));
+        }
+    }
+
+    /// Returns when a message should be published, relative to the start of playback.
+    fn playback_offset(&self, message: &McapMessage) -> Duration {
+        let offset = message.log_time.saturating_sub(self.first_log_time) as f64;
+        Duration::from_secs_f64(offset / self.options.rate / 1e9)
+    }
+
+    /// Sleeps until the deadline, and returns `false` if the context was shut down instead.
+    fn sleep_until(&self, deadline: Instant) -> bool {
+        loop {
+            if !self.context.ok() {
+                return false;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return true;
+            }
+            thread::sleep((deadline - now).min(MAX_SLEEP));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bag::{
+            mcap::{Channel, McapWriter, Schema},
+            MCAP_PROFILE, MESSAGE_ENCODING, *,
+        },
+        *,
+    };
+    use std::{collections::BTreeMap, io::BufWriter, sync::Mutex};
+    use test_msgs::msg::BasicTypes;
+
+    #[test]
+    fn record_and_play_back() -> Result<(), Box<dyn std::error::Error>> {
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_bag_{}", line!()))?;
+        let topic = format!("/test_bag_topic_{}", line!());
+        let file = tempfile::NamedTempFile::new()?;
+        let qos = QoSProfile::topics_default().keep_all();
+
+        let publisher = node.create_publisher::<BasicTypes>(topic.as_str().qos(qos))?;
+        let recorder = Recorder::new(
+            &node,
+            file.path(),
+            RecorderOptions::new()
+                .topics([&topic])
+                .qos(qos.best_effort()),
+        )?;
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while recorder.topics().is_empty() {
+            assert!(Instant::now() < deadline);
+            thread::sleep(Duration::from_millis(10));
+            recorder.discover_topics()?;
+        }
+        // Messages that are published before the subscription of the recorder is matched
+        // are lost, so keep publishing until some were recorded.
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut value = 0;
+        while recorder.message_count() < 3 {
+            assert!(Instant::now() < deadline);
+            publisher.publish(BasicTypes {
+                int32_value: value,
+                ..Default::default()
+            })?;
+            value += 1;
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+        let recorded = recorder.message_count() as usize;
+        recorder.finish()?;
+        drop(publisher);
+
+        // The bag stores the QoS of the publisher, not the one of the recorder.
+        let mut reader = McapReader::new(BufReader::new(File::open(file.path())?))?;
+        let mut offered_qos = None;
+        while let Some(record) = reader.next_record()? {
+            if let Record::Channel(channel) = record {
+                offered_qos = channel.metadata.get(OFFERED_QOS_PROFILES).cloned();
+            }
+        }
+        let offered_qos = qos_profile_from_yaml(&offered_qos.unwrap(), QoSProfile::default());
+        assert_eq!(offered_qos.reliability, QoSReliabilityPolicy::Reliable);
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let _subscription = {
+            let received = Arc::clone(&received);
+            node.create_subscription(topic.as_str().qos(qos), move |msg: BasicTypes| {
+                received.lock().unwrap().push(msg.int32_value);
+            })?
+        };
+        let player = Player::new(
+            &node,
+            file.path(),
+            PlayerOptions::new()
+                .rate(10.0)
+                .delay(Duration::from_millis(500)),
+        )?;
+        assert_eq!(player.topics(), [topic.clone()]);
+        let playback = thread::spawn(move || player.play());
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while Instant::now() < deadline
+            && (!playback.is_finished() || received.lock().unwrap().len() < recorded)
+        {
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+        playback.join().unwrap()?;
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), recorded);
+        assert!(received.windows(2).all(|pair| pair[1] == pair[0] + 1));
+        Ok(())
+    }
+
+    #[test]
+    fn clock_is_published_between_messages() -> Result<(), Box<dyn std::error::Error>> {
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_bag_clock_{}", line!()))?;
+        let topic = format!("/test_bag_clock_topic_{}", line!());
+        let file = tempfile::NamedTempFile::new()?;
+        {
+            let mut mcap = McapWriter::new(
+                BufWriter::new(File::create(file.path())?),
+                MCAP_PROFILE,
+                "rclrs",
+            )?;
+            mcap.write_schema(&Schema {
+                id: 1,
+                name: "test_msgs/msg/BasicTypes".to_owned(),
+                encoding: String::new(),
+                data: Vec::new(),
+            })?;
+            mcap.write_channel(&Channel {
+                id: 1,
+                schema_id: 1,
+                topic: topic.clone(),
+                message_encoding: MESSAGE_ENCODING.to_owned(),
+                metadata: BTreeMap::new(),
+            })?;
+            // Two messages that were recorded one second apart. Nobody subscribes to the
+            // topic, so their content does not matter.
+            for (sequence, log_time) in [(1, 1_000_000_000), (2, 2_000_000_000)] {
+                mcap.write_message(&McapMessage {
+                    channel_id: 1,
+                    sequence,
+                    log_time,
+                    publish_time: log_time,
+                    data: vec![0, 1, 0, 0],
+                })?;
+            }
+            mcap.finish()?;
+        }
+
+        let clock_times = Arc::new(Mutex::new(Vec::new()));
+        let _subscription = {
+            let clock_times = Arc::clone(&clock_times);
+            node.create_subscription("/clock".qos(QOS_PROFILE_CLOCK), move |msg: ClockMsg| {
+                let time = msg.clock.sec as u64 * 1_000_000_000 + msg.clock.nanosec as u64;
+                clock_times.lock().unwrap().push(time);
+            })?
+        };
+        let player = Player::new(
+            &node,
+            file.path(),
+            PlayerOptions::new()
+                .rate(2.0)
+                .publish_clock(true)
+                .clock_period(Duration::from_millis(50))
+                .delay(Duration::from_millis(500)),
+        )?;
+        let playback = thread::spawn(move || player.play());
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !playback.is_finished() {
+            assert!(Instant::now() < deadline);
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+        playback.join().unwrap()?;
+        executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+
+        // The half second of playback between the two messages is covered by several clock
+        // messages, not only by the ones that were published together with the messages.
+        let between = clock_times
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|time| (1_000_000_001..2_000_000_000).contains(*time))
+            .count();
+        assert!(
+            between >= 3,
+            "only {between} clock messages between the messages"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_options_are_rejected() -> Result<(), Box<dyn std::error::Error>> {
+        let executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_bag_options_{}", line!()))?;
+        let file = tempfile::NamedTempFile::new()?;
+        for options in [
+            PlayerOptions::new().rate(0.0),
+            PlayerOptions::new().rate(f64::NAN),
+            PlayerOptions::new().clock_period(Duration::ZERO),
+        ] {
+            assert!(matches!(
+                Player::new(&node, file.path(), options),
+                Err(BagError::InvalidOptions(_))
+            ));
+        }
+        Ok(())
+    }
+}
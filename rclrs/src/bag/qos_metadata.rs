@@ -0,0 +1,158 @@
+use std::{fmt::Write, time::Duration};
+
+use crate::{
+    QoSDurabilityPolicy, QoSDuration, QoSHistoryPolicy, QoSLivelinessPolicy, QoSProfile,
+    QoSReliabilityPolicy,
+};
+
+/// The key of the QoS profiles in the metadata of a channel, as used by rosbag2.
+pub(crate) const OFFERED_QOS_PROFILES: &str = "offered_qos_profiles";
+
+// The values that rosbag2 uses for durations.
+const INFINITE_SEC: u64 = 9223372036;
+const INFINITE_NSEC: u32 = 854775807;
+
+/// Formats QoS profiles as the YAML list that rosbag2 stores in the channel metadata.
+pub(crate) fn qos_profiles_to_yaml(profiles: &[QoSProfile]) -> String {
+    let mut yaml = String::new();
+    for profile in profiles {
+        let (history, depth) = match profile.history {
+            QoSHistoryPolicy::SystemDefault { depth } => (0, depth),
+            QoSHistoryPolicy::KeepLast { depth } => (1, depth),
+            QoSHistoryPolicy::KeepAll => (2, 0),
+        };
+        // Writing to a String cannot fail.
+        let _ = writeln!(yaml, "- history: {}", history);
+        let _ = writeln!(yaml, "  depth: {}", depth);
+        let _ = writeln!(yaml, "  reliability: {}", profile.reliability as u8);
+        let _ = writeln!(yaml, "  durability: {}", profile.durability as u8);
+        write_duration(&mut yaml, "deadline", profile.deadline);
+        write_duration(&mut yaml, "lifespan", profile.lifespan);
+        let _ = writeln!(yaml, "  liveliness: {}", profile.liveliness as u8);
+        write_duration(
+            &mut yaml,
+            "liveliness_lease_duration",
+            profile.liveliness_lease,
+        );
+        let _ = writeln!(
+            yaml,
+            "  avoid_ros_namespace_conventions: {}",
+            profile.avoid_ros_namespace_conventions
+        );
+    }
+    yaml
+}
+
+fn write_duration(yaml: &mut String, key: &str, duration: QoSDuration) {
+    let (sec, nsec) = match duration {
+        QoSDuration::SystemDefault => (0, 0),
+        QoSDuration::Infinite => (INFINITE_SEC, INFINITE_NSEC),
+        QoSDuration::Custom(duration) => (duration.as_secs(), duration.subsec_nanos()),
+    };
+    let _ = writeln!(yaml, "  {}:\n    sec: {}\n    nsec: {}", key, sec, nsec);
+}
+
+/// Parses the first QoS profile from the YAML list that rosbag2 stores in the channel metadata.
+///
+/// Only the flat form written by rosbag2 is supported, with either numbers or names as enum
+/// values. Settings that are missing or cannot be parsed keep the value of the `default` profile.
+pub(crate) fn qos_profile_from_yaml(yaml: &str, default: QoSProfile) -> QoSProfile {
+    let mut profile = default;
+    let mut depth = match default.history {
+        QoSHistoryPolicy::SystemDefault { depth } | QoSHistoryPolicy::KeepLast { depth } => depth,
+        QoSHistoryPolicy::KeepAll => 0,
+    };
+    let mut history = None;
+    let mut duration_key = "";
+    let mut duration = (0u64, 0u32);
+    for (index, line) in yaml.lines().enumerate() {
+        // Stop at the second profile.
+        if line.starts_with('-') && index > 0 {
+            break;
+        }
+        let line = line.trim_start_matches('-').trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key {
+            "history" => history = value.parse::<u8>().ok(),
+            "depth" => depth = value.parse().unwrap_or(depth),
+            "reliability" => {
+                profile.reliability = match value {
+                    "0" | "system_default" => QoSReliabilityPolicy::SystemDefault,
+                    "1" | "reliable" => QoSReliabilityPolicy::Reliable,
+                    "2" | "best_effort" => QoSReliabilityPolicy::BestEffort,
+                    _ => profile.reliability,
+                }
+            }
+            "durability" => {
+                profile.durability = match value {
+                    "0" | "system_default" => QoSDurabilityPolicy::SystemDefault,
+                    "1" | "transient_local" => QoSDurabilityPolicy::TransientLocal,
+                    "2" | "volatile" => QoSDurabilityPolicy::Volatile,
+                    _ => profile.durability,
+                }
+            }
+            "liveliness" => {
+                profile.liveliness = match value {
+                    "0" | "system_default" => QoSLivelinessPolicy::SystemDefault,
+                    "1" | "automatic" => QoSLivelinessPolicy::Automatic,
+                    "3" | "manual_by_topic" => QoSLivelinessPolicy::ManualByTopic,
+                    _ => profile.liveliness,
+                }
+            }
+            "avoid_ros_namespace_conventions" => {
+                profile.avoid_ros_namespace_conventions = value == "true"
+            }
+            "deadline" | "lifespan" | "liveliness_lease_duration" => duration_key = key,
+            "sec" => duration.0 = value.parse().unwrap_or_default(),
+            "nsec" => {
+                duration.1 = value.parse().unwrap_or_default();
+                let value = match duration {
+                    (0, 0) => QoSDuration::SystemDefault,
+                    (INFINITE_SEC, INFINITE_NSEC) => QoSDuration::Infinite,
+                    (sec, nsec) => QoSDuration::Custom(Duration::new(sec, nsec)),
+                };
+                match duration_key {
+                    "deadline" => profile.deadline = value,
+                    "lifespan" => profile.lifespan = value,
+                    "liveliness_lease_duration" => profile.liveliness_lease = value,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    profile.history = match history {
+        Some(0) => QoSHistoryPolicy::SystemDefault { depth },
+        Some(1) => QoSHistoryPolicy::KeepLast { depth },
+        Some(2) => QoSHistoryPolicy::KeepAll,
+        _ => profile.history,
+    };
+    profile
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qos_metadata_round_trip() {
+        let profile = QoSProfile::default()
+            .keep_last(7)
+            .best_effort()
+            .transient_local()
+            .deadline_duration(Duration::from_millis(1500));
+        let yaml = qos_profiles_to_yaml(&[profile, QoSProfile::default()]);
+        assert!(yaml.starts_with("- history: 1\n  depth: 7\n"));
+        assert_eq!(
+            qos_profile_from_yaml(&yaml, QoSProfile::sensor_data_default()),
+            profile
+        );
+        assert_eq!(
+            qos_profile_from_yaml("", QoSProfile::sensor_data_default()),
+            QoSProfile::sensor_data_default()
+        );
+    }
+}
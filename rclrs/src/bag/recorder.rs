@@ -0,0 +1,378 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{self, BufWriter},
+    path::Path,
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use super::{
+    mcap::{Channel, McapMessage, McapWriter, Schema},
+    message_definition::message_definition,
+    qos_metadata::{qos_profiles_to_yaml, OFFERED_QOS_PROFILES},
+    BagError, MCAP_PROFILE, MESSAGE_ENCODING, SCHEMA_ENCODING,
+};
+use crate::{
+    dynamic_message::GenericSubscription, Clock, IntoPrimitiveOptions, MessageInfo, Node,
+    NodeState, QoSProfile, SerializedMessage, Timer, TopicEndpointKind,
+};
+
+/// Options for a [`Recorder`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct RecorderOptions {
+    /// The fully qualified names of the topics to record, e.g. `/chatter`.
+    ///
+    /// If this is empty, all topics are recorded.
+    pub topics: Vec<String>,
+    /// The QoS profile of the subscriptions that receive the messages.
+    ///
+    /// To record topics that are published with a best effort reliability, such as sensor
+    /// data, use a best effort profile. The bag stores the QoS profiles of the publishers
+    /// of each topic instead, so that a [`Player`][1] offers the same QoS as they did.
+    ///
+    /// [1]: crate::bag::Player
+    pub qos: QoSProfile,
+    /// How often the recorder looks for new topics.
+    pub discovery_period: Duration,
+}
+
+impl Default for RecorderOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RecorderOptions {
+    /// Creates options that record all topics with the default QoS profile for topics.
+    pub fn new() -> Self {
+        Self {
+            topics: Vec::new(),
+            qos: QoSProfile::topics_default(),
+            discovery_period: Duration::from_secs(1),
+        }
+    }
+
+    /// Sets the topics to record.
+    pub fn topics(mut self, topics: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.topics = topics.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the QoS profile of the subscriptions.
+    pub fn qos(mut self, qos: QoSProfile) -> Self {
+        self.qos = qos;
+        self
+    }
+
+    /// Sets how often the recorder looks for new topics.
+    pub fn discovery_period(mut self, discovery_period: Duration) -> Self {
+        self.discovery_period = discovery_period;
+        self
+    }
+}
+
+/// Records topics to an MCAP file that can be read by rosbag2.
+///
+/// The recorder uses the node it was created with to discover topics and to subscribe to
+/// them, so the executor of the node must [spin][1] for messages to be recorded. Topics
+/// that appear after the recorder was created are discovered periodically.
+///
+/// Each message is stored with the time of the node's clock at which it was received,
+/// so a bag that is recorded while using sim time follows the simulated time.
+///
+/// The file is complete once [`Recorder::finish`] is called or the recorder is dropped.
+///
+/// ```no_run
+/// # use rclrs::{*, bag::*};
+/// # use std::time::Duration;
+/// # let mut executor = Context::default().create_basic_executor();
+/// # let node = executor.create_node("recorder").unwrap();
+/// let recorder = Recorder::new(
+///     &node,
+///     "my_bag.mcap",
+///     RecorderOptions::new().topics(["/chatter"]),
+/// )?;
+/// // Record for ten seconds.
+/// executor.spin(SpinOptions::default().timeout(Duration::from_secs(10)));
+/// recorder.finish()?;
+/// # Ok::<(), BagError>(())
+/// ```
+///
+/// [1]: crate::Executor::spin
+pub struct Recorder {
+    shared: Arc<RecorderShared>,
+    // Keeps discovering new topics while the recorder exists.
+    _discovery_timer: Timer,
+}
+
+struct RecorderShared {
+    writer: Mutex<BagWriter>,
+    subscriptions: Mutex<HashMap<String, GenericSubscription>>,
+    // The first error that happened while recording in a callback.
+    error: Mutex<Option<BagError>>,
+    node: Weak<NodeState>,
+    clock: Clock,
+    options: RecorderOptions,
+}
+
+struct BagWriter {
+    // This is `None` once the recording is finished.
+    mcap: Option<McapWriter<BufWriter<File>>>,
+    schemas: HashMap<String, u16>,
+    next_channel_id: u16,
+    sequences: HashMap<u16, u32>,
+    message_count: u64,
+}
+
+impl Recorder {
+    /// Creates the file at the given path and starts recording.
+    ///
+    /// If the file already exists, it is overwritten.
+    pub fn new(
+        node: &Node,
+        path: impl AsRef<Path>,
+        options: RecorderOptions,
+    ) -> Result<Self, BagError> {
+        let file = BufWriter::new(File::create(path)?);
+        let mcap = McapWriter::new(file, MCAP_PROFILE, "rclrs")?;
+        let discovery_period = options.discovery_period;
+        let shared = Arc::new(RecorderShared {
+            writer: Mutex::new(BagWriter {
+                mcap: Some(mcap),
+                schemas: HashMap::new(),
+                next_channel_id: 1,
+                sequences: HashMap::new(),
+                message_count: 0,
+            }),
+            subscriptions: Mutex::new(HashMap::new()),
+            error: Mutex::new(None),
+            node: Arc::downgrade(node),
+            clock: node.get_clock(),
+            options,
+        });
+        shared.discover_topics()?;
+
+        let discovery_timer = {
+            let shared = Arc::downgrade(&shared);
+            node.create_wall_timer(discovery_period, move |_| {
+                if let Some(shared) = shared.upgrade() {
+                    if let Err(err) = shared.discover_topics() {
+                        shared.set_error(err);
+                    }
+                }
+            })?
+        };
+        Ok(Self {
+            shared,
+            _discovery_timer: discovery_timer,
+        })
+    }
+
+    /// Looks for new topics right away, instead of waiting for the next periodic discovery.
+    pub fn discover_topics(&self) -> Result<(), BagError> {
+        self.shared.discover_topics()
+    }
+
+    /// Returns the topics that are being recorded, in alphabetical order.
+    pub fn topics(&self) -> Vec<String> {
+        let mut topics: Vec<_> = self
+            .shared
+            .subscriptions
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        topics.sort();
+        topics
+    }
+
+    /// Returns the number of messages that were recorded so far.
+    pub fn message_count(&self) -> u64 {
+        self.shared.writer.lock().unwrap().message_count
+    }
+
+    /// Stops recording and completes the file.
+    ///
+    /// This returns the first error that happened while recording, if any.
+    pub fn finish(self) -> Result<(), BagError> {
+        self.shared.finish()
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        // Errors can only be reported by calling finish() explicitly.
+        let _ = self.shared.finish();
+    }
+}
+
+impl RecorderShared {
+    fn discover_topics(self: &Arc<Self>) -> Result<(), BagError> {
+        let Some(node) = self.node.upgrade() else {
+            return Ok(());
+        };
+        for (topic, types) in node.get_topic_names_and_types()? {
+            if !self.options.topics.is_empty() && !self.options.topics.contains(&topic) {
+                continue;
+            }
+            if self.subscriptions.lock().unwrap().contains_key(&topic) {
+                continue;
+            }
+            // A topic with several types cannot be recorded consistently, so use the first.
+            let Some(topic_type) = types.first() else {
+                continue;
+            };
+            // The QoS profiles of the publishers are stored, so that a player can offer the
+            // same QoS as the original publishers.
+            let offered_qos: Vec<_> = node
+                .get_topic_endpoints(&topic, TopicEndpointKind::Publisher)?
+                .into_iter()
+                .map(|endpoint| endpoint.qos)
+                .collect();
+            // The writer stays locked until the channel is written, so that no message of the
+            // new subscription is recorded before its channel.
+            let mut writer = self.writer.lock().unwrap();
+            if writer.mcap.is_none() {
+                // The recording is finished.
+                return Ok(());
+            }
+            let channel_id = writer.reserve_channel_id();
+            // The subscription is owned by the shared state, so its callback must not keep
+            // the shared state alive.
+            let shared = Arc::downgrade(self);
+            // The subscription is created before the channel is written, so that a topic whose
+            // subscription fails does not leave a channel behind in the bag.
+            let subscription = node.create_generic_subscription(
+                topic_type,
+                topic.as_str().qos(self.options.qos),
+                move |msg: SerializedMessage, info: MessageInfo| {
+                    if let Some(shared) = shared.upgrade() {
+                        shared.record(channel_id, &msg, &info);
+                    }
+                },
+            )?;
+            writer.add_channel(channel_id, &topic, topic_type, &offered_qos)?;
+            drop(writer);
+            self.subscriptions
+                .lock()
+                .unwrap()
+                .insert(topic, subscription);
+        }
+        Ok(())
+    }
+
+    fn record(&self, channel_id: u16, msg: &SerializedMessage, info: &MessageInfo) {
+        let log_time = self.clock.now().nsec.max(0) as u64;
+        let publish_time = info
+            .source_timestamp
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|time| time.as_nanos() as u64)
+            .unwrap_or(log_time);
+        if let Err(err) =
+            self.writer
+                .lock()
+                .unwrap()
+                .write_message(channel_id, log_time, publish_time, msg)
+        {
+            self.set_error(err.into());
+        }
+    }
+
+    fn finish(&self) -> Result<(), BagError> {
+        // Stop receiving messages before completing the file.
+        self.subscriptions.lock().unwrap().clear();
+        if let Some(mut mcap) = self.writer.lock().unwrap().mcap.take() {
+            mcap.finish()?;
+        }
+        match self.error.lock().unwrap().take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    fn set_error(&self, err: BagError) {
+        self.error.lock().unwrap().get_or_insert(err);
+    }
+}
+
+impl BagWriter {
+    /// Returns the ID for the next channel.
+    fn reserve_channel_id(&mut self) -> u16 {
+        let channel_id = self.next_channel_id;
+        self.next_channel_id += 1;
+        channel_id
+    }
+
+    /// Writes the channel of a topic, and the schema of its type if it was not written yet.
+    fn add_channel(
+        &mut self,
+        channel_id: u16,
+        topic: &str,
+        topic_type: &str,
+        offered_qos: &[QoSProfile],
+    ) -> io::Result<()> {
+        let Some(mcap) = self.mcap.as_mut() else {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "The recording is already finished",
+            ));
+        };
+        let schema_id = match self.schemas.get(topic_type) {
+            Some(schema_id) => *schema_id,
+            None => {
+                let schema_id = self.schemas.len() as u16 + 1;
+                // rosbag2 stores an empty schema when the definition is not available.
+                let (encoding, data) = match message_definition(topic_type) {
+                    Some(definition) => (SCHEMA_ENCODING, definition.into_bytes()),
+                    None => ("", Vec::new()),
+                };
+                mcap.write_schema(&Schema {
+                    id: schema_id,
+                    name: topic_type.to_owned(),
+                    encoding: encoding.to_owned(),
+                    data,
+                })?;
+                self.schemas.insert(topic_type.to_owned(), schema_id);
+                schema_id
+            }
+        };
+        mcap.write_channel(&Channel {
+            id: channel_id,
+            schema_id,
+            topic: topic.to_owned(),
+            message_encoding: MESSAGE_ENCODING.to_owned(),
+            metadata: BTreeMap::from([(
+                OFFERED_QOS_PROFILES.to_owned(),
+                qos_profiles_to_yaml(offered_qos),
+            )]),
+        })?;
+        Ok(())
+    }
+
+    fn write_message(
+        &mut self,
+        channel_id: u16,
+        log_time: u64,
+        publish_time: u64,
+        msg: &SerializedMessage,
+    ) -> io::Result<()> {
+        // Messages that arrive while the recording is finished are dropped.
+        let Some(mcap) = self.mcap.as_mut() else {
+            return Ok(());
+        };
+        let sequence = self.sequences.entry(channel_id).or_default();
+        *sequence = sequence.wrapping_add(1);
+        mcap.write_message(&McapMessage {
+            channel_id,
+            sequence: *sequence,
+            log_time,
+            publish_time,
+            data: msg.to_vec(),
+        })?;
+        self.message_count += 1;
+        Ok(())
+    }
+}
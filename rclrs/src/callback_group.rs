@@ -0,0 +1,118 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Determines whether the callbacks of a [`CallbackGroup`] may run concurrently.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CallbackGroupType {
+    /// At most one callback of the group runs at any given time.
+    MutuallyExclusive,
+    /// Callbacks of the group may run in parallel, including several invocations of
+    /// the same callback.
+    Reentrant,
+}
+
+/// A group of callbacks that controls which callbacks a multi-threaded executor may
+/// run concurrently.
+///
+/// Create a callback group using [`Node::create_callback_group`][1], and assign
+/// primitives to it with [`IntoPrimitiveOptions::callback_group`][2]. Primitives that
+/// are not explicitly assigned to a group belong to the
+/// [default callback group][3] of their node, which is mutually exclusive.
+///
+/// The single-threaded [`Executor`][4] runs all callbacks one after another, so
+/// callback groups only make a difference for the [`MultiThreadedExecutor`][5].
+///
+/// [1]: crate::NodeState::create_callback_group
+/// [2]: crate::IntoPrimitiveOptions::callback_group
+/// [3]: crate::NodeState::default_callback_group
+/// [4]: crate::Executor
+/// [5]: crate::MultiThreadedExecutor
+pub type CallbackGroup = Arc<CallbackGroupState>;
+
+/// The inner state of a [`CallbackGroup`].
+#[derive(Debug)]
+pub struct CallbackGroupState {
+    group_type: CallbackGroupType,
+    // Whether a callback of a mutually exclusive group is currently running.
+    busy: AtomicBool,
+}
+
+impl CallbackGroupState {
+    pub(crate) fn new(group_type: CallbackGroupType) -> Self {
+        Self {
+            group_type,
+            busy: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the type of this callback group.
+    pub fn group_type(&self) -> CallbackGroupType {
+        self.group_type
+    }
+
+    /// Returns true if a callback of this group may be started right now.
+    pub(crate) fn can_be_taken_from(&self) -> bool {
+        match self.group_type {
+            CallbackGroupType::MutuallyExclusive => !self.busy.load(Ordering::Acquire),
+            CallbackGroupType::Reentrant => true,
+        }
+    }
+
+    /// Tries to reserve this group for running a callback.
+    ///
+    /// The group is released again when the returned guard is dropped.
+    pub(crate) fn try_acquire(self: &Arc<Self>) -> Option<CallbackGroupGuard> {
+        if self.group_type == CallbackGroupType::MutuallyExclusive
+            && self
+                .busy
+                .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                .is_err()
+        {
+            return None;
+        }
+        Some(CallbackGroupGuard {
+            group: Arc::clone(self),
+        })
+    }
+}
+
+/// Keeps a [`CallbackGroup`] reserved while one of its callbacks is running.
+pub(crate) struct CallbackGroupGuard {
+    group: CallbackGroup,
+}
+
+impl Drop for CallbackGroupGuard {
+    fn drop(&mut self) {
+        if self.group.group_type == CallbackGroupType::MutuallyExclusive {
+            self.group.busy.store(false, Ordering::Release);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mutually_exclusive_group_is_acquired_once() {
+        let group = Arc::new(CallbackGroupState::new(
+            CallbackGroupType::MutuallyExclusive,
+        ));
+        let guard = group.try_acquire().unwrap();
+        assert!(!group.can_be_taken_from());
+        assert!(group.try_acquire().is_none());
+        drop(guard);
+        assert!(group.can_be_taken_from());
+        assert!(group.try_acquire().is_some());
+    }
+
+    #[test]
+    fn reentrant_group_is_acquired_repeatedly() {
+        let group = Arc::new(CallbackGroupState::new(CallbackGroupType::Reentrant));
+        let _guard_1 = group.try_acquire().unwrap();
+        let _guard_2 = group.try_acquire().unwrap();
+        assert!(group.can_be_taken_from());
+    }
+}
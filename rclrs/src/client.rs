@@ -2,19 +2,28 @@ use std::{
     boxed::Box,
     collections::HashMap,
     ffi::CString,
+    future::Future,
     sync::{atomic::AtomicBool, Arc, Mutex, MutexGuard},
+    time::Duration,
 };
 
-use futures::channel::oneshot;
+use futures::{
+    channel::oneshot,
+    future::{select, Either},
+};
 use rosidl_runtime_rs::Message;
 
 use crate::{
     error::{RclReturnCode, ToResult},
     rcl_bindings::*,
+    wait_for_graph_condition, wait_for_graph_condition_async, CallbackGroup, Clock,
     IntoPrimitiveOptions, MessageCow, Node, NodeHandle, QoSProfile, RclrsError,
     ENTITY_LIFECYCLE_MUTEX,
 };
 
+#[cfg(not(ros_distro = "humble"))]
+use crate::ServiceIntrospectionState;
+
 // SAFETY: The functions accessing this type, including drop(), shouldn't care about the thread
 // they are running in. Therefore, this type can be safely sent to another thread.
 unsafe impl Send for rcl_client_t {}
@@ -27,6 +36,9 @@ unsafe impl Send for rcl_client_t {}
 pub struct ClientHandle {
     rcl_client: Mutex<rcl_client_t>,
     node_handle: Arc<NodeHandle>,
+    /// The clock that service introspection events are stamped with. rcl keeps a pointer
+    /// to it for as long as introspection is enabled, so it is kept alive here.
+    introspection_clock: Mutex<Option<Clock>>,
     pub(crate) in_use_by_wait_set: Arc<AtomicBool>,
 }
 
@@ -34,6 +46,38 @@ impl ClientHandle {
     pub(crate) fn lock(&self) -> MutexGuard<rcl_client_t> {
         self.rcl_client.lock().unwrap()
     }
+
+    /// Returns the clock of service introspection, if it is enabled.
+    ///
+    /// rcl reads this clock when sending requests and taking responses, so it must be
+    /// locked for those calls, after the client itself.
+    fn introspection_clock(&self) -> Option<Arc<Mutex<rcl_clock_t>>> {
+        self.introspection_clock
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|clock| Arc::clone(clock.get_rcl_clock()))
+    }
+
+    /// See [`ClientState::service_is_ready`].
+    pub(crate) fn service_is_ready(&self) -> Result<bool, RclrsError> {
+        let mut is_ready = false;
+        let client = &mut *self.rcl_client.lock().unwrap();
+        let node = &mut *self.node_handle.rcl_node.lock().unwrap();
+
+        unsafe {
+            // SAFETY both node and client are guaranteed to be valid here
+            // client is guaranteed to have been generated with node
+            rcl_service_server_is_available(node as *const _, client as *const _, &mut is_ready)
+        }
+        .ok()?;
+        Ok(is_ready)
+    }
+
+    /// See [`ClientState::wait_for_service`].
+    pub(crate) fn wait_for_service(&self, timeout: Option<Duration>) -> Result<bool, RclrsError> {
+        wait_for_graph_condition(&self.node_handle, timeout, || self.service_is_ready())
+    }
 }
 
 impl Drop for ClientHandle {
@@ -55,13 +99,29 @@ impl Drop for ClientHandle {
 pub trait ClientBase: Send + Sync {
     /// Internal function to get a reference to the `rcl` handle.
     fn handle(&self) -> &ClientHandle;
+    /// Returns the callback group that the callback belongs to.
+    fn callback_group(&self) -> &CallbackGroup;
     /// Tries to take a new response and run the callback or future with it.
     fn execute(&self) -> Result<(), RclrsError>;
 }
 
 type RequestValue<Response> = Box<dyn FnOnce(Response) + 'static + Send>;
 
-type RequestId = i64;
+/// The sequence number of a request sent by a [`Client`].
+pub type RequestId = i64;
+
+/// Removes a pending request of a `call_async` future when the future is dropped, e.g.
+/// because it timed out or was canceled.
+struct PendingRequestGuard<Response> {
+    futures: Arc<Mutex<HashMap<RequestId, oneshot::Sender<Response>>>>,
+    request_id: RequestId,
+}
+
+impl<Response> Drop for PendingRequestGuard<Response> {
+    fn drop(&mut self) {
+        self.futures.lock().unwrap().remove(&self.request_id);
+    }
+}
 
 /// Main class responsible for sending requests to a ROS service.
 ///
@@ -90,6 +150,7 @@ where
     pub(crate) handle: Arc<ClientHandle>,
     requests: Mutex<HashMap<RequestId, RequestValue<T::Response>>>,
     futures: Arc<Mutex<HashMap<RequestId, oneshot::Sender<T::Response>>>>,
+    callback_group: CallbackGroup,
     /// Ensure the parent node remains alive as long as the subscription is held.
     /// This implementation will change in the future.
     #[allow(unused)]
@@ -110,7 +171,11 @@ where
     where
         T: rosidl_runtime_rs::Service,
     {
-        let ClientOptions { service_name, qos } = options.into();
+        let ClientOptions {
+            service_name,
+            qos,
+            callback_group,
+        } = options.into();
         // SAFETY: Getting a zero-initialized value is always safe.
         let mut rcl_client = unsafe { rcl_get_zero_initialized_client() };
         let type_support = <T as rosidl_runtime_rs::Service>::get_type_support()
@@ -151,6 +216,7 @@ where
         let handle = Arc::new(ClientHandle {
             rcl_client: Mutex::new(rcl_client),
             node_handle: Arc::clone(&node.handle),
+            introspection_clock: Mutex::new(None),
             in_use_by_wait_set: Arc::new(AtomicBool::new(false)),
         });
 
@@ -160,6 +226,8 @@ where
             futures: Arc::new(Mutex::new(
                 HashMap::<RequestId, oneshot::Sender<T::Response>>::new(),
             )),
+            callback_group: callback_group
+                .unwrap_or_else(|| Arc::clone(node.default_callback_group())),
             node: Arc::clone(node),
         })
     }
@@ -176,28 +244,21 @@ where
     ///
     /// Hence, when a message will not be needed anymore after publishing, pass it by value.
     /// When a message will be needed again after publishing, pass it by reference, instead of cloning and passing by value.
+    ///
+    /// The returned ID can be passed to [`ClientState::cancel_request`] to stop waiting for
+    /// the response, e.g. when the service server is gone.
     pub fn async_send_request_with_callback<'a, M: MessageCow<'a, T::Request>, F>(
         &self,
         message: M,
         callback: F,
-    ) -> Result<(), RclrsError>
+    ) -> Result<RequestId, RclrsError>
     where
         F: FnOnce(T::Response) + 'static + Send,
     {
-        let rmw_message = T::Request::into_rmw_message(message.into_cow());
-        let mut sequence_number = -1;
-        unsafe {
-            // SAFETY: The request type is guaranteed to match the client type by the type system.
-            rcl_send_request(
-                &*self.handle.lock() as *const _,
-                rmw_message.as_ref() as *const <T::Request as Message>::RmwMsg as *mut _,
-                &mut sequence_number,
-            )
-        }
-        .ok()?;
+        let sequence_number = self.send_request(message)?;
         let requests = &mut *self.requests.lock().unwrap();
         requests.insert(sequence_number, Box::new(callback));
-        Ok(())
+        Ok(sequence_number)
     }
 
     /// Sends a request and returns the response as a `Future`.
@@ -212,6 +273,10 @@ where
     ///
     /// Hence, when a message will not be needed anymore after publishing, pass it by value.
     /// When a message will be needed again after publishing, pass it by reference, instead of cloning and passing by value.
+    ///
+    /// Dropping the future stops waiting for the response. If the pending request is
+    /// removed by [`ClientState::prune_pending_requests`], the future returns
+    /// [`RclrsError::RequestCanceled`].
     pub async fn call_async<'a, R: MessageCow<'a, T::Request>>(
         &self,
         request: R,
@@ -219,23 +284,100 @@ where
     where
         T: rosidl_runtime_rs::Service,
     {
-        let rmw_message = T::Request::into_rmw_message(request.into_cow());
+        let (receiver, _guard) = self.send_request_for_future(request)?;
+        // The sender is only dropped without sending when the request is canceled.
+        receiver.await.map_err(|_| RclrsError::RequestCanceled)
+    }
+
+    /// Sends a request and returns the response as a `Future`, or [`RclrsError::Timeout`]
+    /// if no response was received within the timeout.
+    ///
+    /// The timeout is measured with a steady clock. Once it elapses, the request is no
+    /// longer pending, so a response that arrives later is ignored.
+    ///
+    /// See [`ClientState::call_async`] for details.
+    pub async fn call_async_with_timeout<'a, R: MessageCow<'a, T::Request>>(
+        &self,
+        request: R,
+        timeout: Duration,
+    ) -> Result<T::Response, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+    {
+        let (receiver, _guard) = self.send_request_for_future(request)?;
+        let sleep = Box::pin(Clock::steady().sleep_for_async(timeout));
+        match select(receiver, sleep).await {
+            Either::Left((response, _)) => response.map_err(|_| RclrsError::RequestCanceled),
+            Either::Right(_) => Err(RclrsError::Timeout),
+        }
+    }
+
+    /// Stops waiting for the response of a request that was sent with
+    /// [`ClientState::async_send_request_with_callback`].
+    ///
+    /// Returns `false` if the request is not pending anymore, e.g. because its response
+    /// was already received.
+    pub fn cancel_request(&self, request_id: RequestId) -> bool {
+        self.requests.lock().unwrap().remove(&request_id).is_some()
+            || self.futures.lock().unwrap().remove(&request_id).is_some()
+    }
+
+    /// Stops waiting for the responses of all pending requests, and returns how many there
+    /// were.
+    ///
+    /// Futures returned by [`ClientState::call_async`] for these requests return
+    /// [`RclrsError::RequestCanceled`], and the callbacks of requests sent with
+    /// [`ClientState::async_send_request_with_callback`] are dropped without being called.
+    pub fn prune_pending_requests(&self) -> usize {
+        let mut requests = self.requests.lock().unwrap();
+        let mut futures = self.futures.lock().unwrap();
+        let count = requests.len() + futures.len();
+        requests.clear();
+        futures.clear();
+        count
+    }
+
+    fn send_request<'a, M: MessageCow<'a, T::Request>>(
+        &self,
+        message: M,
+    ) -> Result<RequestId, RclrsError> {
+        let rmw_message = T::Request::into_rmw_message(message.into_cow());
         let mut sequence_number = -1;
+        let handle = &*self.handle.lock();
+        let introspection_clock = self.handle.introspection_clock();
+        let _introspection_clock = introspection_clock
+            .as_ref()
+            .map(|clock| clock.lock().unwrap());
         unsafe {
             // SAFETY: The request type is guaranteed to match the client type by the type system.
             rcl_send_request(
-                &*self.handle.lock() as *const _,
+                handle as *const _,
                 rmw_message.as_ref() as *const <T::Request as Message>::RmwMsg as *mut _,
                 &mut sequence_number,
             )
         }
         .ok()?;
-        let (tx, rx) = oneshot::channel::<T::Response>();
-        self.futures.lock().unwrap().insert(sequence_number, tx);
-        // It is safe to call unwrap() here since the `Canceled` error will only happen when the
-        // `Sender` is dropped
-        // https://docs.rs/futures/latest/futures/channel/oneshot/struct.Canceled.html
-        Ok(rx.await.unwrap())
+        Ok(sequence_number)
+    }
+
+    fn send_request_for_future<'a, M: MessageCow<'a, T::Request>>(
+        &self,
+        message: M,
+    ) -> Result<
+        (
+            oneshot::Receiver<T::Response>,
+            PendingRequestGuard<T::Response>,
+        ),
+        RclrsError,
+    > {
+        let sequence_number = self.send_request(message)?;
+        let (sender, receiver) = oneshot::channel::<T::Response>();
+        self.futures.lock().unwrap().insert(sequence_number, sender);
+        let guard = PendingRequestGuard {
+            futures: Arc::clone(&self.futures),
+            request_id: sequence_number,
+        };
+        Ok((receiver, guard))
     }
 
     /// Fetches a new response.
@@ -269,6 +411,10 @@ where
             <<T as rosidl_runtime_rs::Service>::Response as rosidl_runtime_rs::Message>::RmwMsg;
         let mut response_out = RmwMsg::<T>::default();
         let handle = &*self.handle.lock();
+        let introspection_clock = self.handle.introspection_clock();
+        let _introspection_clock = introspection_clock
+            .as_ref()
+            .map(|clock| clock.lock().unwrap());
         unsafe {
             // SAFETY: The three pointers are valid/initialized
             rcl_take_response(
@@ -286,17 +432,94 @@ where
     /// Will return true if there is a service server available, false if unavailable.
     ///
     pub fn service_is_ready(&self) -> Result<bool, RclrsError> {
-        let mut is_ready = false;
-        let client = &mut *self.handle.rcl_client.lock().unwrap();
-        let node = &mut *self.handle.node_handle.rcl_node.lock().unwrap();
+        self.handle.service_is_ready()
+    }
 
-        unsafe {
-            // SAFETY both node and client are guaranteed to be valid here
-            // client is guaranteed to have been generated with node
-            rcl_service_server_is_available(node as *const _, client as *const _, &mut is_ready)
+    /// Blocks until a service server is available.
+    ///
+    /// Returns `true` once a service server is available, and `false` if the timeout elapsed
+    /// or the context shut down before that. A timeout of `None` waits indefinitely.
+    ///
+    /// The availability is checked again whenever the ROS graph changes.
+    pub fn wait_for_service(&self, timeout: Option<Duration>) -> Result<bool, RclrsError> {
+        self.handle.wait_for_service(timeout)
+    }
+
+    /// Returns a future that completes once a service server is available.
+    ///
+    /// The availability is checked again whenever the executor of the client's node
+    /// notices that the ROS graph changed, so the executor must be [spinning][1] while
+    /// the future is awaited. The output is the same as for
+    /// [`ClientState::wait_for_service`].
+    ///
+    /// [1]: crate::Executor::spin
+    pub fn wait_for_service_async(
+        &self,
+        timeout: Option<Duration>,
+    ) -> impl Future<Output = Result<bool, RclrsError>> + Send + 'static {
+        let handle = Arc::clone(&self.handle);
+        wait_for_graph_condition_async(&self.node, timeout, move || handle.service_is_ready())
+    }
+
+    /// Configures [service introspection][1] for this client, which publishes an event
+    /// for every request and response it sends or receives.
+    ///
+    /// The events are published with the given QoS profile and are stamped with the
+    /// given clock, which is usually the clock of the node. The client keeps a clone of
+    /// the clock while introspection is enabled. Passing
+    /// [`ServiceIntrospectionState::Off`] stops publishing events.
+    ///
+    /// [1]: ServiceIntrospectionState
+    #[cfg(not(ros_distro = "humble"))]
+    pub fn configure_introspection(
+        &self,
+        clock: &Clock,
+        qos: QoSProfile,
+        state: ServiceIntrospectionState,
+    ) -> Result<(), RclrsError> {
+        let type_support = <T as rosidl_runtime_rs::Service>::get_type_support()
+            as *const rosidl_service_type_support_t;
+        let mut rcl_client = self.handle.lock();
+        let mut introspection_clock = self.handle.introspection_clock.lock().unwrap();
+        let mut rcl_node = self.handle.node_handle.rcl_node.lock().unwrap();
+        let mut configure = |state: ServiceIntrospectionState| {
+            // SAFETY: No preconditions for this function.
+            let mut publisher_options = unsafe { rcl_publisher_get_default_options() };
+            publisher_options.qos = qos.into();
+            let mut rcl_clock = clock.get_rcl_clock().lock().unwrap();
+            let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+            unsafe {
+                // SAFETY:
+                // * The client, node and clock are valid and locked for the duration of
+                //   this call.
+                // * rcl keeps using the clock afterwards, which is why a clone of it is kept
+                //   in the handle while introspection is enabled.
+                // * The type support matches the one that the client was created with.
+                // * The entity lifecycle mutex is locked, since this creates or destroys the
+                //   publisher of the service events.
+                rcl_client_configure_service_introspection(
+                    &mut *rcl_client,
+                    &mut *rcl_node,
+                    &mut *rcl_clock,
+                    type_support,
+                    publisher_options,
+                    state.into(),
+                )
+                .ok()
+            }
+        };
+        // rcl keeps the clock that introspection was enabled with until it is turned off,
+        // so it needs to be turned off to switch to a different clock.
+        let switches_clock = introspection_clock
+            .as_ref()
+            .is_some_and(|current| !Arc::ptr_eq(current.get_rcl_clock(), clock.get_rcl_clock()));
+        if switches_clock {
+            configure(ServiceIntrospectionState::Off)?;
+            *introspection_clock = None;
         }
-        .ok()?;
-        Ok(is_ready)
+        configure(state)?;
+        *introspection_clock = (state != ServiceIntrospectionState::Off).then(|| clock.clone());
+        Ok(())
     }
 }
 
@@ -311,6 +534,9 @@ pub struct ClientOptions<'a> {
     pub service_name: &'a str,
     /// The quality of the service profile for this client
     pub qos: QoSProfile,
+    /// The callback group of the client. If this is `None`, the default callback
+    /// group of the node is used.
+    pub callback_group: Option<CallbackGroup>,
 }
 
 impl<'a> ClientOptions<'a> {
@@ -319,6 +545,7 @@ impl<'a> ClientOptions<'a> {
         Self {
             service_name,
             qos: QoSProfile::services_default(),
+            callback_group: None,
         }
     }
 }
@@ -328,6 +555,7 @@ impl<'a, T: IntoPrimitiveOptions<'a>> From<T> for ClientOptions<'a> {
         let primitive = value.into_primitive_options();
         let mut options = Self::new(primitive.name);
         primitive.apply_to(&mut options.qos);
+        options.callback_group = primitive.callback_group.cloned();
         options
     }
 }
@@ -340,6 +568,10 @@ where
         &self.handle
     }
 
+    fn callback_group(&self) -> &CallbackGroup {
+        &self.callback_group
+    }
+
     fn execute(&self) -> Result<(), RclrsError> {
         let (res, req_id) = match self.take_response() {
             Ok((res, req_id)) => (res, req_id),
@@ -353,11 +585,19 @@ where
             }
             Err(e) => return Err(e),
         };
-        let requests = &mut *self.requests.lock().unwrap();
-        let futures = &mut *self.futures.lock().unwrap();
-        if let Some(callback) = requests.remove(&req_id.sequence_number) {
+        // The locks are released before running the callback, so that it can send or cancel
+        // requests itself.
+        let callback = self
+            .requests
+            .lock()
+            .unwrap()
+            .remove(&req_id.sequence_number);
+        if let Some(callback) = callback {
             callback(res);
-        } else if let Some(future) = futures.remove(&req_id.sequence_number) {
+            return Ok(());
+        }
+        let future = self.futures.lock().unwrap().remove(&req_id.sequence_number);
+        if let Some(future) = future {
             let _ = future.send(res);
         }
         Ok(())
@@ -397,4 +637,83 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_client_wait_for_service_async() -> Result<(), RclrsError> {
+        use crate::*;
+        use std::time::Instant;
+
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_client_wait_async_{}", line!()))?;
+        let service_name = format!("test_client_wait_async_service_{}", line!());
+        let client = node.create_client::<srv::Empty>(&service_name)?;
+
+        let available = Arc::new(Mutex::new(None));
+        node.spawn({
+            let available = Arc::clone(&available);
+            async move {
+                let result = client
+                    .wait_for_service_async(Some(Duration::from_secs(10)))
+                    .await;
+                *available.lock().unwrap() = Some(result);
+            }
+        });
+        executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        assert!(available.lock().unwrap().is_none());
+
+        let _service = node.create_service::<srv::Empty, _>(&service_name, |_, request| request)?;
+        let start = Instant::now();
+        while available.lock().unwrap().is_none() {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+        assert_eq!(available.lock().unwrap().take(), Some(Ok(true)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_client_wait_for_service_and_timeouts() -> Result<(), RclrsError> {
+        use crate::*;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_client_timeouts_{}", line!()))?;
+        let service_name = format!("test_client_timeouts_service_{}", line!());
+        let client = node.create_client::<srv::Empty>(&service_name)?;
+
+        assert!(!client.wait_for_service(Some(Duration::from_millis(50)))?);
+        let result = futures::executor::block_on(
+            client
+                .call_async_with_timeout(srv::Empty_Request::default(), Duration::from_millis(50)),
+        );
+        assert_eq!(result, Err(RclrsError::Timeout));
+        assert_eq!(client.prune_pending_requests(), 0);
+
+        let request_id =
+            client.async_send_request_with_callback(srv::Empty_Request::default(), |_| {})?;
+        assert!(client.cancel_request(request_id));
+        assert!(!client.cancel_request(request_id));
+
+        let _service = node.create_service::<srv::Empty, _>(&service_name, |_, request| request)?;
+        assert!(client.wait_for_service(Some(Duration::from_secs(10)))?);
+
+        let done = Arc::new(AtomicBool::new(false));
+        let done_inner = Arc::clone(&done);
+        node.spawn(async move {
+            client
+                .call_async_with_timeout(srv::Empty_Request::default(), Duration::from_secs(10))
+                .await
+                .unwrap();
+            done_inner.store(true, Ordering::Release);
+        });
+        for _ in 0..1000 {
+            if done.load(Ordering::Acquire) {
+                break;
+            }
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+        assert!(done.load(Ordering::Acquire));
+
+        Ok(())
+    }
 }
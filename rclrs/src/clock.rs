@@ -1,5 +1,16 @@
-use crate::{error::ToResult, rcl_bindings::*, time::Time, to_rclrs_result};
-use std::sync::{Arc, Mutex};
+use crate::{
+    error::ToResult, rcl_bindings::*, time::Time, to_rclrs_result, ContextHandle, RclrsError,
+    TaskQueue, Timer,
+};
+use std::{
+    future::Future,
+    os::raw::c_void,
+    pin::Pin,
+    ptr::NonNull,
+    sync::{Arc, Condvar, Mutex, Weak},
+    task::{Context as TaskContext, Poll, Waker},
+    time::Duration,
+};
 
 /// Enum to describe clock type. Redefined for readability and to eliminate the uninitialized case
 /// from the `rcl_clock_type_t` enum in the binding.
@@ -29,7 +40,291 @@ impl From<ClockType> for rcl_clock_type_t {
 pub struct Clock {
     kind: ClockType,
     rcl_clock: Arc<Mutex<rcl_clock_t>>,
-    // TODO(luca) Implement jump callbacks
+    // The context of the node that the clock belongs to, if any. Sleeping is interrupted
+    // when it shuts down.
+    context: Option<Weak<ContextHandle>>,
+}
+
+/// The kind of change of a clock that caused a [`TimeJump`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockChange {
+    /// The time of a ROS clock jumped, but its source did not change.
+    RosTimeNoChange,
+    /// A ROS clock switched from system time to ROS time, e.g. because `use_sim_time` was
+    /// enabled.
+    RosTimeActivated,
+    /// A ROS clock switched from ROS time back to system time.
+    RosTimeDeactivated,
+    /// The time of a system clock jumped.
+    SystemTimeNoChange,
+}
+
+impl From<rcl_clock_change_t> for ClockChange {
+    fn from(clock_change: rcl_clock_change_t) -> Self {
+        match clock_change {
+            rcl_clock_change_t::RCL_ROS_TIME_NO_CHANGE => ClockChange::RosTimeNoChange,
+            rcl_clock_change_t::RCL_ROS_TIME_ACTIVATED => ClockChange::RosTimeActivated,
+            rcl_clock_change_t::RCL_ROS_TIME_DEACTIVATED => ClockChange::RosTimeDeactivated,
+            rcl_clock_change_t::RCL_SYSTEM_TIME_NO_CHANGE => ClockChange::SystemTimeNoChange,
+        }
+    }
+}
+
+/// A jump of the time of a clock, as passed to the callbacks registered with
+/// [`Clock::add_jump_callback`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeJump {
+    /// The kind of change of the clock.
+    pub clock_change: ClockChange,
+    /// The time after the jump minus the time before the jump, in nanoseconds.
+    pub delta: i64,
+}
+
+/// Decides which time jumps of a clock trigger a jump callback.
+///
+/// The default threshold does not trigger on anything.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JumpThreshold {
+    /// Trigger when ROS time is activated or deactivated.
+    pub on_clock_change: bool,
+    /// Trigger when the time jumps forward by at least this duration.
+    pub min_forward: Option<Duration>,
+    /// Trigger when the time jumps backward by at least this duration.
+    pub min_backward: Option<Duration>,
+}
+
+impl From<JumpThreshold> for rcl_jump_threshold_t {
+    fn from(threshold: JumpThreshold) -> Self {
+        // A duration of zero disables the threshold.
+        let nanoseconds = |duration: Option<Duration>| {
+            duration.map_or(0, |d| d.as_nanos().min(i64::MAX as u128) as i64)
+        };
+        rcl_jump_threshold_t {
+            on_clock_change: threshold.on_clock_change,
+            min_forward: rcl_duration_t {
+                nanoseconds: nanoseconds(threshold.min_forward),
+            },
+            min_backward: rcl_duration_t {
+                nanoseconds: -nanoseconds(threshold.min_backward),
+            },
+        }
+    }
+}
+
+type JumpCallback = Box<dyn FnMut(&TimeJump) + Send>;
+
+struct JumpCallbacks {
+    pre_jump: JumpCallback,
+    post_jump: JumpCallback,
+}
+
+/// Keeps a jump callback of a [`Clock`] registered.
+///
+/// The callback is unregistered when this handle is dropped.
+#[must_use = "the callback is unregistered when the handle is dropped"]
+pub struct JumpCallbackHandle {
+    rcl_clock: Weak<Mutex<rcl_clock_t>>,
+    callbacks: NonNull<JumpCallbacks>,
+}
+
+// SAFETY: The callbacks are Send, and they are only accessed by rcl while the mutex of the
+// clock is locked, or when the handle is dropped.
+unsafe impl Send for JumpCallbackHandle {}
+
+impl std::fmt::Debug for JumpCallbackHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JumpCallbackHandle").finish_non_exhaustive()
+    }
+}
+
+impl Drop for JumpCallbackHandle {
+    fn drop(&mut self) {
+        if let Some(rcl_clock) = self.rcl_clock.upgrade() {
+            let mut clock = rcl_clock.lock().unwrap();
+            // SAFETY: The clock is locked, and the callback was registered with exactly this
+            // function and user data. The function only fails if the callback is not
+            // registered, which cannot happen.
+            unsafe {
+                rcl_clock_remove_jump_callback(
+                    &mut *clock,
+                    Some(jump_callback),
+                    self.callbacks.as_ptr() as *mut c_void,
+                );
+            }
+        }
+        // SAFETY: The callbacks were created with Box::into_raw, and rcl no longer has a
+        // pointer to them, either because they were removed above or because the clock was
+        // finalized.
+        drop(unsafe { Box::from_raw(self.callbacks.as_ptr()) });
+    }
+}
+
+unsafe extern "C" fn jump_callback(
+    time_jump: *const rcl_time_jump_t,
+    before_jump: bool,
+    user_data: *mut c_void,
+) {
+    // SAFETY: rcl only calls this function with the user data that was registered with it,
+    // while the mutex of the clock is locked, so no other reference to the callbacks exists.
+    let callbacks = unsafe { &mut *(user_data as *mut JumpCallbacks) };
+    // SAFETY: rcl passes a valid time jump.
+    let time_jump = unsafe { &*time_jump };
+    let time_jump = TimeJump {
+        clock_change: time_jump.clock_change.into(),
+        delta: time_jump.delta.nanoseconds,
+    };
+    if before_jump {
+        (callbacks.pre_jump)(&time_jump);
+    } else {
+        (callbacks.post_jump)(&time_jump);
+    }
+}
+
+/// The longest time that a sleeping thread waits before checking if it should stop sleeping.
+const SLEEP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Any change of ROS time wakes up a sleep.
+const SLEEP_JUMP_THRESHOLD: JumpThreshold = JumpThreshold {
+    on_clock_change: true,
+    min_forward: Some(Duration::from_nanos(1)),
+    min_backward: Some(Duration::from_nanos(1)),
+};
+
+#[derive(Default)]
+struct SleepState {
+    // The number of time jumps since the sleep started.
+    jumps: usize,
+    clock_changed: bool,
+    // The waker of the task that awaits an async sleep.
+    waker: Option<Waker>,
+}
+
+impl SleepState {
+    fn on_jump(&mut self, jump: &TimeJump) {
+        self.jumps += 1;
+        self.clock_changed |= matches!(
+            jump.clock_change,
+            ClockChange::RosTimeActivated | ClockChange::RosTimeDeactivated
+        );
+    }
+}
+
+/// A future that completes when the time of a clock reaches a given point.
+///
+/// Returned by [`Clock::sleep_until_async`] and related functions. Instead of polling the
+/// clock, the future is woken up by a jump callback of the clock when ROS time changes,
+/// and by a timer when the clock follows the system time.
+pub(crate) struct ClockSleep {
+    clock: Clock,
+    until: i64,
+    state: Arc<Mutex<SleepState>>,
+    _jump_callback: Option<JumpCallbackHandle>,
+    wakeup: Option<ScheduledWakeup>,
+}
+
+/// Wakes up a [`ClockSleep`] once the clock should have reached the end of the sleep.
+struct ScheduledWakeup {
+    // The number of time jumps when the wakeup was scheduled.
+    jumps: usize,
+    // The timer is `None` when a helper thread wakes up the sleep instead.
+    _timer: Option<Timer>,
+}
+
+impl ClockSleep {
+    fn new(clock: Clock, until: i64) -> Self {
+        let state = Arc::new(Mutex::new(SleepState::default()));
+        let on_jump = {
+            let state = Arc::clone(&state);
+            move |jump: &TimeJump| {
+                let waker = {
+                    let mut state = state.lock().unwrap();
+                    state.on_jump(jump);
+                    state.waker.clone()
+                };
+                if let Some(waker) = waker {
+                    waker.wake();
+                }
+            }
+        };
+        // The callback is registered right away, so that a clock change before the first
+        // poll still interrupts the sleep. Registering only fails for invalid arguments.
+        let jump_callback = clock
+            .add_jump_callback(SLEEP_JUMP_THRESHOLD, |_| {}, on_jump)
+            .ok();
+        Self {
+            clock,
+            until,
+            state,
+            _jump_callback: jump_callback,
+            wakeup: None,
+        }
+    }
+
+    fn schedule_wakeup(&self, delay: Duration) -> Option<Timer> {
+        let wake = {
+            let state = Arc::clone(&self.state);
+            move || {
+                let waker = state.lock().unwrap().waker.clone();
+                if let Some(waker) = waker {
+                    waker.wake();
+                }
+            }
+        };
+        if let Some(queue) = TaskQueue::current() {
+            // Creating a timer only fails if the context is invalid, in which case the
+            // executor does not run anymore anyway.
+            return queue
+                .create_wakeup_timer(self.clock.clone(), delay, wake)
+                .ok();
+        }
+        // The future is not polled by an executor of rclrs, so there is no wait set that
+        // could contain a timer.
+        std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            wake();
+        });
+        None
+    }
+}
+
+impl Future for ClockSleep {
+    type Output = bool;
+
+    fn poll(self: Pin<&mut Self>, task_context: &mut TaskContext<'_>) -> Poll<bool> {
+        let this = self.get_mut();
+        let jumps = {
+            let mut state = this.state.lock().unwrap();
+            if state.clock_changed {
+                return Poll::Ready(false);
+            }
+            state.waker = Some(task_context.waker().clone());
+            state.jumps
+        };
+        if !this.clock.context_is_ok() {
+            return Poll::Ready(false);
+        }
+        // The clock must not be accessed while the sleep state is locked, since the jump
+        // callback locks the sleep state while the clock is locked.
+        let now = this.clock.now().nsec;
+        if now >= this.until {
+            return Poll::Ready(true);
+        }
+        if this.clock.ros_time_is_active() {
+            // The time only advances when the clock jumps, which wakes up the future.
+            this.wakeup = None;
+        } else if this
+            .wakeup
+            .as_ref()
+            .map_or(true, |wakeup| wakeup.jumps != jumps)
+        {
+            let delay = Duration::from_nanos((this.until - now) as u64);
+            this.wakeup = Some(ScheduledWakeup {
+                jumps,
+                _timer: this.schedule_wakeup(delay),
+            });
+        }
+        Poll::Pending
+    }
 }
 
 /// A clock source that can be used to drive the contained clock. Created when a clock of type
@@ -80,9 +375,16 @@ impl Clock {
         Self {
             kind,
             rcl_clock: Arc::new(Mutex::new(rcl_clock)),
+            context: None,
         }
     }
 
+    /// Makes sleeping on this clock stop when the given context shuts down.
+    pub(crate) fn with_context(mut self, context: &Arc<ContextHandle>) -> Self {
+        self.context = Some(Arc::downgrade(context));
+        self
+    }
+
     /// Returns the clock's `ClockType`.
     pub fn clock_type(&self) -> ClockType {
         self.kind
@@ -102,6 +404,216 @@ impl Clock {
         }
     }
 
+    /// Registers callbacks that are called right before and right after the time of this
+    /// clock jumps by more than the given `threshold`.
+    ///
+    /// This allows resetting state that depends on the time, e.g. when a rosbag that provides
+    /// the simulated time loops, or when `use_sim_time` is toggled.
+    ///
+    /// The callbacks are run by whoever changes the time of the clock, while the clock is
+    /// locked, so they must not access this clock. The callbacks stay registered until the
+    /// returned handle is dropped.
+    ///
+    /// ```
+    /// # use rclrs::*;
+    /// # use std::time::Duration;
+    /// let (clock, source) = Clock::with_source();
+    /// let threshold = JumpThreshold {
+    ///     min_backward: Some(Duration::from_secs(1)),
+    ///     ..Default::default()
+    /// };
+    /// let _handle = clock.add_jump_callback(
+    ///     threshold,
+    ///     |_| {},
+    ///     |jump| println!("The time jumped back by {} ns", -jump.delta),
+    /// )?;
+    /// source.set_ros_time_override(10_000_000_000);
+    /// // Calls the post-jump callback
+    /// source.set_ros_time_override(0);
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn add_jump_callback(
+        &self,
+        threshold: JumpThreshold,
+        pre_jump: impl FnMut(&TimeJump) + Send + 'static,
+        post_jump: impl FnMut(&TimeJump) + Send + 'static,
+    ) -> Result<JumpCallbackHandle, RclrsError> {
+        let callbacks = Box::into_raw(Box::new(JumpCallbacks {
+            pre_jump: Box::new(pre_jump),
+            post_jump: Box::new(post_jump),
+        }));
+        let mut clock = self.rcl_clock.lock().unwrap();
+        // SAFETY: The clock is locked, and the callbacks stay valid until they are removed
+        // when the handle is dropped.
+        let result = unsafe {
+            rcl_clock_add_jump_callback(
+                &mut *clock,
+                threshold.into(),
+                Some(jump_callback),
+                callbacks as *mut c_void,
+            )
+        }
+        .ok();
+        if let Err(err) = result {
+            // SAFETY: The callbacks were not registered, so nothing else points to them.
+            drop(unsafe { Box::from_raw(callbacks) });
+            return Err(err);
+        }
+        Ok(JumpCallbackHandle {
+            rcl_clock: Arc::downgrade(&self.rcl_clock),
+            // The pointer comes from a Box, so it is not null.
+            callbacks: NonNull::new(callbacks).unwrap(),
+        })
+    }
+
+    /// Blocks the current thread until the time of this clock reaches `until`.
+    ///
+    /// When ROS time is active, e.g. because the clock belongs to a node that uses sim time,
+    /// this waits until the ROS time has advanced far enough. Jumps back in time extend the
+    /// sleep accordingly.
+    ///
+    /// Returns `true` when the time was reached, and `false` when the sleep was interrupted
+    /// because ROS time was activated or deactivated, or because the context of the node that
+    /// the clock belongs to has shut down. Also returns `false` right away if `until` was not
+    /// created by this clock.
+    ///
+    /// ```
+    /// # use rclrs::*;
+    /// # use std::time::Duration;
+    /// let clock = Clock::steady();
+    /// let until = clock.now() + Duration::from_millis(10);
+    /// assert!(clock.sleep_until(until));
+    /// ```
+    pub fn sleep_until(&self, until: Time) -> bool {
+        if !until.clock.ptr_eq(&Arc::downgrade(&self.rcl_clock)) {
+            return false;
+        }
+        self.sleep_until_nsec(until.nsec)
+    }
+
+    /// Blocks the current thread until the given duration has passed according to this clock.
+    ///
+    /// See [`Clock::sleep_until`] for details.
+    pub fn sleep_for(&self, duration: Duration) -> bool {
+        self.sleep_until(self.now() + duration)
+    }
+
+    /// Returns a future that completes when the time of this clock reaches `until`.
+    ///
+    /// Awaiting the future does not block the executor. When the future runs on the
+    /// executor of a node, e.g. through [`NodeState::spawn`][1], it is woken up by a timer
+    /// of that node. Otherwise, a helper thread wakes it up. The output is the same as
+    /// for [`Clock::sleep_until`].
+    ///
+    /// [1]: crate::NodeState::spawn
+    pub fn sleep_until_async(&self, until: Time) -> impl Future<Output = bool> + Send + 'static {
+        let sleep = until
+            .clock
+            .ptr_eq(&Arc::downgrade(&self.rcl_clock))
+            .then(|| self.sleep_until_nsec_async(until.nsec));
+        async move {
+            match sleep {
+                Some(sleep) => sleep.await,
+                None => false,
+            }
+        }
+    }
+
+    /// Returns a future that completes when the given duration has passed according to this
+    /// clock.
+    ///
+    /// See [`Clock::sleep_until_async`] for details.
+    pub fn sleep_for_async(
+        &self,
+        duration: Duration,
+    ) -> impl Future<Output = bool> + Send + 'static {
+        self.sleep_until_async(self.now() + duration)
+    }
+
+    pub(crate) fn sleep_until_nsec_async(&self, until: i64) -> ClockSleep {
+        ClockSleep::new(self.clone(), until)
+    }
+
+    pub(crate) fn sleep_until_nsec(&self, until: i64) -> bool {
+        let wakeup = Arc::new((Mutex::new(SleepState::default()), Condvar::new()));
+        let on_jump = {
+            let wakeup = Arc::clone(&wakeup);
+            move |jump: &TimeJump| {
+                let (state, condvar) = &*wakeup;
+                state.lock().unwrap().on_jump(jump);
+                condvar.notify_all();
+            }
+        };
+        // Registering the callback only fails for invalid arguments. Without it, the time is
+        // still checked periodically.
+        let _handle = self
+            .add_jump_callback(SLEEP_JUMP_THRESHOLD, |_| {}, on_jump)
+            .ok();
+
+        let (state, condvar) = &*wakeup;
+        loop {
+            let jumps = {
+                let state = state.lock().unwrap();
+                if state.clock_changed {
+                    return false;
+                }
+                state.jumps
+            };
+            if !self.context_is_ok() {
+                return false;
+            }
+            // The clock must not be accessed while the sleep state is locked, since the jump
+            // callback locks the sleep state while the clock is locked.
+            let now = self.now().nsec;
+            if now >= until {
+                return true;
+            }
+            let timeout = if self.ros_time_is_active() {
+                // The time only advances when the clock jumps.
+                SLEEP_POLL_INTERVAL
+            } else {
+                Duration::from_nanos((until - now) as u64).min(SLEEP_POLL_INTERVAL)
+            };
+            let state = state.lock().unwrap();
+            // Don't wait if the clock jumped since the time was checked.
+            if state.jumps == jumps {
+                drop(condvar.wait_timeout(state, timeout).unwrap());
+            }
+        }
+    }
+
+    fn context_is_ok(&self) -> bool {
+        self.context.as_ref().map_or(true, |context| {
+            context.upgrade().is_some_and(|context| context.ok())
+        })
+    }
+
+    fn ros_time_is_active(&self) -> bool {
+        if !matches!(self.kind, ClockType::RosTime) {
+            return false;
+        }
+        let mut clock = self.rcl_clock.lock().unwrap();
+        let mut is_enabled = false;
+        // SAFETY: The clock is locked, and it is a ROS clock. The function cannot fail for a
+        // valid ROS clock.
+        unsafe {
+            rcl_is_enabled_ros_time_override(&mut *clock, &mut is_enabled);
+        }
+        is_enabled
+    }
+
+    /// Creates a source for this clock, which enables ROS time until it is dropped.
+    ///
+    /// Returns `None` if this is not a `ClockType::RosTime` clock.
+    pub(crate) fn create_source(&self) -> Option<ClockSource> {
+        matches!(self.kind, ClockType::RosTime).then(|| ClockSource::new(self.rcl_clock.clone()))
+    }
+
+    /// Returns the `rcl_clock_t` wrapped by this clock, e.g. to attach a timer to it.
+    pub(crate) fn get_rcl_clock(&self) -> &Arc<Mutex<rcl_clock_t>> {
+        &self.rcl_clock
+    }
+
     /// Helper function to privately initialize a default clock, with the same behavior as
     /// `rcl_init_generic_clock`. By defining a private function instead of implementing
     /// `Default`,  we avoid exposing a public API to create an invalid clock.
@@ -218,4 +730,122 @@ mod tests {
         // Ros time is set, should return the value that was set
         assert_eq!(clock.now().nsec, set_time);
     }
+
+    #[test]
+    fn clock_jump_callbacks() {
+        let (clock, source) = Clock::with_source();
+        source.set_ros_time_override(1_000_000_000);
+
+        let pre_jumps = Arc::new(Mutex::new(Vec::new()));
+        let post_jumps = Arc::new(Mutex::new(Vec::new()));
+        let threshold = JumpThreshold {
+            on_clock_change: true,
+            min_forward: Some(Duration::from_secs(1)),
+            min_backward: Some(Duration::from_nanos(1)),
+        };
+        let handle = clock
+            .add_jump_callback(
+                threshold,
+                {
+                    let pre_jumps = Arc::clone(&pre_jumps);
+                    move |jump| pre_jumps.lock().unwrap().push(*jump)
+                },
+                {
+                    let post_jumps = Arc::clone(&post_jumps);
+                    move |jump| post_jumps.lock().unwrap().push(*jump)
+                },
+            )
+            .unwrap();
+
+        // Below the forward threshold
+        source.set_ros_time_override(1_500_000_000);
+        assert!(post_jumps.lock().unwrap().is_empty());
+        // A jump back, like when a rosbag loops
+        source.set_ros_time_override(500_000_000);
+        // Deactivating ROS time is a clock change
+        drop(source);
+        assert_eq!(pre_jumps.lock().unwrap().len(), 2);
+        let jumps = post_jumps.lock().unwrap().clone();
+        assert_eq!(jumps.len(), 2);
+        assert_eq!(jumps[0].clock_change, ClockChange::RosTimeNoChange);
+        assert_eq!(jumps[0].delta, -1_000_000_000);
+        assert_eq!(jumps[1].clock_change, ClockChange::RosTimeDeactivated);
+
+        // The callbacks are removed together with the handle
+        drop(handle);
+        let _source = clock.create_source().unwrap();
+        assert_eq!(post_jumps.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn clock_sleep_for_steady_time() {
+        let clock = Clock::steady();
+        let start = std::time::Instant::now();
+        assert!(clock.sleep_for(Duration::from_millis(20)));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert!(futures::executor::block_on(
+            clock.sleep_for_async(Duration::from_millis(20))
+        ));
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[test]
+    fn clock_sleep_async_on_executor() -> Result<(), RclrsError> {
+        use crate::{Context, SpinOptions};
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("clock_sleep_async_{}", line!()))?;
+        let clock = Clock::steady();
+        let reached = Arc::new(AtomicBool::new(false));
+        node.spawn({
+            let reached = Arc::clone(&reached);
+            async move {
+                let result = clock.sleep_for_async(Duration::from_millis(20)).await;
+                reached.store(result, Ordering::Release);
+            }
+        });
+
+        let start = std::time::Instant::now();
+        while !reached.load(Ordering::Acquire) {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            for err in executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(100))) {
+                assert!(err.is_timeout(), "{err}");
+            }
+        }
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        // The wakeup timer is dropped together with the sleep.
+        assert!(node.task_queue.live_timers().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn clock_sleep_until_ros_time() {
+        let (clock, source) = Clock::with_source();
+        let until = clock.now() + Duration::from_secs(10);
+        let sleeper = std::thread::spawn(move || clock.sleep_until(until));
+        // A jump back in time does not wake up the sleeper
+        source.set_ros_time_override(5_000_000_000);
+        source.set_ros_time_override(1_000_000_000);
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(!sleeper.is_finished());
+        source.set_ros_time_override(10_000_000_000);
+        assert!(sleeper.join().unwrap());
+    }
+
+    #[test]
+    fn clock_sleep_interrupted_by_clock_change() {
+        let (clock, source) = Clock::with_source();
+        let sleep = clock.sleep_for_async(Duration::from_secs(10));
+        std::thread::sleep(Duration::from_millis(10));
+        drop(source);
+        assert!(!futures::executor::block_on(sleep));
+    }
+
+    #[test]
+    fn clock_sleep_until_other_clock() {
+        let clock = Clock::steady();
+        let until = Clock::steady().now();
+        assert!(!clock.sleep_until(until));
+    }
 }
@@ -0,0 +1,315 @@
+use std::{any::Any, collections::BTreeMap, fmt, sync::Arc};
+
+use crate::{IntoNodeOptions, Node, NodeOptions, NodeSpawner, ParameterValue, RclrsError};
+
+mod component_container;
+pub use component_container::*;
+
+/// A node that can be loaded into a [`ComponentContainer`] at runtime.
+///
+/// This is the `rclrs` equivalent of an `rclcpp_components` component. Several components
+/// can share one process and one executor, and can be loaded and unloaded with
+/// `ros2 component load` and `ros2 component unload`.
+///
+/// Since Rust code cannot be loaded from shared libraries safely, components are compiled
+/// into the container binary and made known to it through a [`ComponentRegistry`],
+/// usually with the [`register_components!`][crate::register_components] macro.
+///
+/// # Example
+/// ```
+/// # use rclrs::*;
+/// struct Talker {
+///     node: Node,
+///     _publisher: Publisher<test_msgs::msg::Empty>,
+/// }
+///
+/// impl Component for Talker {
+///     const NODE_NAME: &'static str = "talker";
+///
+///     fn new(spawner: &NodeSpawner, options: NodeOptions) -> Result<Self, RclrsError> {
+///         let node = spawner.create_node(options)?;
+///         let _publisher = node.create_publisher("chatter")?;
+///         Ok(Self { node, _publisher })
+///     }
+///
+///     fn node(&self) -> &Node {
+///         &self.node
+///     }
+/// }
+///
+/// let mut registry = ComponentRegistry::new();
+/// register_components!(registry, "my_package", Talker);
+/// assert!(registry.contains("my_package", "my_package::Talker"));
+/// ```
+pub trait Component: Send + 'static {
+    /// The name of the component's node, unless the load request specifies a different name.
+    const NODE_NAME: &'static str;
+
+    /// Creates the component.
+    ///
+    /// The component should create its node with [`NodeSpawner::create_node`], passing
+    /// the given `options`. They contain the name, namespace, remapping rules and parameter
+    /// overrides of the load request.
+    fn new(spawner: &NodeSpawner, options: NodeOptions) -> Result<Self, RclrsError>
+    where
+        Self: Sized;
+
+    /// Returns the node of the component.
+    fn node(&self) -> &Node;
+}
+
+/// The set of components that a [`ComponentContainer`] is able to load.
+///
+/// Components are identified by the name of their package and their plugin name,
+/// which is `<package>::<type>` by convention.
+#[derive(Default)]
+pub struct ComponentRegistry {
+    factories: BTreeMap<(String, String), ComponentFactory>,
+}
+
+/// Type-erased constructor of a [`Component`].
+#[derive(Clone, Copy)]
+struct ComponentFactory {
+    node_name: &'static str,
+    create: fn(&NodeSpawner, NodeOptions) -> Result<LoadedComponent, RclrsError>,
+}
+
+/// A component instance that has been loaded into a container.
+struct LoadedComponent {
+    node: Node,
+    // Only kept to keep the component alive until it is unloaded.
+    _component: Box<dyn Any + Send>,
+}
+
+fn create_component<C: Component>(
+    spawner: &NodeSpawner,
+    options: NodeOptions,
+) -> Result<LoadedComponent, RclrsError> {
+    let component = C::new(spawner, options)?;
+    Ok(LoadedComponent {
+        node: Arc::clone(component.node()),
+        _component: Box::new(component),
+    })
+}
+
+impl ComponentRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the component type `C` under the given package and plugin name.
+    ///
+    /// A component that was previously registered under the same names is replaced.
+    pub fn register<C: Component>(&mut self, package_name: &str, plugin_name: &str) -> &mut Self {
+        self.factories.insert(
+            (package_name.to_owned(), plugin_name.to_owned()),
+            ComponentFactory {
+                node_name: C::NODE_NAME,
+                create: create_component::<C>,
+            },
+        );
+        self
+    }
+
+    /// Returns true if a component is registered under the given package and plugin name.
+    pub fn contains(&self, package_name: &str, plugin_name: &str) -> bool {
+        self.factory(package_name, plugin_name).is_some()
+    }
+
+    /// Returns the package and plugin names of all registered components.
+    pub fn components(&self) -> Vec<(String, String)> {
+        self.factories.keys().cloned().collect()
+    }
+
+    fn factory(&self, package_name: &str, plugin_name: &str) -> Option<ComponentFactory> {
+        self.factories
+            .get(&(package_name.to_owned(), plugin_name.to_owned()))
+            .copied()
+    }
+}
+
+/// Registers [`Component`] types in a [`ComponentRegistry`].
+///
+/// The plugin name of each component is `<package>::<type>`, which matches the
+/// naming convention of `rclcpp_components`.
+///
+/// ```
+/// # use rclrs::*;
+/// # macro_rules! component {
+/// #     ($name:ident) => {
+/// #         struct $name(Node);
+/// #         impl Component for $name {
+/// #             const NODE_NAME: &'static str = stringify!($name);
+/// #             fn new(spawner: &NodeSpawner, options: NodeOptions) -> Result<Self, RclrsError> {
+/// #                 spawner.create_node(options).map(Self)
+/// #             }
+/// #             fn node(&self) -> &Node { &self.0 }
+/// #         }
+/// #     };
+/// # }
+/// # component!(Talker);
+/// # component!(Listener);
+/// let mut registry = ComponentRegistry::new();
+/// register_components!(registry, "composition", Talker, Listener);
+/// assert_eq!(
+///     registry.components(),
+///     [
+///         ("composition".to_owned(), "composition::Listener".to_owned()),
+///         ("composition".to_owned(), "composition::Talker".to_owned()),
+///     ]
+/// );
+/// ```
+#[macro_export]
+macro_rules! register_components {
+    ($registry: expr, $package: literal, $($component: ident),+ $(,)?) => {{
+        let registry: &mut $crate::ComponentRegistry = &mut $registry;
+        $(
+            registry.register::<$component>(
+                $package,
+                concat!($package, "::", stringify!($component)),
+            );
+        )+
+    }};
+}
+
+/// Options for loading a component into a [`ComponentContainer`].
+///
+/// These correspond to the fields of a `composition_interfaces/srv/LoadNode` request.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LoadComponentOptions {
+    /// The name of the component's node. If `None`, [`Component::NODE_NAME`] is used.
+    pub node_name: Option<String>,
+    /// The namespace of the component's node. If `None`, the node is put into `/`.
+    pub node_namespace: Option<String>,
+    /// Remapping rules for the node, e.g. `"chatter:=my_chatter"`.
+    pub remap_rules: Vec<String>,
+    /// Initial values for parameters of the node.
+    pub parameters: Vec<(String, ParameterValue)>,
+    /// Whether the node should also use the arguments of the container's context.
+    pub forward_global_arguments: bool,
+    /// Whether the node should use intra-process communication, see
+    /// [`IntoNodeOptions::use_intra_process_comms`].
+    pub use_intra_process_comms: bool,
+}
+
+impl LoadComponentOptions {
+    /// Converts the options into the command line arguments of the node.
+    fn arguments(&self) -> Result<Vec<String>, ComponentError> {
+        let mut arguments = vec!["--ros-args".to_owned()];
+        for rule in &self.remap_rules {
+            arguments.extend(["-r".to_owned(), rule.clone()]);
+        }
+        for (name, value) in &self.parameters {
+            let value = parameter_value_to_yaml(value).ok_or_else(|| {
+                ComponentError::InvalidArgument(format!(
+                    "parameter '{name}' has a type that cannot be passed to a node"
+                ))
+            })?;
+            arguments.extend(["-p".to_owned(), format!("{name}:={value}")]);
+        }
+        Ok(arguments)
+    }
+}
+
+/// Formats a parameter value as YAML, so that it can be passed as a `-p` argument.
+///
+/// Byte arrays cannot be expressed in the YAML accepted by `rcl`.
+fn parameter_value_to_yaml(value: &ParameterValue) -> Option<String> {
+    fn string(s: &str) -> String {
+        format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+    fn double(d: f64) -> String {
+        if d.is_nan() {
+            ".nan".to_owned()
+        } else if d.is_infinite() {
+            if d > 0.0 { ".inf" } else { "-.inf" }.to_owned()
+        } else {
+            format!("{d:?}")
+        }
+    }
+    fn array<T>(values: &[T], f: impl Fn(&T) -> String) -> String {
+        format!("[{}]", values.iter().map(f).collect::<Vec<_>>().join(", "))
+    }
+    Some(match value {
+        ParameterValue::Bool(b) => b.to_string(),
+        ParameterValue::Integer(i) => i.to_string(),
+        ParameterValue::Double(d) => double(*d),
+        ParameterValue::String(s) => string(s),
+        ParameterValue::ByteArray(_) => return None,
+        ParameterValue::BoolArray(v) => array(v, bool::to_string),
+        ParameterValue::IntegerArray(v) => array(v, i64::to_string),
+        ParameterValue::DoubleArray(v) => array(v, |d| double(*d)),
+        ParameterValue::StringArray(v) => array(v, |s| string(s)),
+    })
+}
+
+impl ComponentFactory {
+    fn create(
+        self,
+        spawner: &NodeSpawner,
+        options: &LoadComponentOptions,
+    ) -> Result<LoadedComponent, ComponentError> {
+        let node_options = options
+            .node_name
+            .as_deref()
+            .unwrap_or(self.node_name)
+            .namespace(options.node_namespace.as_deref().unwrap_or("/"))
+            .use_global_arguments(options.forward_global_arguments)
+            .use_intra_process_comms(options.use_intra_process_comms)
+            .arguments(options.arguments()?);
+        Ok((self.create)(spawner, node_options)?)
+    }
+}
+
+/// Error that can occur when loading or unloading a [`Component`].
+#[derive(Debug)]
+pub enum ComponentError {
+    /// No component is registered under the given package and plugin name.
+    UnknownComponent {
+        /// The package name of the requested component.
+        package_name: String,
+        /// The plugin name of the requested component.
+        plugin_name: String,
+    },
+    /// No component with the given unique ID is loaded.
+    NoSuchComponent(u64),
+    /// The load request contains an invalid argument.
+    InvalidArgument(String),
+    /// The component failed to create its node or other primitives.
+    CreationFailed(RclrsError),
+}
+
+impl fmt::Display for ComponentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownComponent {
+                package_name,
+                plugin_name,
+            } => write!(
+                f,
+                "no component '{plugin_name}' is registered for package '{package_name}'"
+            ),
+            Self::NoSuchComponent(unique_id) => {
+                write!(f, "no component with unique ID {unique_id} is loaded")
+            }
+            Self::InvalidArgument(msg) => write!(f, "invalid argument: {msg}"),
+            Self::CreationFailed(err) => write!(f, "failed to create component: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ComponentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::CreationFailed(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<RclrsError> for ComponentError {
+    fn from(err: RclrsError) -> Self {
+        Self::CreationFailed(err)
+    }
+}
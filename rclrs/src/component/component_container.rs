@@ -0,0 +1,527 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    sync::{Arc, Mutex, Weak},
+};
+
+use rosidl_runtime_rs::Message;
+
+use super::{ComponentError, ComponentRegistry, LoadComponentOptions, LoadedComponent};
+use crate::{
+    log_error, log_info, rmw_request_id_t,
+    vendor::{composition_interfaces::srv::*, rcl_interfaces::msg::Parameter},
+    Node, NodeSpawner, ParameterValue, RclrsError, Service,
+};
+
+/// A node that loads [`Component`][1]s on request.
+///
+/// Create a container using [`Executor::create_component_container`][2]. The container serves
+/// the `~/_container/load_node`, `~/_container/unload_node` and `~/_container/list_nodes`
+/// services of `composition_interfaces`, so components can be managed with
+/// `ros2 component load`, `ros2 component unload` and `ros2 component list`.
+/// Components can also be loaded directly with [`load_component`][3].
+///
+/// All components run on the executor of the container, and share its [`Context`][4].
+///
+/// [1]: crate::Component
+/// [2]: crate::Executor::create_component_container
+/// [3]: ComponentContainerState::load_component
+/// [4]: crate::Context
+pub type ComponentContainer = Arc<ComponentContainerState>;
+
+/// The inner state of a [`ComponentContainer`].
+///
+/// This is public so that you can choose to create a [`Weak`][1] reference to it
+/// if you want to be able to refer to a [`ComponentContainer`] in a non-owning way.
+///
+/// [1]: std::sync::Weak
+pub struct ComponentContainerState {
+    node: Node,
+    spawner: NodeSpawner,
+    registry: ComponentRegistry,
+    components: Mutex<LoadedComponents>,
+    services: Mutex<Option<ComponentContainerServices>>,
+}
+
+#[derive(Default)]
+struct LoadedComponents {
+    /// The unique ID of the last component that was loaded.
+    last_unique_id: u64,
+    components: BTreeMap<u64, LoadedComponent>,
+}
+
+// The variables only exist to keep a strong reference to the services and are technically unused.
+// What is used is the Weak that is stored in the node, and is upgraded when spinning.
+struct ComponentContainerServices {
+    #[allow(dead_code)]
+    load_node_service: Service<LoadNode>,
+    #[allow(dead_code)]
+    unload_node_service: Service<UnloadNode>,
+    #[allow(dead_code)]
+    list_nodes_service: Service<ListNodes>,
+}
+
+impl ComponentContainerState {
+    pub(crate) fn new(
+        spawner: NodeSpawner,
+        node: Node,
+        registry: ComponentRegistry,
+    ) -> Result<ComponentContainer, RclrsError> {
+        let container = Arc::new(Self {
+            node,
+            spawner,
+            registry,
+            components: Mutex::default(),
+            services: Mutex::new(None),
+        });
+        *container.services.lock().unwrap() = Some(ComponentContainerServices::new(&container)?);
+        Ok(container)
+    }
+
+    /// Returns the node of the container, which serves its services.
+    pub fn node(&self) -> &Node {
+        &self.node
+    }
+
+    /// Returns the components that this container is able to load.
+    pub fn registry(&self) -> &ComponentRegistry {
+        &self.registry
+    }
+
+    /// Loads the component that is registered under the given package and plugin name.
+    ///
+    /// Returns the unique ID of the loaded component, which can be passed to
+    /// [`unload_component`][1].
+    ///
+    /// [1]: Self::unload_component
+    pub fn load_component(
+        &self,
+        package_name: &str,
+        plugin_name: &str,
+        options: &LoadComponentOptions,
+    ) -> Result<u64, ComponentError> {
+        let factory = self
+            .registry
+            .factory(package_name, plugin_name)
+            .ok_or_else(|| ComponentError::UnknownComponent {
+                package_name: package_name.to_owned(),
+                plugin_name: plugin_name.to_owned(),
+            })?;
+        // The component is created without holding the lock, since its constructor may take
+        // a while, and must not be able to deadlock the container.
+        let component = factory.create(&self.spawner, options)?;
+        let mut loaded = self.components.lock().unwrap();
+        loaded.last_unique_id += 1;
+        let unique_id = loaded.last_unique_id;
+        loaded.components.insert(unique_id, component);
+        Ok(unique_id)
+    }
+
+    /// Unloads the component with the given unique ID.
+    ///
+    /// The node of the component is destroyed once all other references to it are dropped.
+    pub fn unload_component(&self, unique_id: u64) -> Result<(), ComponentError> {
+        let component = self
+            .components
+            .lock()
+            .unwrap()
+            .components
+            .remove(&unique_id);
+        // The component is dropped here, after the lock has been released.
+        component
+            .map(drop)
+            .ok_or(ComponentError::NoSuchComponent(unique_id))
+    }
+
+    /// Returns the unique IDs and nodes of all loaded components, ordered by their IDs.
+    pub fn components(&self) -> Vec<(u64, Node)> {
+        self.components
+            .lock()
+            .unwrap()
+            .components
+            .iter()
+            .map(|(unique_id, component)| (*unique_id, Arc::clone(&component.node)))
+            .collect()
+    }
+
+    fn handle_load_node(&self, req: LoadNode_Request) -> LoadNode_Response {
+        let loaded = load_options_from_request(&req).and_then(|options| {
+            let unique_id = self.load_component(&req.package_name, &req.plugin_name, &options)?;
+            let full_node_name = self
+                .components
+                .lock()
+                .unwrap()
+                .components
+                .get(&unique_id)
+                .map(|component| component.node.fully_qualified_name())
+                .unwrap_or_default();
+            Ok((unique_id, full_node_name))
+        });
+        match loaded {
+            Ok((unique_id, full_node_name)) => {
+                log_info!(
+                    self.node.logger(),
+                    "Loaded component '{}' as node '{full_node_name}'",
+                    req.plugin_name,
+                );
+                LoadNode_Response {
+                    success: true,
+                    error_message: String::new(),
+                    full_node_name,
+                    unique_id,
+                }
+            }
+            Err(err) => {
+                log_error!(
+                    self.node.logger(),
+                    "Failed to load component '{}': {err}",
+                    req.plugin_name,
+                );
+                LoadNode_Response {
+                    success: false,
+                    error_message: err.to_string(),
+                    ..Default::default()
+                }
+            }
+        }
+    }
+
+    fn handle_unload_node(&self, req: UnloadNode_Request) -> UnloadNode_Response {
+        match self.unload_component(req.unique_id) {
+            Ok(()) => UnloadNode_Response {
+                success: true,
+                error_message: String::new(),
+            },
+            Err(err) => UnloadNode_Response {
+                success: false,
+                error_message: err.to_string(),
+            },
+        }
+    }
+
+    fn handle_list_nodes(&self) -> ListNodes_Response {
+        let (unique_ids, full_node_names) = self
+            .components()
+            .into_iter()
+            .map(|(unique_id, node)| (unique_id, node.fully_qualified_name()))
+            .unzip();
+        ListNodes_Response {
+            full_node_names,
+            unique_ids,
+        }
+    }
+}
+
+/// Converts a `LoadNode` request into [`LoadComponentOptions`].
+fn load_options_from_request(
+    req: &LoadNode_Request,
+) -> Result<LoadComponentOptions, ComponentError> {
+    let non_empty = |s: &String| (!s.is_empty()).then(|| s.clone());
+    let mut options = LoadComponentOptions {
+        node_name: non_empty(&req.node_name),
+        node_namespace: non_empty(&req.node_namespace),
+        remap_rules: req.remap_rules.clone(),
+        parameters: req
+            .parameters
+            .iter()
+            .map(|parameter| Ok((parameter.name.clone(), parameter_value(parameter)?)))
+            .collect::<Result<_, ComponentError>>()?,
+        forward_global_arguments: false,
+        use_intra_process_comms: false,
+    };
+    for argument in &req.extra_arguments {
+        let flag = match argument.name.as_str() {
+            "forward_global_arguments" => &mut options.forward_global_arguments,
+            "use_intra_process_comms" => &mut options.use_intra_process_comms,
+            _ => continue,
+        };
+        match parameter_value(argument)? {
+            ParameterValue::Bool(value) => *flag = value,
+            _ => {
+                return Err(ComponentError::InvalidArgument(format!(
+                    "extra argument '{}' must be a bool",
+                    argument.name
+                )))
+            }
+        }
+    }
+    Ok(options)
+}
+
+fn parameter_value(parameter: &Parameter) -> Result<ParameterValue, ComponentError> {
+    let value = Message::into_rmw_message(Cow::Borrowed(&parameter.value)).into_owned();
+    ParameterValue::try_from(value).map_err(|err| {
+        ComponentError::InvalidArgument(format!("parameter '{}': {err}", parameter.name))
+    })
+}
+
+impl ComponentContainerServices {
+    fn new(container: &ComponentContainer) -> Result<Self, RclrsError> {
+        let node = &container.node;
+        let fqn = node.fully_qualified_name();
+        let weak_container = Arc::downgrade(container);
+        let load_node_service = node.create_service(
+            &(fqn.clone() + "/_container/load_node"),
+            move |_req_id: &rmw_request_id_t, req: LoadNode_Request| {
+                with_container(&weak_container, |container| container.handle_load_node(req))
+            },
+        )?;
+        let weak_container = Arc::downgrade(container);
+        let unload_node_service = node.create_service(
+            &(fqn.clone() + "/_container/unload_node"),
+            move |_req_id: &rmw_request_id_t, req: UnloadNode_Request| {
+                with_container(&weak_container, |container| {
+                    container.handle_unload_node(req)
+                })
+            },
+        )?;
+        let weak_container = Arc::downgrade(container);
+        let list_nodes_service = node.create_service(
+            &(fqn + "/_container/list_nodes"),
+            move |_req_id: &rmw_request_id_t, _req: ListNodes_Request| {
+                with_container(&weak_container, |container| container.handle_list_nodes())
+            },
+        )?;
+        Ok(Self {
+            load_node_service,
+            unload_node_service,
+            list_nodes_service,
+        })
+    }
+}
+
+/// Runs `f` with the container, or returns a default response if the container is
+/// being dropped.
+fn with_container<R: Default>(
+    weak_container: &Weak<ComponentContainerState>,
+    f: impl FnOnce(&ComponentContainerState) -> R,
+) -> R {
+    weak_container
+        .upgrade()
+        .map(|container| f(&container))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    struct TestComponent {
+        node: Node,
+        _greeting: MandatoryParameter<Arc<str>>,
+    }
+
+    impl Component for TestComponent {
+        const NODE_NAME: &'static str = "test_component";
+
+        fn new(spawner: &NodeSpawner, options: NodeOptions) -> Result<Self, RclrsError> {
+            let node = spawner.create_node(options)?;
+            let greeting = node
+                .declare_parameter("greeting")
+                .default("hello".into())
+                .mandatory()
+                .unwrap();
+            Ok(Self {
+                node,
+                _greeting: greeting,
+            })
+        }
+
+        fn node(&self) -> &Node {
+            &self.node
+        }
+    }
+
+    static TIMER_COMPONENT_TICKS: AtomicUsize = AtomicUsize::new(0);
+
+    struct TimerComponent {
+        node: Node,
+        _timer: Timer,
+    }
+
+    impl Component for TimerComponent {
+        const NODE_NAME: &'static str = "timer_component";
+
+        fn new(spawner: &NodeSpawner, options: NodeOptions) -> Result<Self, RclrsError> {
+            let node = spawner.create_node(options)?;
+            let timer = node.create_timer(Duration::from_millis(10), |_timer| {
+                TIMER_COMPONENT_TICKS.fetch_add(1, Ordering::SeqCst);
+            })?;
+            Ok(Self {
+                node,
+                _timer: timer,
+            })
+        }
+
+        fn node(&self) -> &Node {
+            &self.node
+        }
+    }
+
+    #[test]
+    fn traits() {
+        use crate::test_helpers::*;
+
+        assert_send::<ComponentContainer>();
+        assert_sync::<ComponentContainer>();
+    }
+
+    #[test]
+    fn test_load_and_unload_components() -> Result<(), Box<dyn std::error::Error>> {
+        let executor = Context::default().create_basic_executor();
+        let mut registry = ComponentRegistry::new();
+        register_components!(registry, "rclrs", TestComponent);
+        let container = executor.create_component_container(
+            format!("test_component_container_{}", line!()).as_str(),
+            registry,
+        )?;
+
+        let first = container.load_component(
+            "rclrs",
+            "rclrs::TestComponent",
+            &LoadComponentOptions::default(),
+        )?;
+        let second = container.load_component(
+            "rclrs",
+            "rclrs::TestComponent",
+            &LoadComponentOptions {
+                node_name: Some("renamed_component".to_owned()),
+                node_namespace: Some("/components".to_owned()),
+                parameters: vec![("greeting".to_owned(), ParameterValue::String("hi".into()))],
+                ..Default::default()
+            },
+        )?;
+        assert_ne!(first, second);
+
+        let components = container.components();
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].1.fully_qualified_name(), "/test_component");
+        assert_eq!(
+            components[1].1.fully_qualified_name(),
+            "/components/renamed_component"
+        );
+        let greeting = |node: &Node| -> Arc<str> {
+            node.use_undeclared_parameters()
+                .get::<Arc<str>>("greeting")
+                .unwrap()
+        };
+        assert_eq!(&*greeting(&components[0].1), "hello");
+        assert_eq!(&*greeting(&components[1].1), "hi");
+
+        assert!(matches!(
+            container.load_component("rclrs", "rclrs::Missing", &LoadComponentOptions::default()),
+            Err(ComponentError::UnknownComponent { .. })
+        ));
+
+        container.unload_component(first)?;
+        assert!(matches!(
+            container.unload_component(first),
+            Err(ComponentError::NoSuchComponent(id)) if id == first
+        ));
+        assert_eq!(container.components().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_component_container_services() -> Result<(), Box<dyn std::error::Error>> {
+        let mut executor = Context::default().create_basic_executor();
+        let mut registry = ComponentRegistry::new();
+        register_components!(registry, "rclrs", TestComponent);
+        let container = executor.create_component_container(
+            format!("test_component_services_{}", line!()).as_str(),
+            registry,
+        )?;
+        let fqn = container.node().fully_qualified_name();
+        let client_node =
+            executor.create_node(&format!("test_component_services_client_{}", line!()))?;
+        let load_client =
+            client_node.create_client::<LoadNode>(&(fqn.clone() + "/_container/load_node"))?;
+        let list_client =
+            client_node.create_client::<ListNodes>(&(fqn.clone() + "/_container/list_nodes"))?;
+        let unload_client =
+            client_node.create_client::<UnloadNode>(&(fqn + "/_container/unload_node"))?;
+
+        let responses = Arc::new(Mutex::new(None));
+        let responses_inner = Arc::clone(&responses);
+        client_node.spawn(async move {
+            load_client.wait_for_service_async(None).await.unwrap();
+            list_client.wait_for_service_async(None).await.unwrap();
+            unload_client.wait_for_service_async(None).await.unwrap();
+            let load_response = load_client
+                .call_async(LoadNode_Request {
+                    package_name: "rclrs".to_owned(),
+                    plugin_name: "rclrs::TestComponent".to_owned(),
+                    node_name: "loaded_by_service".to_owned(),
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+            let list_response = list_client
+                .call_async(ListNodes_Request::default())
+                .await
+                .unwrap();
+            let unload_response = unload_client
+                .call_async(UnloadNode_Request {
+                    unique_id: load_response.unique_id,
+                })
+                .await
+                .unwrap();
+            *responses_inner.lock().unwrap() =
+                Some((load_response, list_response, unload_response));
+        });
+        let start = Instant::now();
+        while responses.lock().unwrap().is_none() {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+
+        let (load_response, list_response, unload_response) =
+            responses.lock().unwrap().take().unwrap();
+        assert!(load_response.success, "{}", load_response.error_message);
+        assert_eq!(load_response.full_node_name, "/loaded_by_service");
+        assert_eq!(list_response.full_node_names, ["/loaded_by_service"]);
+        assert_eq!(list_response.unique_ids, [load_response.unique_id]);
+        assert!(unload_response.success);
+        assert!(container.components().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_component_loaded_while_spinning() -> Result<(), Box<dyn std::error::Error>> {
+        let mut executor = Context::default().create_basic_executor();
+        let mut registry = ComponentRegistry::new();
+        register_components!(registry, "rclrs", TimerComponent);
+        let container = executor.create_component_container(
+            format!("test_component_loaded_while_spinning_{}", line!()).as_str(),
+            registry,
+        )?;
+
+        // The executor is already waiting without a timeout when the component is loaded,
+        // so it must be woken up to pick up the timer of the new node.
+        let loader = {
+            let container = Arc::clone(&container);
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(100));
+                container.load_component(
+                    "rclrs",
+                    "rclrs::TimerComponent",
+                    &LoadComponentOptions::default(),
+                )
+            })
+        };
+        let start = Instant::now();
+        while TIMER_COMPONENT_TICKS.load(Ordering::SeqCst) == 0 {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            assert!(executor.spin(SpinOptions::spin_once()).is_empty());
+        }
+        loader.join().unwrap()?;
+        assert_eq!(container.components().len(), 1);
+        Ok(())
+    }
+}
@@ -6,7 +6,7 @@ use std::{
     vec::Vec,
 };
 
-use crate::{rcl_bindings::*, LoggingLifecycle, RclrsError, ToResult};
+use crate::{rcl_bindings::*, IntraProcessManager, LoggingLifecycle, RclrsError, ToResult};
 
 /// This is locked whenever initializing or dropping any middleware entity
 /// because we have found issues in RCL and some RMW implementations that
@@ -76,6 +76,8 @@ pub(crate) struct ContextHandle {
     /// has dropped.
     #[allow(unused)]
     logging: Arc<LoggingLifecycle>,
+    /// The publishers and subscriptions that communicate with each other directly.
+    pub(crate) intra_process: IntraProcessManager,
 }
 
 impl Default for Context {
@@ -174,6 +176,7 @@ impl Context {
             handle: Arc::new(ContextHandle {
                 rcl_context: Mutex::new(rcl_context),
                 logging,
+                intra_process: IntraProcessManager::default(),
             }),
         })
     }
@@ -214,9 +217,15 @@ impl Context {
     /// This will return `false` when a signal has caused the context to shut down (currently
     /// unimplemented).
     pub fn ok(&self) -> bool {
+        self.handle.ok()
+    }
+}
+
+impl ContextHandle {
+    pub(crate) fn ok(&self) -> bool {
         // This will currently always return true, but once we have a signal handler, the signal
         // handler could call `rcl_shutdown()`, hence making the context invalid.
-        let rcl_context = &mut *self.handle.rcl_context.lock().unwrap();
+        let rcl_context = &mut *self.rcl_context.lock().unwrap();
         // SAFETY: No preconditions for this function.
         unsafe { rcl_context_is_valid(rcl_context) }
     }
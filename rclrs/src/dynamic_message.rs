@@ -3,9 +3,13 @@
 //! This is useful for writing generic tools such as introspection tools, bridges to
 //! other communication systems, or nodes that manipulate messages à la `topic_tools`.
 //!
-//! The central type of this module is [`DynamicMessage`].
+//! The central type of this module is [`DynamicMessage`]. It can be published and received
+//! with a [`DynamicPublisher`] and [`DynamicSubscription`]. Tools that only move messages
+//! around without looking inside them, such as recorders and relays, can use a
+//! [`GenericPublisher`] and [`GenericSubscription`] instead, which work with serialized messages.
 
 use std::{
+    ffi::c_void,
     fmt::{self, Display},
     path::PathBuf,
     sync::Arc,
@@ -15,8 +19,25 @@ use crate::rcl_bindings::{
     rosidl_typesupport_introspection_c__MessageMembers_s as rosidl_message_members_t, *,
 };
 
+mod dynamic_publisher;
+mod dynamic_subscription;
 mod error;
+mod field_access;
+mod generic_publisher;
+mod generic_subscription;
+mod message_structure;
+mod sequences;
+pub use dynamic_publisher::*;
+pub use dynamic_subscription::*;
 pub use error::*;
+pub use field_access::*;
+pub use generic_publisher::*;
+pub use generic_subscription::*;
+pub use message_structure::*;
+pub use sequences::{
+    DynamicBoundedString, DynamicBoundedStringSequence, DynamicBoundedWString,
+    DynamicBoundedWStringSequence, DynamicMessageSequence, DynamicSequence,
+};
 
 /// Factory for constructing messages in a certain package dynamically.
 ///
@@ -52,17 +73,44 @@ struct MessageTypeName {
 /// can be used as a factory to create message instances.
 #[derive(Clone)]
 pub struct DynamicMessageMetadata {
-    #[allow(dead_code)]
     message_type: MessageTypeName,
     // The library needs to be kept loaded in order to keep the type_support_ptr valid.
     #[allow(dead_code)]
     introspection_type_support_library: Arc<libloading::Library>,
-    #[allow(dead_code)]
     type_support_ptr: *const rosidl_message_type_support_t,
-    #[allow(dead_code)]
-    fini_function: unsafe extern "C" fn(*mut std::os::raw::c_void),
+    structure: Arc<MessageStructure>,
+    init_function: unsafe extern "C" fn(*mut c_void, rosidl_runtime_c__message_initialization),
+    fini_function: unsafe extern "C" fn(*mut c_void),
+}
+
+/// A message whose type is only known at runtime.
+///
+/// The message is stored in the same memory layout as a message of the C type support,
+/// and its fields are accessed by name through the views returned by [`view()`][1] and
+/// [`view_mut()`][2], or directly through the methods of the same name on this type.
+///
+/// ```no_run
+/// # use rclrs::dynamic_message::*;
+/// let mut message = DynamicMessage::new("test_msgs/msg/BasicTypes")?;
+/// message.set("int32_value", 42)?;
+/// if let Some(Value::Simple(SimpleValue::Int32(value))) = message.get("int32_value") {
+///     assert_eq!(*value, 42);
+/// }
+/// # Ok::<(), DynamicMessageError>(())
+/// ```
+///
+/// [1]: DynamicMessage::view
+/// [2]: DynamicMessage::view_mut
+pub struct DynamicMessage {
+    metadata: DynamicMessageMetadata,
+    storage: Box<[MessageStorage]>,
 }
 
+/// A chunk of memory that is aligned for any field type.
+#[derive(Clone, Copy)]
+#[repr(C, align(16))]
+struct MessageStorage([u8; 16]);
+
 // ========================= impl for DynamicMessagePackage =========================
 
 /// This is an analogue of rclcpp::get_typesupport_library.
@@ -132,6 +180,49 @@ unsafe fn get_type_support_handle(
 }
 
 const INTROSPECTION_TYPE_SUPPORT_IDENTIFIER: &str = "rosidl_typesupport_introspection_c";
+const C_TYPE_SUPPORT_IDENTIFIER: &str = "rosidl_typesupport_c";
+
+/// The C type support of a message type, which can be used with any RMW implementation.
+///
+/// Unlike the introspection type support, this does not describe the layout of the
+/// message, so it is only useful for moving serialized messages around.
+pub(crate) struct MessageTypeSupport {
+    // The library needs to be kept loaded in order to keep the type_support_ptr valid.
+    #[allow(dead_code)]
+    type_support_library: Arc<libloading::Library>,
+    pub(crate) type_support_ptr: *const rosidl_message_type_support_t,
+}
+
+// SAFETY: The type support library can be used from any thread.
+unsafe impl Send for MessageTypeSupport {}
+
+// SAFETY: The type_support_ptr is not used for interior mutability.
+unsafe impl Sync for MessageTypeSupport {}
+
+impl MessageTypeSupport {
+    /// Loads the C type support for the given message type.
+    ///
+    /// See [`DynamicMessage::new()`] for the expected format of the `full_message_type`.
+    pub(crate) fn new(full_message_type: &str) -> Result<Self, DynamicMessageError> {
+        let message_type: MessageTypeName = full_message_type.try_into()?;
+        let type_support_library =
+            get_type_support_library(&message_type.package_name, C_TYPE_SUPPORT_IDENTIFIER)?;
+        // SAFETY: The symbol type of the type support getter function can be trusted
+        // assuming the install dir hasn't been tampered with.
+        // The pointer returned by this function is kept valid by keeping the library loaded.
+        let type_support_ptr = unsafe {
+            get_type_support_handle(
+                type_support_library.as_ref(),
+                C_TYPE_SUPPORT_IDENTIFIER,
+                &message_type,
+            )?
+        };
+        Ok(Self {
+            type_support_library,
+            type_support_ptr,
+        })
+    }
+}
 
 impl DynamicMessagePackage {
     /// Creates a new `DynamicMessagePackage`.
@@ -172,14 +263,20 @@ impl DynamicMessagePackage {
         let message_members: &rosidl_message_members_t =
             // SAFETY: The data pointer is supposed to be always valid.
             unsafe { &*(type_support.data as *const rosidl_message_members_t) };
-        // The fini function will always exist.
+        // The init and fini functions will always exist.
+        let init_function = message_members.init_function.unwrap();
         let fini_function = message_members.fini_function.unwrap();
+        // SAFETY: The message members and the type supports of nested messages are kept
+        // valid by keeping the library loaded.
+        let structure = unsafe { MessageStructure::from_rosidl_message_members(message_members) };
         let metadata = DynamicMessageMetadata {
             message_type,
             introspection_type_support_library: Arc::clone(
                 &self.introspection_type_support_library,
             ),
             type_support_ptr,
+            structure: Arc::new(structure),
+            init_function,
             fini_function,
         };
         Ok(metadata)
@@ -250,6 +347,121 @@ impl DynamicMessageMetadata {
         let pkg = DynamicMessagePackage::new(package_name)?;
         pkg.message_metadata(type_name)
     }
+
+    /// Returns the structure of the message type.
+    pub fn structure(&self) -> &MessageStructure {
+        &self.structure
+    }
+
+    /// Creates a message of this type, in which all fields have their default value.
+    pub fn create(&self) -> DynamicMessage {
+        let size = self
+            .structure
+            .size
+            .div_ceil(std::mem::size_of::<MessageStorage>());
+        let mut storage = vec![MessageStorage([0; 16]); size].into_boxed_slice();
+        // SAFETY: The storage is large enough and aligned for the message, and the library
+        // that contains the init function is kept loaded.
+        unsafe {
+            (self.init_function)(
+                storage.as_mut_ptr() as *mut c_void,
+                rosidl_runtime_c__message_initialization::ROSIDL_RUNTIME_C_MSG_INIT_ALL,
+            );
+        }
+        DynamicMessage {
+            metadata: self.clone(),
+            storage,
+        }
+    }
+}
+
+// ========================= impl for DynamicMessage =========================
+
+impl Drop for DynamicMessage {
+    fn drop(&mut self) {
+        // SAFETY: The storage contains a message that was initialized by the init function
+        // of the same type support.
+        unsafe { (self.metadata.fini_function)(self.storage.as_mut_ptr() as *mut c_void) };
+    }
+}
+
+impl fmt::Debug for DynamicMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.view(), f)
+    }
+}
+
+impl DynamicMessage {
+    /// Creates a message of the given type, in which all fields have their default value.
+    ///
+    /// The type must have the form `<package>/msg/<type_name>`, e.g. `std_msgs/msg/String`.
+    /// The package must be installed in a sourced workspace.
+    pub fn new(full_message_type: &str) -> Result<Self, DynamicMessageError> {
+        Ok(DynamicMessageMetadata::new(full_message_type)?.create())
+    }
+
+    /// Returns the metadata of the message type.
+    pub fn metadata(&self) -> &DynamicMessageMetadata {
+        &self.metadata
+    }
+
+    /// Returns the structure of the message type.
+    pub fn structure(&self) -> &MessageStructure {
+        &self.metadata.structure
+    }
+
+    /// Returns a read-only view of the message.
+    pub fn view(&self) -> DynamicMessageView<'_> {
+        let storage = self.storage_bytes();
+        // SAFETY: The storage contains a valid message of this structure.
+        unsafe { DynamicMessageView::new(&self.metadata.structure, storage) }
+    }
+
+    /// Returns a mutable view of the message.
+    pub fn view_mut(&mut self) -> DynamicMessageViewMut<'_> {
+        let size = self.metadata.structure.size;
+        // SAFETY: The storage is at least as large as the message.
+        let storage =
+            unsafe { std::slice::from_raw_parts_mut(self.storage.as_mut_ptr() as *mut u8, size) };
+        // SAFETY: The storage contains a valid message of this structure.
+        unsafe { DynamicMessageViewMut::new(&self.metadata.structure, storage) }
+    }
+
+    /// Returns the value of the field with the given name, or `None` if there is no such field.
+    ///
+    /// See [`DynamicMessageView::get`].
+    pub fn get(&self, field_name: &str) -> Option<Value<'_>> {
+        self.view().get(field_name)
+    }
+
+    /// Returns a mutable reference to the field with the given name, or `None` if there is no
+    /// such field.
+    ///
+    /// See [`DynamicMessageViewMut::get_mut`].
+    pub fn get_mut(&mut self, field_name: &str) -> Option<ValueMut<'_>> {
+        self.view_mut().into_value_mut(field_name)
+    }
+
+    /// Assigns a value to the field with the given name.
+    ///
+    /// See [`DynamicMessageViewMut::set`].
+    pub fn set(
+        &mut self,
+        field_name: &str,
+        value: impl FieldValue,
+    ) -> Result<(), DynamicMessageError> {
+        self.view_mut().set(field_name, value)
+    }
+
+    fn storage_bytes(&self) -> &[u8] {
+        // SAFETY: The storage is at least as large as the message.
+        unsafe {
+            std::slice::from_raw_parts(
+                self.storage.as_ptr() as *const u8,
+                self.metadata.structure.size,
+            )
+        }
+    }
 }
 
 #[cfg(test)]
@@ -262,6 +474,8 @@ mod tests {
 
         assert_send::<DynamicMessageMetadata>();
         assert_sync::<DynamicMessageMetadata>();
+        assert_send::<DynamicMessage>();
+        assert_sync::<DynamicMessage>();
     }
 
     #[test]
@@ -287,4 +501,180 @@ mod tests {
             Err(DynamicMessageError::InvalidMessageTypeSyntax { .. })
         ));
     }
+
+    #[test]
+    fn dynamic_message_default_values() {
+        let message = DynamicMessage::new("test_msgs/msg/Defaults").unwrap();
+        assert_eq!(message.structure().type_name, "Defaults");
+        assert!(matches!(
+            message.get("bool_value"),
+            Some(Value::Simple(SimpleValue::Boolean(true)))
+        ));
+        assert!(matches!(
+            message.get("int32_value"),
+            Some(Value::Simple(SimpleValue::Int32(-30000)))
+        ));
+        assert!(message.get("no_such_field").is_none());
+    }
+
+    #[test]
+    fn dynamic_message_set_simple_values() {
+        let mut message = DynamicMessage::new("test_msgs/msg/BasicTypes").unwrap();
+        message.set("int64_value", 5i64).unwrap();
+        message.set("float64_value", 2.5).unwrap();
+        assert!(matches!(
+            message.get("int64_value"),
+            Some(Value::Simple(SimpleValue::Int64(5)))
+        ));
+        if let Some(ValueMut::Simple(SimpleValueMut::Uint16(value))) =
+            message.get_mut("uint16_value")
+        {
+            *value = 7;
+        }
+        assert!(matches!(
+            message.get("uint16_value"),
+            Some(Value::Simple(SimpleValue::Uint16(7)))
+        ));
+
+        assert!(matches!(
+            message.set("int64_value", 5i32),
+            Err(DynamicMessageError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            message.set("no_such_field", 5i64),
+            Err(DynamicMessageError::InvalidField { .. })
+        ));
+    }
+
+    #[test]
+    fn dynamic_message_strings() {
+        let mut message = DynamicMessage::new("test_msgs/msg/Strings").unwrap();
+        message.set("string_value", "Hello").unwrap();
+        match message.get("string_value") {
+            Some(Value::Simple(SimpleValue::String(value))) => {
+                assert_eq!(value.to_string(), "Hello")
+            }
+            value => panic!("Unexpected value {:?}", value),
+        }
+        // The field is a string<=22
+        message.set("bounded_string_value", "Short").unwrap();
+        assert!(matches!(
+            message.set("bounded_string_value", "This is a very long string"),
+            Err(DynamicMessageError::UpperBoundExceeded {
+                upper_bound: 22,
+                length: 26
+            })
+        ));
+    }
+
+    #[test]
+    fn dynamic_message_arrays() {
+        let mut message = DynamicMessage::new("test_msgs/msg/Arrays").unwrap();
+        message.set("int32_values", [1i32, 2, 3]).unwrap();
+        assert!(matches!(
+            message.get("int32_values"),
+            Some(Value::Array(ArrayValue::Int32(&[1, 2, 3])))
+        ));
+        assert!(matches!(
+            message.set("int32_values", [1i32, 2]),
+            Err(DynamicMessageError::TypeMismatch { .. })
+        ));
+        message.set("string_values", &["a", "b", "c"][..]).unwrap();
+
+        match message.get_mut("basic_types_values") {
+            Some(ValueMut::Array(ArrayValueMut::Message(mut messages))) => {
+                assert_eq!(messages.len(), 3);
+                messages[1].set("bool_value", true).unwrap();
+            }
+            value => panic!("Unexpected value {:?}", value),
+        }
+        match message.get("basic_types_values") {
+            Some(Value::Array(ArrayValue::Message(messages))) => assert!(matches!(
+                messages[1].get("bool_value"),
+                Some(Value::Simple(SimpleValue::Boolean(true)))
+            )),
+            value => panic!("Unexpected value {:?}", value),
+        }
+    }
+
+    #[test]
+    fn dynamic_message_sequences() {
+        let mut message = DynamicMessage::new("test_msgs/msg/UnboundedSequences").unwrap();
+        message.set("int32_values", vec![1i32, 2, 3]).unwrap();
+        match message.get_mut("int32_values") {
+            Some(ValueMut::Sequence(SequenceValueMut::Int32(mut sequence))) => {
+                assert_eq!(sequence.upper_bound(), None);
+                // Resizing keeps the existing elements
+                sequence.resize(4).unwrap();
+                assert_eq!(sequence.as_slice(), &[1, 2, 3, 0]);
+            }
+            value => panic!("Unexpected value {:?}", value),
+        }
+
+        match message.get_mut("basic_types_values") {
+            Some(ValueMut::Sequence(SequenceValueMut::Message(mut sequence))) => {
+                sequence.resize(2).unwrap();
+                sequence
+                    .get_mut(1)
+                    .unwrap()
+                    .set("int8_value", -3i8)
+                    .unwrap();
+                sequence.resize(3).unwrap();
+                assert!(matches!(
+                    sequence.get(1).unwrap().get("int8_value"),
+                    Some(Value::Simple(SimpleValue::Int8(-3)))
+                ));
+            }
+            value => panic!("Unexpected value {:?}", value),
+        }
+
+        message
+            .set("string_values", &["first", "second"][..])
+            .unwrap();
+        match message.get("string_values") {
+            Some(Value::Sequence(ArrayValue::String(strings))) => {
+                assert_eq!(strings.len(), 2);
+                assert_eq!(strings[1].to_string(), "second");
+            }
+            value => panic!("Unexpected value {:?}", value),
+        }
+    }
+
+    #[test]
+    fn dynamic_message_bounded_sequences() {
+        let mut message = DynamicMessage::new("test_msgs/msg/BoundedSequences").unwrap();
+        message.set("int32_values", [1i32, 2, 3]).unwrap();
+        assert!(matches!(
+            message.set("int32_values", [1i32, 2, 3, 4]),
+            Err(DynamicMessageError::UpperBoundExceeded {
+                upper_bound: 3,
+                length: 4
+            })
+        ));
+        assert!(matches!(
+            message.get("int32_values"),
+            Some(Value::Sequence(ArrayValue::Int32(&[1, 2, 3])))
+        ));
+    }
+
+    #[test]
+    fn dynamic_message_nested() {
+        let mut message = DynamicMessage::new("test_msgs/msg/Nested").unwrap();
+        match message.get_mut("basic_types_value") {
+            Some(ValueMut::Simple(SimpleValueMut::Message(mut nested))) => {
+                nested.set("char_value", b'x').unwrap();
+            }
+            value => panic!("Unexpected value {:?}", value),
+        }
+        match message.get("basic_types_value") {
+            Some(Value::Simple(SimpleValue::Message(nested))) => {
+                assert_eq!(nested.structure().type_name, "BasicTypes");
+                assert!(matches!(
+                    nested.get("char_value"),
+                    Some(Value::Simple(SimpleValue::Char(b'x')))
+                ));
+            }
+            value => panic!("Unexpected value {:?}", value),
+        }
+    }
 }
@@ -0,0 +1,139 @@
+use std::{ffi::c_void, future::Future, sync::Arc, time::Duration};
+
+use super::{DynamicMessage, DynamicMessageError, DynamicMessageMetadata};
+use crate::{
+    error::ToResult, publisher::PublisherHandle, rcl_bindings::*, Node, PublisherBase,
+    PublisherOptions, RclrsError,
+};
+
+/// Struct for sending [`DynamicMessage`]s of a type that is only known at runtime.
+///
+/// Create a dynamic publisher using [`NodeState::create_dynamic_publisher`][1].
+///
+/// The messages are published through the introspection type support of the message type,
+/// so the RMW implementation needs to support it, as e.g. `rmw_cyclonedds_cpp` and
+/// `rmw_fastrtps_dynamic_cpp` do.
+///
+/// [1]: crate::NodeState::create_dynamic_publisher
+pub type DynamicPublisher = Arc<DynamicPublisherState>;
+
+/// The inner state of a [`DynamicPublisher`].
+///
+/// This is public so that you can choose to create a [`Weak`][1] reference to it
+/// if you want to be able to refer to a [`DynamicPublisher`] in a non-owning way.
+///
+/// [1]: std::sync::Weak
+pub struct DynamicPublisherState {
+    // The handle is declared first so that it is dropped before the metadata, which keeps
+    // the type support library loaded.
+    handle: PublisherHandle,
+    metadata: DynamicMessageMetadata,
+}
+
+impl PublisherBase for DynamicPublisherState {
+    fn handle(&self) -> &PublisherHandle {
+        &self.handle
+    }
+}
+
+impl DynamicPublisherState {
+    /// Creates a new `DynamicPublisher`.
+    pub(crate) fn new<'a>(
+        node: &Node,
+        topic_type: &str,
+        options: impl Into<PublisherOptions<'a>>,
+    ) -> Result<Self, DynamicMessageError> {
+        let metadata = DynamicMessageMetadata::new(topic_type)?;
+        let handle = PublisherHandle::new(node, metadata.type_support_ptr, options.into())?;
+        Ok(Self { handle, metadata })
+    }
+
+    /// Returns the topic name of the publisher.
+    ///
+    /// This returns the topic name after remapping, so it is not necessarily the
+    /// topic name which was used when creating the publisher.
+    pub fn topic_name(&self) -> String {
+        self.handle.topic_name()
+    }
+
+    /// Returns the number of subscriptions of the publisher.
+    pub fn get_subscription_count(&self) -> Result<usize, RclrsError> {
+        self.handle.get_subscription_count()
+    }
+
+    /// Blocks until at least `n` subscriptions are matched with this publisher.
+    ///
+    /// See [`PublisherState::wait_for_subscribers`][1].
+    ///
+    /// [1]: crate::PublisherState::wait_for_subscribers
+    pub fn wait_for_subscribers(
+        &self,
+        n: usize,
+        timeout: Option<Duration>,
+    ) -> Result<bool, RclrsError> {
+        self.handle.wait_for_subscribers(n, timeout)
+    }
+
+    /// Returns a future that completes once at least `n` subscriptions are matched with
+    /// this publisher.
+    ///
+    /// See [`PublisherState::wait_for_subscribers_async`][1].
+    ///
+    /// [1]: crate::PublisherState::wait_for_subscribers_async
+    pub fn wait_for_subscribers_async(
+        self: &Arc<Self>,
+        n: usize,
+        timeout: Option<Duration>,
+    ) -> impl Future<Output = Result<bool, RclrsError>> + Send + 'static {
+        let publisher = Arc::clone(self);
+        self.handle
+            .wait_for_subscribers_async(n, timeout, move || publisher.get_subscription_count())
+    }
+
+    /// Returns the metadata of the message type of this publisher.
+    pub fn metadata(&self) -> &DynamicMessageMetadata {
+        &self.metadata
+    }
+
+    /// Creates a message of the type of this publisher, in which all fields have their
+    /// default value.
+    pub fn create_message(&self) -> DynamicMessage {
+        self.metadata.create()
+    }
+
+    /// Publishes a message.
+    ///
+    /// The message must have the type of this publisher, otherwise a
+    /// [`MessageTypeMismatch`][1] error is returned.
+    ///
+    /// [1]: DynamicMessageError::MessageTypeMismatch
+    pub fn publish(&self, message: &DynamicMessage) -> Result<(), DynamicMessageError> {
+        if message.metadata.message_type != self.metadata.message_type {
+            return Err(DynamicMessageError::MessageTypeMismatch);
+        }
+        unsafe {
+            // SAFETY: The message has the type of the publisher, as checked above.
+            // The message does not need to be valid beyond the duration of this function call.
+            // The third argument is explictly allowed to be NULL.
+            rcl_publish(
+                &*self.handle.lock(),
+                message.storage.as_ptr() as *const c_void,
+                std::ptr::null_mut(),
+            )
+            .ok()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+
+    #[test]
+    fn traits() {
+        assert_send::<DynamicPublisher>();
+        assert_sync::<DynamicPublisher>();
+    }
+}
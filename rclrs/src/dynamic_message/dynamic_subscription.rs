@@ -0,0 +1,280 @@
+use std::{
+    ffi::c_void,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use super::{DynamicMessage, DynamicMessageError, DynamicMessageMetadata};
+use crate::{
+    wait_for_graph_condition_async, CallbackGroup, ContentFilter, MessageInfo, Node, RclrsError,
+    SubscriptionBase, SubscriptionHandle, SubscriptionOptions,
+};
+
+/// Struct for receiving [`DynamicMessage`]s of a type that is only known at runtime.
+///
+/// Create a dynamic subscription using [`NodeState::create_dynamic_subscription`][1].
+///
+/// Like a [`DynamicPublisher`][2], this requires an RMW implementation that supports the
+/// introspection type support.
+///
+/// Receiving messages requires the node's executor to [spin][3].
+///
+/// [1]: crate::NodeState::create_dynamic_subscription
+/// [2]: crate::dynamic_message::DynamicPublisher
+/// [3]: crate::Executor::spin
+pub type DynamicSubscription = Arc<DynamicSubscriptionState>;
+
+/// The inner state of a [`DynamicSubscription`].
+///
+/// This is public so that you can choose to create a [`Weak`][1] reference to it
+/// if you want to be able to refer to a [`DynamicSubscription`] in a non-owning way.
+///
+/// [1]: std::sync::Weak
+pub struct DynamicSubscriptionState {
+    handle: Arc<SubscriptionHandle>,
+    metadata: DynamicMessageMetadata,
+    callback: Mutex<AnyDynamicSubscriptionCallback>,
+    callback_group: CallbackGroup,
+    /// Ensure the parent node remains alive as long as the subscription is held.
+    #[allow(dead_code)]
+    node: Node,
+}
+
+/// An enum capturing the possible function signatures for dynamic subscription callbacks.
+///
+/// The correct enum variant is deduced by the [`DynamicSubscriptionCallback`] trait.
+pub enum AnyDynamicSubscriptionCallback {
+    /// A callback with only the message as an argument.
+    Regular(Box<dyn FnMut(DynamicMessage) + Send>),
+    /// A callback with the message and the message info as arguments.
+    RegularWithMessageInfo(Box<dyn FnMut(DynamicMessage, MessageInfo) + Send>),
+}
+
+/// A trait for allowed callbacks for dynamic subscriptions.
+///
+/// This is implemented for functions taking a [`DynamicMessage`], and optionally
+/// a [`MessageInfo`].
+pub trait DynamicSubscriptionCallback<Args>: Send + 'static {
+    /// Converts the callback into an enum.
+    ///
+    /// User code never needs to call this function.
+    fn into_dynamic_callback(self) -> AnyDynamicSubscriptionCallback;
+}
+
+impl<Func> DynamicSubscriptionCallback<(DynamicMessage,)> for Func
+where
+    Func: FnMut(DynamicMessage) + Send + 'static,
+{
+    fn into_dynamic_callback(self) -> AnyDynamicSubscriptionCallback {
+        AnyDynamicSubscriptionCallback::Regular(Box::new(self))
+    }
+}
+
+impl<Func> DynamicSubscriptionCallback<(DynamicMessage, MessageInfo)> for Func
+where
+    Func: FnMut(DynamicMessage, MessageInfo) + Send + 'static,
+{
+    fn into_dynamic_callback(self) -> AnyDynamicSubscriptionCallback {
+        AnyDynamicSubscriptionCallback::RegularWithMessageInfo(Box::new(self))
+    }
+}
+
+impl DynamicSubscriptionState {
+    /// Creates a new dynamic subscription.
+    pub(crate) fn new<'a>(
+        node: &Node,
+        topic_type: &str,
+        options: impl Into<SubscriptionOptions<'a>>,
+        callback: AnyDynamicSubscriptionCallback,
+    ) -> Result<Self, DynamicMessageError> {
+        let SubscriptionOptions {
+            topic,
+            qos,
+            callback_group,
+            content_filter,
+            ..
+        } = options.into();
+        let metadata = DynamicMessageMetadata::new(topic_type)?;
+        let handle = Arc::new(SubscriptionHandle::new(
+            Arc::clone(&node.handle),
+            metadata.type_support_ptr,
+            topic,
+            qos,
+            content_filter.as_ref(),
+        )?);
+        Ok(Self {
+            handle,
+            metadata,
+            callback: Mutex::new(callback),
+            callback_group: callback_group
+                .unwrap_or_else(|| Arc::clone(node.default_callback_group())),
+            node: Arc::clone(node),
+        })
+    }
+
+    /// Returns the topic name of the subscription.
+    ///
+    /// This returns the topic name after remapping, so it is not necessarily the
+    /// topic name which was used when creating the subscription.
+    pub fn topic_name(&self) -> String {
+        self.handle.topic_name()
+    }
+
+    /// Returns the number of publishers that are matched with this subscription.
+    pub fn get_publisher_count(&self) -> Result<usize, RclrsError> {
+        self.handle.get_publisher_count()
+    }
+
+    /// Blocks until at least `n` publishers are matched with this subscription.
+    ///
+    /// See [`SubscriptionState::wait_for_publishers`][1].
+    ///
+    /// [1]: crate::SubscriptionState::wait_for_publishers
+    pub fn wait_for_publishers(
+        &self,
+        n: usize,
+        timeout: Option<Duration>,
+    ) -> Result<bool, RclrsError> {
+        self.handle.wait_for_publishers(n, timeout)
+    }
+
+    /// Returns a future that completes once at least `n` publishers are matched with
+    /// this subscription.
+    ///
+    /// See [`SubscriptionState::wait_for_publishers_async`][1].
+    ///
+    /// [1]: crate::SubscriptionState::wait_for_publishers_async
+    pub fn wait_for_publishers_async(
+        self: &Arc<Self>,
+        n: usize,
+        timeout: Option<Duration>,
+    ) -> impl Future<Output = Result<bool, RclrsError>> + Send + 'static {
+        let subscription = Arc::clone(self);
+        wait_for_graph_condition_async(&self.node, timeout, move || {
+            Ok(subscription.get_publisher_count()? >= n)
+        })
+    }
+
+    /// See [`SubscriptionState::is_content_filter_enabled`][1].
+    ///
+    /// [1]: crate::SubscriptionState::is_content_filter_enabled
+    pub fn is_content_filter_enabled(&self) -> bool {
+        self.handle.is_content_filter_enabled()
+    }
+
+    /// See [`SubscriptionState::set_content_filter`][1].
+    ///
+    /// [1]: crate::SubscriptionState::set_content_filter
+    pub fn set_content_filter(&self, content_filter: &ContentFilter) -> Result<(), RclrsError> {
+        self.handle.set_content_filter(content_filter)
+    }
+
+    /// See [`SubscriptionState::get_content_filter`][1].
+    ///
+    /// [1]: crate::SubscriptionState::get_content_filter
+    pub fn get_content_filter(&self) -> Result<ContentFilter, RclrsError> {
+        self.handle.get_content_filter()
+    }
+
+    /// Returns the metadata of the message type of this subscription.
+    pub fn metadata(&self) -> &DynamicMessageMetadata {
+        &self.metadata
+    }
+
+    /// Fetches a new message.
+    ///
+    /// When there is no new message, this will return a
+    /// [`SubscriptionTakeFailed`][1].
+    ///
+    /// [1]: crate::RclrsError
+    pub fn take(&self) -> Result<(DynamicMessage, MessageInfo), RclrsError> {
+        let mut message = self.metadata.create();
+        // SAFETY: The message is an initialized message of the type of the subscription.
+        let message_info = unsafe {
+            self.handle
+                .take_raw(message.storage.as_mut_ptr() as *mut c_void)?
+        };
+        Ok((message, message_info))
+    }
+}
+
+impl SubscriptionBase for DynamicSubscriptionState {
+    fn handle(&self) -> &SubscriptionHandle {
+        &self.handle
+    }
+
+    fn callback_group(&self) -> &CallbackGroup {
+        &self.callback_group
+    }
+
+    fn execute(&self) -> Result<(), RclrsError> {
+        let (msg, msg_info) = match self.take() {
+            Ok(taken) => taken,
+            // Spurious wakeup – this may happen even when a waitset indicated that this
+            // subscription was ready, so it shouldn't be an error.
+            Err(err) if err.is_take_failed() => return Ok(()),
+            Err(err) => return Err(err),
+        };
+        match &mut *self.callback.lock().unwrap() {
+            AnyDynamicSubscriptionCallback::Regular(cb) => cb(msg),
+            AnyDynamicSubscriptionCallback::RegularWithMessageInfo(cb) => cb(msg, msg_info),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{dynamic_message::*, test_helpers::*, *};
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn traits() {
+        assert_send::<DynamicSubscription>();
+        assert_sync::<DynamicSubscription>();
+    }
+
+    #[test]
+    fn test_dynamic_publish_and_subscribe() {
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node("test_dynamic_pub_sub").unwrap();
+
+        let received = Arc::new(Mutex::new(None));
+        let qos = QoSProfile::default().keep_all().reliable();
+        let subscription = node
+            .create_dynamic_subscription("test_msgs/msg/BasicTypes", "dynamic_topic".qos(qos), {
+                let received = Arc::clone(&received);
+                move |msg: DynamicMessage| *received.lock().unwrap() = Some(msg)
+            })
+            .unwrap();
+        let publisher = node
+            .create_dynamic_publisher("test_msgs/msg/BasicTypes", "dynamic_topic".qos(qos))
+            .unwrap();
+        assert_eq!(subscription.topic_name(), publisher.topic_name());
+
+        let mut message = publisher.create_message();
+        message.set("int32_value", 42).unwrap();
+        publisher.publish(&message).unwrap();
+
+        let start_time = Instant::now();
+        while received.lock().unwrap().is_none() {
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+            assert!(start_time.elapsed() < Duration::from_secs(10));
+        }
+        let received = received.lock().unwrap().take().unwrap();
+        assert!(matches!(
+            received.get("int32_value"),
+            Some(Value::Simple(SimpleValue::Int32(42)))
+        ));
+
+        let other_message = DynamicMessage::new("test_msgs/msg/Empty").unwrap();
+        assert!(matches!(
+            publisher.publish(&other_message),
+            Err(DynamicMessageError::MessageTypeMismatch)
+        ));
+    }
+}
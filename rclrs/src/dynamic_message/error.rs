@@ -1,6 +1,8 @@
 use std::{error::Error, fmt};
 
-/// An error related to creating a dynamic message based on the name of the message's type.
+use crate::RclrsError;
+
+/// An error related to creating or accessing a dynamic message.
 #[derive(Debug)]
 pub enum DynamicMessageError {
     /// The type support library was not found because no matching prefix was sourced.
@@ -19,6 +21,27 @@ pub enum DynamicMessageError {
     MessageTypeMismatch,
     /// Loading the type support library failed.
     LibraryLoadingError(libloading::Error),
+    /// Creating or using a dynamic publisher or subscription failed in the `rcl` layer.
+    RclError(RclrsError),
+    /// The message type does not have a field with the given name.
+    InvalidField {
+        /// The name of the field.
+        field_name: String,
+    },
+    /// A value was assigned to a field of a different type.
+    TypeMismatch {
+        /// The name of the field.
+        field_name: String,
+        /// The type of the field, e.g. `int32[3]`.
+        field_type: String,
+    },
+    /// A bounded string or sequence would exceed its upper bound.
+    UpperBoundExceeded {
+        /// The maximum length of the string or sequence.
+        upper_bound: usize,
+        /// The length that was requested.
+        length: usize,
+    },
 }
 
 impl fmt::Display for DynamicMessageError {
@@ -38,6 +61,26 @@ impl fmt::Display for DynamicMessageError {
                 "The operation expected a dynamic message of a different type"
             ),
             Self::LibraryLoadingError(_) => write!(f, "Loading the type support library failed"),
+            Self::RclError(_) => write!(f, "An operation of the rcl layer failed"),
+            Self::InvalidField { field_name } => {
+                write!(f, "The message type has no field '{}'", field_name)
+            }
+            Self::TypeMismatch {
+                field_name,
+                field_type,
+            } => write!(
+                f,
+                "The value does not match the type '{}' of the field '{}'",
+                field_type, field_name
+            ),
+            Self::UpperBoundExceeded {
+                upper_bound,
+                length,
+            } => write!(
+                f,
+                "The length {} exceeds the upper bound {}",
+                length, upper_bound
+            ),
         }
     }
 }
@@ -46,7 +89,14 @@ impl Error for DynamicMessageError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             DynamicMessageError::LibraryLoadingError(lle) => Some(lle).map(|e| e as &dyn Error),
+            DynamicMessageError::RclError(err) => Some(err).map(|e| e as &dyn Error),
             _ => None,
         }
     }
 }
+
+impl From<RclrsError> for DynamicMessageError {
+    fn from(err: RclrsError) -> Self {
+        Self::RclError(err)
+    }
+}
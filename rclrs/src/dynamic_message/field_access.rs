@@ -0,0 +1,692 @@
+use std::fmt;
+
+use super::{
+    sequences::{cast_slice, cast_slice_mut, SequenceRaw},
+    BaseType, DynamicBoundedString, DynamicBoundedStringSequence, DynamicBoundedWString,
+    DynamicBoundedWStringSequence, DynamicMessageError, DynamicMessageSequence, DynamicSequence,
+    LongDouble, MessageFieldInfo, MessageStructure, ValueKind,
+};
+
+/// A read-only view of a message, or of a nested message.
+///
+/// The values of the fields are accessed by name with [`get()`][1], or all at once
+/// with [`iter()`][2].
+///
+/// [1]: DynamicMessageView::get
+/// [2]: DynamicMessageView::iter
+#[derive(Clone, Copy)]
+pub struct DynamicMessageView<'msg> {
+    structure: &'msg MessageStructure,
+    storage: &'msg [u8],
+}
+
+/// A mutable view of a message, or of a nested message.
+///
+/// Fields can be modified by matching on the typed reference returned by [`get_mut()`][1],
+/// or by assigning a value with [`set()`][2].
+///
+/// [1]: DynamicMessageViewMut::get_mut
+/// [2]: DynamicMessageViewMut::set
+pub struct DynamicMessageViewMut<'msg> {
+    structure: &'msg MessageStructure,
+    storage: &'msg mut [u8],
+}
+
+/// The value of a field of a message.
+#[derive(Debug)]
+pub enum Value<'msg> {
+    /// A single value, e.g. of an `int32` field.
+    Simple(SimpleValue<'msg>),
+    /// The elements of an array with a fixed length, e.g. of an `int32[3]` field.
+    Array(ArrayValue<'msg>),
+    /// The elements of a bounded or unbounded sequence, e.g. of an `int32[]` field.
+    Sequence(ArrayValue<'msg>),
+}
+
+/// A mutable reference to the value of a field of a message.
+#[derive(Debug)]
+pub enum ValueMut<'msg> {
+    /// A single value, e.g. of an `int32` field.
+    Simple(SimpleValueMut<'msg>),
+    /// The elements of an array with a fixed length, e.g. of an `int32[3]` field.
+    Array(ArrayValueMut<'msg>),
+    /// A bounded or unbounded sequence, e.g. of an `int32[]` field.
+    Sequence(SequenceValueMut<'msg>),
+}
+
+macro_rules! define_value_types {
+    ($($variant:ident($ty:ty, $idl_type:literal)),* $(,)?) => {
+        /// A single value of a field.
+        #[derive(Debug)]
+        pub enum SimpleValue<'msg> {
+            $(
+                #[doc = concat!("A `", $idl_type, "` value.")]
+                $variant(&'msg $ty),
+            )*
+            /// A `string` or `string<=N` value.
+            String(&'msg rosidl_runtime_rs::String),
+            /// A `wstring` or `wstring<=N` value.
+            WString(&'msg rosidl_runtime_rs::WString),
+            /// A nested message.
+            Message(DynamicMessageView<'msg>),
+        }
+
+        /// A mutable reference to a single value of a field.
+        #[derive(Debug)]
+        pub enum SimpleValueMut<'msg> {
+            $(
+                #[doc = concat!("A `", $idl_type, "` value.")]
+                $variant(&'msg mut $ty),
+            )*
+            /// A `string` value.
+            String(&'msg mut rosidl_runtime_rs::String),
+            /// A `string<=N` value.
+            BoundedString(DynamicBoundedString<'msg>),
+            /// A `wstring` value.
+            WString(&'msg mut rosidl_runtime_rs::WString),
+            /// A `wstring<=N` value.
+            BoundedWString(DynamicBoundedWString<'msg>),
+            /// A nested message.
+            Message(DynamicMessageViewMut<'msg>),
+        }
+
+        /// The elements of an array or sequence field.
+        #[derive(Debug)]
+        pub enum ArrayValue<'msg> {
+            $(
+                #[doc = concat!("`", $idl_type, "` values.")]
+                $variant(&'msg [$ty]),
+            )*
+            /// `string` or `string<=N` values.
+            String(&'msg [rosidl_runtime_rs::String]),
+            /// `wstring` or `wstring<=N` values.
+            WString(&'msg [rosidl_runtime_rs::WString]),
+            /// Nested messages.
+            Message(Vec<DynamicMessageView<'msg>>),
+        }
+
+        /// Mutable references to the elements of an array field.
+        #[derive(Debug)]
+        pub enum ArrayValueMut<'msg> {
+            $(
+                #[doc = concat!("`", $idl_type, "` values.")]
+                $variant(&'msg mut [$ty]),
+            )*
+            /// `string` values.
+            String(&'msg mut [rosidl_runtime_rs::String]),
+            /// `string<=N` values.
+            BoundedString(Vec<DynamicBoundedString<'msg>>),
+            /// `wstring` values.
+            WString(&'msg mut [rosidl_runtime_rs::WString]),
+            /// `wstring<=N` values.
+            BoundedWString(Vec<DynamicBoundedWString<'msg>>),
+            /// Nested messages.
+            Message(Vec<DynamicMessageViewMut<'msg>>),
+        }
+
+        /// A mutable reference to a sequence field.
+        #[derive(Debug)]
+        pub enum SequenceValueMut<'msg> {
+            $(
+                #[doc = concat!("A sequence of `", $idl_type, "` values.")]
+                $variant(DynamicSequence<'msg, $ty>),
+            )*
+            /// A sequence of `string` values.
+            String(DynamicSequence<'msg, rosidl_runtime_rs::String>),
+            /// A sequence of `string<=N` values.
+            BoundedString(DynamicBoundedStringSequence<'msg>),
+            /// A sequence of `wstring` values.
+            WString(DynamicSequence<'msg, rosidl_runtime_rs::WString>),
+            /// A sequence of `wstring<=N` values.
+            BoundedWString(DynamicBoundedWStringSequence<'msg>),
+            /// A sequence of nested messages.
+            Message(DynamicMessageSequence<'msg>),
+        }
+
+        impl<'msg> SimpleValue<'msg> {
+            /// # Safety
+            ///
+            /// The pointer must point to a valid value of the given type.
+            unsafe fn new(ptr: *const u8, base_type: &'msg BaseType) -> Self {
+                match base_type {
+                    $(BaseType::$variant => Self::$variant(&*(ptr as *const $ty)),)*
+                    BaseType::String | BaseType::BoundedString { .. } => {
+                        Self::String(&*(ptr as *const rosidl_runtime_rs::String))
+                    }
+                    BaseType::WString | BaseType::BoundedWString { .. } => {
+                        Self::WString(&*(ptr as *const rosidl_runtime_rs::WString))
+                    }
+                    BaseType::Message(structure) => Self::Message(DynamicMessageView::new(
+                        structure,
+                        std::slice::from_raw_parts(ptr, structure.size),
+                    )),
+                }
+            }
+        }
+
+        impl<'msg> SimpleValueMut<'msg> {
+            /// # Safety
+            ///
+            /// The pointer must point to a valid value of the given type.
+            unsafe fn new(ptr: *mut u8, base_type: &'msg BaseType) -> Self {
+                match base_type {
+                    $(BaseType::$variant => Self::$variant(&mut *(ptr as *mut $ty)),)*
+                    BaseType::String => Self::String(&mut *(ptr as *mut rosidl_runtime_rs::String)),
+                    BaseType::BoundedString { upper_bound } => {
+                        Self::BoundedString(DynamicBoundedString::new(
+                            &mut *(ptr as *mut rosidl_runtime_rs::String),
+                            *upper_bound,
+                        ))
+                    }
+                    BaseType::WString => {
+                        Self::WString(&mut *(ptr as *mut rosidl_runtime_rs::WString))
+                    }
+                    BaseType::BoundedWString { upper_bound } => {
+                        Self::BoundedWString(DynamicBoundedWString::new(
+                            &mut *(ptr as *mut rosidl_runtime_rs::WString),
+                            *upper_bound,
+                        ))
+                    }
+                    BaseType::Message(structure) => Self::Message(DynamicMessageViewMut::new(
+                        structure,
+                        std::slice::from_raw_parts_mut(ptr, structure.size),
+                    )),
+                }
+            }
+        }
+
+        impl<'msg> ArrayValue<'msg> {
+            /// # Safety
+            ///
+            /// The memory must contain valid values of the given type.
+            unsafe fn new(bytes: &'msg [u8], base_type: &'msg BaseType) -> Self {
+                match base_type {
+                    $(BaseType::$variant => Self::$variant(cast_slice(bytes)),)*
+                    BaseType::String | BaseType::BoundedString { .. } => {
+                        Self::String(cast_slice(bytes))
+                    }
+                    BaseType::WString | BaseType::BoundedWString { .. } => {
+                        Self::WString(cast_slice(bytes))
+                    }
+                    BaseType::Message(structure) => Self::Message(
+                        bytes
+                            .chunks_exact(structure.size)
+                            .map(|storage| DynamicMessageView::new(structure, storage))
+                            .collect(),
+                    ),
+                }
+            }
+        }
+
+        impl<'msg> ArrayValueMut<'msg> {
+            /// # Safety
+            ///
+            /// The memory must contain valid values of the given type.
+            unsafe fn new(bytes: &'msg mut [u8], base_type: &'msg BaseType) -> Self {
+                match base_type {
+                    $(BaseType::$variant => Self::$variant(cast_slice_mut(bytes)),)*
+                    BaseType::String => Self::String(cast_slice_mut(bytes)),
+                    BaseType::BoundedString { upper_bound } => Self::BoundedString(
+                        cast_slice_mut(bytes)
+                            .iter_mut()
+                            .map(|inner| DynamicBoundedString::new(inner, *upper_bound))
+                            .collect(),
+                    ),
+                    BaseType::WString => Self::WString(cast_slice_mut(bytes)),
+                    BaseType::BoundedWString { upper_bound } => Self::BoundedWString(
+                        cast_slice_mut(bytes)
+                            .iter_mut()
+                            .map(|inner| DynamicBoundedWString::new(inner, *upper_bound))
+                            .collect(),
+                    ),
+                    BaseType::Message(structure) => Self::Message(
+                        bytes
+                            .chunks_exact_mut(structure.size)
+                            .map(|storage| DynamicMessageViewMut::new(structure, storage))
+                            .collect(),
+                    ),
+                }
+            }
+        }
+
+        impl<'msg> SequenceValueMut<'msg> {
+            /// # Safety
+            ///
+            /// The sequence must contain valid values of the type of the field.
+            unsafe fn new(
+                sequence: &'msg mut SequenceRaw,
+                field: &'msg MessageFieldInfo,
+                upper_bound: Option<usize>,
+            ) -> Self {
+                // The type support always generates a resize function for sequences.
+                let resize_function = field.resize_function.unwrap();
+                match &field.base_type {
+                    $(BaseType::$variant => {
+                        Self::$variant(DynamicSequence::new(sequence, upper_bound, resize_function))
+                    })*
+                    BaseType::String => {
+                        Self::String(DynamicSequence::new(sequence, upper_bound, resize_function))
+                    }
+                    BaseType::BoundedString { upper_bound: string_upper_bound } => {
+                        Self::BoundedString(DynamicBoundedStringSequence::new(
+                            DynamicSequence::new(sequence, upper_bound, resize_function),
+                            *string_upper_bound,
+                        ))
+                    }
+                    BaseType::WString => {
+                        Self::WString(DynamicSequence::new(sequence, upper_bound, resize_function))
+                    }
+                    BaseType::BoundedWString { upper_bound: string_upper_bound } => {
+                        Self::BoundedWString(DynamicBoundedWStringSequence::new(
+                            DynamicSequence::new(sequence, upper_bound, resize_function),
+                            *string_upper_bound,
+                        ))
+                    }
+                    BaseType::Message(structure) => Self::Message(DynamicMessageSequence::new(
+                        sequence,
+                        structure,
+                        upper_bound,
+                        resize_function,
+                    )),
+                }
+            }
+        }
+    };
+}
+
+define_value_types!(
+    Float(f32, "float32"),
+    Double(f64, "float64"),
+    LongDouble(LongDouble, "long double"),
+    Char(u8, "char"),
+    WChar(u16, "wchar"),
+    Boolean(bool, "bool"),
+    Octet(u8, "byte"),
+    Uint8(u8, "uint8"),
+    Int8(i8, "int8"),
+    Uint16(u16, "uint16"),
+    Int16(i16, "int16"),
+    Uint32(u32, "uint32"),
+    Int32(i32, "int32"),
+    Uint64(u64, "uint64"),
+    Int64(i64, "int64"),
+);
+
+impl<'msg> Value<'msg> {
+    /// # Safety
+    ///
+    /// The storage must contain a valid message that has this field.
+    unsafe fn new(storage: &'msg [u8], field: &'msg MessageFieldInfo) -> Self {
+        let ptr = storage.as_ptr().add(field.offset);
+        let element_size = field.base_type.size();
+        match field.value_kind {
+            ValueKind::Simple => Self::Simple(SimpleValue::new(ptr, &field.base_type)),
+            ValueKind::StaticArray { length } => Self::Array(ArrayValue::new(
+                std::slice::from_raw_parts(ptr, length * element_size),
+                &field.base_type,
+            )),
+            ValueKind::Sequence | ValueKind::BoundedSequence { .. } => {
+                let sequence = &*(ptr as *const SequenceRaw);
+                Self::Sequence(ArrayValue::new(
+                    sequence.bytes(element_size),
+                    &field.base_type,
+                ))
+            }
+        }
+    }
+}
+
+impl<'msg> ValueMut<'msg> {
+    /// # Safety
+    ///
+    /// The storage must point to a valid message that has this field, and the field
+    /// must not be borrowed elsewhere.
+    unsafe fn new(storage: *mut u8, field: &'msg MessageFieldInfo) -> Self {
+        let ptr = storage.add(field.offset);
+        match field.value_kind {
+            ValueKind::Simple => Self::Simple(SimpleValueMut::new(ptr, &field.base_type)),
+            ValueKind::StaticArray { length } => Self::Array(ArrayValueMut::new(
+                std::slice::from_raw_parts_mut(ptr, length * field.base_type.size()),
+                &field.base_type,
+            )),
+            ValueKind::Sequence => Self::Sequence(SequenceValueMut::new(
+                &mut *(ptr as *mut SequenceRaw),
+                field,
+                None,
+            )),
+            ValueKind::BoundedSequence { upper_bound } => Self::Sequence(SequenceValueMut::new(
+                &mut *(ptr as *mut SequenceRaw),
+                field,
+                Some(upper_bound),
+            )),
+        }
+    }
+}
+
+impl fmt::Debug for DynamicMessageView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug_struct = f.debug_struct(&self.structure.type_name);
+        for (name, value) in self.iter() {
+            debug_struct.field(name, &value);
+        }
+        debug_struct.finish()
+    }
+}
+
+impl<'msg> DynamicMessageView<'msg> {
+    /// # Safety
+    ///
+    /// The storage must contain a valid message of the given structure.
+    pub(crate) unsafe fn new(structure: &'msg MessageStructure, storage: &'msg [u8]) -> Self {
+        Self { structure, storage }
+    }
+
+    /// Returns the structure of the message.
+    pub fn structure(&self) -> &'msg MessageStructure {
+        self.structure
+    }
+
+    /// Returns the value of the field with the given name, or `None` if there is no such field.
+    pub fn get(&self, field_name: &str) -> Option<Value<'msg>> {
+        let field = self.structure.get_field_info(field_name)?;
+        // SAFETY: The storage contains a valid message that has this field.
+        Some(unsafe { Value::new(self.storage, field) })
+    }
+
+    /// Iterates over the names and values of all fields, in the order of their declaration.
+    pub fn iter(&self) -> impl Iterator<Item = (&'msg str, Value<'msg>)> {
+        let Self { structure, storage } = *self;
+        structure.fields.iter().map(move |field| {
+            // SAFETY: The storage contains a valid message that has this field.
+            (field.name.as_str(), unsafe { Value::new(storage, field) })
+        })
+    }
+}
+
+impl fmt::Debug for DynamicMessageViewMut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.as_view(), f)
+    }
+}
+
+impl<'msg> DynamicMessageViewMut<'msg> {
+    /// # Safety
+    ///
+    /// The storage must contain a valid message of the given structure.
+    pub(crate) unsafe fn new(structure: &'msg MessageStructure, storage: &'msg mut [u8]) -> Self {
+        Self { structure, storage }
+    }
+
+    /// Returns the structure of the message.
+    pub fn structure(&self) -> &'msg MessageStructure {
+        self.structure
+    }
+
+    /// Returns a read-only view of the message.
+    pub fn as_view(&self) -> DynamicMessageView<'_> {
+        DynamicMessageView {
+            structure: self.structure,
+            storage: self.storage,
+        }
+    }
+
+    /// Returns the value of the field with the given name, or `None` if there is no such field.
+    pub fn get(&self, field_name: &str) -> Option<Value<'_>> {
+        self.as_view().get(field_name)
+    }
+
+    /// Returns a mutable reference to the field with the given name, or `None` if there is no
+    /// such field.
+    pub fn get_mut(&mut self, field_name: &str) -> Option<ValueMut<'_>> {
+        let field = self.structure.get_field_info(field_name)?;
+        // SAFETY: The storage contains a valid message that has this field, and it is
+        // borrowed mutably.
+        Some(unsafe { ValueMut::new(self.storage.as_mut_ptr(), field) })
+    }
+
+    pub(crate) fn into_value_mut(self, field_name: &str) -> Option<ValueMut<'msg>> {
+        let field = self.structure.get_field_info(field_name)?;
+        // SAFETY: The storage contains a valid message that has this field, and it is
+        // borrowed mutably.
+        Some(unsafe { ValueMut::new(self.storage.as_mut_ptr(), field) })
+    }
+
+    /// Iterates over the names and mutable references to all fields, in the order of their
+    /// declaration.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&'msg str, ValueMut<'_>)> {
+        let structure = self.structure;
+        let storage = self.storage.as_mut_ptr();
+        structure.fields.iter().map(move |field| {
+            // SAFETY: The storage contains a valid message that has this field, and it is
+            // borrowed mutably. The fields do not overlap.
+            (field.name.as_str(), unsafe {
+                ValueMut::new(storage, field)
+            })
+        })
+    }
+
+    /// Assigns a value to the field with the given name.
+    ///
+    /// This fails if there is no such field, if the type of the value does not match the type
+    /// of the field, or if the value exceeds the upper bound of a bounded string or sequence.
+    /// Numbers must have exactly the type of the field, e.g. `i32` for an `int32` field, and
+    /// arrays must have the exact length of the field.
+    pub fn set(
+        &mut self,
+        field_name: &str,
+        value: impl FieldValue,
+    ) -> Result<(), DynamicMessageError> {
+        let structure = self.structure;
+        let field = structure.get_field_info(field_name).ok_or_else(|| {
+            DynamicMessageError::InvalidField {
+                field_name: field_name.to_owned(),
+            }
+        })?;
+        // SAFETY: The storage contains a valid message that has this field, and it is
+        // borrowed mutably.
+        let field_mut = unsafe { ValueMut::new(self.storage.as_mut_ptr(), field) };
+        value.assign(field_mut).unwrap_or_else(|| {
+            Err(DynamicMessageError::TypeMismatch {
+                field_name: field_name.to_owned(),
+                field_type: field.type_name(),
+            })
+        })
+    }
+}
+
+mod private {
+    use super::{DynamicMessageError, ValueMut};
+
+    pub trait Sealed {
+        /// Assigns the value to the field, or returns `None` if the field has a different type.
+        fn assign(self, field: ValueMut<'_>) -> Option<Result<(), DynamicMessageError>>;
+    }
+}
+
+/// A value that can be assigned to a field with [`DynamicMessageViewMut::set`].
+///
+/// This is implemented for numbers, `bool`, strings, and for slices, arrays and vectors
+/// of them.
+pub trait FieldValue: private::Sealed {}
+
+impl<T: private::Sealed> FieldValue for T {}
+
+macro_rules! impl_primitive_field_value {
+    ($($ty:ty => $($variant:ident),+;)*) => {
+        $(
+            impl private::Sealed for $ty {
+                fn assign(self, field: ValueMut<'_>) -> Option<Result<(), DynamicMessageError>> {
+                    match field {
+                        $(ValueMut::Simple(SimpleValueMut::$variant(value)) => *value = self,)+
+                        _ => return None,
+                    }
+                    Some(Ok(()))
+                }
+            }
+
+            impl private::Sealed for &[$ty] {
+                fn assign(self, field: ValueMut<'_>) -> Option<Result<(), DynamicMessageError>> {
+                    match field {
+                        $(ValueMut::Array(ArrayValueMut::$variant(array)) => {
+                            if array.len() != self.len() {
+                                return None;
+                            }
+                            array.copy_from_slice(self);
+                        })+
+                        $(ValueMut::Sequence(SequenceValueMut::$variant(mut sequence)) => {
+                            if let Err(err) = sequence.resize(self.len()) {
+                                return Some(Err(err));
+                            }
+                            sequence.as_mut_slice().copy_from_slice(self);
+                        })+
+                        _ => return None,
+                    }
+                    Some(Ok(()))
+                }
+            }
+        )*
+    };
+}
+
+impl_primitive_field_value!(
+    f32 => Float;
+    f64 => Double;
+    LongDouble => LongDouble;
+    u8 => Char, Octet, Uint8;
+    u16 => WChar, Uint16;
+    bool => Boolean;
+    i8 => Int8;
+    i16 => Int16;
+    u32 => Uint32;
+    i32 => Int32;
+    u64 => Uint64;
+    i64 => Int64;
+);
+
+impl<T> private::Sealed for Vec<T>
+where
+    for<'a> &'a [T]: private::Sealed,
+{
+    fn assign(self, field: ValueMut<'_>) -> Option<Result<(), DynamicMessageError>> {
+        self.as_slice().assign(field)
+    }
+}
+
+impl<T, const N: usize> private::Sealed for [T; N]
+where
+    for<'a> &'a [T]: private::Sealed,
+{
+    fn assign(self, field: ValueMut<'_>) -> Option<Result<(), DynamicMessageError>> {
+        self.as_slice().assign(field)
+    }
+}
+
+impl private::Sealed for &str {
+    fn assign(self, field: ValueMut<'_>) -> Option<Result<(), DynamicMessageError>> {
+        match field {
+            ValueMut::Simple(SimpleValueMut::String(string)) => *string = self.into(),
+            ValueMut::Simple(SimpleValueMut::WString(string)) => *string = self.into(),
+            ValueMut::Simple(SimpleValueMut::BoundedString(mut string)) => {
+                return Some(string.set(self))
+            }
+            ValueMut::Simple(SimpleValueMut::BoundedWString(mut string)) => {
+                return Some(string.set(self))
+            }
+            _ => return None,
+        }
+        Some(Ok(()))
+    }
+}
+
+impl private::Sealed for String {
+    fn assign(self, field: ValueMut<'_>) -> Option<Result<(), DynamicMessageError>> {
+        self.as_str().assign(field)
+    }
+}
+
+impl private::Sealed for &[&str] {
+    fn assign(self, field: ValueMut<'_>) -> Option<Result<(), DynamicMessageError>> {
+        let result = match field {
+            ValueMut::Array(array) => {
+                // Arrays must have the exact length.
+                let length = match &array {
+                    ArrayValueMut::String(strings) => strings.len(),
+                    ArrayValueMut::WString(strings) => strings.len(),
+                    ArrayValueMut::BoundedString(strings) => strings.len(),
+                    ArrayValueMut::BoundedWString(strings) => strings.len(),
+                    _ => return None,
+                };
+                if length != self.len() {
+                    return None;
+                }
+                assign_strings(self, array)
+            }
+            ValueMut::Sequence(sequence) => assign_string_sequence(self, sequence)?,
+            ValueMut::Simple(_) => return None,
+        };
+        Some(result)
+    }
+}
+
+fn assign_strings(values: &[&str], array: ArrayValueMut<'_>) -> Result<(), DynamicMessageError> {
+    match array {
+        ArrayValueMut::String(strings) => {
+            for (string, value) in strings.iter_mut().zip(values) {
+                *string = (*value).into();
+            }
+        }
+        ArrayValueMut::WString(strings) => {
+            for (string, value) in strings.iter_mut().zip(values) {
+                *string = (*value).into();
+            }
+        }
+        ArrayValueMut::BoundedString(strings) => {
+            for (mut string, value) in strings.into_iter().zip(values) {
+                string.set(value)?;
+            }
+        }
+        ArrayValueMut::BoundedWString(strings) => {
+            for (mut string, value) in strings.into_iter().zip(values) {
+                string.set(value)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn assign_string_sequence(
+    values: &[&str],
+    sequence: SequenceValueMut<'_>,
+) -> Option<Result<(), DynamicMessageError>> {
+    let result = match sequence {
+        SequenceValueMut::String(mut strings) => strings.resize(values.len()).map(|()| {
+            for (string, value) in strings.as_mut_slice().iter_mut().zip(values) {
+                *string = (*value).into();
+            }
+        }),
+        SequenceValueMut::WString(mut strings) => strings.resize(values.len()).map(|()| {
+            for (string, value) in strings.as_mut_slice().iter_mut().zip(values) {
+                *string = (*value).into();
+            }
+        }),
+        SequenceValueMut::BoundedString(mut strings) => {
+            strings.resize(values.len()).and_then(|()| {
+                strings
+                    .iter_mut()
+                    .zip(values)
+                    .try_for_each(|(mut string, value)| string.set(value))
+            })
+        }
+        SequenceValueMut::BoundedWString(mut strings) => {
+            strings.resize(values.len()).and_then(|()| {
+                strings
+                    .iter_mut()
+                    .zip(values)
+                    .try_for_each(|(mut string, value)| string.set(value))
+            })
+        }
+        _ => return None,
+    };
+    Some(result)
+}
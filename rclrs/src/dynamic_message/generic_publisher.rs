@@ -0,0 +1,122 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use super::{DynamicMessageError, MessageTypeSupport};
+use crate::{publisher::PublisherHandle, Node, PublisherBase, PublisherOptions, RclrsError};
+
+/// Struct for sending serialized messages of a type that is only known at runtime.
+///
+/// Create a generic publisher using [`NodeState::create_generic_publisher`][1].
+///
+/// Unlike a [`DynamicPublisher`][2], this works with any RMW implementation, but the
+/// messages need to be serialized already, e.g. because they were received by a
+/// [`GenericSubscription`][3].
+///
+/// [1]: crate::NodeState::create_generic_publisher
+/// [2]: crate::dynamic_message::DynamicPublisher
+/// [3]: crate::dynamic_message::GenericSubscription
+pub type GenericPublisher = Arc<GenericPublisherState>;
+
+/// The inner state of a [`GenericPublisher`].
+///
+/// This is public so that you can choose to create a [`Weak`][1] reference to it
+/// if you want to be able to refer to a [`GenericPublisher`] in a non-owning way.
+///
+/// [1]: std::sync::Weak
+pub struct GenericPublisherState {
+    // The handle is declared first so that it is dropped before the type support library.
+    handle: PublisherHandle,
+    #[allow(dead_code)]
+    type_support: MessageTypeSupport,
+    topic_type: String,
+}
+
+impl PublisherBase for GenericPublisherState {
+    fn handle(&self) -> &PublisherHandle {
+        &self.handle
+    }
+}
+
+impl GenericPublisherState {
+    /// Creates a new `GenericPublisher`.
+    pub(crate) fn new<'a>(
+        node: &Node,
+        topic_type: &str,
+        options: impl Into<PublisherOptions<'a>>,
+    ) -> Result<Self, DynamicMessageError> {
+        let type_support = MessageTypeSupport::new(topic_type)?;
+        let handle = PublisherHandle::new(node, type_support.type_support_ptr, options.into())?;
+        Ok(Self {
+            handle,
+            type_support,
+            topic_type: topic_type.to_owned(),
+        })
+    }
+
+    /// Returns the topic name of the publisher.
+    ///
+    /// This returns the topic name after remapping, so it is not necessarily the
+    /// topic name which was used when creating the publisher.
+    pub fn topic_name(&self) -> String {
+        self.handle.topic_name()
+    }
+
+    /// Returns the message type of the publisher, e.g. `std_msgs/msg/String`.
+    pub fn topic_type(&self) -> &str {
+        &self.topic_type
+    }
+
+    /// Returns the number of subscriptions of the publisher.
+    pub fn get_subscription_count(&self) -> Result<usize, RclrsError> {
+        self.handle.get_subscription_count()
+    }
+
+    /// Blocks until at least `n` subscriptions are matched with this publisher.
+    ///
+    /// See [`PublisherState::wait_for_subscribers`][1].
+    ///
+    /// [1]: crate::PublisherState::wait_for_subscribers
+    pub fn wait_for_subscribers(
+        &self,
+        n: usize,
+        timeout: Option<Duration>,
+    ) -> Result<bool, RclrsError> {
+        self.handle.wait_for_subscribers(n, timeout)
+    }
+
+    /// Returns a future that completes once at least `n` subscriptions are matched with
+    /// this publisher.
+    ///
+    /// See [`PublisherState::wait_for_subscribers_async`][1].
+    ///
+    /// [1]: crate::PublisherState::wait_for_subscribers_async
+    pub fn wait_for_subscribers_async(
+        self: &Arc<Self>,
+        n: usize,
+        timeout: Option<Duration>,
+    ) -> impl Future<Output = Result<bool, RclrsError>> + Send + 'static {
+        let publisher = Arc::clone(self);
+        self.handle
+            .wait_for_subscribers_async(n, timeout, move || publisher.get_subscription_count())
+    }
+
+    /// Publishes a serialized message.
+    ///
+    /// See [`PublisherState::publish_serialized`][1].
+    ///
+    /// [1]: crate::PublisherState::publish_serialized
+    pub fn publish(&self, bytes: &[u8]) -> Result<(), RclrsError> {
+        self.handle.publish_serialized(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+
+    #[test]
+    fn traits() {
+        assert_send::<GenericPublisher>();
+        assert_sync::<GenericPublisher>();
+    }
+}
@@ -0,0 +1,248 @@
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use super::{DynamicMessageError, MessageTypeSupport};
+use crate::{
+    wait_for_graph_condition_async, CallbackGroup, ContentFilter, MessageInfo, Node, RclrsError,
+    SerializedMessage, SubscriptionBase, SubscriptionHandle, SubscriptionOptions,
+};
+
+type GenericSubscriptionCallback = Box<dyn FnMut(SerializedMessage, MessageInfo) + Send>;
+
+/// Struct for receiving serialized messages of a type that is only known at runtime.
+///
+/// Create a generic subscription using [`NodeState::create_generic_subscription`][1].
+///
+/// The message type can be discovered at runtime, e.g. with
+/// [`NodeState::get_topic_names_and_types`][2].
+///
+/// Receiving messages requires the node's executor to [spin][3].
+///
+/// [1]: crate::NodeState::create_generic_subscription
+/// [2]: crate::NodeState::get_topic_names_and_types
+/// [3]: crate::Executor::spin
+pub type GenericSubscription = Arc<GenericSubscriptionState>;
+
+/// The inner state of a [`GenericSubscription`].
+///
+/// This is public so that you can choose to create a [`Weak`][1] reference to it
+/// if you want to be able to refer to a [`GenericSubscription`] in a non-owning way.
+///
+/// [1]: std::sync::Weak
+pub struct GenericSubscriptionState {
+    // The handle is declared first so that it is dropped before the type support library.
+    handle: Arc<SubscriptionHandle>,
+    #[allow(dead_code)]
+    type_support: MessageTypeSupport,
+    topic_type: String,
+    callback: Mutex<GenericSubscriptionCallback>,
+    callback_group: CallbackGroup,
+    /// Ensure the parent node remains alive as long as the subscription is held.
+    #[allow(dead_code)]
+    node: Node,
+}
+
+impl GenericSubscriptionState {
+    /// Creates a new generic subscription.
+    pub(crate) fn new<'a>(
+        node: &Node,
+        topic_type: &str,
+        options: impl Into<SubscriptionOptions<'a>>,
+        callback: GenericSubscriptionCallback,
+    ) -> Result<Self, DynamicMessageError> {
+        let SubscriptionOptions {
+            topic,
+            qos,
+            callback_group,
+            content_filter,
+            ..
+        } = options.into();
+        let type_support = MessageTypeSupport::new(topic_type)?;
+        let handle = Arc::new(SubscriptionHandle::new(
+            Arc::clone(&node.handle),
+            type_support.type_support_ptr,
+            topic,
+            qos,
+            content_filter.as_ref(),
+        )?);
+        Ok(Self {
+            handle,
+            type_support,
+            topic_type: topic_type.to_owned(),
+            callback: Mutex::new(callback),
+            callback_group: callback_group
+                .unwrap_or_else(|| Arc::clone(node.default_callback_group())),
+            node: Arc::clone(node),
+        })
+    }
+
+    /// Returns the topic name of the subscription.
+    ///
+    /// This returns the topic name after remapping, so it is not necessarily the
+    /// topic name which was used when creating the subscription.
+    pub fn topic_name(&self) -> String {
+        self.handle.topic_name()
+    }
+
+    /// Returns the number of publishers that are matched with this subscription.
+    pub fn get_publisher_count(&self) -> Result<usize, RclrsError> {
+        self.handle.get_publisher_count()
+    }
+
+    /// Blocks until at least `n` publishers are matched with this subscription.
+    ///
+    /// See [`SubscriptionState::wait_for_publishers`][1].
+    ///
+    /// [1]: crate::SubscriptionState::wait_for_publishers
+    pub fn wait_for_publishers(
+        &self,
+        n: usize,
+        timeout: Option<Duration>,
+    ) -> Result<bool, RclrsError> {
+        self.handle.wait_for_publishers(n, timeout)
+    }
+
+    /// Returns a future that completes once at least `n` publishers are matched with
+    /// this subscription.
+    ///
+    /// See [`SubscriptionState::wait_for_publishers_async`][1].
+    ///
+    /// [1]: crate::SubscriptionState::wait_for_publishers_async
+    pub fn wait_for_publishers_async(
+        self: &Arc<Self>,
+        n: usize,
+        timeout: Option<Duration>,
+    ) -> impl Future<Output = Result<bool, RclrsError>> + Send + 'static {
+        let subscription = Arc::clone(self);
+        wait_for_graph_condition_async(&self.node, timeout, move || {
+            Ok(subscription.get_publisher_count()? >= n)
+        })
+    }
+
+    /// See [`SubscriptionState::is_content_filter_enabled`][1].
+    ///
+    /// [1]: crate::SubscriptionState::is_content_filter_enabled
+    pub fn is_content_filter_enabled(&self) -> bool {
+        self.handle.is_content_filter_enabled()
+    }
+
+    /// See [`SubscriptionState::set_content_filter`][1].
+    ///
+    /// [1]: crate::SubscriptionState::set_content_filter
+    pub fn set_content_filter(&self, content_filter: &ContentFilter) -> Result<(), RclrsError> {
+        self.handle.set_content_filter(content_filter)
+    }
+
+    /// See [`SubscriptionState::get_content_filter`][1].
+    ///
+    /// [1]: crate::SubscriptionState::get_content_filter
+    pub fn get_content_filter(&self) -> Result<ContentFilter, RclrsError> {
+        self.handle.get_content_filter()
+    }
+
+    /// Returns the message type of the subscription, e.g. `std_msgs/msg/String`.
+    pub fn topic_type(&self) -> &str {
+        &self.topic_type
+    }
+
+    /// Fetches a new serialized message.
+    ///
+    /// When there is no new message, this will return a
+    /// [`SubscriptionTakeFailed`][1].
+    ///
+    /// [1]: crate::RclrsError
+    pub fn take(&self) -> Result<(SerializedMessage, MessageInfo), RclrsError> {
+        let mut serialized_message = SerializedMessage::new();
+        let message_info = self.handle.take_serialized(&mut serialized_message)?;
+        Ok((serialized_message, message_info))
+    }
+}
+
+impl SubscriptionBase for GenericSubscriptionState {
+    fn handle(&self) -> &SubscriptionHandle {
+        &self.handle
+    }
+
+    fn callback_group(&self) -> &CallbackGroup {
+        &self.callback_group
+    }
+
+    fn execute(&self) -> Result<(), RclrsError> {
+        let (msg, msg_info) = match self.take() {
+            Ok(taken) => taken,
+            // Spurious wakeup – this may happen even when a waitset indicated that this
+            // subscription was ready, so it shouldn't be an error.
+            Err(err) if err.is_take_failed() => return Ok(()),
+            Err(err) => return Err(err),
+        };
+        (*self.callback.lock().unwrap())(msg, msg_info);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{dynamic_message::*, test_helpers::*, *};
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+    use test_msgs::msg;
+
+    #[test]
+    fn traits() {
+        assert_send::<GenericSubscription>();
+        assert_sync::<GenericSubscription>();
+    }
+
+    #[test]
+    fn test_generic_publish_and_subscribe() {
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node("test_generic_pub_sub").unwrap();
+        let qos = QoSProfile::default().keep_all().reliable();
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let subscription = node
+            .create_generic_subscription("test_msgs/msg/Strings", "generic_topic".qos(qos), {
+                let received = Arc::clone(&received);
+                move |msg: SerializedMessage, _info: MessageInfo| received.lock().unwrap().push(msg)
+            })
+            .unwrap();
+        assert_eq!(subscription.topic_type(), "test_msgs/msg/Strings");
+        let publisher = node
+            .create_generic_publisher("test_msgs/msg/Strings", "generic_topic".qos(qos))
+            .unwrap();
+        let typed_publisher = node
+            .create_publisher::<msg::Strings>("generic_topic".qos(qos))
+            .unwrap();
+
+        let message = msg::Strings {
+            string_value: "Hello".into(),
+            ..Default::default()
+        };
+        typed_publisher.publish(&message).unwrap();
+        let start_time = Instant::now();
+        while received.lock().unwrap().is_empty() {
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+            assert!(start_time.elapsed() < Duration::from_secs(10));
+        }
+
+        // Relay the serialized message through the generic publisher
+        let serialized = received.lock().unwrap().remove(0);
+        publisher.publish(&serialized).unwrap();
+        while received.lock().unwrap().is_empty() {
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+            assert!(start_time.elapsed() < Duration::from_secs(10));
+        }
+        let relayed = received.lock().unwrap().remove(0);
+        assert_eq!(relayed.deserialize::<msg::Strings>().unwrap(), message);
+
+        assert!(matches!(
+            node.create_generic_publisher("test_msgs/msg/NoSuchType", "generic_topic"),
+            Err(DynamicMessageError::InvalidMessageType)
+        ));
+    }
+}
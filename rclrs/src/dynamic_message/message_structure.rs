@@ -0,0 +1,302 @@
+use std::{
+    ffi::{c_void, CStr},
+    fmt,
+};
+
+use crate::rcl_bindings::{
+    rosidl_typesupport_introspection_c__MessageMember_s as rosidl_message_member_t,
+    rosidl_typesupport_introspection_c__MessageMembers_s as rosidl_message_members_t, *,
+};
+
+/// The function that resizes a sequence field, by finalizing it and initializing it again.
+pub(crate) type ResizeFunction = unsafe extern "C" fn(*mut c_void, usize) -> bool;
+
+/// A description of the structure of a message type.
+///
+/// This is obtained from the introspection type support of the message, and is
+/// available through [`DynamicMessageMetadata::structure`][1].
+///
+/// [1]: crate::dynamic_message::DynamicMessageMetadata::structure
+#[derive(Clone, Debug)]
+pub struct MessageStructure {
+    /// The namespace of the message type, e.g. `std_msgs__msg`.
+    pub namespace: String,
+    /// The name of the message type, e.g. `String`.
+    pub type_name: String,
+    /// The fields of the message, in the order in which they are declared.
+    pub fields: Vec<MessageFieldInfo>,
+    /// The size of the message in memory, in bytes.
+    pub size: usize,
+}
+
+/// A description of a single field of a message type.
+#[derive(Clone, Debug)]
+pub struct MessageFieldInfo {
+    /// The name of the field.
+    pub name: String,
+    /// The type of a single value of this field.
+    pub base_type: BaseType,
+    /// Whether the field holds a single value, or an array or sequence of values.
+    pub value_kind: ValueKind,
+    pub(crate) offset: usize,
+    pub(crate) resize_function: Option<ResizeFunction>,
+}
+
+/// The type of a single value in a message field.
+#[derive(Clone, Debug)]
+pub enum BaseType {
+    /// `float32`
+    Float,
+    /// `float64`
+    Double,
+    /// A C `long double`, which has no equivalent in Rust or in the ROS IDL.
+    LongDouble,
+    /// `char`
+    Char,
+    /// `wchar`
+    WChar,
+    /// `bool`
+    Boolean,
+    /// `byte`
+    Octet,
+    /// `uint8`
+    Uint8,
+    /// `int8`
+    Int8,
+    /// `uint16`
+    Uint16,
+    /// `int16`
+    Int16,
+    /// `uint32`
+    Uint32,
+    /// `int32`
+    Int32,
+    /// `uint64`
+    Uint64,
+    /// `int64`
+    Int64,
+    /// `string`
+    String,
+    /// `string<=N`
+    BoundedString {
+        /// The maximum length of the string in bytes.
+        upper_bound: usize,
+    },
+    /// `wstring`
+    WString,
+    /// `wstring<=N`
+    BoundedWString {
+        /// The maximum length of the string in UTF-16 code units.
+        upper_bound: usize,
+    },
+    /// A nested message.
+    Message(Box<MessageStructure>),
+}
+
+/// Whether a field holds a single value, or several values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueKind {
+    /// A single value, e.g. `int32`.
+    Simple,
+    /// An array with a fixed length, e.g. `int32[3]`.
+    StaticArray {
+        /// The number of elements in the array.
+        length: usize,
+    },
+    /// A sequence without a maximum length, e.g. `int32[]`.
+    Sequence,
+    /// A sequence with a maximum length, e.g. `int32[<=3]`.
+    BoundedSequence {
+        /// The maximum number of elements in the sequence.
+        upper_bound: usize,
+    },
+}
+
+impl MessageStructure {
+    /// Parses the introspection data of a message type.
+    ///
+    /// # Safety
+    ///
+    /// The message members must be valid, including the type support of nested messages.
+    pub(crate) unsafe fn from_rosidl_message_members(members: &rosidl_message_members_t) -> Self {
+        // SAFETY: The caller guarantees that the pointers are valid.
+        let member_slice = if members.member_count_ == 0 {
+            &[]
+        } else {
+            std::slice::from_raw_parts(members.members_, members.member_count_ as usize)
+        };
+        Self {
+            namespace: CStr::from_ptr(members.message_namespace_)
+                .to_string_lossy()
+                .into_owned(),
+            type_name: CStr::from_ptr(members.message_name_)
+                .to_string_lossy()
+                .into_owned(),
+            fields: member_slice
+                .iter()
+                .map(|member| MessageFieldInfo::from_rosidl_message_member(member))
+                .collect(),
+            size: members.size_of_,
+        }
+    }
+
+    /// Returns the field with the given name, if it exists.
+    pub fn get_field_info(&self, field_name: &str) -> Option<&MessageFieldInfo> {
+        self.fields.iter().find(|field| field.name == field_name)
+    }
+}
+
+impl MessageFieldInfo {
+    /// # Safety
+    ///
+    /// The member must be valid, including the type support of a nested message.
+    unsafe fn from_rosidl_message_member(member: &rosidl_message_member_t) -> Self {
+        let value_kind = match (member.is_array_, member.is_upper_bound_, member.array_size_) {
+            (false, _, _) => ValueKind::Simple,
+            (true, true, upper_bound) => ValueKind::BoundedSequence { upper_bound },
+            (true, false, 0) => ValueKind::Sequence,
+            (true, false, length) => ValueKind::StaticArray { length },
+        };
+        Self {
+            // SAFETY: The name is a valid C string.
+            name: CStr::from_ptr(member.name_).to_string_lossy().into_owned(),
+            base_type: BaseType::from_rosidl_message_member(member),
+            value_kind,
+            offset: member.offset_ as usize,
+            resize_function: member.resize_function,
+        }
+    }
+
+    /// Returns the type of this field in the notation of the ROS IDL, e.g. `int32[<=3]`.
+    pub fn type_name(&self) -> String {
+        match self.value_kind {
+            ValueKind::Simple => self.base_type.to_string(),
+            ValueKind::StaticArray { length } => format!("{}[{}]", self.base_type, length),
+            ValueKind::Sequence => format!("{}[]", self.base_type),
+            ValueKind::BoundedSequence { upper_bound } => {
+                format!("{}[<={}]", self.base_type, upper_bound)
+            }
+        }
+    }
+}
+
+impl BaseType {
+    /// # Safety
+    ///
+    /// The member must be valid, including the type support of a nested message.
+    unsafe fn from_rosidl_message_member(member: &rosidl_message_member_t) -> Self {
+        use rosidl_typesupport_introspection_c_field_types::*;
+        let type_id = u32::from(member.type_id_);
+        let upper_bound = member.string_upper_bound_;
+        match type_id {
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_FLOAT as u32 => Self::Float,
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_DOUBLE as u32 => Self::Double,
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_LONG_DOUBLE as u32 => {
+                Self::LongDouble
+            }
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_CHAR as u32 => Self::Char,
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_WCHAR as u32 => Self::WChar,
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_BOOLEAN as u32 => Self::Boolean,
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_OCTET as u32 => Self::Octet,
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_UINT8 as u32 => Self::Uint8,
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_INT8 as u32 => Self::Int8,
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_UINT16 as u32 => Self::Uint16,
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_INT16 as u32 => Self::Int16,
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_UINT32 as u32 => Self::Uint32,
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_INT32 as u32 => Self::Int32,
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_UINT64 as u32 => Self::Uint64,
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_INT64 as u32 => Self::Int64,
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_STRING as u32 => {
+                match upper_bound {
+                    0 => Self::String,
+                    upper_bound => Self::BoundedString { upper_bound },
+                }
+            }
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_WSTRING as u32 => {
+                match upper_bound {
+                    0 => Self::WString,
+                    upper_bound => Self::BoundedWString { upper_bound },
+                }
+            }
+            x if x == rosidl_typesupport_introspection_c__ROS_TYPE_MESSAGE as u32 => {
+                // SAFETY: The type support of a nested message is valid, and its data
+                // points to the introspection data.
+                let type_support = &*member.members_;
+                let members = &*(type_support.data as *const rosidl_message_members_t);
+                Self::Message(Box::new(MessageStructure::from_rosidl_message_members(
+                    members,
+                )))
+            }
+            _ => panic!("Invalid field type id: {}", type_id),
+        }
+    }
+
+    /// The size of a single value of this type in memory, in bytes.
+    pub(crate) fn size(&self) -> usize {
+        match self {
+            Self::Float => std::mem::size_of::<f32>(),
+            Self::Double => std::mem::size_of::<f64>(),
+            Self::LongDouble => std::mem::size_of::<LongDouble>(),
+            Self::Char | Self::Octet | Self::Uint8 | Self::Int8 | Self::Boolean => 1,
+            Self::WChar | Self::Uint16 | Self::Int16 => 2,
+            Self::Uint32 | Self::Int32 => 4,
+            Self::Uint64 | Self::Int64 => 8,
+            Self::String | Self::BoundedString { .. } => {
+                std::mem::size_of::<rosidl_runtime_rs::String>()
+            }
+            Self::WString | Self::BoundedWString { .. } => {
+                std::mem::size_of::<rosidl_runtime_rs::WString>()
+            }
+            Self::Message(structure) => structure.size,
+        }
+    }
+}
+
+impl fmt::Display for BaseType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Float => write!(f, "float32"),
+            Self::Double => write!(f, "float64"),
+            Self::LongDouble => write!(f, "long double"),
+            Self::Char => write!(f, "char"),
+            Self::WChar => write!(f, "wchar"),
+            Self::Boolean => write!(f, "bool"),
+            Self::Octet => write!(f, "byte"),
+            Self::Uint8 => write!(f, "uint8"),
+            Self::Int8 => write!(f, "int8"),
+            Self::Uint16 => write!(f, "uint16"),
+            Self::Int16 => write!(f, "int16"),
+            Self::Uint32 => write!(f, "uint32"),
+            Self::Int32 => write!(f, "int32"),
+            Self::Uint64 => write!(f, "uint64"),
+            Self::Int64 => write!(f, "int64"),
+            Self::String => write!(f, "string"),
+            Self::BoundedString { upper_bound } => write!(f, "string<={}", upper_bound),
+            Self::WString => write!(f, "wstring"),
+            Self::BoundedWString { upper_bound } => write!(f, "wstring<={}", upper_bound),
+            Self::Message(structure) => {
+                // The namespace has the form `<package>__msg`.
+                let package = structure
+                    .namespace
+                    .split("__")
+                    .next()
+                    .unwrap_or(&structure.namespace);
+                write!(f, "{}/{}", package, structure.type_name)
+            }
+        }
+    }
+}
+
+/// The raw bytes of a C `long double`.
+///
+/// There is no equivalent type in Rust, so the value can only be copied around.
+#[cfg(not(target_os = "windows"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C, align(16))]
+pub struct LongDouble([u8; 16]);
+
+/// The raw bytes of a C `long double`, which is the same as a `double` on Windows.
+#[cfg(target_os = "windows")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C, align(8))]
+pub struct LongDouble([u8; 8]);
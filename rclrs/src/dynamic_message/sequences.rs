@@ -0,0 +1,433 @@
+use std::{
+    ffi::c_void,
+    fmt,
+    marker::PhantomData,
+    ops::Deref,
+    ptr::{self, NonNull},
+};
+
+use super::{
+    message_structure::ResizeFunction, DynamicMessageError, DynamicMessageView,
+    DynamicMessageViewMut, MessageStructure,
+};
+
+/// The memory layout of all sequence types in the C type support.
+#[repr(C)]
+pub(crate) struct SequenceRaw {
+    data: *mut u8,
+    size: usize,
+    capacity: usize,
+}
+
+impl SequenceRaw {
+    /// Returns the memory of the elements.
+    ///
+    /// # Safety
+    ///
+    /// The sequence must contain valid elements of `element_size` bytes each.
+    pub(crate) unsafe fn bytes(&self, element_size: usize) -> &[u8] {
+        if self.size == 0 {
+            // The data pointer may be null for an empty sequence.
+            &[]
+        } else {
+            std::slice::from_raw_parts(self.data, self.size * element_size)
+        }
+    }
+
+    /// Returns the memory of the elements.
+    ///
+    /// # Safety
+    ///
+    /// The sequence must contain valid elements of `element_size` bytes each.
+    pub(crate) unsafe fn bytes_mut(&mut self, element_size: usize) -> &mut [u8] {
+        if self.size == 0 {
+            &mut []
+        } else {
+            std::slice::from_raw_parts_mut(self.data, self.size * element_size)
+        }
+    }
+
+    /// Resizes the sequence, keeping the elements that fit into the new size.
+    ///
+    /// The resize function of the type support finalizes all elements, so the elements that
+    /// are kept are moved into the new sequence first.
+    ///
+    /// # Safety
+    ///
+    /// The sequence must contain valid elements of `element_size` bytes each, and
+    /// `resize_function` must be the resize function of the sequence.
+    unsafe fn resize(&mut self, len: usize, element_size: usize, resize_function: ResizeFunction) {
+        let mut old = std::mem::replace(
+            self,
+            SequenceRaw {
+                data: ptr::null_mut(),
+                size: 0,
+                capacity: 0,
+            },
+        );
+        if !resize_function(self as *mut Self as *mut c_void, len) {
+            *self = old;
+            panic!("Unable to allocate a sequence of {} elements", len);
+        }
+        let kept = old.size.min(len);
+        if kept > 0 {
+            // Messages and strings only own memory through pointers, so they can be moved by
+            // swapping their bytes. The old sequence then owns the new default elements.
+            ptr::swap_nonoverlapping(old.data, self.data, kept * element_size);
+        }
+        // Finalizes the remaining elements of the old sequence and frees its memory.
+        resize_function(&mut old as *mut Self as *mut c_void, 0);
+    }
+}
+
+/// A mutable reference to a sequence field of a [`DynamicMessage`][1].
+///
+/// The elements can be modified through [`as_mut_slice()`][2], and the length can be changed
+/// with [`resize()`][3].
+///
+/// [1]: crate::dynamic_message::DynamicMessage
+/// [2]: DynamicSequence::as_mut_slice
+/// [3]: DynamicSequence::resize
+pub struct DynamicSequence<'msg, T> {
+    sequence: &'msg mut SequenceRaw,
+    upper_bound: Option<usize>,
+    resize_function: ResizeFunction,
+    phantom: PhantomData<&'msg mut [T]>,
+}
+
+impl<T: fmt::Debug> fmt::Debug for DynamicSequence<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl<'msg, T> DynamicSequence<'msg, T> {
+    /// # Safety
+    ///
+    /// The sequence must contain valid elements of type `T`, and `resize_function` must be
+    /// the resize function of the sequence.
+    pub(crate) unsafe fn new(
+        sequence: &'msg mut SequenceRaw,
+        upper_bound: Option<usize>,
+        resize_function: ResizeFunction,
+    ) -> Self {
+        Self {
+            sequence,
+            upper_bound,
+            resize_function,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.sequence.size
+    }
+
+    /// Returns `true` if the sequence has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.sequence.size == 0
+    }
+
+    /// Returns the maximum number of elements for a bounded sequence.
+    pub fn upper_bound(&self) -> Option<usize> {
+        self.upper_bound
+    }
+
+    /// Returns the elements.
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: The sequence contains valid elements of type T.
+        unsafe { cast_slice(self.sequence.bytes(std::mem::size_of::<T>())) }
+    }
+
+    /// Returns the elements for modifying them.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: The sequence contains valid elements of type T.
+        unsafe { cast_slice_mut(self.sequence.bytes_mut(std::mem::size_of::<T>())) }
+    }
+
+    /// Changes the number of elements.
+    ///
+    /// Existing elements are kept if they fit, and new elements have their default value.
+    /// Fails if the new length exceeds the upper bound of the sequence.
+    pub fn resize(&mut self, len: usize) -> Result<(), DynamicMessageError> {
+        check_upper_bound(self.upper_bound, len)?;
+        // SAFETY: The sequence contains valid elements of type T, and the resize function
+        // belongs to it.
+        unsafe {
+            self.sequence
+                .resize(len, std::mem::size_of::<T>(), self.resize_function)
+        };
+        Ok(())
+    }
+}
+
+/// A mutable reference to a sequence of nested messages in a [`DynamicMessage`][1].
+///
+/// [1]: crate::dynamic_message::DynamicMessage
+pub struct DynamicMessageSequence<'msg> {
+    sequence: &'msg mut SequenceRaw,
+    structure: &'msg MessageStructure,
+    upper_bound: Option<usize>,
+    resize_function: ResizeFunction,
+}
+
+impl fmt::Debug for DynamicMessageSequence<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'msg> DynamicMessageSequence<'msg> {
+    /// # Safety
+    ///
+    /// The sequence must contain valid messages of the given structure, and `resize_function`
+    /// must be the resize function of the sequence.
+    pub(crate) unsafe fn new(
+        sequence: &'msg mut SequenceRaw,
+        structure: &'msg MessageStructure,
+        upper_bound: Option<usize>,
+        resize_function: ResizeFunction,
+    ) -> Self {
+        Self {
+            sequence,
+            structure,
+            upper_bound,
+            resize_function,
+        }
+    }
+
+    /// Returns the number of messages.
+    pub fn len(&self) -> usize {
+        self.sequence.size
+    }
+
+    /// Returns `true` if the sequence has no messages.
+    pub fn is_empty(&self) -> bool {
+        self.sequence.size == 0
+    }
+
+    /// Returns the maximum number of messages for a bounded sequence.
+    pub fn upper_bound(&self) -> Option<usize> {
+        self.upper_bound
+    }
+
+    /// Returns the message at the given index.
+    pub fn get(&self, index: usize) -> Option<DynamicMessageView<'_>> {
+        self.iter().nth(index)
+    }
+
+    /// Returns the message at the given index for modifying it.
+    pub fn get_mut(&mut self, index: usize) -> Option<DynamicMessageViewMut<'_>> {
+        self.iter_mut().nth(index)
+    }
+
+    /// Iterates over the messages.
+    pub fn iter(&self) -> impl Iterator<Item = DynamicMessageView<'_>> {
+        let structure = self.structure;
+        // SAFETY: The sequence contains valid messages of this structure.
+        let bytes = unsafe { self.sequence.bytes(structure.size) };
+        bytes
+            .chunks_exact(structure.size)
+            // SAFETY: Each chunk is a valid message of this structure.
+            .map(move |storage| unsafe { DynamicMessageView::new(structure, storage) })
+    }
+
+    /// Iterates over the messages for modifying them.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = DynamicMessageViewMut<'_>> {
+        let structure = self.structure;
+        // SAFETY: The sequence contains valid messages of this structure.
+        let bytes = unsafe { self.sequence.bytes_mut(structure.size) };
+        bytes
+            .chunks_exact_mut(structure.size)
+            // SAFETY: Each chunk is a valid message of this structure.
+            .map(move |storage| unsafe { DynamicMessageViewMut::new(structure, storage) })
+    }
+
+    /// Changes the number of messages.
+    ///
+    /// Existing messages are kept if they fit, and new messages have their default value.
+    /// Fails if the new length exceeds the upper bound of the sequence.
+    pub fn resize(&mut self, len: usize) -> Result<(), DynamicMessageError> {
+        check_upper_bound(self.upper_bound, len)?;
+        // SAFETY: The sequence contains valid messages of this structure, and the resize
+        // function belongs to it.
+        unsafe {
+            self.sequence
+                .resize(len, self.structure.size, self.resize_function)
+        };
+        Ok(())
+    }
+}
+
+macro_rules! define_bounded_string_types {
+    ($string:ident, $sequence:ident, $inner:ty, $idl_type:literal, $unit:literal) => {
+        #[doc = concat!("A mutable reference to a `", $idl_type, "<=N` value.")]
+        ///
+        /// The value can only be changed with `set()`, which checks that the new value does
+        /// not exceed the upper bound.
+        pub struct $string<'msg> {
+            inner: &'msg mut $inner,
+            upper_bound: usize,
+        }
+
+        impl fmt::Debug for $string<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(&*self.inner, f)
+            }
+        }
+
+        impl Deref for $string<'_> {
+            type Target = $inner;
+            fn deref(&self) -> &Self::Target {
+                self.inner
+            }
+        }
+
+        impl<'msg> $string<'msg> {
+            pub(crate) fn new(inner: &'msg mut $inner, upper_bound: usize) -> Self {
+                Self { inner, upper_bound }
+            }
+
+            #[doc = concat!("Returns the maximum length of the string in ", $unit, ".")]
+            pub fn upper_bound(&self) -> usize {
+                self.upper_bound
+            }
+
+            /// Replaces the string, or fails if the new value is longer than the upper bound.
+            pub fn set(&mut self, value: &str) -> Result<(), DynamicMessageError> {
+                let value = <$inner>::from(value);
+                check_upper_bound(Some(self.upper_bound), value.len())?;
+                *self.inner = value;
+                Ok(())
+            }
+        }
+
+        #[doc = concat!("A mutable reference to a sequence of `", $idl_type, "<=N` values.")]
+        pub struct $sequence<'msg> {
+            inner: DynamicSequence<'msg, $inner>,
+            string_upper_bound: usize,
+        }
+
+        impl fmt::Debug for $sequence<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(&self.inner, f)
+            }
+        }
+
+        impl<'msg> $sequence<'msg> {
+            pub(crate) fn new(
+                inner: DynamicSequence<'msg, $inner>,
+                string_upper_bound: usize,
+            ) -> Self {
+                Self {
+                    inner,
+                    string_upper_bound,
+                }
+            }
+
+            /// Returns the number of strings.
+            pub fn len(&self) -> usize {
+                self.inner.len()
+            }
+
+            /// Returns `true` if the sequence has no strings.
+            pub fn is_empty(&self) -> bool {
+                self.inner.is_empty()
+            }
+
+            /// Returns the maximum number of strings for a bounded sequence.
+            pub fn upper_bound(&self) -> Option<usize> {
+                self.inner.upper_bound()
+            }
+
+            #[doc = concat!("Returns the maximum length of each string in ", $unit, ".")]
+            pub fn string_upper_bound(&self) -> usize {
+                self.string_upper_bound
+            }
+
+            /// Returns the strings.
+            pub fn as_slice(&self) -> &[$inner] {
+                self.inner.as_slice()
+            }
+
+            /// Returns the string at the given index for modifying it.
+            pub fn get_mut(&mut self, index: usize) -> Option<$string<'_>> {
+                let string_upper_bound = self.string_upper_bound;
+                self.inner
+                    .as_mut_slice()
+                    .get_mut(index)
+                    .map(|inner| $string::new(inner, string_upper_bound))
+            }
+
+            /// Iterates over the strings for modifying them.
+            pub fn iter_mut(&mut self) -> impl Iterator<Item = $string<'_>> {
+                let string_upper_bound = self.string_upper_bound;
+                self.inner
+                    .as_mut_slice()
+                    .iter_mut()
+                    .map(move |inner| $string::new(inner, string_upper_bound))
+            }
+
+            /// Changes the number of strings.
+            ///
+            /// Existing strings are kept if they fit, and new strings are empty.
+            /// Fails if the new length exceeds the upper bound of the sequence.
+            pub fn resize(&mut self, len: usize) -> Result<(), DynamicMessageError> {
+                self.inner.resize(len)
+            }
+        }
+    };
+}
+
+define_bounded_string_types!(
+    DynamicBoundedString,
+    DynamicBoundedStringSequence,
+    rosidl_runtime_rs::String,
+    "string",
+    "bytes"
+);
+define_bounded_string_types!(
+    DynamicBoundedWString,
+    DynamicBoundedWStringSequence,
+    rosidl_runtime_rs::WString,
+    "wstring",
+    "UTF-16 code units"
+);
+
+fn check_upper_bound(upper_bound: Option<usize>, length: usize) -> Result<(), DynamicMessageError> {
+    match upper_bound {
+        Some(upper_bound) if length > upper_bound => Err(DynamicMessageError::UpperBoundExceeded {
+            upper_bound,
+            length,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Reinterprets the memory of values of type `T`.
+///
+/// # Safety
+///
+/// The memory must contain valid values of type `T`, and be aligned for `T`.
+pub(crate) unsafe fn cast_slice<T>(bytes: &[u8]) -> &[T] {
+    let len = bytes.len() / std::mem::size_of::<T>();
+    // An empty slice must still have an aligned, non-null pointer.
+    let data = NonNull::new(bytes.as_ptr() as *mut T)
+        .filter(|_| len > 0)
+        .unwrap_or(NonNull::dangling());
+    std::slice::from_raw_parts(data.as_ptr(), len)
+}
+
+/// Reinterprets the memory of values of type `T`.
+///
+/// # Safety
+///
+/// The memory must contain valid values of type `T`, and be aligned for `T`.
+pub(crate) unsafe fn cast_slice_mut<T>(bytes: &mut [u8]) -> &mut [T] {
+    let len = bytes.len() / std::mem::size_of::<T>();
+    let data = NonNull::new(bytes.as_mut_ptr() as *mut T)
+        .filter(|_| len > 0)
+        .unwrap_or(NonNull::dangling());
+    std::slice::from_raw_parts_mut(data.as_ptr(), len)
+}
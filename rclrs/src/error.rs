@@ -32,6 +32,13 @@ pub enum RclrsError {
     },
     /// It was attempted to add a waitable to a wait set twice.
     AlreadyAddedToWaitSet,
+    /// An operation, such as a service call, did not complete before its timeout elapsed.
+    Timeout,
+    /// A service request was canceled before its response was received.
+    RequestCanceled,
+    /// A lifecycle transition was triggered from inside of a transition callback of the
+    /// same node.
+    TransitionInProgress,
 }
 
 impl RclrsError {
@@ -42,19 +49,22 @@ impl RclrsError {
             RclrsError::RclError {
                 code: RclReturnCode::Timeout,
                 ..
-            }
+            } | RclrsError::Timeout
         )
     }
 
-    /// Returns true if the error was because a subscription, service, or client
-    /// take failed, otherwise returns false.
+    /// Returns true if the error was because a subscription, service, client,
+    /// action server, action client, or QoS event take failed, otherwise returns false.
     pub fn is_take_failed(&self) -> bool {
         matches!(
             self,
             RclrsError::RclError {
                 code: RclReturnCode::SubscriptionTakeFailed
                     | RclReturnCode::ServiceTakeFailed
-                    | RclReturnCode::ClientTakeFailed,
+                    | RclReturnCode::ClientTakeFailed
+                    | RclReturnCode::ActionClientTakeFailed
+                    | RclReturnCode::ActionServerTakeFailed
+                    | RclReturnCode::EventTakeFailed,
                 ..
             }
         )
@@ -75,6 +85,16 @@ impl Display for RclrsError {
                     "Could not add entity to wait set because it was already added to a wait set"
                 )
             }
+            RclrsError::Timeout => write!(f, "The operation timed out"),
+            RclrsError::RequestCanceled => {
+                write!(f, "The request was canceled before a response was received")
+            }
+            RclrsError::TransitionInProgress => {
+                write!(
+                    f,
+                    "Cannot trigger a lifecycle transition from inside of a transition callback"
+                )
+            }
         }
     }
 }
@@ -107,6 +127,9 @@ impl Error for RclrsError {
             RclrsError::UnknownRclError { msg, .. } => msg.as_ref().map(|e| e as &dyn Error),
             RclrsError::StringContainsNul { err, .. } => Some(err).map(|e| e as &dyn Error),
             RclrsError::AlreadyAddedToWaitSet => None,
+            RclrsError::Timeout => None,
+            RclrsError::RequestCanceled => None,
+            RclrsError::TransitionInProgress => None,
         }
     }
 }
@@ -201,6 +224,25 @@ pub enum RclReturnCode {
     EventInvalid = 2000,
     /// Failed to take an event from the event handle
     EventTakeFailed = 2001,
+    // ====== 21XX: action client-specific errors ======
+    /// Action goal accepted
+    ActionGoalAccepted = 2100,
+    /// Action goal rejected
+    ActionGoalRejected = 2101,
+    /// Invalid `rcl_action_client_t` given
+    ActionClientInvalid = 2102,
+    /// Failed to take a response from the action client
+    ActionClientTakeFailed = 2103,
+    // ====== 22XX: action server-specific errors ======
+    /// Invalid `rcl_action_server_t` given
+    ActionServerInvalid = 2200,
+    /// Failed to take a request from the action server
+    ActionServerTakeFailed = 2201,
+    // ====== 23XX: action goal handle-specific errors ======
+    /// Invalid `rcl_action_goal_handle_t` given
+    ActionGoalHandleInvalid = 2300,
+    /// Invalid event for the current state of the action goal
+    ActionGoalEventInvalid = 2301,
     // ====== 30XX: lifecycle-specific errors ======
     /// `rcl_lifecycle` state registered
     LifecycleStateRegistered = 3000,
@@ -249,6 +291,14 @@ impl TryFrom<i32> for RclReturnCode {
             x if x == Self::InvalidLogLevelRule as i32 => Self::InvalidLogLevelRule,
             x if x == Self::EventInvalid as i32 => Self::EventInvalid,
             x if x == Self::EventTakeFailed as i32 => Self::EventTakeFailed,
+            x if x == Self::ActionGoalAccepted as i32 => Self::ActionGoalAccepted,
+            x if x == Self::ActionGoalRejected as i32 => Self::ActionGoalRejected,
+            x if x == Self::ActionClientInvalid as i32 => Self::ActionClientInvalid,
+            x if x == Self::ActionClientTakeFailed as i32 => Self::ActionClientTakeFailed,
+            x if x == Self::ActionServerInvalid as i32 => Self::ActionServerInvalid,
+            x if x == Self::ActionServerTakeFailed as i32 => Self::ActionServerTakeFailed,
+            x if x == Self::ActionGoalHandleInvalid as i32 => Self::ActionGoalHandleInvalid,
+            x if x == Self::ActionGoalEventInvalid as i32 => Self::ActionGoalEventInvalid,
             x if x == Self::LifecycleStateRegistered as i32 => Self::LifecycleStateRegistered,
             x if x == Self::LifecycleStateNotRegistered as i32 => Self::LifecycleStateNotRegistered,
             other => {
@@ -330,6 +380,26 @@ impl Display for RclReturnCode {
             Self::EventTakeFailed => {
                 "Failed to take an event from the event handle (RCL_RET_EVENT_TAKE_FAILED)."
             }
+            Self::ActionGoalAccepted => "Action goal accepted (RCL_RET_ACTION_GOAL_ACCEPTED).",
+            Self::ActionGoalRejected => "Action goal rejected (RCL_RET_ACTION_GOAL_REJECTED).",
+            Self::ActionClientInvalid => {
+                "Action client is invalid (RCL_RET_ACTION_CLIENT_INVALID)."
+            }
+            Self::ActionClientTakeFailed => {
+                "Failed to take a response from the action client (RCL_RET_ACTION_CLIENT_TAKE_FAILED)."
+            }
+            Self::ActionServerInvalid => {
+                "Action server is invalid (RCL_RET_ACTION_SERVER_INVALID)."
+            }
+            Self::ActionServerTakeFailed => {
+                "Failed to take a request from the action server (RCL_RET_ACTION_SERVER_TAKE_FAILED)."
+            }
+            Self::ActionGoalHandleInvalid => {
+                "Action goal handle is invalid (RCL_RET_ACTION_GOAL_HANDLE_INVALID)."
+            }
+            Self::ActionGoalEventInvalid => {
+                "Action invalid event (RCL_RET_ACTION_GOAL_EVENT_INVALID)."
+            }
             Self::LifecycleStateRegistered => {
                 "`rcl_lifecycle` state registered (RCL_RET_LIFECYCLE_STATE_REGISTERED)."
             }
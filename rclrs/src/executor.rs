@@ -1,16 +1,31 @@
 use crate::{
-    rcl_bindings::rcl_context_is_valid, Context, ContextHandle, IntoNodeOptions, Node, NodeState,
-    RclrsError, WaitSet,
+    rcl_bindings::rcl_context_is_valid, wait::WaitableEntities, ComponentContainer,
+    ComponentContainerState, ComponentRegistry, Context, ContextHandle, GuardCondition,
+    IntoNodeOptions, LifecycleNode, LifecycleNodeState, Node, NodeState, RclrsError,
 };
 use std::{
     sync::{Arc, Mutex, Weak},
     time::Duration,
 };
 
+mod multi_threaded;
+mod task_queue;
+pub use multi_threaded::*;
+pub(crate) use task_queue::*;
+
 /// Single-threaded executor implementation.
+///
+/// The executor waits for work on all of its nodes at once. All callbacks are run one
+/// after another on the thread that spins the executor, so [callback groups][1] have no
+/// effect. Use a [`MultiThreadedExecutor`] to run callbacks concurrently.
+///
+/// [1]: crate::CallbackGroup
 pub struct Executor {
     context: Arc<ContextHandle>,
-    nodes_mtx: Mutex<Vec<Weak<NodeState>>>,
+    nodes_mtx: Arc<Mutex<Vec<Weak<NodeState>>>>,
+    /// Triggered when a node is added, so that the executor stops waiting and takes the
+    /// new node into account.
+    wakeup: Arc<GuardCondition>,
 }
 
 impl Executor {
@@ -19,10 +34,36 @@ impl Executor {
         &'a self,
         options: impl IntoNodeOptions<'a>,
     ) -> Result<Node, RclrsError> {
-        let options = options.into_node_options();
-        let node = options.build(&self.context)?;
-        self.nodes_mtx.lock().unwrap().push(Arc::downgrade(&node));
-        Ok(node)
+        self.node_spawner().create_node(options)
+    }
+
+    /// Create a [`LifecycleNode`] that will run on this Executor.
+    pub fn create_lifecycle_node<'a>(
+        &'a self,
+        options: impl IntoNodeOptions<'a>,
+    ) -> Result<LifecycleNode, RclrsError> {
+        self.node_spawner().create_lifecycle_node(options)
+    }
+
+    /// Create a [`ComponentContainer`] that will run on this Executor.
+    ///
+    /// The components that the container loads run on this Executor as well.
+    pub fn create_component_container<'a>(
+        &'a self,
+        options: impl IntoNodeOptions<'a>,
+        registry: ComponentRegistry,
+    ) -> Result<ComponentContainer, RclrsError> {
+        self.node_spawner()
+            .create_component_container(options, registry)
+    }
+
+    /// Returns a [`NodeSpawner`] that creates nodes on this Executor.
+    pub fn node_spawner(&self) -> NodeSpawner {
+        NodeSpawner {
+            context: Arc::clone(&self.context),
+            nodes: Arc::clone(&self.nodes_mtx),
+            wakeup: Arc::clone(&self.wakeup),
+        }
     }
 
     /// Spin the Executor. The current thread will be blocked until the Executor
@@ -51,19 +92,34 @@ impl Executor {
         }
     }
 
-    /// Polls the nodes for new messages and executes the corresponding callbacks.
+    /// Waits for the primitives of all nodes and executes the ready callbacks.
     ///
     /// This function additionally checks that the context is still valid.
     fn spin_once(&self, timeout: Option<Duration>) -> Result<(), RclrsError> {
-        for node in { self.nodes_mtx.lock().unwrap() }
+        // Collect the nodes first, so that the lock is not held while callbacks run.
+        // Otherwise, creating a node from a callback would deadlock.
+        let nodes: Vec<Node> = { self.nodes_mtx.lock().unwrap() }
             .iter()
             .filter_map(Weak::upgrade)
             .filter(|node| unsafe {
                 rcl_context_is_valid(&*node.handle.context_handle.rcl_context.lock().unwrap())
             })
-        {
-            let wait_set = WaitSet::new_for_node(&node)?;
-            let ready_entities = wait_set.wait(timeout)?;
+            .collect();
+        if !nodes.is_empty() {
+            let mut entities = WaitableEntities::default();
+            for node in &nodes {
+                entities.extend(WaitableEntities::from_node(node));
+            }
+            entities.guard_conditions.push(Arc::clone(&self.wakeup));
+
+            let context = Context {
+                handle: Arc::clone(&self.context),
+            };
+            let ready_entities = entities.into_wait_set(&context)?.wait(timeout)?;
+
+            for ready_timer in ready_entities.timers {
+                ready_timer.execute()?;
+            }
 
             for ready_subscription in ready_entities.subscriptions {
                 ready_subscription.execute()?;
@@ -76,6 +132,26 @@ impl Executor {
             for ready_service in ready_entities.services {
                 ready_service.execute()?;
             }
+
+            for (ready_action_server, ready) in ready_entities.action_servers {
+                ready_action_server.execute(ready)?;
+            }
+
+            for (ready_action_client, ready) in ready_entities.action_clients {
+                ready_action_client.execute(ready)?;
+            }
+
+            for ready_event in ready_entities.events {
+                ready_event.execute()?;
+            }
+
+            for ready_graph_watcher in ready_entities.graph_watchers {
+                ready_graph_watcher.execute()?;
+            }
+
+            for node in &nodes {
+                node.task_queue.run_ready();
+            }
         }
 
         // Clear out any nodes that have been dropped.
@@ -90,13 +166,71 @@ impl Executor {
     /// Used by [`Context`] to create the `Executor`. Users cannot call this
     /// function.
     pub(crate) fn new(context: Arc<ContextHandle>) -> Self {
+        let wakeup = Arc::new(GuardCondition::new_with_context_handle(
+            Arc::clone(&context),
+            None,
+        ));
         Self {
             context,
-            nodes_mtx: Mutex::new(Vec::new()),
+            nodes_mtx: Arc::default(),
+            wakeup,
         }
     }
 }
 
+/// Creates nodes that run on an executor.
+///
+/// Unlike the executor itself, a `NodeSpawner` can be cloned and moved into callbacks, which
+/// allows creating nodes while the executor is spinning. Get one from
+/// [`Executor::node_spawner`] or [`MultiThreadedExecutor::node_spawner`].
+///
+/// ```
+/// # use rclrs::*;
+/// let executor = Context::default().create_basic_executor();
+/// let spawner = executor.node_spawner();
+/// let node = spawner.create_node("spawned_node")?;
+/// assert_eq!(node.name(), "spawned_node");
+/// # Ok::<(), RclrsError>(())
+/// ```
+#[derive(Clone)]
+pub struct NodeSpawner {
+    context: Arc<ContextHandle>,
+    nodes: Arc<Mutex<Vec<Weak<NodeState>>>>,
+    /// The guard condition that wakes up the executor.
+    wakeup: Arc<GuardCondition>,
+}
+
+impl NodeSpawner {
+    /// Create a [`Node`] that will run on the executor of this spawner.
+    ///
+    /// If the executor is currently waiting, it is woken up to take the new node into
+    /// account.
+    pub fn create_node<'a>(&self, options: impl IntoNodeOptions<'a>) -> Result<Node, RclrsError> {
+        let options = options.into_node_options();
+        let node = options.build(&self.context)?;
+        self.nodes.lock().unwrap().push(Arc::downgrade(&node));
+        self.wakeup.trigger()?;
+        Ok(node)
+    }
+
+    /// Create a [`LifecycleNode`] that will run on the executor of this spawner.
+    pub fn create_lifecycle_node<'a>(
+        &self,
+        options: impl IntoNodeOptions<'a>,
+    ) -> Result<LifecycleNode, RclrsError> {
+        LifecycleNodeState::new(self.create_node(options)?)
+    }
+
+    /// Create a [`ComponentContainer`] that will run on the executor of this spawner.
+    pub fn create_component_container<'a>(
+        &self,
+        options: impl IntoNodeOptions<'a>,
+        registry: ComponentRegistry,
+    ) -> Result<ComponentContainer, RclrsError> {
+        ComponentContainerState::new(self.clone(), self.create_node(options)?, registry)
+    }
+}
+
 /// A bundle of optional conditions that a user may want to impose on how long
 /// an executor spins for.
 ///
@@ -0,0 +1,505 @@
+use std::{
+    collections::HashSet,
+    sync::{mpsc, Arc, Mutex, Weak},
+    time::Duration,
+};
+
+use crate::{
+    rcl_bindings::rcl_context_is_valid, wait::WaitableEntities, CallbackGroup, ComponentContainer,
+    ComponentRegistry, Context, ContextHandle, GuardCondition, IntoNodeOptions, LifecycleNode,
+    Node, NodeSpawner, NodeState, RclrsError, SpinOptions,
+};
+
+type Work = Box<dyn FnOnce() + Send + 'static>;
+
+/// Multi-threaded executor implementation.
+///
+/// The executor waits for work on all of its nodes at once, and dispatches the ready
+/// callbacks onto a pool of worker threads. Which callbacks may run concurrently is
+/// controlled by their [`CallbackGroup`]:
+/// - At most one callback of a [mutually exclusive][1] group runs at any given time.
+/// - Callbacks of a [reentrant][2] group may run concurrently with each other.
+///
+/// Callbacks of different groups may always run concurrently. Since primitives belong to
+/// the mutually exclusive [default callback group][3] of their node unless specified
+/// otherwise, primitives need to be assigned to different groups to benefit from
+/// multiple threads.
+///
+/// A single primitive never runs its callback concurrently with itself, regardless of
+/// its callback group.
+///
+/// [1]: crate::CallbackGroupType::MutuallyExclusive
+/// [2]: crate::CallbackGroupType::Reentrant
+/// [3]: crate::NodeState::default_callback_group
+pub struct MultiThreadedExecutor {
+    context: Arc<ContextHandle>,
+    nodes_mtx: Arc<Mutex<Vec<Weak<NodeState>>>>,
+    num_threads: usize,
+    /// Triggered whenever a callback finishes or a node is added, so that the executor
+    /// stops waiting and takes the primitive of the callback or the new node into account.
+    wakeup: Arc<GuardCondition>,
+}
+
+/// State that is shared between the executor and its worker threads while spinning.
+#[derive(Default)]
+struct SpinState {
+    /// The primitives whose callbacks are currently running, identified by their address.
+    in_flight: Mutex<HashSet<usize>>,
+    /// The errors returned by callbacks.
+    errors: Mutex<Vec<RclrsError>>,
+}
+
+impl MultiThreadedExecutor {
+    /// Create a [`Node`] that will run on this executor.
+    pub fn create_node<'a>(
+        &'a self,
+        options: impl IntoNodeOptions<'a>,
+    ) -> Result<Node, RclrsError> {
+        self.node_spawner().create_node(options)
+    }
+
+    /// Create a [`LifecycleNode`] that will run on this executor.
+    pub fn create_lifecycle_node<'a>(
+        &'a self,
+        options: impl IntoNodeOptions<'a>,
+    ) -> Result<LifecycleNode, RclrsError> {
+        self.node_spawner().create_lifecycle_node(options)
+    }
+
+    /// Create a [`ComponentContainer`] that will run on this executor.
+    ///
+    /// The components that the container loads run on this executor as well.
+    pub fn create_component_container<'a>(
+        &'a self,
+        options: impl IntoNodeOptions<'a>,
+        registry: ComponentRegistry,
+    ) -> Result<ComponentContainer, RclrsError> {
+        self.node_spawner()
+            .create_component_container(options, registry)
+    }
+
+    /// Returns a [`NodeSpawner`] that creates nodes on this executor.
+    pub fn node_spawner(&self) -> NodeSpawner {
+        NodeSpawner {
+            context: Arc::clone(&self.context),
+            nodes: Arc::clone(&self.nodes_mtx),
+            wakeup: Arc::clone(&self.wakeup),
+        }
+    }
+
+    /// Returns the number of worker threads that run callbacks.
+    pub fn num_threads(&self) -> usize {
+        self.num_threads
+    }
+
+    /// Spin the executor. The current thread will be blocked until the executor
+    /// stops spinning.
+    ///
+    /// The worker threads are started when spinning begins, and are joined before this
+    /// function returns, so all callbacks that have been started have also finished by then.
+    ///
+    /// [`SpinOptions`] can be used to automatically stop the spinning when
+    /// certain conditions are met. Use `SpinOptions::default()` to allow the
+    /// executor to keep spinning indefinitely.
+    pub fn spin(&mut self, options: SpinOptions) -> Vec<RclrsError> {
+        let state = Arc::new(SpinState::default());
+        let (sender, receiver) = mpsc::channel::<Work>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        std::thread::scope(|scope| {
+            for _ in 0..self.num_threads {
+                let receiver = Arc::clone(&receiver);
+                scope.spawn(move || loop {
+                    // The lock is released before the work is run, so that other workers
+                    // can pick up work in the meantime.
+                    let work = receiver.lock().unwrap().recv();
+                    match work {
+                        Ok(work) => work(),
+                        // The executor has stopped spinning.
+                        Err(_) => break,
+                    }
+                });
+            }
+
+            loop {
+                if self.nodes_mtx.lock().unwrap().is_empty() {
+                    // Nothing to spin for, so just quit here
+                    break;
+                }
+
+                if let Err(err) = self.spin_once(options.timeout, &sender, &state) {
+                    state.errors.lock().unwrap().push(err);
+                    break;
+                }
+
+                if options.only_next_available_work || !state.errors.lock().unwrap().is_empty() {
+                    break;
+                }
+
+                std::thread::yield_now();
+            }
+
+            // Dropping the sender stops the workers once they have finished their work.
+            drop(sender);
+        });
+
+        std::mem::take(&mut *state.errors.lock().unwrap())
+    }
+
+    /// Waits for the primitives of all nodes and dispatches the ready callbacks to the
+    /// worker threads.
+    ///
+    /// Primitives whose callbacks are still running, or whose callback group is busy,
+    /// are left out of the wait, since they could not be dispatched anyway.
+    fn spin_once(
+        &self,
+        timeout: Option<Duration>,
+        sender: &mpsc::Sender<Work>,
+        state: &Arc<SpinState>,
+    ) -> Result<(), RclrsError> {
+        let mut entities = WaitableEntities::default();
+        let mut task_queues = Vec::new();
+        for node in { self.nodes_mtx.lock().unwrap() }
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter(|node| unsafe {
+                rcl_context_is_valid(&*node.handle.context_handle.rcl_context.lock().unwrap())
+            })
+        {
+            entities.extend(WaitableEntities::from_node(&node));
+            task_queues.push(Arc::clone(&node.task_queue));
+        }
+
+        {
+            let in_flight = state.in_flight.lock().unwrap();
+            let available = |key: usize, group: &CallbackGroup| {
+                !in_flight.contains(&key) && group.can_be_taken_from()
+            };
+            entities
+                .subscriptions
+                .retain(|e| available(entity_key(e), e.callback_group()));
+            entities
+                .clients
+                .retain(|e| available(entity_key(e), e.callback_group()));
+            entities
+                .services
+                .retain(|e| available(entity_key(e), e.callback_group()));
+            entities
+                .timers
+                .retain(|e| available(entity_key(e), &e.callback_group));
+            entities
+                .action_servers
+                .retain(|e| available(entity_key(e), e.callback_group()));
+            entities
+                .action_clients
+                .retain(|e| available(entity_key(e), e.callback_group()));
+            entities
+                .events
+                .retain(|e| available(entity_key(e), &e.callback_group));
+            entities
+                .graph_watchers
+                .retain(|e| available(entity_key(e), &e.callback_group));
+        }
+        entities.guard_conditions.push(Arc::clone(&self.wakeup));
+
+        let context = Context {
+            handle: Arc::clone(&self.context),
+        };
+        let ready_entities = entities.into_wait_set(&context)?.wait(timeout)?;
+
+        for ready_timer in ready_entities.timers {
+            let group = Arc::clone(&ready_timer.callback_group);
+            self.dispatch(entity_key(&ready_timer), &group, state, sender, move || {
+                ready_timer.execute()
+            });
+        }
+
+        for ready_subscription in ready_entities.subscriptions {
+            let group = Arc::clone(ready_subscription.callback_group());
+            self.dispatch(
+                entity_key(&ready_subscription),
+                &group,
+                state,
+                sender,
+                move || ready_subscription.execute(),
+            );
+        }
+
+        for ready_client in ready_entities.clients {
+            let group = Arc::clone(ready_client.callback_group());
+            self.dispatch(
+                entity_key(&ready_client),
+                &group,
+                state,
+                sender,
+                move || ready_client.execute(),
+            );
+        }
+
+        for ready_service in ready_entities.services {
+            let group = Arc::clone(ready_service.callback_group());
+            self.dispatch(
+                entity_key(&ready_service),
+                &group,
+                state,
+                sender,
+                move || ready_service.execute(),
+            );
+        }
+
+        for (ready_action_server, ready) in ready_entities.action_servers {
+            let group = Arc::clone(ready_action_server.callback_group());
+            self.dispatch(
+                entity_key(&ready_action_server),
+                &group,
+                state,
+                sender,
+                move || ready_action_server.execute(ready),
+            );
+        }
+
+        for (ready_action_client, ready) in ready_entities.action_clients {
+            let group = Arc::clone(ready_action_client.callback_group());
+            self.dispatch(
+                entity_key(&ready_action_client),
+                &group,
+                state,
+                sender,
+                move || ready_action_client.execute(ready),
+            );
+        }
+
+        for ready_event in ready_entities.events {
+            let group = Arc::clone(&ready_event.callback_group);
+            self.dispatch(entity_key(&ready_event), &group, state, sender, move || {
+                ready_event.execute()
+            });
+        }
+
+        for ready_graph_watcher in ready_entities.graph_watchers {
+            let group = Arc::clone(&ready_graph_watcher.callback_group);
+            self.dispatch(
+                entity_key(&ready_graph_watcher),
+                &group,
+                state,
+                sender,
+                move || ready_graph_watcher.execute(),
+            );
+        }
+
+        // Async tasks are not part of any callback group, so they can always be polled.
+        for task_queue in task_queues {
+            if task_queue.has_ready_tasks() {
+                let _ = sender.send(Box::new(move || task_queue.run_ready()));
+            }
+        }
+
+        // Clear out any nodes that have been dropped.
+        self.nodes_mtx
+            .lock()
+            .unwrap()
+            .retain(|weak_node| weak_node.strong_count() > 0);
+
+        Ok(())
+    }
+
+    /// Sends the callback of a primitive to the worker threads, unless its callback group
+    /// is busy. In that case, the primitive is still ready in the next wait once the
+    /// group has become available again.
+    fn dispatch(
+        &self,
+        key: usize,
+        group: &CallbackGroup,
+        state: &Arc<SpinState>,
+        sender: &mpsc::Sender<Work>,
+        execute: impl FnOnce() -> Result<(), RclrsError> + Send + 'static,
+    ) {
+        let Some(group_guard) = group.try_acquire() else {
+            return;
+        };
+        state.in_flight.lock().unwrap().insert(key);
+        let in_flight = InFlightGuard {
+            key,
+            state: Arc::clone(state),
+            wakeup: Arc::clone(&self.wakeup),
+        };
+        // The receivers are only dropped after the sender, so sending cannot fail.
+        let _ = sender.send(Box::new(move || {
+            // The group is released before the primitive, which wakes up the executor.
+            let in_flight = in_flight;
+            let _group_guard = group_guard;
+            if let Err(err) = execute() {
+                in_flight.state.errors.lock().unwrap().push(err);
+            }
+        }));
+    }
+
+    /// Used by [`Context`] to create the `MultiThreadedExecutor`. Users cannot call this
+    /// function.
+    pub(crate) fn new(context: Arc<ContextHandle>, num_threads: usize) -> Self {
+        let num_threads = if num_threads == 0 {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(2)
+        } else {
+            num_threads
+        };
+        let wakeup = Arc::new(GuardCondition::new_with_context_handle(
+            Arc::clone(&context),
+            None,
+        ));
+        Self {
+            context,
+            nodes_mtx: Arc::default(),
+            num_threads,
+            wakeup,
+        }
+    }
+}
+
+/// Keeps a primitive out of the wait set while its callback is running.
+///
+/// The primitive is released when this is dropped, even if the callback panics, so
+/// that it can be dispatched again.
+struct InFlightGuard {
+    key: usize,
+    state: Arc<SpinState>,
+    wakeup: Arc<GuardCondition>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.state.in_flight.lock().unwrap().remove(&self.key);
+        if let Err(err) = self.wakeup.trigger() {
+            self.state.errors.lock().unwrap().push(err);
+        }
+    }
+}
+
+/// Identifies a primitive by the address of its state.
+fn entity_key<T: ?Sized>(entity: &Arc<T>) -> usize {
+    Arc::as_ptr(entity) as *const () as usize
+}
+
+/// This trait allows [`Context`] to create a multi-threaded executor.
+pub trait CreateMultiThreadedExecutor {
+    /// Create a [`MultiThreadedExecutor`] associated with this [`Context`], which runs
+    /// callbacks on the given number of threads.
+    ///
+    /// If `num_threads` is zero, one thread per CPU core is used.
+    fn create_multi_threaded_executor(&self, num_threads: usize) -> MultiThreadedExecutor;
+}
+
+impl CreateMultiThreadedExecutor for Context {
+    fn create_multi_threaded_executor(&self, num_threads: usize) -> MultiThreadedExecutor {
+        MultiThreadedExecutor::new(Arc::clone(&self.handle), num_threads)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_helpers::*, *};
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
+    use test_msgs::msg;
+
+    #[test]
+    fn traits() {
+        assert_send::<MultiThreadedExecutor>();
+        assert_sync::<MultiThreadedExecutor>();
+    }
+
+    /// Runs two subscriptions in the given callback groups, whose callbacks each take
+    /// a while, and returns the maximum number of callbacks that ran at the same time.
+    fn max_concurrent_callbacks(
+        group_type: Option<CallbackGroupType>,
+        topic: &str,
+    ) -> Result<usize, RclrsError> {
+        let mut executor = Context::default().create_multi_threaded_executor(4);
+        let node = executor.create_node(&format!("test_multi_threaded_{topic}"))?;
+        let group = group_type.map(|group_type| node.create_callback_group(group_type));
+
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let finished = Arc::new(AtomicUsize::new(0));
+        let make_callback = || {
+            let running = Arc::clone(&running);
+            let max_running = Arc::clone(&max_running);
+            let finished = Arc::clone(&finished);
+            move |_msg: msg::Empty| {
+                let now_running = running.fetch_add(1, Ordering::AcqRel) + 1;
+                max_running.fetch_max(now_running, Ordering::AcqRel);
+                // Give the other callback some time to start.
+                let start = Instant::now();
+                while max_running.load(Ordering::Acquire) < 2
+                    && start.elapsed() < Duration::from_millis(500)
+                {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                running.fetch_sub(1, Ordering::AcqRel);
+                finished.fetch_add(1, Ordering::AcqRel);
+            }
+        };
+
+        let topic_1 = format!("{topic}_1");
+        let topic_2 = format!("{topic}_2");
+        let (subscription_1, subscription_2) = match &group {
+            Some(group) => (
+                node.create_subscription(topic_1.as_str().callback_group(group), make_callback())?,
+                node.create_subscription(topic_2.as_str().callback_group(group), make_callback())?,
+            ),
+            None => (
+                node.create_subscription(topic_1.as_str(), make_callback())?,
+                node.create_subscription(topic_2.as_str(), make_callback())?,
+            ),
+        };
+        let publisher_1 = node.create_publisher::<msg::Empty>(topic_1.as_str())?;
+        let publisher_2 = node.create_publisher::<msg::Empty>(topic_2.as_str())?;
+
+        let start = Instant::now();
+        while finished.load(Ordering::Acquire) < 2 {
+            // Give both messages time to arrive before spinning, so that both callbacks
+            // can be dispatched in the same round.
+            publisher_1.publish(msg::Empty::default())?;
+            publisher_2.publish(msg::Empty::default())?;
+            std::thread::sleep(Duration::from_millis(100));
+            for err in executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10))) {
+                assert!(err.is_timeout(), "{err}");
+            }
+            assert!(start.elapsed() < Duration::from_secs(10));
+        }
+
+        drop((subscription_1, subscription_2));
+        Ok(max_running.load(Ordering::Acquire))
+    }
+
+    #[test]
+    fn reentrant_callbacks_run_concurrently() -> Result<(), RclrsError> {
+        let max_running =
+            max_concurrent_callbacks(Some(CallbackGroupType::Reentrant), "reentrant")?;
+        assert_eq!(max_running, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn mutually_exclusive_callbacks_run_one_at_a_time() -> Result<(), RclrsError> {
+        let max_running = max_concurrent_callbacks(
+            Some(CallbackGroupType::MutuallyExclusive),
+            "mutually_exclusive",
+        )?;
+        assert_eq!(max_running, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn default_group_is_mutually_exclusive() -> Result<(), RclrsError> {
+        let max_running = max_concurrent_callbacks(None, "default_group")?;
+        assert_eq!(max_running, 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    future::Future,
+    sync::{Arc, Mutex, Weak},
+    task::Context as TaskContext,
+    time::Duration,
+};
+
+use futures::{
+    future::BoxFuture,
+    task::{waker_ref, ArcWake},
+};
+
+use crate::{
+    CallbackGroupState, CallbackGroupType, Clock, ContextHandle, GuardCondition, RclrsError, Timer,
+    TimerState,
+};
+
+thread_local! {
+    /// The queue whose tasks are currently being polled on this thread.
+    static CURRENT_TASK_QUEUE: RefCell<Weak<TaskQueue>> = RefCell::new(Weak::new());
+}
+
+/// A queue of asynchronous tasks that are polled by the executor of a node.
+///
+/// Whenever a task is woken up, it is put back into the queue and the guard condition
+/// of the queue is triggered. Since the guard condition belongs to the node, this wakes
+/// up the executor, which then polls the task again.
+///
+/// Tasks can also wait for a point in time with a wakeup timer. These timers are part of
+/// the wait set of the node, so that the executor wakes up when they elapse.
+pub(crate) struct TaskQueue {
+    ready: Mutex<VecDeque<Arc<Task>>>,
+    pub(crate) guard_condition: Arc<GuardCondition>,
+    context_handle: Arc<ContextHandle>,
+    timers: Mutex<Vec<Weak<TimerState>>>,
+}
+
+/// A future that has been spawned onto a [`TaskQueue`].
+struct Task {
+    /// The future is `None` once it has completed.
+    ///
+    /// The lock is held while the future is polled, so that a task that is woken up while
+    /// it is being polled on one thread is not polled on another thread at the same time.
+    future: Mutex<Option<BoxFuture<'static, ()>>>,
+    queue: Weak<TaskQueue>,
+}
+
+impl ArcWake for Task {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        if let Some(queue) = arc_self.queue.upgrade() {
+            queue.push(Arc::clone(arc_self));
+        }
+    }
+}
+
+impl TaskQueue {
+    pub(crate) fn new(context_handle: Arc<ContextHandle>) -> Self {
+        Self {
+            ready: Mutex::new(VecDeque::new()),
+            guard_condition: Arc::new(GuardCondition::new_with_context_handle(
+                Arc::clone(&context_handle),
+                None,
+            )),
+            context_handle,
+            timers: Mutex::default(),
+        }
+    }
+
+    /// Returns the queue whose tasks are being polled on the current thread, if any.
+    pub(crate) fn current() -> Option<Arc<TaskQueue>> {
+        CURRENT_TASK_QUEUE.with(|current| current.borrow().upgrade())
+    }
+
+    /// Adds a future to the queue. It will be polled for the first time the next time the
+    /// executor checks the queue.
+    pub(crate) fn spawn<F>(self: &Arc<Self>, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.push(Arc::new(Task {
+            future: Mutex::new(Some(Box::pin(future))),
+            queue: Arc::downgrade(self),
+        }));
+    }
+
+    /// Returns true if there are tasks waiting to be polled.
+    pub(crate) fn has_ready_tasks(&self) -> bool {
+        !self.ready.lock().unwrap().is_empty()
+    }
+
+    /// Polls all tasks that have been woken up since the last call.
+    ///
+    /// Tasks that are woken up while this function runs are polled in the next call.
+    pub(crate) fn run_ready(self: &Arc<Self>) {
+        let ready = std::mem::take(&mut *self.ready.lock().unwrap());
+        let _current = CurrentTaskQueue::enter(self);
+        for task in ready {
+            let waker = waker_ref(&task);
+            let mut task_context = TaskContext::from_waker(&waker);
+            let mut future_slot = task.future.lock().unwrap();
+            if let Some(future) = future_slot.as_mut() {
+                if future.as_mut().poll(&mut task_context).is_ready() {
+                    *future_slot = None;
+                }
+            }
+        }
+    }
+
+    /// Creates a timer that calls `wake` once, when `delay` has passed according to `clock`.
+    ///
+    /// The timer is run by the executor of the queue until it elapses or is dropped.
+    pub(crate) fn create_wakeup_timer(
+        &self,
+        clock: Clock,
+        delay: Duration,
+        wake: impl Fn() + Send + 'static,
+    ) -> Result<Timer, RclrsError> {
+        let timer = Arc::new(TimerState::new(
+            &self.context_handle,
+            clock,
+            delay,
+            Box::new(move |timer: &TimerState| {
+                wake();
+                // Canceling only fails if the timer is invalid, which cannot happen while
+                // its callback runs.
+                let _ = timer.cancel();
+            }),
+            Arc::new(CallbackGroupState::new(CallbackGroupType::Reentrant)),
+        )?);
+        {
+            let mut timers = self.timers.lock().unwrap();
+            timers.retain(|timer| timer.strong_count() > 0);
+            timers.push(Arc::downgrade(&timer));
+        }
+        // Wake up the executor, so that it includes the new timer in its wait set.
+        let _ = self.guard_condition.trigger();
+        Ok(timer)
+    }
+
+    /// Returns the wakeup timers that are still in use.
+    pub(crate) fn live_timers(&self) -> Vec<Arc<TimerState>> {
+        { self.timers.lock().unwrap() }
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+
+    fn push(&self, task: Arc<Task>) {
+        self.ready.lock().unwrap().push_back(task);
+        // Triggering the guard condition can only fail if it is invalid, which cannot
+        // happen while the queue exists.
+        let _ = self.guard_condition.trigger();
+    }
+}
+
+/// Marks a queue as the current one of this thread while its tasks are polled.
+struct CurrentTaskQueue {
+    previous: Weak<TaskQueue>,
+}
+
+impl CurrentTaskQueue {
+    fn enter(queue: &Arc<TaskQueue>) -> Self {
+        let previous = CURRENT_TASK_QUEUE.with(|current| current.replace(Arc::downgrade(queue)));
+        Self { previous }
+    }
+}
+
+impl Drop for CurrentTaskQueue {
+    fn drop(&mut self) {
+        let previous = std::mem::take(&mut self.previous);
+        CURRENT_TASK_QUEUE.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Context;
+    use futures::channel::oneshot;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn task_is_polled_again_after_wake() {
+        let context = Context::default();
+        let queue = Arc::new(TaskQueue::new(Arc::clone(&context.handle)));
+        let (sender, receiver) = oneshot::channel::<()>();
+        let done = Arc::new(AtomicBool::new(false));
+        let done_inner = Arc::clone(&done);
+        queue.spawn(async move {
+            receiver.await.unwrap();
+            done_inner.store(true, Ordering::Release);
+        });
+
+        assert!(queue.has_ready_tasks());
+        queue.run_ready();
+        assert!(!queue.has_ready_tasks());
+        assert!(!done.load(Ordering::Acquire));
+
+        sender.send(()).unwrap();
+        assert!(queue.has_ready_tasks());
+        queue.run_ready();
+        assert!(done.load(Ordering::Acquire));
+    }
+}
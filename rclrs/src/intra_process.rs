@@ -0,0 +1,397 @@
+use std::{
+    any::Any,
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::SystemTime,
+};
+
+use rosidl_runtime_rs::Message;
+
+use crate::{
+    error::ToResult, rcl_bindings::*, ContextHandle, GuardCondition, MessageInfo, PublisherGid,
+    QoSDurabilityPolicy, QoSHistoryPolicy, QoSProfile,
+};
+
+/// Keeps track of the publishers and subscriptions of a context that communicate
+/// without going through the middleware.
+///
+/// Publishers are identified by their GID, so that subscriptions can discard the copies
+/// of their messages that also arrive through the middleware. Subscriptions are kept as
+/// type-erased [`IntraProcessBuffer`]s, together with their topic name and QoS profile.
+#[derive(Default)]
+pub(crate) struct IntraProcessManager {
+    publishers: Mutex<Vec<PublisherGid>>,
+    subscriptions: Mutex<Vec<IntraProcessSubscription>>,
+}
+
+struct IntraProcessSubscription {
+    topic: String,
+    qos: QoSProfile,
+    buffer: Weak<dyn Any + Send + Sync>,
+}
+
+impl IntraProcessManager {
+    /// Returns true if the message with this publisher GID was published by an
+    /// intra-process publisher, and has therefore already been delivered to the
+    /// intra-process subscriptions.
+    pub(crate) fn is_intra_process_publisher(&self, gid: &PublisherGid) -> bool {
+        self.publishers.lock().unwrap().contains(gid)
+    }
+
+    pub(crate) fn add_subscription<T: Message>(
+        &self,
+        topic: String,
+        qos: QoSProfile,
+        buffer: &Arc<IntraProcessBuffer<T>>,
+    ) {
+        let buffer = Arc::downgrade(buffer) as Weak<dyn Any + Send + Sync>;
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        subscriptions.retain(|subscription| subscription.buffer.strong_count() > 0);
+        subscriptions.push(IntraProcessSubscription { topic, qos, buffer });
+    }
+
+    /// Returns the buffers of all live intra-process subscriptions for messages of type `T`
+    /// on the given topic, whose QoS profile is compatible with that of the publisher.
+    fn subscriptions<T: Message>(
+        &self,
+        topic: &str,
+        publisher_qos: &QoSProfile,
+    ) -> Vec<Arc<IntraProcessBuffer<T>>> {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|subscription| {
+                subscription.topic == topic && qos_is_compatible(publisher_qos, &subscription.qos)
+            })
+            .filter_map(|subscription| {
+                subscription
+                    .buffer
+                    .upgrade()?
+                    .downcast::<IntraProcessBuffer<T>>()
+                    .ok()
+            })
+            .collect()
+    }
+}
+
+/// Returns true if publishers and subscriptions with this QoS profile can use intra-process
+/// communication.
+///
+/// Late-joining subscriptions with transient local durability need the history that is
+/// kept by the middleware, so these always communicate through it.
+pub(crate) fn qos_supports_intra_process(qos: &QoSProfile) -> bool {
+    qos.durability != QoSDurabilityPolicy::TransientLocal
+}
+
+/// Returns true if the middleware would match a publisher and a subscription with these
+/// QoS profiles, e.g. not for a best effort publisher and a reliable subscription.
+///
+/// Profiles that may or may not match, e.g. because they use system default policies, are
+/// treated as compatible.
+fn qos_is_compatible(publisher_qos: &QoSProfile, subscription_qos: &QoSProfile) -> bool {
+    let mut compatibility = rmw_qos_compatibility_type_t::RMW_QOS_COMPATIBILITY_OK;
+    // SAFETY: The profiles are passed by value and the out-parameter is a valid pointer.
+    // No reason is requested, so the reason buffer may be null.
+    let result = unsafe {
+        rmw_qos_profile_check_compatible(
+            (*publisher_qos).into(),
+            (*subscription_qos).into(),
+            &mut compatibility,
+            std::ptr::null_mut(),
+            0,
+        )
+    }
+    .ok();
+    // The check only fails for invalid arguments, in which case the middleware decides.
+    result.is_err() || compatibility != rmw_qos_compatibility_type_t::RMW_QOS_COMPATIBILITY_ERROR
+}
+
+/// The intra-process part of a publisher.
+///
+/// The publisher is registered with the [`IntraProcessManager`] of its context until
+/// this is dropped.
+pub(crate) struct IntraProcessPublisher {
+    context_handle: Arc<ContextHandle>,
+    gid: PublisherGid,
+    topic: String,
+    qos: QoSProfile,
+    sequence_number: AtomicU64,
+}
+
+impl IntraProcessPublisher {
+    pub(crate) fn new(
+        context_handle: Arc<ContextHandle>,
+        gid: PublisherGid,
+        topic: String,
+        qos: QoSProfile,
+    ) -> Self {
+        { context_handle.intra_process.publishers.lock() }
+            .unwrap()
+            .push(gid.clone());
+        Self {
+            context_handle,
+            gid,
+            topic,
+            qos,
+            sequence_number: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the buffers of the intra-process subscriptions that currently match
+    /// this publisher.
+    pub(crate) fn subscriptions<T: Message>(&self) -> Vec<Arc<IntraProcessBuffer<T>>> {
+        self.context_handle
+            .intra_process
+            .subscriptions(&self.topic, &self.qos)
+    }
+
+    /// Hands the message to the given subscriptions.
+    ///
+    /// The subscriptions share the message, so it is only cloned when more than one of
+    /// them still holds it by the time it is taken.
+    pub(crate) fn deliver<T: Message>(
+        &self,
+        subscriptions: &[Arc<IntraProcessBuffer<T>>],
+        message: T,
+    ) {
+        let now = SystemTime::now();
+        let message_info = MessageInfo {
+            source_timestamp: Some(now),
+            received_timestamp: Some(now),
+            publication_sequence_number: self.sequence_number.fetch_add(1, Ordering::Relaxed) + 1,
+            // The subscription does not number the messages it receives this way.
+            reception_sequence_number: u64::MAX,
+            publisher_gid: self.gid.clone(),
+        };
+        let message = Arc::new(message);
+        for subscription in subscriptions {
+            subscription.push(Arc::clone(&message), message_info.clone());
+        }
+    }
+}
+
+impl Drop for IntraProcessPublisher {
+    fn drop(&mut self) {
+        let mut publishers = self.context_handle.intra_process.publishers.lock().unwrap();
+        if let Some(index) = publishers.iter().position(|gid| *gid == self.gid) {
+            publishers.swap_remove(index);
+        }
+    }
+}
+
+/// The queue of messages that were delivered to a subscription by intra-process
+/// publishers, but not taken yet.
+///
+/// Like the queue of the middleware, it holds up to the depth of the subscription's
+/// QoS history, dropping the oldest message when it is full.
+pub(crate) struct IntraProcessBuffer<T: Message> {
+    messages: Mutex<VecDeque<(Arc<T>, MessageInfo)>>,
+    depth: Option<usize>,
+    /// Triggered whenever the buffer is not empty, so that the subscription is woken up
+    /// in the wait set.
+    pub(crate) guard_condition: Arc<GuardCondition>,
+}
+
+impl<T: Message> IntraProcessBuffer<T> {
+    pub(crate) fn new(context_handle: Arc<ContextHandle>, qos: &QoSProfile) -> Self {
+        let depth = match qos.history {
+            QoSHistoryPolicy::KeepLast { depth } | QoSHistoryPolicy::SystemDefault { depth } => {
+                Some((depth as usize).max(1))
+            }
+            QoSHistoryPolicy::KeepAll => None,
+        };
+        Self {
+            messages: Mutex::new(VecDeque::new()),
+            depth,
+            guard_condition: Arc::new(GuardCondition::new_with_context_handle(
+                context_handle,
+                None,
+            )),
+        }
+    }
+
+    fn push(&self, message: Arc<T>, message_info: MessageInfo) {
+        {
+            let mut messages = self.messages.lock().unwrap();
+            if let Some(depth) = self.depth {
+                while messages.len() >= depth {
+                    messages.pop_front();
+                }
+            }
+            messages.push_back((message, message_info));
+        }
+        // This only fails if the guard condition is invalid, which it never is.
+        let _ = self.guard_condition.trigger();
+    }
+
+    /// Takes the oldest message out of the buffer.
+    pub(crate) fn pop(&self) -> Option<(T, MessageInfo)> {
+        let (message, message_info) = {
+            let mut messages = self.messages.lock().unwrap();
+            let next = messages.pop_front()?;
+            if !messages.is_empty() {
+                // Wake up the subscription again for the remaining messages.
+                let _ = self.guard_condition.trigger();
+            }
+            next
+        };
+        let message = Arc::try_unwrap(message).unwrap_or_else(|message| (*message).clone());
+        Some((message, message_info))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+    use test_msgs::msg;
+
+    #[test]
+    fn test_intra_process_delivery() -> Result<(), RclrsError> {
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node(
+            format!("test_intra_process_delivery_{}", line!())
+                .as_str()
+                .use_intra_process_comms(true),
+        )?;
+        let other_node =
+            executor.create_node(&format!("test_intra_process_middleware_{}", line!()))?;
+        let topic = format!("test_intra_process_delivery_topic_{}", line!());
+
+        let intra_process_received = Arc::new(Mutex::new(Vec::new()));
+        let _intra_process_subscription = {
+            let received = Arc::clone(&intra_process_received);
+            node.create_subscription(topic.as_str(), move |msg: msg::BasicTypes| {
+                received.lock().unwrap().push(msg.int32_value);
+            })?
+        };
+        let middleware_received = Arc::new(Mutex::new(Vec::new()));
+        let _middleware_subscription = {
+            let received = Arc::clone(&middleware_received);
+            other_node.create_subscription(topic.as_str(), move |msg: msg::BasicTypes| {
+                received.lock().unwrap().push(msg.int32_value);
+            })?
+        };
+
+        let publisher = node.create_publisher::<msg::BasicTypes>(topic.as_str())?;
+        assert!(publisher.wait_for_subscribers(2, Some(Duration::from_secs(10)))?);
+        for int32_value in 0..3 {
+            publisher.publish(msg::BasicTypes {
+                int32_value,
+                ..Default::default()
+            })?;
+        }
+
+        let start = Instant::now();
+        while middleware_received.lock().unwrap().len() < 3
+            || intra_process_received.lock().unwrap().len() < 3
+        {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+        // The copies that went through the middleware are not delivered a second time.
+        executor.spin(SpinOptions::default().timeout(Duration::from_millis(100)));
+        assert_eq!(*intra_process_received.lock().unwrap(), [0, 1, 2]);
+        assert_eq!(*middleware_received.lock().unwrap(), [0, 1, 2]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_intra_process_only_bypasses_middleware() -> Result<(), RclrsError> {
+        let executor = Context::default().create_basic_executor();
+        let node = executor.create_node(
+            format!("test_intra_process_only_{}", line!())
+                .as_str()
+                .use_intra_process_comms(true),
+        )?;
+        let topic = format!("test_intra_process_only_topic_{}", line!());
+
+        let subscription = node.create_subscription(topic.as_str(), |_: msg::BasicTypes| {})?;
+        let publisher = node.create_publisher::<msg::BasicTypes>(topic.as_str())?;
+        // The middleware matches the intra-process subscription as well.
+        assert!(publisher.wait_for_subscribers(1, Some(Duration::from_secs(10)))?);
+        publisher.publish(msg::BasicTypes {
+            int32_value: 1,
+            ..Default::default()
+        })?;
+        assert_eq!(subscription.take()?.0.int32_value, 1);
+
+        // Nothing was published through the middleware, since the only subscription
+        // received the message directly.
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(matches!(
+            subscription.take_serialized(),
+            Err(RclrsError::RclError {
+                code: RclReturnCode::SubscriptionTakeFailed,
+                ..
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_intra_process_respects_depth() -> Result<(), RclrsError> {
+        let executor = Context::default().create_basic_executor();
+        let node = executor.create_node(
+            format!("test_intra_process_depth_{}", line!())
+                .as_str()
+                .use_intra_process_comms(true),
+        )?;
+        let topic = format!("test_intra_process_depth_topic_{}", line!());
+
+        let subscription =
+            node.create_subscription(topic.as_str().keep_last(2), |_: msg::BasicTypes| {})?;
+        let publisher = node.create_publisher::<msg::BasicTypes>(topic.as_str().keep_last(2))?;
+        for int32_value in 0..5 {
+            publisher.publish(msg::BasicTypes {
+                int32_value,
+                ..Default::default()
+            })?;
+        }
+
+        assert_eq!(subscription.take()?.0.int32_value, 3);
+        assert_eq!(subscription.take_boxed()?.0.int32_value, 4);
+        assert!(matches!(
+            subscription.take(),
+            Err(RclrsError::RclError {
+                code: RclReturnCode::SubscriptionTakeFailed,
+                ..
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_intra_process_respects_qos_compatibility() -> Result<(), RclrsError> {
+        let executor = Context::default().create_basic_executor();
+        let node = executor.create_node(
+            format!("test_intra_process_qos_{}", line!())
+                .as_str()
+                .use_intra_process_comms(true),
+        )?;
+        let topic = format!("test_intra_process_qos_topic_{}", line!());
+
+        let subscription =
+            node.create_subscription(topic.as_str().reliable(), |_: msg::BasicTypes| {})?;
+        let publisher = node.create_publisher::<msg::BasicTypes>(topic.as_str().best_effort())?;
+        publisher.publish(msg::BasicTypes::default())?;
+
+        // The middleware does not match a best effort publisher with a reliable subscription,
+        // so the message is not delivered directly either.
+        assert!(matches!(
+            subscription.take(),
+            Err(RclrsError::RclError {
+                code: RclReturnCode::SubscriptionTakeFailed,
+                ..
+            })
+        ));
+        Ok(())
+    }
+}
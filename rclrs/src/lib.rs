@@ -5,21 +5,32 @@
 //!
 //! [1]: https://github.com/ros2-rust/ros2_rust/blob/main/README.md
 
+mod action;
 mod arguments;
+mod callback_group;
 mod client;
 mod clock;
+mod component;
 mod context;
 mod error;
 mod executor;
+mod intra_process;
+mod lifecycle;
 mod logging;
 mod node;
 mod parameter;
 mod publisher;
 mod qos;
+mod qos_event;
+mod rate;
+mod serialized_message;
 mod service;
+#[cfg(not(ros_distro = "humble"))]
+mod service_introspection;
 mod subscription;
 mod time;
 mod time_source;
+mod timer;
 mod vendor;
 mod wait;
 
@@ -28,23 +39,36 @@ mod test_helpers;
 
 mod rcl_bindings;
 
+#[cfg(feature = "dyn_msg")]
+pub mod bag;
 #[cfg(feature = "dyn_msg")]
 pub mod dynamic_message;
 
+pub use action::*;
 pub use arguments::*;
+pub use callback_group::*;
 pub use client::*;
 pub use clock::*;
+pub use component::*;
 pub use context::*;
 pub use error::*;
 pub use executor::*;
+use intra_process::*;
+pub use lifecycle::*;
 pub use logging::*;
 pub use node::*;
 pub use parameter::*;
 pub use publisher::*;
 pub use qos::*;
+pub use qos_event::*;
+pub use rate::*;
 pub use rcl_bindings::rmw_request_id_t;
+pub use serialized_message::*;
 pub use service::*;
+#[cfg(not(ros_distro = "humble"))]
+pub use service_introspection::*;
 pub use subscription::*;
 pub use time::*;
 use time_source::*;
+pub use timer::*;
 pub use wait::*;
@@ -0,0 +1,871 @@
+use std::{
+    ffi::{CStr, CString},
+    ops::Deref,
+    os::raw::c_char,
+    sync::{Arc, Mutex, Weak},
+    thread::{self, ThreadId},
+};
+
+use rosidl_runtime_rs::{Message, RmwMessage};
+
+use crate::{
+    error::ToResult,
+    rcl_bindings::*,
+    vendor::lifecycle_msgs::{msg::TransitionEvent, srv},
+    Node, NodeHandle, Publisher, PublisherOptions, RclrsError, ENTITY_LIFECYCLE_MUTEX,
+};
+
+mod lifecycle_publisher;
+pub use lifecycle_publisher::*;
+
+mod lifecycle_services;
+use lifecycle_services::*;
+
+// SAFETY: The functions accessing this type, including drop(), shouldn't care about the thread
+// they are running in. Therefore, this type can be safely sent to another thread.
+unsafe impl Send for rcl_lifecycle_state_machine_t {}
+
+/// Manage the lifecycle of an `rcl_lifecycle_state_machine_t`, including managing its
+/// dependency on `rcl_node_t` by ensuring that this dependency is [dropped after][1] the
+/// `rcl_lifecycle_state_machine_t`.
+///
+/// [1]: <https://doc.rust-lang.org/reference/destructors.html>
+struct LifecycleStateMachineHandle {
+    rcl_state_machine: Mutex<rcl_lifecycle_state_machine_t>,
+    node_handle: Arc<NodeHandle>,
+}
+
+impl Drop for LifecycleStateMachineHandle {
+    fn drop(&mut self) {
+        let rcl_state_machine = self.rcl_state_machine.get_mut().unwrap();
+        let mut rcl_node = self.node_handle.rcl_node.lock().unwrap();
+        let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+        // SAFETY: The entity lifecycle mutex is locked to protect against the risk of
+        // global variables in the rmw implementation being unsafely modified during cleanup.
+        unsafe {
+            rcl_lifecycle_state_machine_fini(rcl_state_machine, &mut *rcl_node);
+        }
+    }
+}
+
+/// A state of the [lifecycle state machine][1].
+///
+/// [1]: https://design.ros2.org/articles/node_lifecycle.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LifecycleState {
+    /// The ID of the state, which is one of the associated constants of this type.
+    pub id: u8,
+    /// The human-readable name of the state.
+    pub label: String,
+}
+
+impl LifecycleState {
+    /// The state has not been set yet.
+    pub const PRIMARY_STATE_UNKNOWN: u8 = 0;
+    /// The state of a node right after it has been created.
+    pub const PRIMARY_STATE_UNCONFIGURED: u8 = 1;
+    /// The node is configured, but does not perform any processing.
+    pub const PRIMARY_STATE_INACTIVE: u8 = 2;
+    /// The node performs its processing.
+    pub const PRIMARY_STATE_ACTIVE: u8 = 3;
+    /// The state of a node right before it is destroyed.
+    pub const PRIMARY_STATE_FINALIZED: u8 = 4;
+    /// The `on_configure` callback is running.
+    pub const TRANSITION_STATE_CONFIGURING: u8 = 10;
+    /// The `on_cleanup` callback is running.
+    pub const TRANSITION_STATE_CLEANINGUP: u8 = 11;
+    /// The `on_shutdown` callback is running.
+    pub const TRANSITION_STATE_SHUTTINGDOWN: u8 = 12;
+    /// The `on_activate` callback is running.
+    pub const TRANSITION_STATE_ACTIVATING: u8 = 13;
+    /// The `on_deactivate` callback is running.
+    pub const TRANSITION_STATE_DEACTIVATING: u8 = 14;
+    /// The `on_error` callback is running.
+    pub const TRANSITION_STATE_ERRORPROCESSING: u8 = 15;
+
+    /// SAFETY: The label of the state must be a valid C string.
+    unsafe fn from_rcl(rcl_state: &rcl_lifecycle_state_t) -> Self {
+        Self {
+            id: rcl_state.id,
+            label: CStr::from_ptr(rcl_state.label)
+                .to_string_lossy()
+                .into_owned(),
+        }
+    }
+}
+
+/// A transition between two states of the [lifecycle state machine][1].
+///
+/// [1]: https://design.ros2.org/articles/node_lifecycle.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LifecycleTransition {
+    /// The ID of the transition. The IDs of the transitions that can be requested by
+    /// users are the associated constants of this type.
+    pub id: u8,
+    /// The human-readable name of the transition.
+    pub label: String,
+    /// The state that the transition starts in.
+    pub start_state: LifecycleState,
+    /// The state that the transition leads to.
+    pub goal_state: LifecycleState,
+}
+
+impl LifecycleTransition {
+    /// Runs the `on_configure` callback.
+    pub const TRANSITION_CONFIGURE: u8 = 1;
+    /// Runs the `on_cleanup` callback.
+    pub const TRANSITION_CLEANUP: u8 = 2;
+    /// Runs the `on_activate` callback.
+    pub const TRANSITION_ACTIVATE: u8 = 3;
+    /// Runs the `on_deactivate` callback.
+    pub const TRANSITION_DEACTIVATE: u8 = 4;
+    /// Runs the `on_shutdown` callback while the node is unconfigured.
+    pub const TRANSITION_UNCONFIGURED_SHUTDOWN: u8 = 5;
+    /// Runs the `on_shutdown` callback while the node is inactive.
+    pub const TRANSITION_INACTIVE_SHUTDOWN: u8 = 6;
+    /// Runs the `on_shutdown` callback while the node is active.
+    pub const TRANSITION_ACTIVE_SHUTDOWN: u8 = 7;
+
+    /// SAFETY: The transition must point to valid states and the labels must be valid
+    /// C strings.
+    unsafe fn from_rcl(rcl_transition: &rcl_lifecycle_transition_t) -> Self {
+        Self {
+            id: rcl_transition.id as u8,
+            label: CStr::from_ptr(rcl_transition.label)
+                .to_string_lossy()
+                .into_owned(),
+            start_state: LifecycleState::from_rcl(&*rcl_transition.start),
+            goal_state: LifecycleState::from_rcl(&*rcl_transition.goal),
+        }
+    }
+}
+
+/// The outcome of a transition callback of a [`LifecycleNode`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransitionCallbackReturn {
+    /// The transition succeeded and the node moves on to the goal state of the transition.
+    Success,
+    /// The transition failed and the node returns to the state it started in.
+    Failure,
+    /// An error occurred. The `on_error` callback is run to decide whether the node can
+    /// recover, which moves it into the unconfigured state, or not, which finalizes it.
+    Error,
+}
+
+impl TransitionCallbackReturn {
+    fn label(self) -> *const c_char {
+        // SAFETY: The labels are constant global strings of rcl_lifecycle.
+        unsafe {
+            match self {
+                Self::Success => rcl_lifecycle_transition_success_label,
+                Self::Failure => rcl_lifecycle_transition_failure_label,
+                Self::Error => rcl_lifecycle_transition_error_label,
+            }
+        }
+    }
+}
+
+type TransitionCallback = Box<dyn FnMut(&LifecycleState) -> TransitionCallbackReturn + Send>;
+
+#[derive(Default)]
+struct TransitionCallbacks {
+    on_configure: Option<TransitionCallback>,
+    on_cleanup: Option<TransitionCallback>,
+    on_shutdown: Option<TransitionCallback>,
+    on_activate: Option<TransitionCallback>,
+    on_deactivate: Option<TransitionCallback>,
+    on_error: Option<TransitionCallback>,
+}
+
+/// Identifies a transition that starts in the current state.
+#[derive(Clone, Copy)]
+enum TransitionKey {
+    Id(u8),
+    Label(*const c_char),
+}
+
+/// A node with a managed lifecycle, also known as a [lifecycle node][1].
+///
+/// Create a lifecycle node using [`Executor::create_lifecycle_node`][2].
+///
+/// A lifecycle node starts out in the unconfigured state and is moved through the
+/// states of the standard lifecycle state machine by transitions. Transitions can be
+/// triggered from within the process with e.g. [`configure()`][3] and
+/// [`activate()`][4], or by other processes through the `~/change_state` service.
+/// While a transition is performed, the corresponding callback runs, e.g. the one
+/// registered with [`on_configure()`][5]. Transitions without a callback always
+/// succeed.
+///
+/// Besides `~/change_state`, the node offers the `~/get_state`,
+/// `~/get_available_states`, `~/get_available_transitions` and
+/// `~/get_transition_graph` services and publishes every transition on the
+/// `~/transition_event` topic.
+///
+/// A `LifecycleNode` dereferences to a regular [`Node`], so all primitives can be
+/// created on it as usual. Publishers that should only publish while the node is active
+/// can be created with [`create_lifecycle_publisher()`][6].
+///
+/// [1]: https://design.ros2.org/articles/node_lifecycle.html
+/// [2]: crate::Executor::create_lifecycle_node
+/// [3]: LifecycleNodeState::configure
+/// [4]: LifecycleNodeState::activate
+/// [5]: LifecycleNodeState::on_configure
+/// [6]: LifecycleNodeState::create_lifecycle_publisher
+pub type LifecycleNode = Arc<LifecycleNodeState>;
+
+/// The inner state of a [`LifecycleNode`].
+///
+/// This is public so that you can choose to put it inside a [`Weak`] if you
+/// want to be able to refer to a [`LifecycleNode`] in a non-owning way.
+///
+/// The public API of the [`LifecycleNode`] type is implemented via `LifecycleNodeState`.
+pub struct LifecycleNodeState {
+    handle: LifecycleStateMachineHandle,
+    /// Held for the whole duration of a transition, so that transitions don't interleave.
+    transition_mtx: Mutex<()>,
+    /// The thread that is currently performing a transition, if any.
+    transition_thread: Mutex<Option<ThreadId>>,
+    callbacks_mtx: Mutex<TransitionCallbacks>,
+    managed_entities_mtx: Mutex<Vec<Weak<dyn ManagedEntity>>>,
+    transition_event_publisher: Publisher<TransitionEvent>,
+    services: Mutex<Option<LifecycleServices>>,
+    node: Node,
+}
+
+impl Deref for LifecycleNodeState {
+    type Target = Node;
+
+    fn deref(&self) -> &Node {
+        &self.node
+    }
+}
+
+impl LifecycleNodeState {
+    /// Turns a regular node into a lifecycle node. Users cannot call this function,
+    /// see [`Executor::create_lifecycle_node`][1] instead.
+    ///
+    /// [1]: crate::Executor::create_lifecycle_node
+    pub(crate) fn new(node: Node) -> Result<LifecycleNode, RclrsError> {
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut rcl_state_machine = unsafe { rcl_lifecycle_get_zero_initialized_state_machine() };
+        // SAFETY: No preconditions for this function.
+        let mut state_machine_options =
+            unsafe { rcl_lifecycle_get_default_state_machine_options() };
+        // The services and the transition event publisher are regular rclrs primitives,
+        // so that they are executed like all other primitives of the node.
+        state_machine_options.enable_com_interface = false;
+        {
+            let mut rcl_node = node.handle.rcl_node.lock().unwrap();
+            let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+            // SAFETY: The state machine is zero-initialized as expected by this function.
+            // The type supports are only used by the communication interface, which is
+            // disabled, but they are valid nonetheless.
+            // The entity lifecycle mutex is locked to protect against the risk of global
+            // variables in the rmw implementation being unsafely modified during initialization.
+            unsafe {
+                rcl_lifecycle_state_machine_init(
+                    &mut rcl_state_machine,
+                    &mut *rcl_node,
+                    <TransitionEvent as Message>::RmwMsg::get_type_support()
+                        as *const rosidl_message_type_support_t,
+                    <srv::ChangeState as rosidl_runtime_rs::Service>::get_type_support()
+                        as *const rosidl_service_type_support_t,
+                    <srv::GetState as rosidl_runtime_rs::Service>::get_type_support()
+                        as *const rosidl_service_type_support_t,
+                    <srv::GetAvailableStates as rosidl_runtime_rs::Service>::get_type_support()
+                        as *const rosidl_service_type_support_t,
+                    <srv::GetAvailableTransitions as rosidl_runtime_rs::Service>::get_type_support()
+                        as *const rosidl_service_type_support_t,
+                    <srv::GetAvailableTransitions as rosidl_runtime_rs::Service>::get_type_support()
+                        as *const rosidl_service_type_support_t,
+                    &state_machine_options,
+                )
+                .ok()?;
+            }
+        }
+        let handle = LifecycleStateMachineHandle {
+            rcl_state_machine: Mutex::new(rcl_state_machine),
+            node_handle: Arc::clone(&node.handle),
+        };
+
+        let transition_event_topic = node.fully_qualified_name() + "/transition_event";
+        let transition_event_publisher = node.create_publisher(&transition_event_topic)?;
+
+        let lifecycle_node = Arc::new(Self {
+            handle,
+            transition_mtx: Mutex::new(()),
+            transition_thread: Mutex::new(None),
+            callbacks_mtx: Mutex::new(TransitionCallbacks::default()),
+            managed_entities_mtx: Mutex::new(Vec::new()),
+            transition_event_publisher,
+            services: Mutex::new(None),
+            node,
+        });
+        *lifecycle_node.services.lock().unwrap() = Some(LifecycleServices::new(&lifecycle_node)?);
+        Ok(lifecycle_node)
+    }
+
+    /// Returns the underlying [`Node`].
+    pub fn node(&self) -> &Node {
+        &self.node
+    }
+
+    /// Returns the state that the node is currently in.
+    pub fn current_state(&self) -> LifecycleState {
+        let rcl_state_machine = self.handle.rcl_state_machine.lock().unwrap();
+        // SAFETY: The current state of an initialized state machine is always valid.
+        unsafe { LifecycleState::from_rcl(&*rcl_state_machine.current_state) }
+    }
+
+    /// Returns all states of the state machine, including the transition states.
+    pub fn available_states(&self) -> Vec<LifecycleState> {
+        let rcl_state_machine = self.handle.rcl_state_machine.lock().unwrap();
+        let transition_map = &rcl_state_machine.transition_map;
+        // SAFETY: The transition map of an initialized state machine contains
+        // `states_size` valid states.
+        unsafe {
+            std::slice::from_raw_parts(transition_map.states, transition_map.states_size as usize)
+                .iter()
+                .map(|rcl_state| LifecycleState::from_rcl(rcl_state))
+                .collect()
+        }
+    }
+
+    /// Returns the transitions that can be triggered from the current state.
+    pub fn available_transitions(&self) -> Vec<LifecycleTransition> {
+        let rcl_state_machine = self.handle.rcl_state_machine.lock().unwrap();
+        // SAFETY: The current state of an initialized state machine is always valid and
+        // has `valid_transition_size` valid transitions.
+        unsafe {
+            let current_state = &*rcl_state_machine.current_state;
+            if current_state.valid_transitions.is_null() {
+                return Vec::new();
+            }
+            std::slice::from_raw_parts(
+                current_state.valid_transitions,
+                current_state.valid_transition_size as usize,
+            )
+            .iter()
+            .map(|rcl_transition| LifecycleTransition::from_rcl(rcl_transition))
+            .collect()
+        }
+    }
+
+    /// Returns all transitions of the state machine.
+    pub fn transition_graph(&self) -> Vec<LifecycleTransition> {
+        let rcl_state_machine = self.handle.rcl_state_machine.lock().unwrap();
+        let transition_map = &rcl_state_machine.transition_map;
+        // SAFETY: The transition map of an initialized state machine contains
+        // `transitions_size` valid transitions.
+        unsafe {
+            std::slice::from_raw_parts(
+                transition_map.transitions,
+                transition_map.transitions_size as usize,
+            )
+            .iter()
+            .map(|rcl_transition| LifecycleTransition::from_rcl(rcl_transition))
+            .collect()
+        }
+    }
+
+    /// Triggers the transition with the given ID, e.g. [`LifecycleTransition::TRANSITION_CONFIGURE`],
+    /// and returns the state that the node ends up in.
+    ///
+    /// An error is returned if the transition can not be triggered from the current state,
+    /// or if this is called from inside of a transition callback of the node.
+    /// A failing transition callback is not an error, instead the node ends up in a
+    /// different state.
+    pub fn trigger_transition(&self, transition_id: u8) -> Result<LifecycleState, RclrsError> {
+        self.change_state(TransitionKey::Id(transition_id))?;
+        Ok(self.current_state())
+    }
+
+    /// Triggers the transition with the given label, e.g. `"configure"`, and returns the
+    /// state that the node ends up in.
+    ///
+    /// See [`trigger_transition()`][1] for details.
+    ///
+    /// [1]: Self::trigger_transition
+    pub fn trigger_transition_by_label(&self, label: &str) -> Result<LifecycleState, RclrsError> {
+        self.change_state_by_label(label)?;
+        Ok(self.current_state())
+    }
+
+    /// Moves the node from the unconfigured into the inactive state.
+    pub fn configure(&self) -> Result<LifecycleState, RclrsError> {
+        self.trigger_transition(LifecycleTransition::TRANSITION_CONFIGURE)
+    }
+
+    /// Moves the node from the inactive back into the unconfigured state.
+    pub fn cleanup(&self) -> Result<LifecycleState, RclrsError> {
+        self.trigger_transition(LifecycleTransition::TRANSITION_CLEANUP)
+    }
+
+    /// Moves the node from the inactive into the active state.
+    pub fn activate(&self) -> Result<LifecycleState, RclrsError> {
+        self.trigger_transition(LifecycleTransition::TRANSITION_ACTIVATE)
+    }
+
+    /// Moves the node from the active back into the inactive state.
+    pub fn deactivate(&self) -> Result<LifecycleState, RclrsError> {
+        self.trigger_transition(LifecycleTransition::TRANSITION_DEACTIVATE)
+    }
+
+    /// Moves the node from any primary state into the finalized state.
+    pub fn shutdown(&self) -> Result<LifecycleState, RclrsError> {
+        // SAFETY: The label is a constant global string of rcl_lifecycle.
+        self.change_state(TransitionKey::Label(unsafe {
+            rcl_lifecycle_shutdown_label
+        }))?;
+        Ok(self.current_state())
+    }
+
+    /// Sets the callback that runs when the node is configured.
+    ///
+    /// The callbacks receive the state that the node was in before the transition.
+    /// They must not register other transition callbacks.
+    pub fn on_configure<F>(&self, callback: F)
+    where
+        F: FnMut(&LifecycleState) -> TransitionCallbackReturn + Send + 'static,
+    {
+        self.callbacks_mtx.lock().unwrap().on_configure = Some(Box::new(callback));
+    }
+
+    /// Sets the callback that runs when the node is cleaned up.
+    pub fn on_cleanup<F>(&self, callback: F)
+    where
+        F: FnMut(&LifecycleState) -> TransitionCallbackReturn + Send + 'static,
+    {
+        self.callbacks_mtx.lock().unwrap().on_cleanup = Some(Box::new(callback));
+    }
+
+    /// Sets the callback that runs when the node is shut down.
+    pub fn on_shutdown<F>(&self, callback: F)
+    where
+        F: FnMut(&LifecycleState) -> TransitionCallbackReturn + Send + 'static,
+    {
+        self.callbacks_mtx.lock().unwrap().on_shutdown = Some(Box::new(callback));
+    }
+
+    /// Sets the callback that runs when the node is activated.
+    ///
+    /// The [lifecycle publishers][1] of the node are already activated when this
+    /// callback runs.
+    ///
+    /// [1]: LifecycleNodeState::create_lifecycle_publisher
+    pub fn on_activate<F>(&self, callback: F)
+    where
+        F: FnMut(&LifecycleState) -> TransitionCallbackReturn + Send + 'static,
+    {
+        self.callbacks_mtx.lock().unwrap().on_activate = Some(Box::new(callback));
+    }
+
+    /// Sets the callback that runs when the node is deactivated.
+    ///
+    /// The [lifecycle publishers][1] of the node are already deactivated when this
+    /// callback runs.
+    ///
+    /// [1]: LifecycleNodeState::create_lifecycle_publisher
+    pub fn on_deactivate<F>(&self, callback: F)
+    where
+        F: FnMut(&LifecycleState) -> TransitionCallbackReturn + Send + 'static,
+    {
+        self.callbacks_mtx.lock().unwrap().on_deactivate = Some(Box::new(callback));
+    }
+
+    /// Sets the callback that runs when another transition callback returned
+    /// [`TransitionCallbackReturn::Error`].
+    ///
+    /// If this callback succeeds, the node moves into the unconfigured state, otherwise
+    /// it is finalized.
+    pub fn on_error<F>(&self, callback: F)
+    where
+        F: FnMut(&LifecycleState) -> TransitionCallbackReturn + Send + 'static,
+    {
+        self.callbacks_mtx.lock().unwrap().on_error = Some(Box::new(callback));
+    }
+
+    /// Creates a [`LifecyclePublisher`], which only publishes messages while the node is
+    /// in the active state.
+    ///
+    /// See [`NodeState::create_publisher`][1] for how to configure the publisher options.
+    ///
+    /// [1]: crate::NodeState::create_publisher
+    pub fn create_lifecycle_publisher<'a, T>(
+        &self,
+        options: impl Into<PublisherOptions<'a>>,
+    ) -> Result<LifecyclePublisher<T>, RclrsError>
+    where
+        T: Message,
+    {
+        let publisher = Arc::new(LifecyclePublisherState::new(
+            self.node.create_publisher(options)?,
+            self.node.logger().clone(),
+        ));
+        if self.current_state().id == LifecycleState::PRIMARY_STATE_ACTIVE {
+            publisher.on_activate();
+        }
+        { self.managed_entities_mtx.lock().unwrap() }
+            .push(Arc::downgrade(&publisher) as Weak<dyn ManagedEntity>);
+        Ok(publisher)
+    }
+
+    fn change_state_by_label(&self, label: &str) -> Result<TransitionCallbackReturn, RclrsError> {
+        let label_c_string = CString::new(label).map_err(|err| RclrsError::StringContainsNul {
+            err,
+            s: label.into(),
+        })?;
+        self.change_state(TransitionKey::Label(label_c_string.as_ptr()))
+    }
+
+    /// Performs a transition and returns the outcome of its callback.
+    ///
+    /// Transitions of other threads are waited for, while a transition that is triggered
+    /// from inside of a transition callback is an error instead of a deadlock.
+    fn change_state(&self, key: TransitionKey) -> Result<TransitionCallbackReturn, RclrsError> {
+        let current_thread = thread::current().id();
+        if *self.transition_thread.lock().unwrap() == Some(current_thread) {
+            return Err(RclrsError::TransitionInProgress);
+        }
+        let _transition_lock = self.transition_mtx.lock().unwrap();
+        *self.transition_thread.lock().unwrap() = Some(current_thread);
+        let _transition_thread = TransitionThreadGuard(&self.transition_thread);
+        let initial_state = self.current_state();
+        self.trigger(key)?;
+
+        let transition_state = self.current_state();
+        match transition_state.id {
+            LifecycleState::TRANSITION_STATE_ACTIVATING => self.set_activated(true),
+            LifecycleState::TRANSITION_STATE_DEACTIVATING => self.set_activated(false),
+            _ => {}
+        }
+        let outcome = self.execute_callback(transition_state.id, &initial_state);
+        self.trigger(TransitionKey::Label(outcome.label()))?;
+
+        if outcome == TransitionCallbackReturn::Error {
+            // The node is now in the error processing state.
+            let error_state = self.current_state();
+            let error_outcome = self.execute_callback(error_state.id, &initial_state);
+            self.trigger(TransitionKey::Label(error_outcome.label()))?;
+        }
+
+        self.set_activated(self.current_state().id == LifecycleState::PRIMARY_STATE_ACTIVE);
+        Ok(outcome)
+    }
+
+    /// Triggers a single transition of the state machine and publishes it.
+    fn trigger(&self, key: TransitionKey) -> Result<(), RclrsError> {
+        let mut rcl_state_machine = self.handle.rcl_state_machine.lock().unwrap();
+        let start_state = rcl_state_machine.current_state;
+        // SAFETY: The state machine is initialized and the label is a valid C string.
+        // The transition event is published by rclrs, since the communication interface
+        // of the state machine is disabled.
+        let rcl_transition = unsafe {
+            match key {
+                TransitionKey::Id(id) => {
+                    rcl_lifecycle_trigger_transition_by_id(&mut *rcl_state_machine, id, false)
+                        .ok()?;
+                    rcl_lifecycle_get_transition_by_id(start_state, id)
+                }
+                TransitionKey::Label(label) => {
+                    rcl_lifecycle_trigger_transition_by_label(
+                        &mut *rcl_state_machine,
+                        label,
+                        false,
+                    )
+                    .ok()?;
+                    rcl_lifecycle_get_transition_by_label(start_state, label)
+                }
+            }
+        };
+        // SAFETY: The transition was triggered successfully, so it exists.
+        let transition = unsafe { LifecycleTransition::from_rcl(&*rcl_transition) };
+        drop(rcl_state_machine);
+
+        self.transition_event_publisher.publish(transition_event(
+            transition,
+            self.node.get_clock().now().nsec as u64,
+        ))
+    }
+
+    fn execute_callback(
+        &self,
+        transition_state_id: u8,
+        previous_state: &LifecycleState,
+    ) -> TransitionCallbackReturn {
+        let mut callbacks = self.callbacks_mtx.lock().unwrap();
+        let callback = match transition_state_id {
+            LifecycleState::TRANSITION_STATE_CONFIGURING => &mut callbacks.on_configure,
+            LifecycleState::TRANSITION_STATE_CLEANINGUP => &mut callbacks.on_cleanup,
+            LifecycleState::TRANSITION_STATE_SHUTTINGDOWN => &mut callbacks.on_shutdown,
+            LifecycleState::TRANSITION_STATE_ACTIVATING => &mut callbacks.on_activate,
+            LifecycleState::TRANSITION_STATE_DEACTIVATING => &mut callbacks.on_deactivate,
+            LifecycleState::TRANSITION_STATE_ERRORPROCESSING => &mut callbacks.on_error,
+            _ => return TransitionCallbackReturn::Success,
+        };
+        callback
+            .as_mut()
+            .map_or(TransitionCallbackReturn::Success, |callback| {
+                callback(previous_state)
+            })
+    }
+
+    fn set_activated(&self, activated: bool) {
+        let mut managed_entities = self.managed_entities_mtx.lock().unwrap();
+        managed_entities.retain(|entity| entity.strong_count() > 0);
+        for entity in managed_entities.iter().filter_map(Weak::upgrade) {
+            if activated {
+                entity.on_activate();
+            } else {
+                entity.on_deactivate();
+            }
+        }
+    }
+}
+
+/// Clears the thread that performs a transition when the transition ends, including
+/// by an early return.
+struct TransitionThreadGuard<'a>(&'a Mutex<Option<ThreadId>>);
+
+impl Drop for TransitionThreadGuard<'_> {
+    fn drop(&mut self) {
+        *self.0.lock().unwrap() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_helpers::*, vendor::lifecycle_msgs::msg::Transition, Context, CreateBasicExecutor,
+        SpinOptions,
+    };
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn traits() {
+        assert_send::<LifecycleNode>();
+        assert_sync::<LifecycleNode>();
+        assert_send::<LifecyclePublisher<test_msgs::msg::Empty>>();
+        assert_sync::<LifecyclePublisher<test_msgs::msg::Empty>>();
+    }
+
+    #[test]
+    fn test_primary_transitions() -> Result<(), RclrsError> {
+        let executor = Context::default().create_basic_executor();
+        let node = executor.create_lifecycle_node("test_lifecycle_primary_transitions")?;
+        let publisher =
+            node.create_lifecycle_publisher::<test_msgs::msg::Empty>("test_lifecycle_topic")?;
+        let configure_count = Arc::new(AtomicUsize::new(0));
+        let configure_count_inner = Arc::clone(&configure_count);
+        node.on_configure(move |previous_state| {
+            assert_eq!(
+                previous_state.id,
+                LifecycleState::PRIMARY_STATE_UNCONFIGURED
+            );
+            configure_count_inner.fetch_add(1, Ordering::SeqCst);
+            TransitionCallbackReturn::Success
+        });
+
+        assert_eq!(
+            node.current_state().id,
+            LifecycleState::PRIMARY_STATE_UNCONFIGURED
+        );
+        assert!(node.activate().is_err());
+
+        assert_eq!(node.configure()?.id, LifecycleState::PRIMARY_STATE_INACTIVE);
+        assert_eq!(configure_count.load(Ordering::SeqCst), 1);
+        assert!(!publisher.is_activated());
+
+        assert_eq!(node.activate()?.id, LifecycleState::PRIMARY_STATE_ACTIVE);
+        assert!(publisher.is_activated());
+        publisher.publish(test_msgs::msg::Empty::default())?;
+
+        assert_eq!(
+            node.deactivate()?.id,
+            LifecycleState::PRIMARY_STATE_INACTIVE
+        );
+        assert!(!publisher.is_activated());
+        // Publishing while inactive drops the message without an error.
+        publisher.publish(test_msgs::msg::Empty::default())?;
+
+        assert_eq!(
+            node.cleanup()?.id,
+            LifecycleState::PRIMARY_STATE_UNCONFIGURED
+        );
+        assert_eq!(node.shutdown()?.id, LifecycleState::PRIMARY_STATE_FINALIZED);
+        assert!(node.available_transitions().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_failing_transitions() -> Result<(), RclrsError> {
+        let executor = Context::default().create_basic_executor();
+        let node = executor.create_lifecycle_node("test_lifecycle_failing_transitions")?;
+
+        node.on_configure(|_| TransitionCallbackReturn::Failure);
+        assert_eq!(
+            node.configure()?.id,
+            LifecycleState::PRIMARY_STATE_UNCONFIGURED
+        );
+
+        // A successful error handler recovers into the unconfigured state.
+        node.on_configure(|_| TransitionCallbackReturn::Error);
+        node.on_error(|_| TransitionCallbackReturn::Success);
+        assert_eq!(
+            node.configure()?.id,
+            LifecycleState::PRIMARY_STATE_UNCONFIGURED
+        );
+
+        // Otherwise, the node is finalized.
+        node.on_error(|_| TransitionCallbackReturn::Failure);
+        assert_eq!(
+            node.configure()?.id,
+            LifecycleState::PRIMARY_STATE_FINALIZED
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_available_states_and_transitions() -> Result<(), RclrsError> {
+        let executor = Context::default().create_basic_executor();
+        let node = executor.create_lifecycle_node("test_lifecycle_available")?;
+
+        let state_ids: Vec<u8> = node.available_states().iter().map(|s| s.id).collect();
+        for id in [
+            LifecycleState::PRIMARY_STATE_UNCONFIGURED,
+            LifecycleState::PRIMARY_STATE_INACTIVE,
+            LifecycleState::PRIMARY_STATE_ACTIVE,
+            LifecycleState::PRIMARY_STATE_FINALIZED,
+            LifecycleState::TRANSITION_STATE_CONFIGURING,
+        ] {
+            assert!(state_ids.contains(&id));
+        }
+
+        let transition_ids: Vec<u8> = node.available_transitions().iter().map(|t| t.id).collect();
+        assert!(transition_ids.contains(&LifecycleTransition::TRANSITION_CONFIGURE));
+        assert!(!transition_ids.contains(&LifecycleTransition::TRANSITION_ACTIVATE));
+        assert!(node.transition_graph().len() > transition_ids.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_transition_from_callback() -> Result<(), RclrsError> {
+        let executor = Context::default().create_basic_executor();
+        let node = executor.create_lifecycle_node("test_lifecycle_transition_from_callback")?;
+        let nested_result = Arc::new(Mutex::new(None));
+        let nested_result_inner = Arc::clone(&nested_result);
+        let weak_node = Arc::downgrade(&node);
+        node.on_configure(move |_| {
+            let node = weak_node.upgrade().unwrap();
+            *nested_result_inner.lock().unwrap() = Some(node.activate().map(|state| state.id));
+            TransitionCallbackReturn::Success
+        });
+
+        assert_eq!(node.configure()?.id, LifecycleState::PRIMARY_STATE_INACTIVE);
+        assert_eq!(
+            nested_result.lock().unwrap().take(),
+            Some(Err(RclrsError::TransitionInProgress))
+        );
+        // Transitions are possible again once the callback has finished.
+        assert_eq!(node.activate()?.id, LifecycleState::PRIMARY_STATE_ACTIVE);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lifecycle_services() -> Result<(), RclrsError> {
+        let mut executor = Context::default().create_basic_executor();
+        let node =
+            executor.create_lifecycle_node(&format!("test_lifecycle_services_{}", line!()))?;
+        let fqn = node.fully_qualified_name();
+        let client_node =
+            executor.create_node(&format!("test_lifecycle_services_client_{}", line!()))?;
+        let change_state_client =
+            client_node.create_client::<srv::ChangeState>(&(fqn.clone() + "/change_state"))?;
+        let get_state_client =
+            client_node.create_client::<srv::GetState>(&(fqn.clone() + "/get_state"))?;
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_inner = Arc::clone(&events);
+        let _subscription = client_node.create_subscription(
+            &(fqn + "/transition_event"),
+            move |event: TransitionEvent| {
+                events_inner.lock().unwrap().push(event);
+            },
+        )?;
+
+        // Wait until the transition events can be received, since they are not latched.
+        let start = Instant::now();
+        while node.transition_event_publisher.get_subscription_count()? == 0 {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+
+        let responses = Arc::new(Mutex::new(None));
+        let responses_inner = Arc::clone(&responses);
+        client_node.spawn(async move {
+            change_state_client
+                .wait_for_service_async(None)
+                .await
+                .unwrap();
+            get_state_client.wait_for_service_async(None).await.unwrap();
+            let change_state_response = change_state_client
+                .call_async(srv::ChangeState_Request {
+                    transition: Transition {
+                        id: Transition::TRANSITION_CONFIGURE,
+                        ..Default::default()
+                    },
+                })
+                .await
+                .unwrap();
+            let get_state_response = get_state_client
+                .call_async(srv::GetState_Request::default())
+                .await
+                .unwrap();
+            *responses_inner.lock().unwrap() = Some((change_state_response, get_state_response));
+        });
+        let start = Instant::now();
+        while responses.lock().unwrap().is_none() || events.lock().unwrap().len() < 2 {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+
+        let (change_state_response, get_state_response) = responses.lock().unwrap().take().unwrap();
+        assert!(change_state_response.success);
+        assert_eq!(
+            get_state_response.current_state.id,
+            LifecycleState::PRIMARY_STATE_INACTIVE
+        );
+        assert_eq!(
+            node.current_state().id,
+            LifecycleState::PRIMARY_STATE_INACTIVE
+        );
+
+        // The transition into the configuring state, followed by the transition out of it.
+        let events = events.lock().unwrap();
+        assert_eq!(
+            events[0].start_state.id,
+            LifecycleState::PRIMARY_STATE_UNCONFIGURED
+        );
+        assert_eq!(
+            events[0].goal_state.id,
+            LifecycleState::TRANSITION_STATE_CONFIGURING
+        );
+        assert_eq!(
+            events[1].start_state.id,
+            LifecycleState::TRANSITION_STATE_CONFIGURING
+        );
+        assert_eq!(
+            events[1].goal_state.id,
+            LifecycleState::PRIMARY_STATE_INACTIVE
+        );
+
+        Ok(())
+    }
+}
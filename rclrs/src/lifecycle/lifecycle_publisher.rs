@@ -0,0 +1,115 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use rosidl_runtime_rs::Message;
+
+use crate::{log_warn, Logger, MessageCow, Publisher, RclrsError};
+
+/// An entity of a [`LifecycleNode`][1] that is activated and deactivated together with
+/// the node.
+///
+/// [1]: crate::LifecycleNode
+pub(crate) trait ManagedEntity: Send + Sync {
+    fn on_activate(&self);
+    fn on_deactivate(&self);
+}
+
+/// A publisher that only publishes messages while its [`LifecycleNode`][1] is active.
+///
+/// Create a lifecycle publisher using
+/// [`LifecycleNode::create_lifecycle_publisher`][2].
+///
+/// The publisher is activated when the node is activated, and deactivated when the node
+/// leaves the active state. Messages that are published while the publisher is
+/// deactivated are dropped.
+///
+/// [1]: crate::LifecycleNode
+/// [2]: crate::LifecycleNodeState::create_lifecycle_publisher
+pub type LifecyclePublisher<T> = Arc<LifecyclePublisherState<T>>;
+
+/// The inner state of a [`LifecyclePublisher`].
+///
+/// This is public so that you can choose to create a [`Weak`][1] reference to it
+/// if you want to be able to refer to a [`LifecyclePublisher`] in a non-owning way.
+///
+/// [1]: std::sync::Weak
+pub struct LifecyclePublisherState<T>
+where
+    T: Message,
+{
+    publisher: Publisher<T>,
+    activated: AtomicBool,
+    // Only warn about dropped messages once per deactivation.
+    should_warn: AtomicBool,
+    logger: Logger,
+}
+
+impl<T> LifecyclePublisherState<T>
+where
+    T: Message,
+{
+    pub(crate) fn new(publisher: Publisher<T>, logger: Logger) -> Self {
+        Self {
+            publisher,
+            activated: AtomicBool::new(false),
+            should_warn: AtomicBool::new(true),
+            logger,
+        }
+    }
+
+    /// Publishes a message if the publisher is activated, and drops it otherwise.
+    ///
+    /// See [`PublisherState::publish`][1] for details.
+    ///
+    /// [1]: crate::PublisherState::publish
+    pub fn publish<'a, M: MessageCow<'a, T>>(&self, message: M) -> Result<(), RclrsError> {
+        if !self.is_activated() {
+            if self.should_warn.swap(false, Ordering::AcqRel) {
+                log_warn!(
+                    &self.logger,
+                "Trying to publish a message on the topic '{}', but the publisher is not activated",
+                    self.publisher.topic_name(),
+                );
+            }
+            return Ok(());
+        }
+        self.publisher.publish(message)
+    }
+
+    /// Returns true if messages are currently published.
+    pub fn is_activated(&self) -> bool {
+        self.activated.load(Ordering::Acquire)
+    }
+
+    /// Activates the publisher, independently of the state of its node.
+    pub fn on_activate(&self) {
+        self.activated.store(true, Ordering::Release);
+        self.should_warn.store(true, Ordering::Release);
+    }
+
+    /// Deactivates the publisher, independently of the state of its node.
+    pub fn on_deactivate(&self) {
+        self.activated.store(false, Ordering::Release);
+    }
+
+    /// Returns the underlying publisher, which publishes regardless of the state of the
+    /// node.
+    pub fn publisher(&self) -> &Publisher<T> {
+        &self.publisher
+    }
+}
+
+impl<T> ManagedEntity for LifecyclePublisherState<T>
+where
+    T: Message,
+{
+    fn on_activate(&self) {
+        LifecyclePublisherState::on_activate(self);
+    }
+
+    fn on_deactivate(&self) {
+        LifecyclePublisherState::on_deactivate(self);
+    }
+}
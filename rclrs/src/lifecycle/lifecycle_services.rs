@@ -0,0 +1,158 @@
+use std::sync::{Arc, Weak};
+
+use crate::vendor::lifecycle_msgs::{msg::*, srv::*};
+
+use super::{
+    LifecycleNodeState, LifecycleState, LifecycleTransition, TransitionCallbackReturn,
+    TransitionKey,
+};
+use crate::{log_error, rmw_request_id_t, RclrsError, Service};
+
+// The variables only exist to keep a strong reference to the services and are technically unused.
+// What is used is the Weak that is stored in the node, and is upgraded when spinning.
+pub(super) struct LifecycleServices {
+    #[allow(dead_code)]
+    change_state_service: Service<ChangeState>,
+    #[allow(dead_code)]
+    get_state_service: Service<GetState>,
+    #[allow(dead_code)]
+    get_available_states_service: Service<GetAvailableStates>,
+    #[allow(dead_code)]
+    get_available_transitions_service: Service<GetAvailableTransitions>,
+    #[allow(dead_code)]
+    get_transition_graph_service: Service<GetAvailableTransitions>,
+}
+
+impl From<LifecycleState> for State {
+    fn from(state: LifecycleState) -> Self {
+        Self {
+            id: state.id,
+            label: state.label,
+        }
+    }
+}
+
+impl From<LifecycleTransition> for TransitionDescription {
+    fn from(transition: LifecycleTransition) -> Self {
+        Self {
+            transition: Transition {
+                id: transition.id,
+                label: transition.label,
+            },
+            start_state: transition.start_state.into(),
+            goal_state: transition.goal_state.into(),
+        }
+    }
+}
+
+pub(super) fn transition_event(transition: LifecycleTransition, timestamp: u64) -> TransitionEvent {
+    let TransitionDescription {
+        transition,
+        start_state,
+        goal_state,
+    } = transition.into();
+    TransitionEvent {
+        timestamp,
+        transition,
+        start_state,
+        goal_state,
+    }
+}
+
+fn change_state(req: ChangeState_Request, node: &LifecycleNodeState) -> ChangeState_Response {
+    let outcome =
+        if req.transition.id == Transition::TRANSITION_CREATE && !req.transition.label.is_empty() {
+            node.change_state_by_label(&req.transition.label)
+        } else {
+            node.change_state(TransitionKey::Id(req.transition.id))
+        };
+    let success = match outcome {
+        Ok(outcome) => outcome == TransitionCallbackReturn::Success,
+        Err(err) => {
+            log_error!(
+                node.logger(),
+                "Unable to perform the requested transition: {err}"
+            );
+            false
+        }
+    };
+    ChangeState_Response { success }
+}
+
+impl LifecycleServices {
+    pub(super) fn new(lifecycle_node: &Arc<LifecycleNodeState>) -> Result<Self, RclrsError> {
+        let node = lifecycle_node.node();
+        let fqn = node.fully_qualified_name();
+        let weak_node = Arc::downgrade(lifecycle_node);
+        let change_state_service = node.create_service(
+            &(fqn.clone() + "/change_state"),
+            move |_req_id: &rmw_request_id_t, req: ChangeState_Request| {
+                with_node(&weak_node, |node| change_state(req, node))
+            },
+        )?;
+        let weak_node = Arc::downgrade(lifecycle_node);
+        let get_state_service = node.create_service(
+            &(fqn.clone() + "/get_state"),
+            move |_req_id: &rmw_request_id_t, _req: GetState_Request| {
+                with_node(&weak_node, |node| GetState_Response {
+                    current_state: node.current_state().into(),
+                })
+            },
+        )?;
+        let weak_node = Arc::downgrade(lifecycle_node);
+        let get_available_states_service = node.create_service(
+            &(fqn.clone() + "/get_available_states"),
+            move |_req_id: &rmw_request_id_t, _req: GetAvailableStates_Request| {
+                with_node(&weak_node, |node| GetAvailableStates_Response {
+                    available_states: node
+                        .available_states()
+                        .into_iter()
+                        .map(State::from)
+                        .collect(),
+                })
+            },
+        )?;
+        let weak_node = Arc::downgrade(lifecycle_node);
+        let get_available_transitions_service = node.create_service(
+            &(fqn.clone() + "/get_available_transitions"),
+            move |_req_id: &rmw_request_id_t, _req: GetAvailableTransitions_Request| {
+                with_node(&weak_node, |node| GetAvailableTransitions_Response {
+                    available_transitions: node
+                        .available_transitions()
+                        .into_iter()
+                        .map(TransitionDescription::from)
+                        .collect(),
+                })
+            },
+        )?;
+        let weak_node = Arc::downgrade(lifecycle_node);
+        let get_transition_graph_service = node.create_service(
+            &(fqn + "/get_transition_graph"),
+            move |_req_id: &rmw_request_id_t, _req: GetAvailableTransitions_Request| {
+                with_node(&weak_node, |node| GetAvailableTransitions_Response {
+                    available_transitions: node
+                        .transition_graph()
+                        .into_iter()
+                        .map(TransitionDescription::from)
+                        .collect(),
+                })
+            },
+        )?;
+        Ok(Self {
+            change_state_service,
+            get_state_service,
+            get_available_states_service,
+            get_available_transitions_service,
+            get_transition_graph_service,
+        })
+    }
+}
+
+/// Runs `f` with the lifecycle node, or returns a default response if the node is
+/// being dropped.
+fn with_node<R: Default>(
+    weak_node: &Weak<LifecycleNodeState>,
+    f: impl FnOnce(&LifecycleNodeState) -> R,
+) -> R {
+    weak_node.upgrade().map(|node| f(&node)).unwrap_or_default()
+}
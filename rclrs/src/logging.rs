@@ -14,6 +14,19 @@ use crate::{rcl_bindings::*, ENTITY_LIFECYCLE_MUTEX};
 mod logging_configuration;
 pub(crate) use logging_configuration::*;
 
+mod log_handler;
+pub use log_handler::*;
+
+#[cfg(feature = "log")]
+mod log_bridge;
+#[cfg(feature = "log")]
+pub use log_bridge::*;
+
+#[cfg(feature = "tracing")]
+mod tracing_bridge;
+#[cfg(feature = "tracing")]
+pub use tracing_bridge::*;
+
 mod log_params;
 pub use log_params::*;
 
@@ -323,6 +336,13 @@ pub unsafe fn impl_log(
     file: &CString,
     line: u32,
 ) {
+    if is_in_output_handler() {
+        // The output handler may be called while the entity lifecycle mutex is
+        // locked, so logging from inside of it would deadlock, or recurse forever
+        // if the handler forwards entries to a bridge that logs them again.
+        return;
+    }
+
     // We use a closure here because there are several different points in this
     // function where we may need to run this same logic.
     let send_log = |severity: LogSeverity, logger_name: &CString, message: &CString| {
@@ -337,44 +357,10 @@ pub unsafe fn impl_log(
 
         let severity = severity.as_native();
 
-        let _lifecycle = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
-
-        #[cfg(test)]
-        {
-            // If we are compiling for testing purposes, when the default log
-            // output handler is being used we need to use the format_string,
-            // but when our custom log output handler is being used we need to
-            // pass the raw message string so that it can be viewed by the
-            // custom log output handler, allowing us to use it for test assertions.
-            if log_handler::is_using_custom_handler() {
-                // We are using the custom log handler that is only used during
-                // logging tests, so pass the raw message as the format string.
-                unsafe {
-                    // SAFETY: The global mutex is locked as _lifecycle
-                    rcutils_log(
-                        &location,
-                        severity as i32,
-                        logger_name.as_ptr(),
-                        message.as_ptr(),
-                    );
-                }
-            } else {
-                // We are using the normal log handler so call rcutils_log the normal way.
-                unsafe {
-                    // SAFETY: The global mutex is locked as _lifecycle
-                    rcutils_log(
-                        &location,
-                        severity as i32,
-                        logger_name.as_ptr(),
-                        format_string.as_ptr(),
-                        message.as_ptr(),
-                    );
-                }
-            }
-        }
+        // The custom output handler only runs once the global mutex is unlocked again.
+        with_deferred_output_handler(|| {
+            let _lifecycle = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
 
-        #[cfg(not(test))]
-        {
             unsafe {
                 // SAFETY: The global mutex is locked as _lifecycle
                 rcutils_log(
@@ -385,7 +371,7 @@ pub unsafe fn impl_log(
                     message.as_ptr(),
                 );
             }
-        }
+        });
     };
 
     match logger_name {
@@ -453,6 +439,35 @@ pub unsafe fn impl_log(
     }
 }
 
+/// Sends a log entry that was created through the `log` or `tracing` crate to rcutils.
+///
+/// Module paths like `my_crate::module` are turned into the logger name
+/// `my_crate.module`, so that the usual hierarchy of ROS loggers applies to them.
+#[cfg(any(feature = "log", feature = "tracing"))]
+pub(crate) fn log_from_bridge(
+    severity: LogSeverity,
+    target: &str,
+    message: &str,
+    function: &str,
+    file: &str,
+    line: u32,
+) {
+    let c_string =
+        |s: &str| CString::new(s).unwrap_or_else(|_| CString::new(s.replace('\0', "")).unwrap());
+    let logger_name = target.replace("::", ".");
+    // SAFETY: impl_log is not actually unsafe.
+    unsafe {
+        impl_log(
+            severity,
+            &LoggerName::Unvalidated(&logger_name),
+            &c_string(message),
+            &c_string(function),
+            &c_string(file),
+            line,
+        );
+    }
+}
+
 /// Used internally by logging macros to get the name of the function that called the
 /// logging macro. This is not meant for public use, but we need to export it so the
 /// other exported macros can use it. We should remove it if an official function! macro
@@ -472,11 +487,8 @@ macro_rules! function {
 
 #[cfg(test)]
 mod tests {
-    use crate::{log_handler::*, test_helpers::*, *};
-    use std::{
-        sync::{Arc, Mutex},
-        time::Duration,
-    };
+    use crate::{rcl_bindings::*, test_helpers::*, *};
+    use std::time::Duration;
 
     #[test]
     fn test_logging_macros() -> Result<(), RclrsError> {
@@ -496,52 +508,17 @@ mod tests {
 
         let graph = construct_test_graph("test_logging_macros")?;
 
-        let log_collection: Arc<Mutex<Vec<LogEntry<'static>>>> = Arc::new(Mutex::new(Vec::new()));
-        let inner_log_collection = log_collection.clone();
-
-        log_handler::set_logging_output_handler(move |log_entry: log_handler::LogEntry| {
-            inner_log_collection
-                .lock()
-                .unwrap()
-                .push(log_entry.into_owned());
-        })
-        .unwrap();
-
-        let last_logger_name = || {
-            log_collection
-                .lock()
-                .unwrap()
-                .last()
-                .unwrap()
-                .logger_name
-                .clone()
-        };
-
-        let last_message = || {
-            log_collection
-                .lock()
-                .unwrap()
-                .last()
-                .unwrap()
-                .message
-                .clone()
-        };
+        set_test_logging_output_handler();
 
-        let last_location = || {
-            log_collection
-                .lock()
-                .unwrap()
-                .last()
-                .unwrap()
-                .location
-                .clone()
-        };
-
-        let last_severity = || log_collection.lock().unwrap().last().unwrap().severity;
+        let last_entry = || test_log_entries().pop().unwrap();
+        let last_logger_name = || last_entry().logger_name;
+        let last_message = || last_entry().message;
+        let last_location = || last_entry().location.unwrap();
+        let last_severity = || last_entry().severity;
 
         let count_message = |message: &str| {
             let mut count = 0;
-            for log in log_collection.lock().unwrap().iter() {
+            for log in test_log_entries() {
                 if log.message == message {
                     count += 1;
                 }
@@ -659,10 +636,65 @@ mod tests {
         // should see two messages in the log.
         assert_eq!(count_message("custom clock throttled message"), 2);
 
-        reset_logging_output_handler();
+        // Entries logged by C libraries are formatted before they reach the handler
+        let c_string = |s: &str| std::ffi::CString::new(s).unwrap();
+        let (function_name, file_name) = (c_string("c_function"), c_string("c_file.c"));
+        let location = rcutils_log_location_t {
+            function_name: function_name.as_ptr(),
+            file_name: file_name.as_ptr(),
+            line_number: 42,
+        };
+        let (logger_name, format, argument) = (
+            c_string("c_logger"),
+            c_string("formatted by %s: %d"),
+            c_string("rcutils"),
+        );
+        unsafe {
+            let _lifecycle = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+            rcutils_log(
+                &location,
+                LogSeverity::Warn.as_native() as i32,
+                logger_name.as_ptr(),
+                format.as_ptr(),
+                argument.as_ptr(),
+                42 as std::os::raw::c_int,
+            );
+        }
+        assert_eq!(last_logger_name(), "c_logger");
+        assert_eq!(last_message(), "formatted by rcutils: 42");
+        assert_eq!(last_severity(), LogSeverity::Warn);
+        assert_eq!(last_location().file_name, "c_file.c");
+        assert_eq!(last_location().line_number, 42);
         Ok(())
     }
 
+    #[test]
+    fn test_output_handler_panic() {
+        set_test_logging_output_handler();
+
+        // The panic is caught before it reaches rcutils.
+        log!("panicking handler", "{TEST_PANIC_MESSAGE}");
+        // Later entries of the same thread still reach the handler.
+        log!("panicking handler", "logged after the panic");
+        let entries = test_log_entries();
+        let last = entries.last().unwrap();
+        assert_eq!(last.logger_name, "panicking handler");
+        assert_eq!(last.message, "logged after the panic");
+    }
+
+    #[test]
+    fn test_output_handler_runs_unlocked() {
+        set_test_logging_output_handler();
+
+        // The handler of the tests locks the global mutex for this message, which would
+        // deadlock if the handler ran while the mutex is still locked.
+        log!("unlocked handler", "{TEST_LOCK_MESSAGE}");
+        let entries = test_log_entries();
+        let last = entries.last().unwrap();
+        assert_eq!(last.logger_name, "unlocked handler");
+        assert_eq!(last.message, TEST_LOCK_MESSAGE);
+    }
+
     #[test]
     fn test_function_macro() {
         assert_eq!(function!(), "rclrs::logging::tests::test_function_macro");
@@ -0,0 +1,195 @@
+//! Bridges between the logging of `rclrs` and the [`log`] crate.
+
+use crate::{log_from_bridge, LogEntry, LogSeverity};
+
+impl LogEntry<'_> {
+    /// Forwards this entry to the logger of the [`log`] crate.
+    ///
+    /// The name of the ROS logger is used as the target of the record. Use this inside
+    /// of a [logging output handler][1] to receive the log output of `rclrs` and `rcl`
+    /// through the `log` crate:
+    ///
+    /// ```no_run
+    /// # use rclrs::*;
+    /// set_logging_output_handler(|entry: LogEntry| entry.forward_to_log()).unwrap();
+    /// ```
+    ///
+    /// [1]: crate::set_logging_output_handler
+    pub fn forward_to_log(&self) {
+        let level = match self.severity {
+            LogSeverity::Debug => log::Level::Debug,
+            LogSeverity::Unset | LogSeverity::Info => log::Level::Info,
+            LogSeverity::Warn => log::Level::Warn,
+            LogSeverity::Error | LogSeverity::Fatal => log::Level::Error,
+        };
+        let metadata = log::Metadata::builder()
+            .level(level)
+            .target(&self.logger_name)
+            .build();
+        let logger = log::logger();
+        if logger.enabled(&metadata) {
+            let location = self.location.as_ref();
+            logger.log(
+                &log::Record::builder()
+                    .metadata(metadata)
+                    .file(location.map(|location| location.file_name.as_ref()))
+                    .line(location.map(|location| location.line_number as u32))
+                    .args(format_args!("{}", self.message))
+                    .build(),
+            );
+        }
+    }
+}
+
+/// A logger for the [`log`] crate that sends all records to the logging of `rclrs`.
+///
+/// This makes records created with the `log` crate show up like any other ROS log
+/// output, i.e. on the console, on `/rosout` and in the log files. The target of a
+/// record is used as its logger name, with `::` replaced by `.`. Records are filtered
+/// by the severity level of that logger, and `trace` records are logged as `debug`.
+///
+/// ```no_run
+/// # use rclrs::*;
+/// RosLogBridge::init().unwrap();
+/// log::info!("This is sent to /rosout");
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RosLogBridge;
+
+impl RosLogBridge {
+    /// Installs the bridge as the global logger of the [`log`] crate.
+    ///
+    /// This fails if a logger has already been installed.
+    pub fn init() -> Result<(), log::SetLoggerError> {
+        log::set_logger(&RosLogBridge)?;
+        log::set_max_level(log::LevelFilter::Trace);
+        Ok(())
+    }
+}
+
+impl log::Log for RosLogBridge {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        // The severity level of the ROS logger is checked by rcutils.
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        let severity = match record.level() {
+            log::Level::Trace | log::Level::Debug => LogSeverity::Debug,
+            log::Level::Info => LogSeverity::Info,
+            log::Level::Warn => LogSeverity::Warn,
+            log::Level::Error => LogSeverity::Error,
+        };
+        log_from_bridge(
+            severity,
+            record.target(),
+            &record.args().to_string(),
+            record.module_path().unwrap_or_default(),
+            record.file().unwrap_or_default(),
+            record.line().unwrap_or_default(),
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use log::Log;
+    use std::{
+        sync::{Mutex, Once},
+        thread::{self, ThreadId},
+    };
+
+    /// Records that reached the test logger, together with the thread that logged them.
+    static RECORDS: Mutex<Vec<(ThreadId, log::Level, String, String)>> = Mutex::new(Vec::new());
+
+    /// A logger that collects all records, and passes them on to the bridge like a
+    /// logger with several outputs would.
+    struct TestLogger;
+
+    impl log::Log for TestLogger {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            RECORDS.lock().unwrap().push((
+                thread::current().id(),
+                record.level(),
+                record.target().to_owned(),
+                record.args().to_string(),
+            ));
+            RosLogBridge.log(record);
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn set_up() {
+        static SET_LOGGER: Once = Once::new();
+        SET_LOGGER.call_once(|| {
+            log::set_logger(&TestLogger).unwrap();
+            log::set_max_level(log::LevelFilter::Trace);
+        });
+        set_test_logging_output_handler();
+    }
+
+    /// Returns the records that reached the test logger from this thread.
+    fn test_records() -> Vec<(log::Level, String, String)> {
+        let thread = thread::current().id();
+        RECORDS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(record_thread, ..)| *record_thread == thread)
+            .map(|(_, level, target, message)| (*level, target.clone(), message.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_log_to_ros() {
+        set_up();
+
+        let line = line!() + 1;
+        log::warn!(target: "rclrs_test::log_to_ros", "hello from log");
+
+        let entries: Vec<_> = test_log_entries()
+            .into_iter()
+            .filter(|entry| entry.message == "hello from log")
+            .collect();
+        // The entry is forwarded back to the test logger by the output handler, but
+        // the bridge does not send it to ROS a second time.
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.severity, LogSeverity::Warn);
+        assert_eq!(entry.logger_name, "rclrs_test.log_to_ros");
+        let location = entry.location.as_ref().unwrap();
+        assert_eq!(location.function_name, module_path!());
+        assert_eq!(location.file_name, file!());
+        assert_eq!(location.line_number, line as usize);
+    }
+
+    #[test]
+    fn test_ros_to_log() {
+        set_up();
+
+        log!("rclrs_test.ros_to_log", "hello from ROS");
+
+        assert_eq!(
+            test_records(),
+            [(
+                log::Level::Info,
+                "rclrs_test.ros_to_log".to_owned(),
+                "hello from ROS".to_owned()
+            )]
+        );
+        // The bridge does not send the record back to ROS.
+        let count = test_log_entries()
+            .iter()
+            .filter(|entry| entry.message == "hello from ROS")
+            .count();
+        assert_eq!(count, 1);
+    }
+}
@@ -0,0 +1,411 @@
+//! This module provides a way to customize how log output is handled.
+//!
+//! By default, `rcl` sends log output to the console, to the `/rosout` topic and to
+//! log files. A custom output handler receives every log entry of the process: those
+//! created with the [`log`][crate::log] macros of `rclrs`, as well as those created by
+//! `rcl` and other C libraries. A handler set with [`set_logging_output_handler`]
+//! replaces the default output, while one set with [`chain_logging_output_handler`]
+//! runs in addition to it.
+
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    ffi::CStr,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::OnceLock,
+    time::{Duration, SystemTime},
+};
+
+use crate::{rcl_bindings::*, LogSeverity, ToResult, ENTITY_LIFECYCLE_MUTEX};
+
+/// Global variable that allows a custom log handler to be set. This log
+/// handler will be applied throughout the entire application and cannot be
+/// replaced with a different custom log handler. If you want to be able to
+/// change the log handler over the lifetime of your application, you should
+/// design your own custom handler with an Arc<Mutex<T>> inside that allows
+/// its own behavior to be modified.
+static LOGGING_OUTPUT_HANDLER: OnceLock<OutputHandler> = OnceLock::new();
+
+type LogHandler = Box<dyn Fn(LogEntry) + 'static + Send + Sync>;
+
+struct OutputHandler {
+    handler: LogHandler,
+    /// Whether entries are also passed on to the default output of `rcl`.
+    keep_default_output: bool,
+}
+
+thread_local! {
+    /// Whether this thread is currently running the custom log output handler.
+    static IN_OUTPUT_HANDLER: Cell<bool> = const { Cell::new(false) };
+
+    /// If this is `Some`, entries that reach the output handler on this thread are
+    /// collected here instead of being passed to the custom handler right away.
+    static DEFERRED_ENTRIES: RefCell<Option<Vec<LogEntry<'static>>>> =
+        const { RefCell::new(None) };
+}
+
+/// This is an idiomatic representation of all the information for a log entry
+#[derive(Clone, Debug)]
+pub struct LogEntry<'a> {
+    /// Where the entry was logged, if the caller provided it.
+    pub location: Option<LogLocation<'a>>,
+    /// The severity of the entry.
+    pub severity: LogSeverity,
+    /// The name of the logger that the entry was logged with.
+    pub logger_name: Cow<'a, str>,
+    /// The time at which the entry was logged.
+    pub timestamp: SystemTime,
+    /// The fully formatted message.
+    pub message: Cow<'a, str>,
+}
+
+impl LogEntry<'_> {
+    /// Change the entry from something borrowed into something owned
+    pub fn into_owned(self) -> LogEntry<'static> {
+        LogEntry {
+            location: self.location.map(LogLocation::into_owned),
+            severity: self.severity,
+            logger_name: Cow::Owned(self.logger_name.into_owned()),
+            timestamp: self.timestamp,
+            message: Cow::Owned(self.message.into_owned()),
+        }
+    }
+}
+
+/// Rust-idiomatic representation of the location of a log
+#[derive(Debug, Clone)]
+pub struct LogLocation<'a> {
+    /// The name of the function that created the entry.
+    pub function_name: Cow<'a, str>,
+    /// The name of the source file that created the entry.
+    pub file_name: Cow<'a, str>,
+    /// The line in the source file that created the entry.
+    pub line_number: usize,
+}
+
+impl LogLocation<'_> {
+    /// Change the location from something borrowed into something owned
+    pub fn into_owned(self) -> LogLocation<'static> {
+        LogLocation {
+            function_name: Cow::Owned(self.function_name.into_owned()),
+            file_name: Cow::Owned(self.file_name.into_owned()),
+            line_number: self.line_number,
+        }
+    }
+}
+
+/// Error returned by [`set_logging_output_handler`] and [`chain_logging_output_handler`]
+/// if a custom output handler has already been set.
+#[derive(Debug)]
+pub struct OutputHandlerAlreadySet;
+
+impl fmt::Display for OutputHandlerAlreadySet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a logging output handler has already been set")
+    }
+}
+
+impl std::error::Error for OutputHandlerAlreadySet {}
+
+/// Sets a custom handler for all log output of the process.
+///
+/// The handler replaces the default output of `rcl`, so log entries are no longer
+/// printed to the console, published to `/rosout` or written to log files. Entries are
+/// still filtered by the severity level of their logger before they reach the handler.
+///
+/// The handler can only be set once. If you want to be able to change the behavior
+/// of the handler over the lifetime of your application, it should contain e.g. an
+/// `Arc<Mutex<T>>` that allows its behavior to be modified.
+///
+/// The handler may be called from any thread. Log entries that are created while the
+/// handler is running on the same thread, e.g. by logging with `rclrs` from inside the
+/// handler, are dropped to avoid infinite recursion.
+///
+/// Entries logged with the macros of `rclrs` reach the handler after the internal locks
+/// of `rclrs` have been released. Entries logged by `rcl` and other C libraries however
+/// reach the handler while `rclrs` may hold the lock that guards the creation and
+/// destruction of all entities, so the handler must not create or drop entities such
+/// as nodes or publishers, or wait for another thread that does, since that would
+/// deadlock.
+///
+/// ```no_run
+/// # use rclrs::*;
+/// set_logging_output_handler(|entry: LogEntry| {
+///     eprintln!("[{:?}] [{}]: {}", entry.severity, entry.logger_name, entry.message);
+/// })
+/// .unwrap();
+/// ```
+pub fn set_logging_output_handler(
+    handler: impl Fn(LogEntry) + 'static + Send + Sync,
+) -> Result<(), OutputHandlerAlreadySet> {
+    install_output_handler(Box::new(handler), false)
+}
+
+/// Sets a custom handler for all log output of the process, in addition to the default
+/// output of `rcl`.
+///
+/// In contrast to [`set_logging_output_handler`], log entries are still printed to the
+/// console, published to `/rosout` and written to log files, before they are passed to
+/// the handler. Otherwise, the handler behaves the same, and it can also only be set
+/// once.
+///
+/// ```no_run
+/// # use rclrs::*;
+/// chain_logging_output_handler(|entry: LogEntry| {
+///     if entry.severity >= LogSeverity::Error {
+///         // e.g. report the error to a monitoring service
+///     }
+/// })
+/// .unwrap();
+/// ```
+pub fn chain_logging_output_handler(
+    handler: impl Fn(LogEntry) + 'static + Send + Sync,
+) -> Result<(), OutputHandlerAlreadySet> {
+    install_output_handler(Box::new(handler), true)
+}
+
+fn install_output_handler(
+    handler: LogHandler,
+    keep_default_output: bool,
+) -> Result<(), OutputHandlerAlreadySet> {
+    LOGGING_OUTPUT_HANDLER
+        .set(OutputHandler {
+            handler,
+            keep_default_output,
+        })
+        .map_err(|_| OutputHandlerAlreadySet)?;
+    let _lifecycle = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+    unsafe {
+        // SAFETY:
+        // - We have locked the global mutex
+        rcutils_logging_set_output_handler(Some(rclrs_logging_output_handler));
+    }
+    Ok(())
+}
+
+/// Installs the custom output handler in rcutils again, if one has been set.
+///
+/// `rcl_logging_configure` and `rcl_logging_fini` replace the output handler of
+/// rcutils, so this needs to be called after them.
+///
+/// SAFETY: The global mutex must be locked.
+pub(crate) unsafe fn reinstall_logging_output_handler() {
+    if LOGGING_OUTPUT_HANDLER.get().is_some() {
+        rcutils_logging_set_output_handler(Some(rclrs_logging_output_handler));
+    }
+}
+
+/// Returns true if the custom log output handler is currently running on this thread.
+pub(crate) fn is_in_output_handler() -> bool {
+    IN_OUTPUT_HANDLER.with(Cell::get)
+}
+
+/// Runs `log` and passes the entries that it logs to the custom output handler only
+/// after it has returned.
+///
+/// This allows `log` to hold internal locks while calling into rcutils, without
+/// holding them while the custom handler runs.
+pub(crate) fn with_deferred_output_handler(log: impl FnOnce()) {
+    let previous = DEFERRED_ENTRIES.with(|deferred| deferred.replace(Some(Vec::new())));
+    log();
+    let entries = DEFERRED_ENTRIES.with(|deferred| deferred.replace(previous));
+    if let Some(handler) = LOGGING_OUTPUT_HANDLER.get() {
+        for entry in entries.into_iter().flatten() {
+            dispatch(handler, entry);
+        }
+    }
+}
+
+/// Passes the entry to the custom handler.
+fn dispatch(handler: &OutputHandler, entry: LogEntry) {
+    let _guard = OutputHandlerGuard::enter();
+    // Unwinding into rcutils is undefined behavior, so a panic of the handler is
+    // caught here. The panic hook has already reported it, and the entry is lost.
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| (handler.handler)(entry)));
+}
+
+/// Marks the current thread as running the output handler until it is dropped, so that
+/// the mark is also removed if the handler panics.
+struct OutputHandlerGuard;
+
+impl OutputHandlerGuard {
+    fn enter() -> Self {
+        IN_OUTPUT_HANDLER.with(|flag| flag.set(true));
+        Self
+    }
+}
+
+impl Drop for OutputHandlerGuard {
+    fn drop(&mut self) {
+        IN_OUTPUT_HANDLER.with(|flag| flag.set(false));
+    }
+}
+
+/// This function exists so that we can give a raw function pointer to
+/// rcutils_logging_set_output_handler, which is needed by its API.
+extern "C" fn rclrs_logging_output_handler(
+    raw_location: *const rcutils_log_location_t,
+    raw_severity: std::os::raw::c_int,
+    raw_logger_name: *const std::os::raw::c_char,
+    raw_timestamp: rcutils_time_point_value_t,
+    raw_format: *const std::os::raw::c_char,
+    raw_formatting_arguments: *mut va_list,
+) {
+    let Some(handler) = LOGGING_OUTPUT_HANDLER.get() else {
+        return;
+    };
+    if is_in_output_handler() {
+        return;
+    }
+
+    // SAFETY: rcutils passes valid pointers, except for the location which may be null.
+    unsafe {
+        if handler.keep_default_output {
+            // This only formats copies of the arguments, so they can still be formatted
+            // below.
+            rcl_logging_multiple_output_handler(
+                raw_location,
+                raw_severity,
+                raw_logger_name,
+                raw_timestamp,
+                raw_format,
+                raw_formatting_arguments,
+            );
+        }
+
+        let location = raw_location.as_ref().map(|location| LogLocation {
+            function_name: c_str_or_empty(location.function_name),
+            file_name: c_str_or_empty(location.file_name),
+            line_number: location.line_number,
+        });
+        let timestamp = if raw_timestamp < 0 {
+            SystemTime::UNIX_EPOCH - Duration::from_nanos(raw_timestamp.unsigned_abs())
+        } else {
+            SystemTime::UNIX_EPOCH + Duration::from_nanos(raw_timestamp.unsigned_abs())
+        };
+        let message = format_message(raw_format, raw_formatting_arguments);
+        let entry = LogEntry {
+            location,
+            severity: LogSeverity::from_native(raw_severity),
+            logger_name: c_str_or_empty(raw_logger_name),
+            timestamp,
+            message: Cow::Owned(message),
+        };
+
+        let entry = DEFERRED_ENTRIES.with(|deferred| match &mut *deferred.borrow_mut() {
+            Some(deferred) => {
+                deferred.push(entry.into_owned());
+                None
+            }
+            None => Some(entry),
+        });
+        if let Some(entry) = entry {
+            dispatch(handler, entry);
+        }
+    }
+}
+
+/// SAFETY: The pointer must be null or point to a valid null-terminated string.
+unsafe fn c_str_or_empty<'a>(ptr: *const std::os::raw::c_char) -> Cow<'a, str> {
+    if ptr.is_null() {
+        Cow::Borrowed("")
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy()
+    }
+}
+
+/// Fills in the C-style format string with its arguments, the same way that the
+/// default output handler of `rcl` does.
+///
+/// Log entries created by `rclrs` always use `"%s"` as their format string, while
+/// entries created by C libraries may use any format.
+///
+/// SAFETY: The format and arguments must be the ones passed to the output handler.
+unsafe fn format_message(format: *const std::os::raw::c_char, arguments: *mut va_list) -> String {
+    let allocator = rcutils_get_default_allocator();
+    let mut char_array = rcutils_get_zero_initialized_char_array();
+    if rcutils_char_array_init(&mut char_array, 0, &allocator)
+        .ok()
+        .is_err()
+    {
+        return c_str_or_empty(format).into_owned();
+    }
+
+    // A va_list is an array on x86_64, so it decays to a pointer when it is passed to
+    // a function. On other architectures it is passed by value.
+    #[cfg(target_arch = "x86_64")]
+    let va_args = (*arguments).as_mut_ptr();
+    #[cfg(not(target_arch = "x86_64"))]
+    let va_args = std::ptr::read(arguments);
+
+    let message = if rcutils_char_array_vsprintf(&mut char_array, format, va_args)
+        .ok()
+        .is_ok()
+        && !char_array.buffer.is_null()
+    {
+        c_str_or_empty(char_array.buffer).into_owned()
+    } else {
+        c_str_or_empty(format).into_owned()
+    };
+    rcutils_char_array_fini(&mut char_array);
+    message
+}
+
+/// Log entries collected by the output handler of the tests, together with the thread
+/// that logged them.
+#[cfg(test)]
+static TEST_LOG_ENTRIES: std::sync::Mutex<Vec<(std::thread::ThreadId, LogEntry<'static>)>> =
+    std::sync::Mutex::new(Vec::new());
+
+/// An entry with this message makes the output handler of the tests panic.
+#[cfg(test)]
+pub(crate) const TEST_PANIC_MESSAGE: &str = "make the test output handler panic";
+
+/// An entry with this message makes the output handler of the tests lock the global
+/// mutex, which deadlocks if the mutex is still held by the thread that logged it.
+#[cfg(test)]
+pub(crate) const TEST_LOCK_MESSAGE: &str = "make the test output handler lock the mutex";
+
+/// Sets an output handler that collects all log entries, for the tests.
+///
+/// The handler can only be set once per process, so all tests share it, and each test
+/// only looks at the entries that were logged on its own thread, see [`test_log_entries`].
+/// Like an application that uses the bridges would, the handler also forwards every
+/// entry to the bridges that are enabled.
+#[cfg(test)]
+pub(crate) fn set_test_logging_output_handler() {
+    static SET_HANDLER: std::sync::Once = std::sync::Once::new();
+    SET_HANDLER.call_once(|| {
+        set_logging_output_handler(|entry: LogEntry| {
+            if entry.message == TEST_PANIC_MESSAGE {
+                panic!("{TEST_PANIC_MESSAGE}");
+            }
+            if entry.message == TEST_LOCK_MESSAGE {
+                drop(ENTITY_LIFECYCLE_MUTEX.lock().unwrap());
+            }
+            #[cfg(feature = "log")]
+            entry.forward_to_log();
+            #[cfg(feature = "tracing")]
+            entry.forward_to_tracing();
+            TEST_LOG_ENTRIES
+                .lock()
+                .unwrap()
+                .push((std::thread::current().id(), entry.into_owned()));
+        })
+        .unwrap();
+    });
+}
+
+/// Returns the entries that reached the output handler of the tests from this thread.
+#[cfg(test)]
+pub(crate) fn test_log_entries() -> Vec<LogEntry<'static>> {
+    let thread = std::thread::current().id();
+    TEST_LOG_ENTRIES
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(entry_thread, _)| *entry_thread == thread)
+        .map(|(_, entry)| entry.clone())
+        .collect()
+}
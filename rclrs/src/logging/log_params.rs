@@ -181,7 +181,6 @@ pub enum LoggerName<'a> {
 // Perhaps we can customize the output of bindgen to automatically change the name
 // of RCUTILS_LOG_SEVERITY to just LogSeverity so it's more idiomatic and then
 // export it from the rclrs module.
-#[doc(hidden)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum LogSeverity {
     /// Use the severity level of the parent logger (or the root logger if the
@@ -219,9 +218,6 @@ impl LogSeverity {
         }
     }
 
-    /// This is only used by the log output handler during testing, so it will
-    /// not be compiled when testing is not configured
-    #[cfg(test)]
     pub(crate) fn from_native(native: i32) -> Self {
         use crate::rcl_bindings::rcl_log_severity_t::*;
         match native {
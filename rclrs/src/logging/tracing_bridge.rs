@@ -0,0 +1,199 @@
+//! Bridges between the logging of `rclrs` and the [`tracing`] crate.
+
+use std::fmt::{self, Write};
+
+use tracing::{
+    field::{Field, Visit},
+    Event, Level, Subscriber,
+};
+use tracing_subscriber::layer::{Context, Layer};
+
+use crate::{log_from_bridge, LogEntry, LogSeverity};
+
+impl LogEntry<'_> {
+    /// Emits this entry as an event of the [`tracing`] crate.
+    ///
+    /// The event has the target `rclrs`, and carries the name of the ROS logger and the
+    /// location of the entry as the fields `logger`, `file` and `line`. Use this inside
+    /// of a [logging output handler][1] to receive the log output of `rclrs` and `rcl`
+    /// through `tracing`:
+    ///
+    /// ```no_run
+    /// # use rclrs::*;
+    /// set_logging_output_handler(|entry: LogEntry| entry.forward_to_tracing()).unwrap();
+    /// ```
+    ///
+    /// [1]: crate::set_logging_output_handler
+    pub fn forward_to_tracing(&self) {
+        let logger = self.logger_name.as_ref();
+        let file = self.location.as_ref().map(|l| l.file_name.as_ref());
+        let line = self.location.as_ref().map(|l| l.line_number as u64);
+        let message = self.message.as_ref();
+        // The level of an event has to be known at compile time.
+        macro_rules! forward {
+            ($level:expr) => {
+                tracing::event!(target: "rclrs", $level, logger, file, line, "{message}")
+            };
+        }
+        match self.severity {
+            LogSeverity::Debug => forward!(Level::DEBUG),
+            LogSeverity::Unset | LogSeverity::Info => forward!(Level::INFO),
+            LogSeverity::Warn => forward!(Level::WARN),
+            LogSeverity::Error | LogSeverity::Fatal => forward!(Level::ERROR),
+        }
+    }
+}
+
+/// A [`Layer`] that sends all [`tracing`] events to the logging of `rclrs`.
+///
+/// This makes events created with `tracing` show up like any other ROS log output,
+/// i.e. on the console, on `/rosout` and in the log files. The target of an event is
+/// used as its logger name, with `::` replaced by `.`. Events are filtered by the
+/// severity level of that logger, and `TRACE` events are logged as `debug`.
+///
+/// Fields other than the message are appended to the message as `name=value`.
+/// Spans are ignored.
+///
+/// ```no_run
+/// # use rclrs::*;
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// let subscriber = tracing_subscriber::registry().with(RosTracingLayer);
+/// tracing::subscriber::set_global_default(subscriber).unwrap();
+/// tracing::info!(answer = 42, "This is sent to /rosout");
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RosTracingLayer;
+
+impl<S: Subscriber> Layer<S> for RosTracingLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let severity = match *metadata.level() {
+            Level::TRACE | Level::DEBUG => LogSeverity::Debug,
+            Level::INFO => LogSeverity::Info,
+            Level::WARN => LogSeverity::Warn,
+            Level::ERROR => LogSeverity::Error,
+        };
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        log_from_bridge(
+            severity,
+            metadata.target(),
+            &visitor.message,
+            metadata.module_path().unwrap_or_default(),
+            metadata.file().unwrap_or_default(),
+            metadata.line().unwrap_or_default(),
+        );
+    }
+}
+
+/// Formats the fields of an event into a single message.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            // Put the message in front of the other fields.
+            let fields = std::mem::replace(&mut self.message, format!("{value:?}"));
+            self.message.push_str(&fields);
+        } else {
+            let _ = write!(self.message, " {}={value:?}", field.name());
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.record_debug(field, &format_args!("{value}"));
+        } else {
+            let _ = write!(self.message, " {}={value}", field.name());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{log, set_test_logging_output_handler, test_log_entries};
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// A layer that collects the level, target and formatted fields of all events.
+    #[derive(Clone, Default)]
+    struct TestLayer {
+        events: Arc<Mutex<Vec<(Level, String, String)>>>,
+    }
+
+    impl<S: Subscriber> Layer<S> for TestLayer {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            let mut visitor = MessageVisitor::default();
+            event.record(&mut visitor);
+            let metadata = event.metadata();
+            self.events.lock().unwrap().push((
+                *metadata.level(),
+                metadata.target().to_owned(),
+                visitor.message,
+            ));
+        }
+    }
+
+    #[test]
+    fn test_tracing_to_ros() {
+        set_test_logging_output_handler();
+        let layer = TestLayer::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(RosTracingLayer)
+            .with(layer.clone());
+
+        let line = tracing::subscriber::with_default(subscriber, || {
+            let line = line!() + 1;
+            tracing::warn!(target: "rclrs_test::tracing_to_ros", answer = 42, "hello from tracing");
+            line
+        });
+
+        let entries: Vec<_> = test_log_entries()
+            .into_iter()
+            .filter(|entry| entry.message.starts_with("hello from tracing"))
+            .collect();
+        // The entry is forwarded back to tracing by the output handler, but the layer
+        // does not send it to ROS a second time.
+        assert_eq!(entries.len(), 1);
+        assert_eq!(layer.events.lock().unwrap().len(), 2);
+        let entry = &entries[0];
+        assert_eq!(entry.message, "hello from tracing answer=42");
+        assert_eq!(entry.severity, LogSeverity::Warn);
+        assert_eq!(entry.logger_name, "rclrs_test.tracing_to_ros");
+        let location = entry.location.as_ref().unwrap();
+        assert_eq!(location.function_name, module_path!());
+        assert_eq!(location.file_name, file!());
+        assert_eq!(location.line_number, line as usize);
+    }
+
+    #[test]
+    fn test_ros_to_tracing() {
+        set_test_logging_output_handler();
+        let layer = TestLayer::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(RosTracingLayer)
+            .with(layer.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            log!("rclrs_test.ros_to_tracing", "hello from ROS");
+        });
+
+        let events = layer.events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        let (level, target, message) = &events[0];
+        assert_eq!(*level, Level::INFO);
+        assert_eq!(target, "rclrs");
+        assert!(message.starts_with("hello from ROS logger=rclrs_test.ros_to_tracing file="));
+        // The layer does not send the event back to ROS.
+        let count = test_log_entries()
+            .iter()
+            .filter(|entry| entry.message == "hello from ROS")
+            .count();
+        assert_eq!(count, 1);
+    }
+}
@@ -1,6 +1,9 @@
 mod graph;
 pub use graph::*;
 
+mod graph_watcher;
+pub use graph_watcher::*;
+
 mod node_options;
 pub use node_options::*;
 
@@ -11,19 +14,37 @@ use std::{
     cmp::PartialEq,
     ffi::CStr,
     fmt,
+    future::Future,
     os::raw::c_char,
     sync::{atomic::AtomicBool, Arc, Mutex, Weak},
+    time::Duration,
     vec::Vec,
 };
 
+use futures::future::BoxFuture;
 use rosidl_runtime_rs::Message;
 
 use crate::{
-    rcl_bindings::*, Client, ClientBase, ClientOptions, ClientState, Clock, ContextHandle,
-    GuardCondition, LogParams, Logger, ParameterBuilder, ParameterInterface, ParameterVariant,
-    Parameters, Publisher, PublisherOptions, PublisherState, RclrsError, Service, ServiceBase,
-    ServiceOptions, ServiceState, Subscription, SubscriptionBase, SubscriptionCallback,
-    SubscriptionOptions, SubscriptionState, TimeSource, ToLogParams, ENTITY_LIFECYCLE_MUTEX,
+    rcl_bindings::*, ActionClient, ActionClientBase, ActionClientOptions, ActionClientState,
+    ActionServer, ActionServerBase, ActionServerOptions, ActionServerState, AnyServiceCallback,
+    AnySubscriptionCallback, CallbackGroup, CallbackGroupState, CallbackGroupType, CancelResponse,
+    Client, ClientBase, ClientOptions, ClientState, Clock, ContextHandle, GoalResponse, GoalUuid,
+    GuardCondition, LogParams, Logger, MessageInfo, Parameter, ParameterBuilder,
+    ParameterCallbackHandle, ParameterClient, ParameterClientState, ParameterEventHandler,
+    ParameterInterface, ParameterVariant, Parameters, Publisher, PublisherBase,
+    PublisherEventCallback, PublisherOptions, PublisherState, QoSEventHandler,
+    QoSEventHandlerState, Rate, RclrsError, SerializedMessage, ServerGoalHandle, Service,
+    ServiceBase, ServiceInfo, ServiceOptions, ServiceResponder, ServiceState, Subscription,
+    SubscriptionBase, SubscriptionCallback, SubscriptionEventCallback, SubscriptionOptions,
+    SubscriptionState, TaskQueue, TimeSource, Timer, TimerOptions, TimerState, ToLogParams,
+    ENTITY_LIFECYCLE_MUTEX,
+};
+
+#[cfg(feature = "dyn_msg")]
+use crate::dynamic_message::{
+    DynamicMessageError, DynamicPublisher, DynamicPublisherState, DynamicSubscription,
+    DynamicSubscriptionCallback, DynamicSubscriptionState, GenericPublisher, GenericPublisherState,
+    GenericSubscription, GenericSubscriptionState,
 };
 
 // SAFETY: The functions accessing this type, including drop(), shouldn't care about the thread
@@ -87,7 +108,17 @@ pub struct NodeState {
     pub(crate) guard_conditions_mtx: Mutex<Vec<Weak<GuardCondition>>>,
     pub(crate) services_mtx: Mutex<Vec<Weak<dyn ServiceBase>>>,
     pub(crate) subscriptions_mtx: Mutex<Vec<Weak<dyn SubscriptionBase>>>,
-    time_source: TimeSource,
+    pub(crate) timers_mtx: Mutex<Vec<Weak<TimerState>>>,
+    pub(crate) action_servers_mtx: Mutex<Vec<Weak<dyn ActionServerBase>>>,
+    pub(crate) action_clients_mtx: Mutex<Vec<Weak<dyn ActionClientBase>>>,
+    pub(crate) events_mtx: Mutex<Vec<Weak<QoSEventHandlerState>>>,
+    pub(crate) graph_watchers_mtx: Mutex<Vec<Weak<GraphWatcherState>>>,
+    /// The graph guard condition, while it is used by a graph watcher or the user.
+    graph_guard_condition: Mutex<Weak<GuardCondition>>,
+    default_callback_group: CallbackGroup,
+    pub(crate) task_queue: Arc<TaskQueue>,
+    use_intra_process_comms: bool,
+    pub(crate) time_source: TimeSource,
     parameter: ParameterInterface,
     logger: Logger,
     pub(crate) handle: Arc<NodeHandle>,
@@ -285,6 +316,45 @@ impl NodeState {
         Ok(client)
     }
 
+    /// Returns the callback group that primitives of this node belong to unless they are
+    /// explicitly assigned to another group.
+    ///
+    /// The default callback group is [mutually exclusive][1], so callbacks in it
+    /// never run concurrently.
+    ///
+    /// [1]: CallbackGroupType::MutuallyExclusive
+    pub fn default_callback_group(&self) -> &CallbackGroup {
+        &self.default_callback_group
+    }
+
+    /// Returns whether publishers and subscriptions of this node use intra-process
+    /// communication unless their options say otherwise.
+    ///
+    /// See [`IntoNodeOptions::use_intra_process_comms`].
+    pub fn use_intra_process_comms(&self) -> bool {
+        self.use_intra_process_comms
+    }
+
+    /// Creates a new [`CallbackGroup`].
+    ///
+    /// Primitives are assigned to the group through their options:
+    ///
+    /// ```
+    /// # use rclrs::*;
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let sensor_group = node.create_callback_group(CallbackGroupType::Reentrant);
+    /// let subscription = node.create_subscription(
+    ///     "my_topic".callback_group(&sensor_group),
+    ///     |_msg: test_msgs::msg::Empty| {
+    ///         println!("Received message!");
+    ///     },
+    /// );
+    /// ```
+    pub fn create_callback_group(&self, group_type: CallbackGroupType) -> CallbackGroup {
+        Arc::new(CallbackGroupState::new(group_type))
+    }
+
     /// Creates a [`GuardCondition`][1] with no callback.
     ///
     /// A weak pointer to the `GuardCondition` is stored within this node.
@@ -303,6 +373,74 @@ impl NodeState {
         guard_condition
     }
 
+    /// Returns the [`GuardCondition`][1] that is triggered whenever the ROS graph changes,
+    /// e.g. when a node, publisher or subscription appears or disappears anywhere.
+    ///
+    /// The guard condition can be added to a [`WaitSet`][2] to wait for graph changes. While
+    /// the node has a [`GraphWatcher`], the node's executor waits on the same guard
+    /// condition, so it cannot be added to another wait set at the same time.
+    ///
+    /// [1]: crate::GuardCondition
+    /// [2]: crate::WaitSet
+    pub fn graph_guard_condition(&self) -> Arc<GuardCondition> {
+        let mut graph_guard_condition = self.graph_guard_condition.lock().unwrap();
+        if let Some(guard_condition) = graph_guard_condition.upgrade() {
+            return guard_condition;
+        }
+        let guard_condition = Arc::new(GuardCondition::new_for_graph(Arc::clone(&self.handle)));
+        *graph_guard_condition = Arc::downgrade(&guard_condition);
+        guard_condition
+    }
+
+    /// Creates a [`GraphWatcher`] that calls the callback with every change of the ROS graph.
+    ///
+    /// The callback runs in the default callback group of the node whenever the executor
+    /// notices that the graph changed.
+    ///
+    /// ```no_run
+    /// # use rclrs::*;
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let watcher = node.create_graph_watcher(|event| {
+    ///     if let GraphEvent::NodeLeft(info) = event {
+    ///         println!("Node {}/{} is gone", info.namespace, info.name);
+    ///     }
+    /// })?;
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn create_graph_watcher<F>(
+        self: &Arc<Self>,
+        callback: F,
+    ) -> Result<GraphWatcher, RclrsError>
+    where
+        F: FnMut(GraphEvent) + Send + 'static,
+    {
+        let watcher = Arc::new(GraphWatcherState::new(
+            self,
+            Box::new(callback),
+            Arc::clone(&self.default_callback_group),
+        )?);
+        { self.graph_watchers_mtx.lock().unwrap() }.push(Arc::downgrade(&watcher));
+        Ok(watcher)
+    }
+
+    /// Creates a [`GraphWatcher`] that only calls the callback when the ROS graph changes,
+    /// without taking snapshots of the graph.
+    pub(crate) fn create_graph_change_notifier<F>(self: &Arc<Self>, callback: F) -> GraphWatcher
+    where
+        F: FnMut() + Send + 'static,
+    {
+        let watcher = Arc::new(GraphWatcherState::new_change_notifier(
+            self,
+            Box::new(callback),
+            Arc::clone(&self.default_callback_group),
+        ));
+        { self.graph_watchers_mtx.lock().unwrap() }.push(Arc::downgrade(&watcher));
+        // Wake up the executor, so that it starts waiting for graph changes.
+        let _ = self.task_queue.guard_condition.trigger();
+        watcher
+    }
+
     /// Creates a [`GuardCondition`][1] with a callback.
     ///
     /// A weak pointer to the `GuardCondition` is stored within this node.
@@ -357,13 +495,13 @@ impl NodeState {
     /// ```
     ///
     pub fn create_publisher<'a, T>(
-        &self,
+        self: &Arc<Self>,
         options: impl Into<PublisherOptions<'a>>,
     ) -> Result<Publisher<T>, RclrsError>
     where
         T: Message,
     {
-        let publisher = Arc::new(PublisherState::<T>::new(Arc::clone(&self.handle), options)?);
+        let publisher = Arc::new(PublisherState::<T>::new(self, options)?);
         Ok(publisher)
     }
 
@@ -418,6 +556,87 @@ impl NodeState {
         T: rosidl_runtime_rs::Service,
         F: Fn(&rmw_request_id_t, T::Request) -> T::Response + 'static + Send,
     {
+        let callback = AnyServiceCallback::Regular(Box::new(callback));
+        let service = Arc::new(ServiceState::<T>::new(self, options, callback)?);
+        { self.services_mtx.lock().unwrap() }
+            .push(Arc::downgrade(&service) as Weak<dyn ServiceBase>);
+        Ok(service)
+    }
+
+    /// Creates a [`Service`] with an async callback.
+    ///
+    /// The future returned by the callback is run by the executor of this node, and the
+    /// response is sent once it completes. This allows the callback to await other
+    /// futures, such as the response of a [`Client`], without blocking the executor.
+    ///
+    /// ```
+    /// # use rclrs::*;
+    /// # use std::sync::Arc;
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let client = node.create_client::<test_msgs::srv::Empty>("other_service").unwrap();
+    /// let service = node.create_async_service::<test_msgs::srv::Empty, _, _>(
+    ///     "my_service",
+    ///     move |_info, request| {
+    ///         let client = Arc::clone(&client);
+    ///         async move { client.call_async(&request).await.unwrap() }
+    ///     },
+    /// );
+    /// ```
+    ///
+    /// See [`NodeState::create_service`] for how to configure the service options.
+    pub fn create_async_service<'a, T, F, Fut>(
+        self: &Arc<Self>,
+        options: impl Into<ServiceOptions<'a>>,
+        callback: F,
+    ) -> Result<Service<T>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+        F: Fn(&rmw_request_id_t, T::Request) -> Fut + 'static + Send,
+        Fut: Future<Output = T::Response> + 'static + Send,
+    {
+        let callback = AnyServiceCallback::Async(Box::new(move |request_id, request| {
+            Box::pin(callback(request_id, request)) as BoxFuture<'static, T::Response>
+        }));
+        let service = Arc::new(ServiceState::<T>::new(self, options, callback)?);
+        { self.services_mtx.lock().unwrap() }
+            .push(Arc::downgrade(&service) as Weak<dyn ServiceBase>);
+        Ok(service)
+    }
+
+    /// Creates a [`Service`] whose responses can be sent after the callback has returned.
+    ///
+    /// Instead of returning the response, the callback receives a [`ServiceResponder`]
+    /// for the request, which can be stored and used later, e.g. from another thread.
+    /// The callback also receives the [`ServiceInfo`] of the request, which contains
+    /// its timestamps and the GUID of the client that sent it.
+    ///
+    /// ```
+    /// # use rclrs::*;
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let service = node.create_deferred_service::<test_msgs::srv::Empty, _>(
+    ///     "my_service",
+    ///     |_info, _request, responder| {
+    ///         std::thread::spawn(move || {
+    ///             // Do some long-running work here.
+    ///             responder.send(test_msgs::srv::Empty_Response::default()).unwrap();
+    ///         });
+    ///     },
+    /// );
+    /// ```
+    ///
+    /// See [`NodeState::create_service`] for how to configure the service options.
+    pub fn create_deferred_service<'a, T, F>(
+        self: &Arc<Self>,
+        options: impl Into<ServiceOptions<'a>>,
+        callback: F,
+    ) -> Result<Service<T>, RclrsError>
+    where
+        T: rosidl_runtime_rs::Service,
+        F: Fn(ServiceInfo, T::Request, ServiceResponder<T::Response>) + 'static + Send,
+    {
+        let callback = AnyServiceCallback::Deferred(Box::new(callback));
         let service = Arc::new(ServiceState::<T>::new(self, options, callback)?);
         { self.services_mtx.lock().unwrap() }
             .push(Arc::downgrade(&service) as Weak<dyn ServiceBase>);
@@ -473,6 +692,49 @@ impl NodeState {
     where
         T: Message,
     {
+        let subscription = Arc::new(SubscriptionState::<T>::new(
+            self,
+            options,
+            callback.into_callback(),
+        )?);
+        { self.subscriptions_mtx.lock() }
+            .unwrap()
+            .push(Arc::downgrade(&subscription) as Weak<dyn SubscriptionBase>);
+        Ok(subscription)
+    }
+
+    /// Creates a [`Subscription`] with an async callback.
+    ///
+    /// The future returned by the callback is run by the executor of this node. Every
+    /// message starts a new future, so the futures of several messages may be in
+    /// progress at the same time.
+    ///
+    /// ```
+    /// # use rclrs::*;
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let subscription = node.create_async_subscription(
+    ///     "my_topic",
+    ///     |_msg: test_msgs::msg::Empty| async move {
+    ///         println!("Received message!");
+    ///     },
+    /// );
+    /// ```
+    ///
+    /// See [`NodeState::create_subscription`] for how to configure the subscription options.
+    pub fn create_async_subscription<'a, T, F, Fut>(
+        self: &Arc<Self>,
+        options: impl Into<SubscriptionOptions<'a>>,
+        mut callback: F,
+    ) -> Result<Subscription<T>, RclrsError>
+    where
+        T: Message,
+        F: FnMut(T) -> Fut + 'static + Send,
+        Fut: Future<Output = ()> + 'static + Send,
+    {
+        let callback = AnySubscriptionCallback::Async(Box::new(move |msg| {
+            Box::pin(callback(msg)) as BoxFuture<'static, ()>
+        }));
         let subscription = Arc::new(SubscriptionState::<T>::new(self, options, callback)?);
         { self.subscriptions_mtx.lock() }
             .unwrap()
@@ -480,6 +742,487 @@ impl NodeState {
         Ok(subscription)
     }
 
+    /// Creates a [`DynamicPublisher`] for a message type that is only known at runtime.
+    ///
+    /// The `topic_type` has the form `<package>/msg/<type_name>`, e.g. `sensor_msgs/msg/Image`,
+    /// and is resolved with [`DynamicMessageMetadata::new`][1]. The `options` are the same
+    /// as for [`NodeState::create_publisher`].
+    ///
+    /// ```no_run
+    /// # use rclrs::*;
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let publisher = node.create_dynamic_publisher("test_msgs/msg/BasicTypes", "my_topic")?;
+    /// let mut message = publisher.create_message();
+    /// message.set("int32_value", 42)?;
+    /// publisher.publish(&message)?;
+    /// # Ok::<(), rclrs::dynamic_message::DynamicMessageError>(())
+    /// ```
+    ///
+    /// [1]: crate::dynamic_message::DynamicMessageMetadata::new
+    #[cfg(feature = "dyn_msg")]
+    pub fn create_dynamic_publisher<'a>(
+        self: &Arc<Self>,
+        topic_type: &str,
+        options: impl Into<PublisherOptions<'a>>,
+    ) -> Result<DynamicPublisher, DynamicMessageError> {
+        let publisher = Arc::new(DynamicPublisherState::new(self, topic_type, options)?);
+        Ok(publisher)
+    }
+
+    /// Creates a [`DynamicSubscription`] for a message type that is only known at runtime.
+    ///
+    /// The `topic_type` has the same form as for [`NodeState::create_dynamic_publisher`],
+    /// and the `options` are the same as for [`NodeState::create_subscription`]. The
+    /// callback receives a [`DynamicMessage`][1], and optionally a [`MessageInfo`][2].
+    ///
+    /// ```no_run
+    /// # use rclrs::*;
+    /// # use rclrs::dynamic_message::DynamicMessage;
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let subscription = node.create_dynamic_subscription(
+    ///     "test_msgs/msg/BasicTypes",
+    ///     "my_topic",
+    ///     |msg: DynamicMessage| {
+    ///         println!("Received {:?}", msg);
+    ///     },
+    /// )?;
+    /// # Ok::<(), rclrs::dynamic_message::DynamicMessageError>(())
+    /// ```
+    ///
+    /// [1]: crate::dynamic_message::DynamicMessage
+    /// [2]: crate::MessageInfo
+    #[cfg(feature = "dyn_msg")]
+    pub fn create_dynamic_subscription<'a, Args>(
+        self: &Arc<Self>,
+        topic_type: &str,
+        options: impl Into<SubscriptionOptions<'a>>,
+        callback: impl DynamicSubscriptionCallback<Args>,
+    ) -> Result<DynamicSubscription, DynamicMessageError> {
+        let subscription = Arc::new(DynamicSubscriptionState::new(
+            self,
+            topic_type,
+            options,
+            callback.into_dynamic_callback(),
+        )?);
+        { self.subscriptions_mtx.lock() }
+            .unwrap()
+            .push(Arc::downgrade(&subscription) as Weak<dyn SubscriptionBase>);
+        Ok(subscription)
+    }
+
+    /// Creates a [`GenericPublisher`] for serialized messages of a type that is only known
+    /// at runtime.
+    ///
+    /// The `topic_type` has the form `<package>/msg/<type_name>`, as returned by
+    /// [`NodeState::get_topic_names_and_types`]. The `options` are the same as for
+    /// [`NodeState::create_publisher`].
+    ///
+    /// ```no_run
+    /// # use rclrs::*;
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let publisher = node.create_generic_publisher("test_msgs/msg/Empty", "my_topic")?;
+    /// let message = SerializedMessage::serialize(&test_msgs::msg::Empty::default())?;
+    /// publisher.publish(&message)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "dyn_msg")]
+    pub fn create_generic_publisher<'a>(
+        self: &Arc<Self>,
+        topic_type: &str,
+        options: impl Into<PublisherOptions<'a>>,
+    ) -> Result<GenericPublisher, DynamicMessageError> {
+        let publisher = Arc::new(GenericPublisherState::new(self, topic_type, options)?);
+        Ok(publisher)
+    }
+
+    /// Creates a [`GenericSubscription`] for serialized messages of a type that is only
+    /// known at runtime.
+    ///
+    /// The `topic_type` has the same form as for [`NodeState::create_generic_publisher`],
+    /// and the `options` are the same as for [`NodeState::create_subscription`].
+    ///
+    /// ```no_run
+    /// # use rclrs::*;
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// for (topic, types) in node.get_topic_names_and_types()? {
+    ///     let subscription = node.create_generic_subscription(
+    ///         &types[0],
+    ///         topic.as_str(),
+    ///         |msg: SerializedMessage, _info: MessageInfo| {
+    ///             println!("Received {} bytes", msg.len());
+    ///         },
+    ///     )?;
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "dyn_msg")]
+    pub fn create_generic_subscription<'a, F>(
+        self: &Arc<Self>,
+        topic_type: &str,
+        options: impl Into<SubscriptionOptions<'a>>,
+        callback: F,
+    ) -> Result<GenericSubscription, DynamicMessageError>
+    where
+        F: FnMut(SerializedMessage, MessageInfo) + 'static + Send,
+    {
+        let subscription = Arc::new(GenericSubscriptionState::new(
+            self,
+            topic_type,
+            options,
+            Box::new(callback),
+        )?);
+        { self.subscriptions_mtx.lock() }
+            .unwrap()
+            .push(Arc::downgrade(&subscription) as Weak<dyn SubscriptionBase>);
+        Ok(subscription)
+    }
+
+    /// Creates a [`Subscription`] that receives messages without deserializing them.
+    ///
+    /// The callback receives the [`SerializedMessage`] and its [`MessageInfo`]. This is
+    /// useful to forward or record messages, which can be deserialized later with
+    /// [`SerializedMessage::deserialize`].
+    ///
+    /// ```
+    /// # use rclrs::*;
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let subscription = node.create_serialized_subscription::<test_msgs::msg::Empty, _>(
+    ///     "my_topic",
+    ///     |msg: SerializedMessage, _info: MessageInfo| {
+    ///         println!("Received {} bytes", msg.len());
+    ///     },
+    /// );
+    /// ```
+    ///
+    /// See [`NodeState::create_subscription`] for how to configure the subscription options.
+    pub fn create_serialized_subscription<'a, T, F>(
+        self: &Arc<Self>,
+        options: impl Into<SubscriptionOptions<'a>>,
+        callback: F,
+    ) -> Result<Subscription<T>, RclrsError>
+    where
+        T: Message,
+        F: FnMut(SerializedMessage, MessageInfo) + 'static + Send,
+    {
+        let callback = AnySubscriptionCallback::Serialized(Box::new(callback));
+        let subscription = Arc::new(SubscriptionState::<T>::new(self, options, callback)?);
+        { self.subscriptions_mtx.lock() }
+            .unwrap()
+            .push(Arc::downgrade(&subscription) as Weak<dyn SubscriptionBase>);
+        Ok(subscription)
+    }
+
+    /// Runs a future on the executor of this node.
+    ///
+    /// The future is polled by the executor while it is [spinning][1], in between
+    /// the other callbacks of the node. This makes it possible to await e.g. the
+    /// response of a [`Client`] without needing a separate async runtime.
+    ///
+    /// The future should not block, since that would also block the executor.
+    ///
+    /// [1]: crate::Executor::spin
+    pub fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + 'static + Send,
+    {
+        self.task_queue.spawn(future);
+    }
+
+    /// Creates a [`Timer`] that uses the clock of this node.
+    ///
+    /// The timer follows the node's [`Clock`], so if the node is using simulated
+    /// time (i.e. the `use_sim_time` parameter is set), the timer elapses according
+    /// to the time received on the `/clock` topic.
+    ///
+    /// ```
+    /// # use rclrs::*;
+    /// # use std::time::Duration;
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let timer = node.create_timer(
+    ///     Duration::from_millis(500),
+    ///     |_timer| {
+    ///         println!("Timer elapsed!");
+    ///     },
+    /// )
+    /// .unwrap();
+    /// ```
+    ///
+    /// The timer is passed into the callback, which allows e.g. canceling it after it
+    /// has elapsed a certain number of times.
+    ///
+    /// Pass in [`TimerOptions`] to put the timer into a different callback group:
+    ///
+    /// ```
+    /// # use rclrs::*;
+    /// # use std::time::Duration;
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let group = node.create_callback_group(CallbackGroupType::MutuallyExclusive);
+    /// let timer = node.create_timer(
+    ///     TimerOptions::new(Duration::from_millis(500)).callback_group(&group),
+    ///     |_timer| {
+    ///         println!("Timer elapsed!");
+    ///     },
+    /// )
+    /// .unwrap();
+    /// ```
+    pub fn create_timer<'a, F>(
+        &self,
+        options: impl Into<TimerOptions<'a>>,
+        callback: F,
+    ) -> Result<Timer, RclrsError>
+    where
+        F: FnMut(&TimerState) + Send + 'static,
+    {
+        self.create_timer_with_clock(self.get_clock(), options.into(), callback)
+    }
+
+    /// Creates a [`Timer`] that uses a steady clock.
+    ///
+    /// In contrast to [`NodeState::create_timer`], the timer is not affected by simulated
+    /// time, so it keeps elapsing according to the time that passes in the real world.
+    ///
+    /// ```
+    /// # use rclrs::*;
+    /// # use std::time::Duration;
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let timer = node.create_wall_timer(
+    ///     Duration::from_secs(1),
+    ///     |_timer| {
+    ///         println!("One second has passed!");
+    ///     },
+    /// )
+    /// .unwrap();
+    /// ```
+    pub fn create_wall_timer<'a, F>(
+        &self,
+        options: impl Into<TimerOptions<'a>>,
+        callback: F,
+    ) -> Result<Timer, RclrsError>
+    where
+        F: FnMut(&TimerState) + Send + 'static,
+    {
+        self.create_timer_with_clock(Clock::steady(), options.into(), callback)
+    }
+
+    /// Creates a [`Rate`] that uses the clock of this node.
+    ///
+    /// When the node uses sim time, the rate follows the time published on `/clock`, and
+    /// sleeping stops when the context of the node shuts down.
+    ///
+    /// ```no_run
+    /// # use rclrs::*;
+    /// # use std::time::Duration;
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let mut rate = node.create_rate(Duration::from_millis(100));
+    /// while rate.sleep() {
+    ///     println!("Running at 10 Hz");
+    /// }
+    /// ```
+    pub fn create_rate(&self, period: Duration) -> Rate {
+        Rate::new(self.get_clock(), period)
+    }
+
+    fn create_timer_with_clock<F>(
+        &self,
+        clock: Clock,
+        options: TimerOptions,
+        callback: F,
+    ) -> Result<Timer, RclrsError>
+    where
+        F: FnMut(&TimerState) + Send + 'static,
+    {
+        let callback_group = options
+            .callback_group
+            .unwrap_or(&self.default_callback_group);
+        let timer = Arc::new(TimerState::new(
+            &self.handle.context_handle,
+            clock,
+            options.period,
+            Box::new(callback),
+            Arc::clone(callback_group),
+        )?);
+        { self.timers_mtx.lock().unwrap() }.push(Arc::downgrade(&timer));
+        Ok(timer)
+    }
+
+    /// Creates a [`QoSEventHandler`] for an event of a publisher.
+    ///
+    /// The variant of the callback determines which event is handled, e.g. a missed
+    /// deadline or an incompatible QoS profile of a subscription. The callback runs in the
+    /// default callback group of the node.
+    ///
+    /// ```no_run
+    /// # use rclrs::*;
+    /// # use std::time::Duration;
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let publisher = node.create_publisher::<test_msgs::msg::Empty>(
+    ///     "my_topic".qos(QoSProfile::default().deadline_duration(Duration::from_millis(100))),
+    /// )?;
+    /// let handler = node.create_publisher_event_handler(
+    ///     &publisher,
+    ///     PublisherEventCallback::OfferedDeadlineMissed(Box::new(|status| {
+    ///         println!("Missed {} deadlines", status.total_count);
+    ///     })),
+    /// )?;
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn create_publisher_event_handler<P>(
+        &self,
+        publisher: &Arc<P>,
+        callback: PublisherEventCallback,
+    ) -> Result<QoSEventHandler, RclrsError>
+    where
+        P: PublisherBase + 'static,
+    {
+        let handler = Arc::new(QoSEventHandlerState::new_for_publisher(
+            Arc::clone(publisher) as Arc<dyn PublisherBase>,
+            callback,
+            Arc::clone(&self.default_callback_group),
+        )?);
+        { self.events_mtx.lock().unwrap() }.push(Arc::downgrade(&handler));
+        Ok(handler)
+    }
+
+    /// Creates a [`QoSEventHandler`] for an event of a subscription.
+    ///
+    /// The variant of the callback determines which event is handled, e.g. a missed
+    /// deadline or lost messages. The callback runs in the callback group of the
+    /// subscription.
+    ///
+    /// ```no_run
+    /// # use rclrs::*;
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let subscription = node.create_subscription(
+    ///     "my_topic",
+    ///     |_msg: test_msgs::msg::Empty| {},
+    /// )?;
+    /// let handler = node.create_subscription_event_handler(
+    ///     &subscription,
+    ///     SubscriptionEventCallback::RequestedIncompatibleQoS(Box::new(|status| {
+    ///         println!("Incompatible {:?} policy", status.last_policy_kind);
+    ///     })),
+    /// )?;
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn create_subscription_event_handler<S>(
+        &self,
+        subscription: &Arc<S>,
+        callback: SubscriptionEventCallback,
+    ) -> Result<QoSEventHandler, RclrsError>
+    where
+        S: SubscriptionBase + 'static,
+    {
+        let callback_group = Arc::clone(subscription.callback_group());
+        let handler = Arc::new(QoSEventHandlerState::new_for_subscription(
+            Arc::clone(subscription) as Arc<dyn SubscriptionBase>,
+            callback,
+            callback_group,
+        )?);
+        { self.events_mtx.lock().unwrap() }.push(Arc::downgrade(&handler));
+        Ok(handler)
+    }
+
+    /// Creates an [`ActionServer`].
+    ///
+    /// The action server calls the three callbacks to handle incoming requests:
+    /// - `goal_callback` decides whether a new goal is accepted or rejected.
+    /// - `cancel_callback` decides whether a request to cancel a goal is accepted.
+    /// - `accepted_callback` is called with the [`ServerGoalHandle`] of every goal that
+    ///   was accepted. The goal is finished by calling e.g. [`ServerGoalHandle::succeed`].
+    ///
+    /// The callbacks are run by the executor, so long-running goals should be executed
+    /// on a separate thread to keep the executor responsive.
+    ///
+    /// ```
+    /// # use rclrs::*;
+    /// # use std::sync::Arc;
+    /// # use test_msgs::action::{Fibonacci, Fibonacci_Result};
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let action_server = node.create_action_server::<Fibonacci, _, _, _>(
+    ///     "fibonacci",
+    ///     |_uuid, _goal| GoalResponse::AcceptAndExecute,
+    ///     |_goal_handle| CancelResponse::Accept,
+    ///     |goal_handle: Arc<ServerGoalHandle<Fibonacci>>| {
+    ///         std::thread::spawn(move || {
+    ///             let sequence = vec![0, 1, 1, 2, 3];
+    ///             goal_handle.succeed(Fibonacci_Result { sequence }).unwrap();
+    ///         });
+    ///     },
+    /// )
+    /// .unwrap();
+    /// ```
+    ///
+    /// Any quality of service options that you explicitly specify through the
+    /// [`IntoPrimitiveOptions`][1] API are applied to the services and the feedback topic
+    /// of the action server.
+    ///
+    /// [1]: crate::IntoPrimitiveOptions
+    pub fn create_action_server<'a, A, G, C, Acc>(
+        self: &Arc<Self>,
+        options: impl Into<ActionServerOptions<'a>>,
+        goal_callback: G,
+        cancel_callback: C,
+        accepted_callback: Acc,
+    ) -> Result<ActionServer<A>, RclrsError>
+    where
+        A: rosidl_runtime_rs::ActionImpl,
+        G: Fn(GoalUuid, &A::Goal) -> GoalResponse + 'static + Send,
+        C: Fn(Arc<ServerGoalHandle<A>>) -> CancelResponse + 'static + Send,
+        Acc: Fn(Arc<ServerGoalHandle<A>>) + 'static + Send,
+    {
+        let action_server = Arc::new(ActionServerState::<A>::new(
+            self,
+            options,
+            goal_callback,
+            cancel_callback,
+            accepted_callback,
+        )?);
+        { self.action_servers_mtx.lock().unwrap() }
+            .push(Arc::downgrade(&action_server) as Weak<dyn ActionServerBase>);
+        Ok(action_server)
+    }
+
+    /// Creates an [`ActionClient`].
+    ///
+    /// ```
+    /// # use rclrs::*;
+    /// # use test_msgs::action::{Fibonacci, Fibonacci_Goal};
+    /// # let executor = Context::default().create_basic_executor();
+    /// # let node = executor.create_node("my_node").unwrap();
+    /// let action_client = node.create_action_client::<Fibonacci>("fibonacci").unwrap();
+    /// let goal_handle = action_client.send_goal(Fibonacci_Goal { order: 5 });
+    /// ```
+    ///
+    /// Any quality of service options that you explicitly specify through the
+    /// [`IntoPrimitiveOptions`][1] API are applied to the services and the feedback topic
+    /// of the action client.
+    ///
+    /// [1]: crate::IntoPrimitiveOptions
+    pub fn create_action_client<'a, A>(
+        self: &Arc<Self>,
+        options: impl Into<ActionClientOptions<'a>>,
+    ) -> Result<ActionClient<A>, RclrsError>
+    where
+        A: rosidl_runtime_rs::ActionImpl,
+    {
+        let action_client = Arc::new(ActionClientState::<A>::new(self, options)?);
+        { self.action_clients_mtx.lock().unwrap() }
+            .push(Arc::downgrade(&action_client) as Weak<dyn ActionClientBase>);
+        Ok(action_client)
+    }
+
     /// Returns the subscriptions that have not been dropped yet.
     pub(crate) fn live_subscriptions(&self) -> Vec<Arc<dyn SubscriptionBase>> {
         { self.subscriptions_mtx.lock().unwrap() }
@@ -509,6 +1252,42 @@ impl NodeState {
             .collect()
     }
 
+    pub(crate) fn live_timers(&self) -> Vec<Arc<TimerState>> {
+        { self.timers_mtx.lock().unwrap() }
+            .iter()
+            .filter_map(Weak::upgrade)
+            .chain(self.task_queue.live_timers())
+            .collect()
+    }
+
+    pub(crate) fn live_action_servers(&self) -> Vec<Arc<dyn ActionServerBase>> {
+        { self.action_servers_mtx.lock().unwrap() }
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+
+    pub(crate) fn live_action_clients(&self) -> Vec<Arc<dyn ActionClientBase>> {
+        { self.action_clients_mtx.lock().unwrap() }
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+
+    pub(crate) fn live_events(&self) -> Vec<Arc<QoSEventHandlerState>> {
+        { self.events_mtx.lock().unwrap() }
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+
+    pub(crate) fn live_graph_watchers(&self) -> Vec<Arc<GraphWatcherState>> {
+        { self.graph_watchers_mtx.lock().unwrap() }
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+
     /// Returns the ROS domain ID that the node is using.
     ///
     /// The domain ID controls which nodes can send messages to each other, see the [ROS 2 concept article][1].
@@ -587,6 +1366,105 @@ impl NodeState {
         }
     }
 
+    /// Registers a callback that validates parameter changes before they are applied.
+    ///
+    /// The callback receives the parameters that are about to be set, and can reject the
+    /// change by returning an error with a reason. A rejected change is not applied, and
+    /// the reason is reported back to the caller, either as a
+    /// [`ParameterValueError::Rejected`] or in the result of the `set_parameters` service.
+    ///
+    /// Changes requested through the `set_parameters_atomically` service are passed to the
+    /// callback all at once, all other changes are passed one parameter at a time.
+    ///
+    /// The callback must not get or set parameters of this node, nor register or drop
+    /// parameter callbacks, since that would deadlock.
+    /// The callback stays registered until the returned handle is dropped.
+    ///
+    /// ```
+    /// # use rclrs::*;
+    /// # let executor = Context::default().create_basic_executor();
+    /// let node = executor.create_node("my_node")?;
+    /// let param = node.declare_parameter("param").default(0).mandatory().unwrap();
+    /// let _callback = node.add_on_set_parameters_callback(|parameters| {
+    ///     for parameter in parameters {
+    ///         if parameter.value == Some(ParameterValue::Integer(13)) {
+    ///             return Err(String::from("13 is an unlucky number"));
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// });
+    /// assert!(param.set(13).is_err());
+    /// assert!(param.set(7).is_ok());
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn add_on_set_parameters_callback(
+        &self,
+        callback: impl FnMut(&[Parameter]) -> Result<(), String> + Send + 'static,
+    ) -> ParameterCallbackHandle {
+        self.parameter.add_on_set_callback(callback)
+    }
+
+    /// Registers a callback that is called after parameters were changed.
+    ///
+    /// The callback receives the parameters that were set, after they were accepted by all
+    /// callbacks registered with [`add_on_set_parameters_callback`][1]. To be notified about
+    /// changes of a single parameter, use the `add_post_set_callback` method of the
+    /// parameter instead.
+    ///
+    /// The callback must not set parameters of this node, nor register or drop parameter
+    /// callbacks, since that would deadlock.
+    /// The callback stays registered until the returned handle is dropped.
+    ///
+    /// [1]: Self::add_on_set_parameters_callback
+    pub fn add_post_set_parameters_callback(
+        &self,
+        callback: impl FnMut(&[Parameter]) + Send + 'static,
+    ) -> ParameterCallbackHandle {
+        self.parameter.add_post_set_callback(callback)
+    }
+
+    /// Creates a [`ParameterEventHandler`] to react to parameter changes of any node.
+    ///
+    /// Parameter changes are received through the `/parameter_events` topic, so the
+    /// callbacks of the handler are run while this node is spun by its executor.
+    ///
+    /// ```
+    /// # use rclrs::*;
+    /// # let executor = Context::default().create_basic_executor();
+    /// let node = executor.create_node("my_node")?;
+    /// let handler = node.create_parameter_event_handler()?;
+    /// let _callback = handler.add_parameter_callback("speed", "other_node", |parameter| {
+    ///     println!("The speed of other_node is now {:?}", parameter.value);
+    /// });
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn create_parameter_event_handler(
+        self: &Arc<Self>,
+    ) -> Result<ParameterEventHandler, RclrsError> {
+        ParameterEventHandler::new(self)
+    }
+
+    /// Creates a [`ParameterClient`] to access the parameters of another node.
+    ///
+    /// The `remote_node_name` is either a fully qualified node name, or a node name that is
+    /// resolved in the namespace of this node.
+    ///
+    /// ```no_run
+    /// # use rclrs::*;
+    /// # let executor = Context::default().create_basic_executor();
+    /// let node = executor.create_node("my_node")?;
+    /// let client = node.create_parameter_client("other_node")?;
+    /// client.wait_for_services(None)?;
+    /// let request = client.get_parameter("speed");
+    /// # Ok::<(), RclrsError>(())
+    /// ```
+    pub fn create_parameter_client(
+        self: &Arc<Self>,
+        remote_node_name: &str,
+    ) -> Result<ParameterClient, RclrsError> {
+        Ok(Arc::new(ParameterClientState::new(self, remote_node_name)?))
+    }
+
     /// Get the logger associated with this Node.
     pub fn logger(&self) -> &Logger {
         &self.logger
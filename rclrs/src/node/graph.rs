@@ -1,9 +1,23 @@
 use std::{
     collections::HashMap,
     ffi::{CStr, CString},
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
-use crate::{rcl_bindings::*, NodeState, RclrsError, ToResult};
+use futures::{channel::mpsc, future::select, StreamExt};
+
+use crate::{
+    rcl_bindings::*, Clock, Node, NodeHandle, NodeState, QoSProfile, RclReturnCode, RclrsError,
+    ToResult,
+};
+
+/// The longest time to wait for a graph change before checking the condition again.
+///
+/// A graph change may be missed when the graph guard condition of a node is waited on by
+/// several threads at once, so the condition is also checked periodically.
+const GRAPH_WAIT_PERIOD: Duration = Duration::from_millis(100);
 
 impl Drop for rmw_names_and_types_t {
     fn drop(&mut self) {
@@ -38,7 +52,7 @@ impl Drop for rcutils_string_array_t {
 pub type TopicNamesAndTypes = HashMap<String, Vec<String>>;
 
 /// Stores a node's name and namespace
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct NodeNameInfo {
     /// The name of the node
     pub name: String,
@@ -57,6 +71,34 @@ pub struct TopicEndpointInfo {
     pub topic_type: String,
 }
 
+/// Whether a [`TopicEndpoint`] publishes or subscribes to its topic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TopicEndpointKind {
+    /// The endpoint is a publisher.
+    Publisher,
+    /// The endpoint is a subscription.
+    Subscription,
+}
+
+/// A publisher or subscription in the ROS graph, together with its QoS profile.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TopicEndpoint {
+    /// The fully qualified name of the topic
+    pub topic_name: String,
+    /// Whether the endpoint is a publisher or a subscription
+    pub kind: TopicEndpointKind,
+    /// The name of the endpoint node
+    pub node_name: String,
+    /// The namespace of the endpoint node
+    pub node_namespace: String,
+    /// The type of the topic
+    pub topic_type: String,
+    /// The globally unique identifier of the endpoint in the RMW implementation
+    pub gid: [u8; RMW_GID_STORAGE_SIZE],
+    /// The QoS profile of the endpoint
+    pub qos: QoSProfile,
+}
+
 impl NodeState {
     /// Returns a list of topic names and types for publishers associated with a node.
     pub fn get_publisher_names_and_types_by_node(
@@ -292,7 +334,8 @@ impl NodeState {
         &self,
         topic: &str,
     ) -> Result<Vec<TopicEndpointInfo>, RclrsError> {
-        self.get_publisher_subscriber_info_by_topic(topic, rcl_get_publishers_info_by_topic)
+        self.get_topic_endpoints(topic, TopicEndpointKind::Publisher)
+            .map(|endpoints| endpoints.into_iter().map(TopicEndpointInfo::from).collect())
     }
 
     /// Returns topic subscriptions info.
@@ -300,7 +343,8 @@ impl NodeState {
         &self,
         topic: &str,
     ) -> Result<Vec<TopicEndpointInfo>, RclrsError> {
-        self.get_publisher_subscriber_info_by_topic(topic, rcl_get_subscriptions_info_by_topic)
+        self.get_topic_endpoints(topic, TopicEndpointKind::Subscription)
+            .map(|endpoints| endpoints.into_iter().map(TopicEndpointInfo::from).collect())
     }
 
     /// Returns an rcl names_and_types function, without a "no_demangle" argument.
@@ -345,18 +389,17 @@ impl NodeState {
     }
 
     /// Returns publisher or subscriber info by topic.
-    fn get_publisher_subscriber_info_by_topic(
+    /// Returns the publishers or subscriptions of a topic, including their QoS profiles.
+    pub(crate) fn get_topic_endpoints(
         &self,
         topic: &str,
-        getter: unsafe extern "C" fn(
-            *const rcl_node_t,
-            *mut rcl_allocator_t,
-            *const ::std::os::raw::c_char,
-            bool,
-            *mut rcl_topic_endpoint_info_array_t,
-        ) -> rcl_ret_t,
-    ) -> Result<Vec<TopicEndpointInfo>, RclrsError> {
-        let topic = CString::new(topic).map_err(|err| RclrsError::StringContainsNul {
+        kind: TopicEndpointKind,
+    ) -> Result<Vec<TopicEndpoint>, RclrsError> {
+        let getter = match kind {
+            TopicEndpointKind::Publisher => rcl_get_publishers_info_by_topic,
+            TopicEndpointKind::Subscription => rcl_get_subscriptions_info_by_topic,
+        };
+        let topic_c_string = CString::new(topic).map_err(|err| RclrsError::StringContainsNul {
             s: topic.to_string(),
             err,
         })?;
@@ -371,7 +414,7 @@ impl NodeState {
             getter(
                 &*rcl_node,
                 &mut rcutils_get_default_allocator(),
-                topic.as_ptr(),
+                topic_c_string.as_ptr(),
                 false,
                 &mut rcl_publishers_info,
             )
@@ -384,7 +427,7 @@ impl NodeState {
 
         // SAFETY: Because the rcl call returned successfully, each element of the slice points
         // to a valid topic_endpoint_info object, which contains valid C strings
-        let topic_endpoint_infos_vec = topic_endpoint_infos_slice
+        let topic_endpoints = topic_endpoint_infos_slice
             .iter()
             .map(|info| {
                 let (node_name, node_namespace, topic_type) = unsafe {
@@ -400,15 +443,150 @@ impl NodeState {
                             .into_owned(),
                     )
                 };
-                TopicEndpointInfo {
+                TopicEndpoint {
+                    topic_name: topic.to_owned(),
+                    kind,
                     node_name,
                     node_namespace,
                     topic_type,
+                    gid: info.endpoint_gid,
+                    qos: (&info.qos_profile).into(),
                 }
             })
             .collect();
 
-        Ok(topic_endpoint_infos_vec)
+        Ok(topic_endpoints)
+    }
+}
+
+impl From<TopicEndpoint> for TopicEndpointInfo {
+    fn from(endpoint: TopicEndpoint) -> Self {
+        Self {
+            node_name: endpoint.node_name,
+            node_namespace: endpoint.node_namespace,
+            topic_type: endpoint.topic_type,
+        }
+    }
+}
+
+/// Blocks until `condition` returns `true`, and returns whether that happened.
+///
+/// The condition is checked whenever the graph of the node changes. Waiting stops early
+/// when the timeout elapses or the context shuts down. A timeout of `None` waits
+/// indefinitely.
+pub(crate) fn wait_for_graph_condition(
+    node_handle: &NodeHandle,
+    timeout: Option<Duration>,
+    condition: impl Fn() -> Result<bool, RclrsError>,
+) -> Result<bool, RclrsError> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    loop {
+        if condition()? {
+            return Ok(true);
+        }
+        if !node_handle.context_handle.ok() {
+            return Ok(false);
+        }
+        let wait = match deadline {
+            Some(deadline) => {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Ok(false);
+                }
+                GRAPH_WAIT_PERIOD.min(deadline - now)
+            }
+            None => GRAPH_WAIT_PERIOD,
+        };
+        wait_for_graph_change(node_handle, wait)?;
+    }
+}
+
+/// Returns a future that completes once `condition` returns `true`, with the same output
+/// as [`wait_for_graph_condition`].
+///
+/// The condition is checked whenever the executor of the node notices that the graph
+/// changed, so the executor must be spinning for the future to make progress.
+pub(crate) fn wait_for_graph_condition_async(
+    node: &Node,
+    timeout: Option<Duration>,
+    condition: impl Fn() -> Result<bool, RclrsError> + Send + 'static,
+) -> impl Future<Output = Result<bool, RclrsError>> + Send + 'static {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let context = Arc::clone(&node.handle.context_handle);
+    // The notifier is created before the condition is checked for the first time, so that
+    // no graph change is missed in between.
+    let (sender, mut changes) = mpsc::unbounded();
+    let notifier = node.create_graph_change_notifier(move || {
+        sender.unbounded_send(()).ok();
+    });
+    async move {
+        let _notifier = notifier;
+        loop {
+            if condition()? {
+                return Ok(true);
+            }
+            if !context.ok() {
+                return Ok(false);
+            }
+            let wait = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Ok(false);
+                    }
+                    GRAPH_WAIT_PERIOD.min(deadline - now)
+                }
+                None => GRAPH_WAIT_PERIOD,
+            };
+            let sleep = Box::pin(Clock::steady().sleep_for_async(wait));
+            select(changes.next(), sleep).await;
+        }
+    }
+}
+
+/// Blocks until the graph of the node changes or the timeout elapses.
+fn wait_for_graph_change(node_handle: &NodeHandle, timeout: Duration) -> Result<(), RclrsError> {
+    // SAFETY: Getting a zero-initialized value is always safe.
+    let mut rcl_wait_set = unsafe { rcl_get_zero_initialized_wait_set() };
+    {
+        let mut rcl_context = node_handle.context_handle.rcl_context.lock().unwrap();
+        // SAFETY: The wait set is zero-initialized and the context is valid.
+        unsafe {
+            rcl_wait_set_init(
+                &mut rcl_wait_set,
+                0,
+                1,
+                0,
+                0,
+                0,
+                0,
+                &mut *rcl_context,
+                rcutils_get_default_allocator(),
+            )
+            .ok()?;
+        }
+    }
+    // SAFETY: The node is valid. The returned guard condition is owned by the node, which
+    // is kept alive by the node handle while waiting.
+    let graph_guard_condition =
+        unsafe { rcl_node_get_graph_guard_condition(&*node_handle.rcl_node.lock().unwrap()) };
+    // SAFETY: The wait set was initialized with capacity for one guard condition.
+    unsafe {
+        rcl_wait_set_add_guard_condition(
+            &mut rcl_wait_set,
+            graph_guard_condition,
+            std::ptr::null_mut(),
+        )
+        .ok()?;
+    }
+    // The wait set is finalized when it is dropped.
+    // SAFETY: The wait set is only used by this thread.
+    match unsafe { rcl_wait(&mut rcl_wait_set, timeout.as_nanos() as i64) }.ok() {
+        Err(RclrsError::RclError {
+            code: RclReturnCode::Timeout,
+            ..
+        }) => Ok(()),
+        result => result,
     }
 }
 
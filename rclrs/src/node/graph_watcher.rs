@@ -0,0 +1,276 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    rcl_bindings::RMW_GID_STORAGE_SIZE, CallbackGroup, GuardCondition, Node, NodeNameInfo,
+    NodeState, RclrsError, TopicEndpoint, TopicEndpointKind,
+};
+
+/// A change of the ROS graph, reported by a [`GraphWatcher`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GraphEvent {
+    /// A node appeared in the graph.
+    NodeJoined(NodeNameInfo),
+    /// A node disappeared from the graph, e.g. because it shut down or crashed.
+    NodeLeft(NodeNameInfo),
+    /// A publisher or subscription appeared on a topic.
+    EndpointAdded(TopicEndpoint),
+    /// A publisher or subscription disappeared from a topic.
+    EndpointRemoved(TopicEndpoint),
+}
+
+/// Reports changes of the ROS graph as [`GraphEvent`]s.
+///
+/// Create a graph watcher using [`Node::create_graph_watcher`][1].
+///
+/// The watcher takes a snapshot of all nodes and topic endpoints in the graph when it is
+/// created. Whenever the graph changes, it takes a new snapshot and calls its callback with
+/// the differences. This requires the node's executor to [spin][2].
+///
+/// Nodes that crash are only removed from the graph once the middleware notices that
+/// they are gone, which may take a few seconds depending on the RMW implementation.
+///
+/// [1]: crate::NodeState::create_graph_watcher
+/// [2]: crate::Executor::spin
+pub type GraphWatcher = Arc<GraphWatcherState>;
+
+/// The inner state of a [`GraphWatcher`].
+///
+/// This is public so that you can choose to create a [`Weak`][1] reference to it
+/// if you want to be able to refer to a [`GraphWatcher`] in a non-owning way.
+///
+/// [1]: std::sync::Weak
+pub struct GraphWatcherState {
+    callback: Mutex<GraphCallback>,
+    snapshot: Mutex<GraphSnapshot>,
+    /// The graph guard condition of the node, which is shared by all of its watchers.
+    pub(crate) guard_condition: Arc<GuardCondition>,
+    pub(crate) callback_group: CallbackGroup,
+    /// Ensure the parent node remains alive as long as the watcher is held.
+    node: Node,
+}
+
+enum GraphCallback {
+    /// Receives the changes between snapshots of the graph.
+    Events(Box<dyn FnMut(GraphEvent) + Send>),
+    /// Is only told that the graph changed, so no snapshots are taken.
+    Changed(Box<dyn FnMut() + Send>),
+}
+
+type EndpointKey = (String, TopicEndpointKind, [u8; RMW_GID_STORAGE_SIZE]);
+
+struct GraphSnapshot {
+    nodes: BTreeSet<NodeNameInfo>,
+    endpoints: BTreeMap<EndpointKey, TopicEndpoint>,
+}
+
+impl GraphWatcherState {
+    pub(crate) fn new(
+        node: &Node,
+        callback: Box<dyn FnMut(GraphEvent) + Send>,
+        callback_group: CallbackGroup,
+    ) -> Result<Self, RclrsError> {
+        Ok(Self {
+            callback: Mutex::new(GraphCallback::Events(callback)),
+            snapshot: Mutex::new(GraphSnapshot::take(node)?),
+            guard_condition: node.graph_guard_condition(),
+            callback_group,
+            node: Arc::clone(node),
+        })
+    }
+
+    /// Creates a watcher that calls the callback whenever the graph changes, without
+    /// reporting what changed.
+    ///
+    /// This is used to wake up futures that wait for a condition of the graph.
+    pub(crate) fn new_change_notifier(
+        node: &Node,
+        callback: Box<dyn FnMut() + Send>,
+        callback_group: CallbackGroup,
+    ) -> Self {
+        Self {
+            callback: Mutex::new(GraphCallback::Changed(callback)),
+            snapshot: Mutex::new(GraphSnapshot::empty()),
+            guard_condition: node.graph_guard_condition(),
+            callback_group,
+            node: Arc::clone(node),
+        }
+    }
+
+    /// Returns the nodes in the graph as of the last change that the watcher processed.
+    pub fn nodes(&self) -> Vec<NodeNameInfo> {
+        self.snapshot
+            .lock()
+            .unwrap()
+            .nodes
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the publishers and subscriptions in the graph as of the last change that the
+    /// watcher processed.
+    pub fn endpoints(&self) -> Vec<TopicEndpoint> {
+        self.snapshot
+            .lock()
+            .unwrap()
+            .endpoints
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Takes a new snapshot of the graph and calls the callback with the changes.
+    pub(crate) fn execute(&self) -> Result<(), RclrsError> {
+        let mut callback = self.callback.lock().unwrap();
+        let callback = match &mut *callback {
+            GraphCallback::Events(callback) => callback,
+            GraphCallback::Changed(callback) => {
+                callback();
+                return Ok(());
+            }
+        };
+        let new_snapshot = GraphSnapshot::take(&self.node)?;
+        let events = {
+            let mut snapshot = self.snapshot.lock().unwrap();
+            let events = snapshot.diff(&new_snapshot);
+            *snapshot = new_snapshot;
+            events
+        };
+        for event in events {
+            callback(event);
+        }
+        Ok(())
+    }
+}
+
+impl GraphSnapshot {
+    fn empty() -> Self {
+        Self {
+            nodes: BTreeSet::new(),
+            endpoints: BTreeMap::new(),
+        }
+    }
+
+    fn take(node: &NodeState) -> Result<Self, RclrsError> {
+        let mut snapshot = Self {
+            nodes: node.get_node_names()?.into_iter().collect(),
+            ..Self::empty()
+        };
+        for topic in node.get_topic_names_and_types()?.into_keys() {
+            for kind in [
+                TopicEndpointKind::Publisher,
+                TopicEndpointKind::Subscription,
+            ] {
+                for endpoint in node.get_topic_endpoints(&topic, kind)? {
+                    let key = (topic.clone(), kind, endpoint.gid);
+                    snapshot.endpoints.insert(key, endpoint);
+                }
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// Returns the events that turn this snapshot into the other one.
+    ///
+    /// Nodes join before their endpoints are added, and leave after their endpoints were
+    /// removed.
+    fn diff(&self, other: &Self) -> Vec<GraphEvent> {
+        let joined = other.nodes.difference(&self.nodes).cloned();
+        let added = other
+            .endpoints
+            .iter()
+            .filter(|(key, _)| !self.endpoints.contains_key(key))
+            .map(|(_, endpoint)| endpoint.clone());
+        let removed = self
+            .endpoints
+            .iter()
+            .filter(|(key, _)| !other.endpoints.contains_key(key))
+            .map(|(_, endpoint)| endpoint.clone());
+        let left = self.nodes.difference(&other.nodes).cloned();
+        joined
+            .map(GraphEvent::NodeJoined)
+            .chain(added.map(GraphEvent::EndpointAdded))
+            .chain(removed.map(GraphEvent::EndpointRemoved))
+            .chain(left.map(GraphEvent::NodeLeft))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn traits() {
+        use crate::test_helpers::*;
+
+        assert_send::<GraphWatcher>();
+        assert_sync::<GraphWatcher>();
+    }
+
+    #[test]
+    fn test_graph_watcher_reports_changes() -> Result<(), RclrsError> {
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_graph_watcher_{}", line!()))?;
+        let topic = format!("/test_graph_watcher_topic_{}", line!());
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let _watcher = {
+            let events = Arc::clone(&events);
+            node.create_graph_watcher(move |event| events.lock().unwrap().push(event))?
+        };
+        let spin_until = |executor: &mut Executor, done: &dyn Fn(&[GraphEvent]) -> bool| {
+            let start = Instant::now();
+            while !done(&events.lock().unwrap()) {
+                assert!(start.elapsed() < Duration::from_secs(10));
+                executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+            }
+        };
+
+        let other_node = executor.create_node(&format!("test_graph_watcher_other_{}", line!()))?;
+        let publisher = other_node.create_publisher::<test_msgs::msg::Empty>(
+            topic.as_str().qos(QoSProfile::default().keep_last(7)),
+        )?;
+        spin_until(&mut executor, &|events| {
+            events.iter().any(|event| {
+                matches!(event, GraphEvent::EndpointAdded(endpoint) if endpoint.topic_name == topic)
+            })
+        });
+        {
+            let events = events.lock().unwrap();
+            assert!(events.iter().any(|event| {
+                matches!(event, GraphEvent::NodeJoined(info) if info.name == other_node.name())
+            }));
+            let added = events
+                .iter()
+                .find_map(|event| match event {
+                    GraphEvent::EndpointAdded(endpoint) if endpoint.topic_name == topic => {
+                        Some(endpoint)
+                    }
+                    _ => None,
+                })
+                .unwrap();
+            assert_eq!(added.kind, TopicEndpointKind::Publisher);
+            assert_eq!(added.topic_type, "test_msgs/msg/Empty");
+            assert_eq!(added.qos.history, QoSHistoryPolicy::KeepLast { depth: 7 });
+        }
+
+        let other_node_name = other_node.name();
+        drop(publisher);
+        drop(other_node);
+        spin_until(&mut executor, &|events| {
+            events.iter().any(
+                |event| matches!(event, GraphEvent::NodeLeft(info) if info.name == other_node_name),
+            )
+        });
+        assert!(events.lock().unwrap().iter().any(|event| {
+            matches!(event, GraphEvent::EndpointRemoved(endpoint) if endpoint.topic_name == topic)
+        }));
+        Ok(())
+    }
+}
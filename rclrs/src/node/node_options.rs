@@ -5,9 +5,9 @@ use std::{
 };
 
 use crate::{
-    rcl_bindings::*, ClockType, ContextHandle, Logger, Node, NodeHandle, NodeState,
-    ParameterInterface, QoSProfile, RclrsError, TimeSource, ToResult, ENTITY_LIFECYCLE_MUTEX,
-    QOS_PROFILE_CLOCK,
+    rcl_bindings::*, CallbackGroupState, CallbackGroupType, ClockType, ContextHandle, Logger, Node,
+    NodeHandle, NodeState, ParameterInterface, QoSProfile, RclrsError, TaskQueue, TimeSource,
+    ToResult, ENTITY_LIFECYCLE_MUTEX, QOS_PROFILE_CLOCK,
 };
 
 /// This trait helps to build [`NodeOptions`] which can be passed into
@@ -164,6 +164,37 @@ pub trait IntoNodeOptions<'a>: Sized {
         options
     }
 
+    /// Enables or disables the parameter event publisher.
+    ///
+    /// When enabled, changes of the node's parameters are published on the
+    /// `/parameter_events` topic, where they can be received by a
+    /// [`ParameterEventHandler`][1].
+    ///
+    /// [1]: crate::ParameterEventHandler
+    fn start_parameter_event_publisher(self, start: bool) -> NodeOptions<'a> {
+        let mut options = self.into_node_options();
+        options.start_parameter_event_publisher = start;
+        options
+    }
+
+    /// Enables or disables intra-process communication for the node's publishers and
+    /// subscriptions.
+    ///
+    /// When enabled, a publisher hands its messages directly to the subscriptions of the
+    /// same topic and type in the same [`Context`][1], without serializing them.
+    /// Subscriptions in other contexts or processes still receive them through the
+    /// middleware. This can be overridden for single publishers and subscriptions with
+    /// [`PublisherOptions::intra_process`][2] and [`SubscriptionOptions::intra_process`][3].
+    ///
+    /// [1]: crate::Context
+    /// [2]: crate::PublisherOptions::intra_process
+    /// [3]: crate::SubscriptionOptions::intra_process
+    fn use_intra_process_comms(self, enable: bool) -> NodeOptions<'a> {
+        let mut options = self.into_node_options();
+        options.use_intra_process_comms = enable;
+        options
+    }
+
     /// Sets the node's clock type.
     fn clock_type(self, clock_type: ClockType) -> NodeOptions<'a> {
         let mut options = self.into_node_options();
@@ -190,6 +221,8 @@ pub trait IntoNodeOptions<'a>: Sized {
 /// - `arguments: []`
 /// - `enable_rosout: true`
 /// - `start_parameter_services: true`
+/// - `start_parameter_event_publisher: true`
+/// - `use_intra_process_comms: false`
 /// - `clock_type: ClockType::RosTime`
 /// - `clock_qos: QOS_PROFILE_CLOCK`
 ///
@@ -231,6 +264,8 @@ pub struct NodeOptions<'a> {
     arguments: Vec<String>,
     enable_rosout: bool,
     start_parameter_services: bool,
+    start_parameter_event_publisher: bool,
+    use_intra_process_comms: bool,
     clock_type: ClockType,
     clock_qos: QoSProfile,
 }
@@ -277,6 +312,8 @@ impl<'a> NodeOptions<'a> {
             arguments: vec![],
             enable_rosout: true,
             start_parameter_services: true,
+            start_parameter_event_publisher: true,
+            use_intra_process_comms: false,
             clock_type: ClockType::RosTime,
             clock_qos: QOS_PROFILE_CLOCK,
         }
@@ -297,6 +334,9 @@ impl<'a> NodeOptions<'a> {
                 s: self.namespace.to_owned(),
             })?;
         let rcl_node_options = self.create_rcl_node_options()?;
+        // The task queue needs to be created before locking the context, since its
+        // guard condition is initialized with the context.
+        let task_queue = Arc::new(TaskQueue::new(Arc::clone(context)));
         let rcl_context = &mut *context.rcl_context.lock().unwrap();
 
         let handle = Arc::new(NodeHandle {
@@ -358,16 +398,30 @@ impl<'a> NodeOptions<'a> {
 
         let node = Arc::new(NodeState {
             clients_mtx: Mutex::default(),
-            guard_conditions_mtx: Mutex::default(),
+            guard_conditions_mtx: Mutex::new(vec![Arc::downgrade(&task_queue.guard_condition)]),
             services_mtx: Mutex::default(),
             subscriptions_mtx: Mutex::default(),
+            timers_mtx: Mutex::default(),
+            action_servers_mtx: Mutex::default(),
+            action_clients_mtx: Mutex::default(),
+            events_mtx: Mutex::default(),
+            graph_watchers_mtx: Mutex::default(),
+            graph_guard_condition: Mutex::default(),
+            default_callback_group: Arc::new(CallbackGroupState::new(
+                CallbackGroupType::MutuallyExclusive,
+            )),
+            task_queue,
+            use_intra_process_comms: self.use_intra_process_comms,
             time_source: TimeSource::builder(self.clock_type)
                 .clock_qos(self.clock_qos)
-                .build(),
+                .build(context),
             parameter,
             logger: Logger::new(logger_name)?,
             handle,
         });
+        if self.start_parameter_event_publisher {
+            node.parameter.create_event_publisher(&node)?;
+        }
         node.time_source.attach_node(&node);
 
         if self.start_parameter_services {
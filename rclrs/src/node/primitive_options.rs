@@ -1,6 +1,6 @@
 use crate::{
-    QoSDurabilityPolicy, QoSDuration, QoSHistoryPolicy, QoSLivelinessPolicy, QoSProfile,
-    QoSReliabilityPolicy,
+    CallbackGroup, QoSDurabilityPolicy, QoSDuration, QoSHistoryPolicy, QoSLivelinessPolicy,
+    QoSProfile, QoSReliabilityPolicy,
 };
 
 use std::{borrow::Borrow, time::Duration};
@@ -41,6 +41,9 @@ pub struct PrimitiveOptions<'a> {
     pub liveliness_lease: Option<QoSDuration>,
     /// Override the default [`QoSProfile::avoid_ros_namespace_conventions`] for the primitive.
     pub avoid_ros_namespace_conventions: Option<bool>,
+    /// The [`CallbackGroup`] that the callbacks of the primitive will belong to.
+    /// If this is `None`, the default callback group of the node is used.
+    pub callback_group: Option<&'a CallbackGroup>,
 }
 
 /// Trait to implicitly convert a compatible object into [`PrimitiveOptions`].
@@ -214,6 +217,15 @@ pub trait IntoPrimitiveOptions<'a>: Sized {
         options.avoid_ros_namespace_conventions = Some(true);
         options
     }
+
+    /// Assign the callbacks of the primitive to a [`CallbackGroup`].
+    ///
+    /// This has no effect on primitives without callbacks, such as publishers.
+    fn callback_group(self, callback_group: &'a CallbackGroup) -> PrimitiveOptions<'a> {
+        let mut options = self.into_primitive_options();
+        options.callback_group = Some(callback_group);
+        options
+    }
 }
 
 impl<'a> IntoPrimitiveOptions<'a> for PrimitiveOptions<'a> {
@@ -247,6 +259,7 @@ impl<'a> PrimitiveOptions<'a> {
             liveliness: None,
             liveliness_lease: None,
             avoid_ros_namespace_conventions: None,
+            callback_group: None,
         }
     }
 
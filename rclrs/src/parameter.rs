@@ -1,8 +1,14 @@
+mod callbacks;
+mod client;
+mod events;
 mod override_map;
 mod range;
 mod service;
 mod value;
 
+pub use callbacks::*;
+pub use client::*;
+pub use events::*;
 pub(crate) use override_map::*;
 pub use range::*;
 use service::*;
@@ -314,8 +320,7 @@ impl<T: ParameterVariant> Drop for MandatoryParameter<T> {
     fn drop(&mut self) {
         // Clear the entry from the parameter map
         if let Some(map) = self.map.upgrade() {
-            let storage = &mut map.lock().unwrap().storage;
-            storage.remove(&self.name);
+            map.lock().unwrap().remove_parameter(&self.name);
         }
     }
 }
@@ -378,8 +383,7 @@ impl<T: ParameterVariant> Drop for OptionalParameter<T> {
     fn drop(&mut self) {
         // Clear the entry from the parameter map
         if let Some(map) = self.map.upgrade() {
-            let storage = &mut map.lock().unwrap().storage;
-            storage.remove(&self.name);
+            map.lock().unwrap().remove_parameter(&self.name);
         }
     }
 }
@@ -407,8 +411,7 @@ impl<T: ParameterVariant> Drop for ReadOnlyParameter<T> {
     fn drop(&mut self) {
         // Clear the entry from the parameter map
         if let Some(map) = self.map.upgrade() {
-            let storage = &mut map.lock().unwrap().storage;
-            storage.remove(&self.name);
+            map.lock().unwrap().remove_parameter(&self.name);
         }
     }
 }
@@ -494,6 +497,8 @@ impl ParameterStorage {
 pub(crate) struct ParameterMap {
     storage: BTreeMap<Arc<str>, ParameterStorage>,
     allow_undeclared: bool,
+    callbacks: Arc<Mutex<ParameterCallbacks>>,
+    events: Option<ParameterEventPublisher>,
 }
 
 impl ParameterMap {
@@ -539,7 +544,9 @@ impl ParameterMap {
     }
 
     /// Stores the requested parameter in the map.
-    fn store_parameter(&mut self, name: Arc<str>, value: ParameterValue) {
+    ///
+    /// Returns `true` if the parameter did not exist before.
+    fn store_parameter(&mut self, name: Arc<str>, value: ParameterValue) -> bool {
         match self.storage.entry(name) {
             Entry::Occupied(mut entry) => match entry.get_mut() {
                 ParameterStorage::Declared(storage) => match &storage.value {
@@ -553,8 +560,28 @@ impl ParameterMap {
             },
             Entry::Vacant(entry) => {
                 entry.insert(ParameterStorage::Undeclared(value));
+                return true;
             }
         }
+        false
+    }
+
+    /// Removes the parameter from the map, publishing an event if it existed.
+    fn remove_parameter(&mut self, name: &Arc<str>) {
+        if self.storage.remove(name).is_some() {
+            let deleted = Parameter {
+                name: name.clone(),
+                value: None,
+            };
+            self.publish_event(&[], &[], &[deleted]);
+        }
+    }
+
+    /// Publishes a parameter event, if the parameter event publisher is enabled.
+    fn publish_event(&self, new: &[Parameter], changed: &[Parameter], deleted: &[Parameter]) {
+        if let Some(events) = &self.events {
+            events.publish(new, changed, deleted);
+        }
     }
 }
 
@@ -565,14 +592,36 @@ impl<T: ParameterVariant> MandatoryParameter<T> {
     }
 
     /// Sets the parameter value.
-    /// Returns [`ParameterValueError::OutOfRange`] if the value is out of the parameter's range.
+    ///
+    /// Returns [`ParameterValueError::OutOfRange`] if the value is out of the parameter's range,
+    /// or [`ParameterValueError::Rejected`] if an on-set callback of the node rejected the value.
     pub fn set<U: Into<T>>(&self, value: U) -> Result<(), ParameterValueError> {
         let value = value.into().into();
         if !self.ranges.in_range(&value) {
             return Err(ParameterValueError::OutOfRange);
         }
-        *self.value.write().unwrap() = value;
-        Ok(())
+        let parameter = Parameter {
+            name: self.name.clone(),
+            value: Some(value.clone()),
+        };
+        set_with_callbacks(&self.map, parameter, || {
+            *self.value.write().unwrap() = value;
+        })
+    }
+
+    /// Registers a callback that is called with the new value whenever the parameter was set,
+    /// either through this object or through the parameter services of the node.
+    ///
+    /// The callback stays registered until the returned handle is dropped.
+    pub fn add_post_set_callback(
+        &self,
+        mut callback: impl FnMut(T) + Send + 'static,
+    ) -> ParameterCallbackHandle {
+        add_parameter_post_set_callback(&self.map, self.name.clone(), move |value| {
+            if let Some(value) = value.and_then(|v| v.clone().try_into().ok()) {
+                callback(value);
+            }
+        })
     }
 }
 
@@ -594,22 +643,94 @@ impl<T: ParameterVariant> OptionalParameter<T> {
     }
 
     /// Assigns a value to the optional parameter, setting it to `Some(value)`.
-    /// Returns [`ParameterValueError::OutOfRange`] if the value is out of the parameter's range.
+    ///
+    /// Returns [`ParameterValueError::OutOfRange`] if the value is out of the parameter's range,
+    /// or [`ParameterValueError::Rejected`] if an on-set callback of the node rejected the value.
     pub fn set<U: Into<T>>(&self, value: U) -> Result<(), ParameterValueError> {
         let value = value.into().into();
         if !self.ranges.in_range(&value) {
             return Err(ParameterValueError::OutOfRange);
         }
-        *self.value.write().unwrap() = Some(value);
-        Ok(())
+        let parameter = Parameter {
+            name: self.name.clone(),
+            value: Some(value.clone()),
+        };
+        set_with_callbacks(&self.map, parameter, || {
+            *self.value.write().unwrap() = Some(value);
+        })
     }
 
     /// Unsets the optional parameter value to `None`.
-    pub fn unset(&self) {
-        *self.value.write().unwrap() = None;
+    ///
+    /// Returns [`ParameterValueError::Rejected`] if an on-set callback of the node rejected
+    /// the change.
+    pub fn unset(&self) -> Result<(), ParameterValueError> {
+        let parameter = Parameter {
+            name: self.name.clone(),
+            value: None,
+        };
+        set_with_callbacks(&self.map, parameter, || {
+            *self.value.write().unwrap() = None;
+        })
+    }
+
+    /// Registers a callback that is called with the new value whenever the parameter was set
+    /// or unset, either through this object or through the parameter services of the node.
+    ///
+    /// The callback stays registered until the returned handle is dropped.
+    pub fn add_post_set_callback(
+        &self,
+        mut callback: impl FnMut(Option<T>) + Send + 'static,
+    ) -> ParameterCallbackHandle {
+        add_parameter_post_set_callback(&self.map, self.name.clone(), move |value| match value {
+            Some(value) => {
+                if let Ok(value) = value.clone().try_into() {
+                    callback(Some(value));
+                }
+            }
+            None => callback(None),
+        })
     }
 }
 
+/// Runs the on-set callbacks of the node for a change of a declared parameter, applies the
+/// change with `store` if it was accepted, and then runs the post-set callbacks.
+fn set_with_callbacks(
+    map: &Weak<Mutex<ParameterMap>>,
+    parameter: Parameter,
+    store: impl FnOnce(),
+) -> Result<(), ParameterValueError> {
+    let Some(map) = map.upgrade() else {
+        // The node is gone, so there are no callbacks left to run.
+        store();
+        return Ok(());
+    };
+    // The callbacks run without the parameter map being locked, so that they can access
+    // parameters.
+    let callbacks = Arc::clone(&map.lock().unwrap().callbacks);
+    let parameters = [parameter];
+    ParameterCallbacks::validate(&callbacks, &parameters).map_err(ParameterValueError::Rejected)?;
+    {
+        let map = map.lock().unwrap();
+        store();
+        map.publish_event(&[], &parameters, &[]);
+    }
+    ParameterCallbacks::notify(&callbacks, &parameters);
+    Ok(())
+}
+
+fn add_parameter_post_set_callback(
+    map: &Weak<Mutex<ParameterMap>>,
+    name: Arc<str>,
+    callback: impl FnMut(Option<&ParameterValue>) + Send + 'static,
+) -> ParameterCallbackHandle {
+    let Some(map) = map.upgrade() else {
+        return ParameterCallbackHandle::detached();
+    };
+    let callbacks = Arc::clone(&map.lock().unwrap().callbacks);
+    ParameterCallbackHandle::parameter_post_set(&callbacks, name, callback)
+}
+
 /// Allows access to all parameters via get / set functions, using their name as a key.
 pub struct Parameters<'a> {
     pub(crate) interface: &'a ParameterInterface,
@@ -624,6 +745,8 @@ pub enum ParameterValueError {
     TypeMismatch,
     /// A write on a read-only parameter was attempted.
     ReadOnly,
+    /// An on-set parameter callback rejected the new value, for the given reason.
+    Rejected(String),
 }
 
 impl std::fmt::Display for ParameterValueError {
@@ -632,6 +755,7 @@ impl std::fmt::Display for ParameterValueError {
             ParameterValueError::OutOfRange => write!(f, "parameter value was out of the parameter's range"),
             ParameterValueError::TypeMismatch => write!(f, "parameter was stored in a static type and an operation on a different type was attempted"),
             ParameterValueError::ReadOnly => write!(f, "a write on a read-only parameter was attempted"),
+            ParameterValueError::Rejected(reason) => write!(f, "the parameter change was rejected: {reason}"),
         }
     }
 }
@@ -707,44 +831,49 @@ impl Parameters<'_> {
     /// * [`Err(DeclarationError::OutOfRange)`] if the requested value is out of the parameter's
     ///   range.
     /// * [`Err(DeclarationError::ReadOnly)`] if the parameter is read only.
+    /// * [`Err(ParameterValueError::Rejected)`] if an on-set callback of the node rejected the
+    ///   value.
     pub fn set<T: ParameterVariant>(
         &self,
         name: impl Into<Arc<str>>,
         value: T,
     ) -> Result<(), ParameterValueError> {
-        let mut map = self.interface.parameter_map.lock().unwrap();
         let name: Arc<str> = name.into();
-        match map.storage.entry(name) {
-            Entry::Occupied(mut entry) => {
-                // If it's declared, we can only set if it's the same variant.
-                // Undeclared parameters are dynamic by default
-                match entry.get_mut() {
-                    ParameterStorage::Declared(param) => {
-                        if T::kind() == param.kind {
-                            let value = value.into();
-                            if !param.options.ranges.in_range(&value) {
-                                return Err(ParameterValueError::OutOfRange);
-                            }
-                            match &param.value {
-                                DeclaredValue::Mandatory(p) => *p.write().unwrap() = value,
-                                DeclaredValue::Optional(p) => *p.write().unwrap() = Some(value),
-                                DeclaredValue::ReadOnly(_) => {
-                                    return Err(ParameterValueError::ReadOnly);
-                                }
-                            }
-                        } else {
-                            return Err(ParameterValueError::TypeMismatch);
-                        }
-                    }
-                    ParameterStorage::Undeclared(param) => {
-                        *param = value.into();
-                    }
+        let value: ParameterValue = value.into();
+        let callbacks = {
+            let map = self.interface.parameter_map.lock().unwrap();
+            // If it's declared, we can only set if it's the same variant.
+            // Undeclared parameters are dynamic by default
+            if let Some(ParameterStorage::Declared(param)) = map.storage.get(&name) {
+                if T::kind() != param.kind {
+                    return Err(ParameterValueError::TypeMismatch);
+                }
+                if !param.options.ranges.in_range(&value) {
+                    return Err(ParameterValueError::OutOfRange);
+                }
+                if matches!(param.value, DeclaredValue::ReadOnly(_)) {
+                    return Err(ParameterValueError::ReadOnly);
                 }
             }
-            Entry::Vacant(entry) => {
-                entry.insert(ParameterStorage::Undeclared(value.into()));
+            Arc::clone(&map.callbacks)
+        };
+        // The callbacks run without the parameter map being locked, so that they can
+        // access parameters.
+        let parameters = [Parameter {
+            name: name.clone(),
+            value: Some(value.clone()),
+        }];
+        ParameterCallbacks::validate(&callbacks, &parameters)
+            .map_err(ParameterValueError::Rejected)?;
+        {
+            let mut map = self.interface.parameter_map.lock().unwrap();
+            if map.store_parameter(name, value) {
+                map.publish_event(&parameters, &[], &[]);
+            } else {
+                map.publish_event(&[], &parameters, &[]);
             }
         }
+        ParameterCallbacks::notify(&callbacks, &parameters);
         Ok(())
     }
 }
@@ -789,6 +918,12 @@ impl ParameterInterface {
         }
     }
 
+    pub(crate) fn create_event_publisher(&self, node: &Node) -> Result<(), RclrsError> {
+        let events = ParameterEventPublisher::new(node)?;
+        self.parameter_map.lock().unwrap().events = Some(events);
+        Ok(())
+    }
+
     pub(crate) fn create_services(&self, node: &Node) -> Result<(), RclrsError> {
         *self.services.lock().unwrap() =
             Some(ParameterService::new(node, self.parameter_map.clone())?);
@@ -856,7 +991,16 @@ impl ParameterInterface {
         value: DeclaredValue,
         options: ParameterOptionsStorage,
     ) {
-        self.parameter_map.lock().unwrap().storage.insert(
+        let declared = Parameter {
+            name: name.clone(),
+            value: match &value {
+                DeclaredValue::Mandatory(v) => Some(v.read().unwrap().clone()),
+                DeclaredValue::Optional(v) => v.read().unwrap().clone(),
+                DeclaredValue::ReadOnly(v) => Some(v.clone()),
+            },
+        };
+        let mut map = self.parameter_map.lock().unwrap();
+        map.storage.insert(
             name,
             ParameterStorage::Declared(DeclaredStorage {
                 options,
@@ -864,11 +1008,28 @@ impl ParameterInterface {
                 kind,
             }),
         );
+        map.publish_event(&[declared], &[], &[]);
     }
 
     pub(crate) fn allow_undeclared(&self) {
         self.parameter_map.lock().unwrap().allow_undeclared = true;
     }
+
+    pub(crate) fn add_on_set_callback(
+        &self,
+        callback: impl FnMut(&[Parameter]) -> Result<(), String> + Send + 'static,
+    ) -> ParameterCallbackHandle {
+        let callbacks = Arc::clone(&self.parameter_map.lock().unwrap().callbacks);
+        ParameterCallbackHandle::on_set(&callbacks, callback)
+    }
+
+    pub(crate) fn add_post_set_callback(
+        &self,
+        callback: impl FnMut(&[Parameter]) + Send + 'static,
+    ) -> ParameterCallbackHandle {
+        let callbacks = Arc::clone(&self.parameter_map.lock().unwrap().callbacks);
+        ParameterCallbackHandle::post_set(&callbacks, callback)
+    }
 }
 
 #[cfg(test)]
@@ -1051,7 +1212,7 @@ mod tests {
         assert_eq!(optional_param.get(), None);
         optional_param.set(true).unwrap();
         assert_eq!(optional_param.get(), Some(true));
-        optional_param.unset();
+        optional_param.unset().unwrap();
         assert_eq!(optional_param.get(), None);
 
         let optional_param2 = node
@@ -1410,4 +1571,227 @@ mod tests {
             .optional()
             .unwrap();
     }
+
+    #[test]
+    fn test_on_set_parameters_callback() {
+        let node = Context::default()
+            .create_basic_executor()
+            .create_node(&format!("param_test_node_{}", line!()))
+            .unwrap();
+        let mandatory_param = node
+            .declare_parameter("mandatory_param")
+            .default(1)
+            .mandatory()
+            .unwrap();
+        let optional_param = node
+            .declare_parameter::<i64>("optional_param")
+            .optional()
+            .unwrap();
+        let callback = node.add_on_set_parameters_callback(|parameters| {
+            for parameter in parameters {
+                match parameter.value {
+                    Some(ParameterValue::Integer(value)) if value < 0 => {
+                        return Err(format!("{} must not be negative", parameter.name));
+                    }
+                    None => return Err(format!("{} must not be unset", parameter.name)),
+                    _ => {}
+                }
+            }
+            Ok(())
+        });
+
+        assert!(matches!(
+            mandatory_param.set(-1),
+            Err(ParameterValueError::Rejected(reason)) if reason == "mandatory_param must not be negative"
+        ));
+        assert_eq!(mandatory_param.get(), 1);
+        mandatory_param.set(2).unwrap();
+        assert_eq!(mandatory_param.get(), 2);
+
+        optional_param.set(3).unwrap();
+        assert!(matches!(
+            optional_param.unset(),
+            Err(ParameterValueError::Rejected(_))
+        ));
+        assert_eq!(optional_param.get(), Some(3));
+
+        let parameters = node.use_undeclared_parameters();
+        assert!(matches!(
+            parameters.set("mandatory_param", -5),
+            Err(ParameterValueError::Rejected(_))
+        ));
+        assert!(matches!(
+            parameters.set("undeclared_param", -5),
+            Err(ParameterValueError::Rejected(_))
+        ));
+        assert_eq!(parameters.get::<i64>("undeclared_param"), None);
+
+        // Dropping the handle unregisters the callback
+        drop(callback);
+        mandatory_param.set(-1).unwrap();
+        assert_eq!(mandatory_param.get(), -1);
+        optional_param.unset().unwrap();
+        assert_eq!(optional_param.get(), None);
+    }
+
+    #[test]
+    fn test_post_set_parameters_callbacks() {
+        let node = Context::default()
+            .create_basic_executor()
+            .create_node(&format!("param_test_node_{}", line!()))
+            .unwrap();
+        let mandatory_param = node
+            .declare_parameter("mandatory_param")
+            .default(1)
+            .mandatory()
+            .unwrap();
+        let optional_param = node
+            .declare_parameter::<Arc<str>>("optional_param")
+            .optional()
+            .unwrap();
+
+        let node_changes = Arc::new(Mutex::new(Vec::new()));
+        let node_callback = node.add_post_set_parameters_callback({
+            let node_changes = Arc::clone(&node_changes);
+            move |parameters| {
+                node_changes
+                    .lock()
+                    .unwrap()
+                    .extend(parameters.iter().cloned());
+            }
+        });
+        let mandatory_changes = Arc::new(Mutex::new(Vec::new()));
+        let _mandatory_callback = mandatory_param.add_post_set_callback({
+            let mandatory_changes = Arc::clone(&mandatory_changes);
+            move |value: i64| mandatory_changes.lock().unwrap().push(value)
+        });
+        let optional_changes = Arc::new(Mutex::new(Vec::new()));
+        let _optional_callback = optional_param.add_post_set_callback({
+            let optional_changes = Arc::clone(&optional_changes);
+            move |value: Option<Arc<str>>| optional_changes.lock().unwrap().push(value)
+        });
+
+        mandatory_param.set(2).unwrap();
+        optional_param.set("foo").unwrap();
+        optional_param.unset().unwrap();
+        let parameters = node.use_undeclared_parameters();
+        parameters.set("mandatory_param", 3).unwrap();
+        // Failed sets are not reported
+        assert!(parameters.set("mandatory_param", true).is_err());
+
+        assert_eq!(*mandatory_changes.lock().unwrap(), vec![2, 3]);
+        assert_eq!(
+            *optional_changes.lock().unwrap(),
+            vec![Some("foo".into()), None]
+        );
+        assert_eq!(
+            *node_changes.lock().unwrap(),
+            vec![
+                Parameter {
+                    name: "mandatory_param".into(),
+                    value: Some(ParameterValue::Integer(2)),
+                },
+                Parameter {
+                    name: "optional_param".into(),
+                    value: Some(ParameterValue::String("foo".into())),
+                },
+                Parameter {
+                    name: "optional_param".into(),
+                    value: None,
+                },
+                Parameter {
+                    name: "mandatory_param".into(),
+                    value: Some(ParameterValue::Integer(3)),
+                },
+            ]
+        );
+
+        // Dropping the handle unregisters the callback
+        drop(node_callback);
+        mandatory_param.set(4).unwrap();
+        assert_eq!(node_changes.lock().unwrap().len(), 4);
+        assert_eq!(*mandatory_changes.lock().unwrap(), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_parameter_callbacks_access_parameters() {
+        let node = Context::default()
+            .create_basic_executor()
+            .create_node(&format!("param_test_node_{}", line!()))
+            .unwrap();
+        let source = node
+            .declare_parameter("source")
+            .default(1)
+            .mandatory()
+            .unwrap();
+        let mirror = Arc::new(
+            node.declare_parameter("mirror")
+                .default(1)
+                .mandatory()
+                .unwrap(),
+        );
+        let limit = Arc::new(
+            node.declare_parameter("limit")
+                .default(10)
+                .mandatory()
+                .unwrap(),
+        );
+
+        // An on-set callback that reads a parameter.
+        let _validator = node.add_on_set_parameters_callback({
+            let limit = Arc::clone(&limit);
+            move |parameters| {
+                let exceeds_limit = parameters.iter().any(|parameter| {
+                    matches!(parameter.value, Some(ParameterValue::Integer(value)) if value > limit.get())
+                });
+                if exceeds_limit {
+                    return Err("the value exceeds the limit".into());
+                }
+                Ok(())
+            }
+        });
+        // A post-set callback that sets another parameter.
+        let _mirror_callback = source.add_post_set_callback({
+            let mirror = Arc::clone(&mirror);
+            move |value: i64| mirror.set(value).unwrap()
+        });
+        let node_changes = Arc::new(Mutex::new(Vec::new()));
+        let _node_callback = node.add_post_set_parameters_callback({
+            let node_changes = Arc::clone(&node_changes);
+            move |parameters| {
+                node_changes
+                    .lock()
+                    .unwrap()
+                    .extend(parameters.iter().map(|parameter| parameter.name.clone()));
+            }
+        });
+        // A post-set callback that unregisters itself.
+        let once_count = Arc::new(Mutex::new(0));
+        let once_callback = Arc::new(Mutex::new(None));
+        *once_callback.lock().unwrap() = Some(node.add_post_set_parameters_callback({
+            let once_count = Arc::clone(&once_count);
+            let once_callback = Arc::clone(&once_callback);
+            move |_| {
+                *once_count.lock().unwrap() += 1;
+                once_callback.lock().unwrap().take();
+            }
+        }));
+
+        source.set(5).unwrap();
+        assert_eq!(mirror.get(), 5);
+        // The change of the mirror is reported after the callbacks for the source.
+        assert_eq!(
+            *node_changes.lock().unwrap(),
+            vec![Arc::from("source"), Arc::from("mirror")]
+        );
+        assert_eq!(*once_count.lock().unwrap(), 1);
+
+        assert!(matches!(
+            source.set(11),
+            Err(ParameterValueError::Rejected(_))
+        ));
+        assert_eq!(source.get(), 5);
+        assert_eq!(mirror.get(), 5);
+        assert_eq!(node_changes.lock().unwrap().len(), 2);
+    }
 }
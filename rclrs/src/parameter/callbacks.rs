@@ -0,0 +1,247 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    sync::{Arc, Mutex, Weak},
+};
+
+use crate::ParameterValue;
+
+/// A parameter change that is about to be applied or that was just applied.
+///
+/// This is passed to the callbacks registered with
+/// [`NodeState::add_on_set_parameters_callback`][1] and
+/// [`NodeState::add_post_set_parameters_callback`][2], and is contained in a
+/// [`ParameterEvent`][3].
+///
+/// [1]: crate::NodeState::add_on_set_parameters_callback
+/// [2]: crate::NodeState::add_post_set_parameters_callback
+/// [3]: crate::ParameterEvent
+#[derive(Clone, Debug, PartialEq)]
+pub struct Parameter {
+    /// The name of the parameter.
+    pub name: Arc<str>,
+    /// The new value of the parameter, or `None` if an optional parameter is being unset.
+    pub value: Option<ParameterValue>,
+}
+
+type OnSetParametersCallback = Arc<Mutex<dyn FnMut(&[Parameter]) -> Result<(), String> + Send>>;
+type PostSetParametersCallback = Arc<Mutex<dyn FnMut(&[Parameter]) + Send>>;
+type PostSetParameterCallback = Arc<Mutex<dyn FnMut(Option<&ParameterValue>) + Send>>;
+
+/// Post-set notifications that were triggered on this thread while post-set callbacks were
+/// running, and that are run once these callbacks have returned.
+type PendingNotifications = VecDeque<(Arc<Mutex<ParameterCallbacks>>, Vec<Parameter>)>;
+
+thread_local! {
+    /// Whether this thread is currently running on-set callbacks.
+    static IN_ON_SET_CALLBACKS: Cell<bool> = const { Cell::new(false) };
+    /// The pending notifications of this thread, or `None` if it is not currently running
+    /// post-set callbacks.
+    static PENDING_NOTIFICATIONS: RefCell<Option<PendingNotifications>> =
+        const { RefCell::new(None) };
+}
+
+/// The parameter callbacks that are registered on a node.
+#[derive(Default)]
+pub(crate) struct ParameterCallbacks {
+    next_id: usize,
+    on_set: Vec<(usize, OnSetParametersCallback)>,
+    post_set: Vec<(usize, PostSetParametersCallback)>,
+    parameter_post_set: Vec<(usize, Arc<str>, PostSetParameterCallback)>,
+}
+
+impl std::fmt::Debug for ParameterCallbacks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParameterCallbacks")
+            .field("on_set", &self.on_set.len())
+            .field("post_set", &self.post_set.len())
+            .field("parameter_post_set", &self.parameter_post_set.len())
+            .finish()
+    }
+}
+
+impl ParameterCallbacks {
+    /// Runs the on-set callbacks, stopping at the first callback that rejects the change.
+    ///
+    /// The callbacks run without holding the lock of `callbacks`, so they may register
+    /// and unregister callbacks. Setting parameters from inside of an on-set callback is
+    /// not possible though, and such a change is rejected.
+    pub(crate) fn validate(
+        callbacks: &Mutex<Self>,
+        parameters: &[Parameter],
+    ) -> Result<(), String> {
+        if IN_ON_SET_CALLBACKS.with(Cell::get) {
+            return Err("parameters cannot be set from inside of an on-set callback".into());
+        }
+        let on_set: Vec<_> = callbacks
+            .lock()
+            .unwrap()
+            .on_set
+            .iter()
+            .map(|(_, callback)| Arc::clone(callback))
+            .collect();
+        IN_ON_SET_CALLBACKS.with(|flag| flag.set(true));
+        let _reset = ResetOnDrop(|| IN_ON_SET_CALLBACKS.with(|flag| flag.set(false)));
+        for callback in on_set {
+            let mut callback = callback.lock().unwrap();
+            (*callback)(parameters)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the post-set callbacks of the individual parameters, followed by the
+    /// post-set callbacks of the node.
+    ///
+    /// The callbacks run without holding the lock of `callbacks`, so they may set
+    /// parameters and register and unregister callbacks. The callbacks for parameters
+    /// that are set from inside of a post-set callback run after that callback returned.
+    pub(crate) fn notify(callbacks: &Arc<Mutex<Self>>, parameters: &[Parameter]) {
+        if parameters.is_empty() {
+            return;
+        }
+        let deferred = PENDING_NOTIFICATIONS.with(|pending| {
+            let mut pending = pending.borrow_mut();
+            if let Some(pending) = pending.as_mut() {
+                pending.push_back((Arc::clone(callbacks), parameters.to_vec()));
+                return true;
+            }
+            *pending = Some(VecDeque::new());
+            false
+        });
+        if deferred {
+            return;
+        }
+        let _reset =
+            ResetOnDrop(|| PENDING_NOTIFICATIONS.with(|pending| *pending.borrow_mut() = None));
+        Self::run_post_set(callbacks, parameters);
+        while let Some((callbacks, parameters)) = PENDING_NOTIFICATIONS
+            .with(|pending| pending.borrow_mut().as_mut().and_then(VecDeque::pop_front))
+        {
+            Self::run_post_set(&callbacks, &parameters);
+        }
+    }
+
+    fn run_post_set(callbacks: &Mutex<Self>, parameters: &[Parameter]) {
+        let (parameter_post_set, post_set) = {
+            let callbacks = callbacks.lock().unwrap();
+            let parameter_post_set: Vec<_> = callbacks
+                .parameter_post_set
+                .iter()
+                .map(|(_, name, callback)| (name.clone(), Arc::clone(callback)))
+                .collect();
+            let post_set: Vec<_> = callbacks
+                .post_set
+                .iter()
+                .map(|(_, callback)| Arc::clone(callback))
+                .collect();
+            (parameter_post_set, post_set)
+        };
+        for (name, callback) in parameter_post_set {
+            let mut callback = callback.lock().unwrap();
+            for parameter in parameters.iter().filter(|p| p.name == name) {
+                (*callback)(parameter.value.as_ref());
+            }
+        }
+        for callback in post_set {
+            let mut callback = callback.lock().unwrap();
+            (*callback)(parameters);
+        }
+    }
+
+    fn next_id(&mut self) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+}
+
+/// Runs the closure when dropped, also when a callback panics.
+struct ResetOnDrop<F: FnMut()>(F);
+
+impl<F: FnMut()> Drop for ResetOnDrop<F> {
+    fn drop(&mut self) {
+        (self.0)();
+    }
+}
+
+/// A collection of callbacks that can be unregistered through a [`ParameterCallbackHandle`].
+pub(crate) trait CallbackRegistry: Send {
+    fn remove(&mut self, id: usize);
+}
+
+impl CallbackRegistry for ParameterCallbacks {
+    fn remove(&mut self, id: usize) {
+        self.on_set.retain(|(i, _)| *i != id);
+        self.post_set.retain(|(i, _)| *i != id);
+        self.parameter_post_set.retain(|(i, _, _)| *i != id);
+    }
+}
+
+/// Keeps a parameter callback registered.
+///
+/// The callback is unregistered when this handle is dropped.
+#[must_use = "the callback is unregistered when the handle is dropped"]
+pub struct ParameterCallbackHandle {
+    id: usize,
+    callbacks: Weak<Mutex<dyn CallbackRegistry>>,
+}
+
+impl std::fmt::Debug for ParameterCallbackHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParameterCallbackHandle")
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl ParameterCallbackHandle {
+    pub(crate) fn new(id: usize, callbacks: Weak<Mutex<dyn CallbackRegistry>>) -> Self {
+        Self { id, callbacks }
+    }
+
+    pub(crate) fn on_set(
+        callbacks: &Arc<Mutex<ParameterCallbacks>>,
+        callback: impl FnMut(&[Parameter]) -> Result<(), String> + Send + 'static,
+    ) -> Self {
+        let mut guard = callbacks.lock().unwrap();
+        let id = guard.next_id();
+        guard.on_set.push((id, Arc::new(Mutex::new(callback))));
+        Self::new(id, Arc::downgrade(callbacks) as _)
+    }
+
+    pub(crate) fn post_set(
+        callbacks: &Arc<Mutex<ParameterCallbacks>>,
+        callback: impl FnMut(&[Parameter]) + Send + 'static,
+    ) -> Self {
+        let mut guard = callbacks.lock().unwrap();
+        let id = guard.next_id();
+        guard.post_set.push((id, Arc::new(Mutex::new(callback))));
+        Self::new(id, Arc::downgrade(callbacks) as _)
+    }
+
+    pub(crate) fn parameter_post_set(
+        callbacks: &Arc<Mutex<ParameterCallbacks>>,
+        name: Arc<str>,
+        callback: impl FnMut(Option<&ParameterValue>) + Send + 'static,
+    ) -> Self {
+        let mut guard = callbacks.lock().unwrap();
+        let id = guard.next_id();
+        guard
+            .parameter_post_set
+            .push((id, name, Arc::new(Mutex::new(callback))));
+        Self::new(id, Arc::downgrade(callbacks) as _)
+    }
+
+    /// A handle for a node that no longer exists, so no callback can be registered.
+    pub(crate) fn detached() -> Self {
+        Self::new(0, Weak::<Mutex<ParameterCallbacks>>::new() as _)
+    }
+}
+
+impl Drop for ParameterCallbackHandle {
+    fn drop(&mut self) {
+        if let Some(callbacks) = self.callbacks.upgrade() {
+            callbacks.lock().unwrap().remove(self.id);
+        }
+    }
+}
@@ -0,0 +1,409 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::vendor::rcl_interfaces::{msg::rmw::ParameterType, srv::rmw::*};
+use rosidl_runtime_rs::Sequence;
+
+use super::events::{resolve_node_name, to_rmw_parameters};
+
+use crate::{
+    wait_for_graph_condition, Client, IntoPrimitiveOptions, Node, NodeHandle, Parameter,
+    ParameterKind, ParameterRanges, ParameterValue, QoSProfile, RclrsError,
+};
+
+/// A client for the parameters of another node.
+///
+/// Create a parameter client using [`NodeState::create_parameter_client`][1].
+///
+/// The parameter client sends requests to the parameter services of the remote node.
+/// The futures that are returned by its methods are completed while the node that created
+/// the client is spun by its executor.
+///
+/// [1]: crate::NodeState::create_parameter_client
+pub type ParameterClient = Arc<ParameterClientState>;
+
+/// The inner state of a [`ParameterClient`].
+///
+/// This is public so that you can choose to create a [`Weak`][1] reference to it
+/// if you want to be able to refer to a [`ParameterClient`] in a non-owning way.
+///
+/// [1]: std::sync::Weak
+pub struct ParameterClientState {
+    remote_node_name: String,
+    describe_parameters_client: Client<DescribeParameters>,
+    get_parameters_client: Client<GetParameters>,
+    list_parameters_client: Client<ListParameters>,
+    set_parameters_client: Client<SetParameters>,
+    set_parameters_atomically_client: Client<SetParametersAtomically>,
+    node_handle: Arc<NodeHandle>,
+}
+
+/// The parameters of a remote node, as returned by [`ParameterClientState::list_parameters`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParameterList {
+    /// The names of the parameters.
+    pub names: Vec<Arc<str>>,
+    /// The prefixes of the parameter names, i.e. the namespaces that contain parameters.
+    pub prefixes: Vec<Arc<str>>,
+}
+
+/// The description of a parameter of a remote node, as returned by
+/// [`ParameterClientState::describe_parameters`].
+#[derive(Clone, Debug)]
+pub struct ParameterDescription {
+    /// The name of the parameter.
+    pub name: Arc<str>,
+    /// The type of the current value of the parameter, or `None` if it is not set.
+    pub kind: Option<ParameterKind>,
+    /// The description of the parameter.
+    pub description: Arc<str>,
+    /// Human readable constraints of the parameter value.
+    pub constraints: Arc<str>,
+    /// True if the parameter can change its type.
+    pub dynamic_typing: bool,
+    /// True if the parameter cannot be set.
+    pub read_only: bool,
+    /// The valid ranges for the parameter value.
+    pub ranges: ParameterRanges,
+}
+
+impl ParameterClientState {
+    pub(crate) fn new(node: &Node, remote_node_name: &str) -> Result<Self, RclrsError> {
+        let remote_node_name = resolve_node_name(&node.namespace(), remote_node_name);
+        let service_name = |service: &str| format!("{remote_node_name}/{service}");
+        let qos = QoSProfile::parameter_services_default();
+        Ok(Self {
+            describe_parameters_client: node
+                .create_client(service_name("describe_parameters").as_str().qos(qos))?,
+            get_parameters_client: node
+                .create_client(service_name("get_parameters").as_str().qos(qos))?,
+            list_parameters_client: node
+                .create_client(service_name("list_parameters").as_str().qos(qos))?,
+            set_parameters_client: node
+                .create_client(service_name("set_parameters").as_str().qos(qos))?,
+            set_parameters_atomically_client: node
+                .create_client(service_name("set_parameters_atomically").as_str().qos(qos))?,
+            remote_node_name,
+            node_handle: Arc::clone(&node.handle),
+        })
+    }
+
+    /// Returns the fully qualified name of the remote node.
+    pub fn remote_node_name(&self) -> &str {
+        &self.remote_node_name
+    }
+
+    /// Returns true if all the parameter services of the remote node are available.
+    pub fn services_are_ready(&self) -> Result<bool, RclrsError> {
+        Ok(self.describe_parameters_client.service_is_ready()?
+            && self.get_parameters_client.service_is_ready()?
+            && self.list_parameters_client.service_is_ready()?
+            && self.set_parameters_client.service_is_ready()?
+            && self.set_parameters_atomically_client.service_is_ready()?)
+    }
+
+    /// Blocks until the parameter services of the remote node are available.
+    ///
+    /// Returns `false` if the services did not become available within the `timeout`,
+    /// or waits indefinitely if the `timeout` is `None`. The availability is checked
+    /// again whenever the ROS graph changes.
+    pub fn wait_for_services(&self, timeout: Option<Duration>) -> Result<bool, RclrsError> {
+        wait_for_graph_condition(&self.node_handle, timeout, || self.services_are_ready())
+    }
+
+    /// Lists the parameters of the remote node.
+    ///
+    /// If `prefixes` is not empty, only parameters with one of the given prefixes are listed.
+    /// The `depth` limits how many namespaces below the prefixes are listed, where `None`
+    /// lists all of them.
+    pub async fn list_parameters(
+        &self,
+        prefixes: &[&str],
+        depth: Option<u64>,
+    ) -> Result<ParameterList, RclrsError> {
+        let request = ListParameters_Request {
+            prefixes: prefixes.iter().map(|prefix| (*prefix).into()).collect(),
+            depth: depth.unwrap_or(ListParameters_Request::DEPTH_RECURSIVE),
+        };
+        let response = self.list_parameters_client.call_async(&request).await?;
+        Ok(ParameterList {
+            names: to_names(response.result.names),
+            prefixes: to_names(response.result.prefixes),
+        })
+    }
+
+    /// Gets the values of the given parameters of the remote node.
+    ///
+    /// The values are returned in the order of the names, and are `None` for
+    /// parameters that are not set.
+    pub async fn get_parameters(
+        &self,
+        names: &[&str],
+    ) -> Result<Vec<Option<ParameterValue>>, RclrsError> {
+        let request = GetParameters_Request {
+            names: names.iter().map(|name| (*name).into()).collect(),
+        };
+        let response = self.get_parameters_client.call_async(&request).await?;
+        Ok(response
+            .values
+            .into_iter()
+            .map(|value| value.try_into().ok())
+            .collect())
+    }
+
+    /// Gets the value of a single parameter of the remote node, or `None` if it is not set.
+    pub async fn get_parameter(&self, name: &str) -> Result<Option<ParameterValue>, RclrsError> {
+        Ok(self.get_parameters(&[name]).await?.pop().flatten())
+    }
+
+    /// Sets the given parameters of the remote node, one by one.
+    ///
+    /// Returns the result of setting each parameter in the order of the parameters, where
+    /// an error contains the reason why the remote node rejected the value.
+    pub async fn set_parameters(
+        &self,
+        parameters: &[Parameter],
+    ) -> Result<Vec<Result<(), String>>, RclrsError> {
+        let request = SetParameters_Request {
+            parameters: to_rmw_parameters(parameters),
+        };
+        let response = self.set_parameters_client.call_async(&request).await?;
+        Ok(response
+            .results
+            .into_iter()
+            .map(|result| {
+                if result.successful {
+                    Ok(())
+                } else {
+                    Err(result.reason.to_string())
+                }
+            })
+            .collect())
+    }
+
+    /// Sets the given parameters of the remote node, either all of them or none.
+    ///
+    /// Returns an error with the reason why the remote node rejected the values.
+    pub async fn set_parameters_atomically(
+        &self,
+        parameters: &[Parameter],
+    ) -> Result<Result<(), String>, RclrsError> {
+        let request = SetParametersAtomically_Request {
+            parameters: to_rmw_parameters(parameters),
+        };
+        let response = self
+            .set_parameters_atomically_client
+            .call_async(&request)
+            .await?;
+        if response.result.successful {
+            Ok(Ok(()))
+        } else {
+            Ok(Err(response.result.reason.to_string()))
+        }
+    }
+
+    /// Describes the given parameters of the remote node.
+    pub async fn describe_parameters(
+        &self,
+        names: &[&str],
+    ) -> Result<Vec<ParameterDescription>, RclrsError> {
+        let request = DescribeParameters_Request {
+            names: names.iter().map(|name| (*name).into()).collect(),
+        };
+        let response = self.describe_parameters_client.call_async(&request).await?;
+        Ok(response
+            .descriptors
+            .into_iter()
+            .map(|descriptor| ParameterDescription {
+                name: descriptor.name.to_string().into(),
+                kind: to_parameter_kind(descriptor.type_),
+                description: descriptor.description.to_string().into(),
+                constraints: descriptor.additional_constraints.to_string().into(),
+                dynamic_typing: descriptor.dynamic_typing,
+                read_only: descriptor.read_only,
+                ranges: ParameterRanges::from_descriptor_ranges(
+                    &descriptor.integer_range,
+                    &descriptor.floating_point_range,
+                ),
+            })
+            .collect())
+    }
+}
+
+fn to_names(names: Sequence<rosidl_runtime_rs::String>) -> Vec<Arc<str>> {
+    names.iter().map(|name| name.to_string().into()).collect()
+}
+
+fn to_parameter_kind(parameter_type: u8) -> Option<ParameterKind> {
+    match parameter_type {
+        ParameterType::PARAMETER_BOOL => Some(ParameterKind::Bool),
+        ParameterType::PARAMETER_INTEGER => Some(ParameterKind::Integer),
+        ParameterType::PARAMETER_DOUBLE => Some(ParameterKind::Double),
+        ParameterType::PARAMETER_STRING => Some(ParameterKind::String),
+        ParameterType::PARAMETER_BYTE_ARRAY => Some(ParameterKind::ByteArray),
+        ParameterType::PARAMETER_BOOL_ARRAY => Some(ParameterKind::BoolArray),
+        ParameterType::PARAMETER_INTEGER_ARRAY => Some(ParameterKind::IntegerArray),
+        ParameterType::PARAMETER_DOUBLE_ARRAY => Some(ParameterKind::DoubleArray),
+        ParameterType::PARAMETER_STRING_ARRAY => Some(ParameterKind::StringArray),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    #[test]
+    fn test_parameter_client() {
+        let mut executor = Context::default().create_basic_executor();
+        let server = executor
+            .create_node(NodeOptions::new("server").namespace("/param_client_test"))
+            .unwrap();
+        let range = ParameterRange {
+            lower: Some(0),
+            upper: Some(10),
+            step: None,
+        };
+        let speed = server
+            .declare_parameter("speed")
+            .default(5)
+            .range(range)
+            .mandatory()
+            .unwrap();
+        let node = executor
+            .create_node(NodeOptions::new("client").namespace("/param_client_test"))
+            .unwrap();
+        let client = node.create_parameter_client("server").unwrap();
+        assert_eq!(client.remote_node_name(), "/param_client_test/server");
+        assert!(client
+            .wait_for_services(Some(Duration::from_secs(5)))
+            .unwrap());
+
+        let outcome = Arc::new(Mutex::new(None));
+        node.spawn({
+            let outcome = Arc::clone(&outcome);
+            async move {
+                let list = client.list_parameters(&[], None).await.unwrap();
+                let value = client.get_parameter("speed").await.unwrap();
+                let results = client
+                    .set_parameters(&[
+                        Parameter {
+                            name: "speed".into(),
+                            value: Some(ParameterValue::Integer(20)),
+                        },
+                        Parameter {
+                            name: "speed".into(),
+                            value: Some(ParameterValue::Integer(7)),
+                        },
+                    ])
+                    .await
+                    .unwrap();
+                let descriptions = client.describe_parameters(&["speed"]).await.unwrap();
+                *outcome.lock().unwrap() = Some((list, value, results, descriptions));
+            }
+        });
+        for _ in 0..100 {
+            if outcome.lock().unwrap().is_some() {
+                break;
+            }
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+
+        let (list, value, results, descriptions) = outcome.lock().unwrap().take().unwrap();
+        assert!(list.names.iter().any(|name| &**name == "speed"));
+        assert_eq!(value, Some(ParameterValue::Integer(5)));
+        assert!(results[0].is_err());
+        assert!(results[1].is_ok());
+        assert_eq!(speed.get(), 7);
+        assert_eq!(descriptions.len(), 1);
+        assert_eq!(descriptions[0].kind, Some(ParameterKind::Integer));
+        assert!(!descriptions[0].read_only);
+        let integer_range = descriptions[0].ranges.integer().unwrap();
+        assert_eq!(integer_range.lower, Some(0));
+        assert_eq!(integer_range.upper, Some(10));
+        assert_eq!(integer_range.step, None);
+    }
+
+    #[test]
+    fn test_parameter_client_set_parameters_atomically() {
+        let mut executor = Context::default().create_basic_executor();
+        let server = executor
+            .create_node(NodeOptions::new("server").namespace("/param_client_atomic_test"))
+            .unwrap();
+        let range = ParameterRange {
+            lower: Some(0),
+            upper: Some(10),
+            step: None,
+        };
+        let speed = server
+            .declare_parameter("speed")
+            .default(5)
+            .range(range)
+            .mandatory()
+            .unwrap();
+        let name = server
+            .declare_parameter("name")
+            .default(Arc::<str>::from("slow"))
+            .mandatory()
+            .unwrap();
+        let node = executor
+            .create_node(NodeOptions::new("client").namespace("/param_client_atomic_test"))
+            .unwrap();
+        let client = node.create_parameter_client("server").unwrap();
+        assert!(client
+            .wait_for_services(Some(Duration::from_secs(5)))
+            .unwrap());
+
+        let outcome = Arc::new(Mutex::new(None));
+        node.spawn({
+            let outcome = Arc::clone(&outcome);
+            async move {
+                // The speed is out of range, so the name must not change either.
+                let rejected = client
+                    .set_parameters_atomically(&[
+                        Parameter {
+                            name: "name".into(),
+                            value: Some(ParameterValue::String("fast".into())),
+                        },
+                        Parameter {
+                            name: "speed".into(),
+                            value: Some(ParameterValue::Integer(20)),
+                        },
+                    ])
+                    .await
+                    .unwrap();
+                let rejected_name = client.get_parameter("name").await.unwrap();
+                let accepted = client
+                    .set_parameters_atomically(&[
+                        Parameter {
+                            name: "name".into(),
+                            value: Some(ParameterValue::String("fast".into())),
+                        },
+                        Parameter {
+                            name: "speed".into(),
+                            value: Some(ParameterValue::Integer(9)),
+                        },
+                    ])
+                    .await
+                    .unwrap();
+                *outcome.lock().unwrap() = Some((rejected, rejected_name, accepted));
+            }
+        });
+        for _ in 0..100 {
+            if outcome.lock().unwrap().is_some() {
+                break;
+            }
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+
+        let (rejected, rejected_name, accepted) = outcome.lock().unwrap().take().unwrap();
+        assert!(rejected.is_err());
+        assert_eq!(rejected_name, Some(ParameterValue::String("slow".into())));
+        assert_eq!(accepted, Ok(()));
+        assert_eq!(speed.get(), 9);
+        assert_eq!(&*name.get(), "fast");
+    }
+}
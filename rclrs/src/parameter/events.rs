@@ -0,0 +1,331 @@
+use std::sync::{Arc, Mutex};
+
+use rosidl_runtime_rs::Sequence;
+
+use crate::vendor::{
+    builtin_interfaces::msg::rmw::Time as RmwTime,
+    rcl_interfaces::msg::rmw::{Parameter as RmwParameter, ParameterEvent as RmwParameterEvent},
+};
+use crate::{
+    log_error, CallbackRegistry, Clock, IntoPrimitiveOptions, Logger, Node, Parameter,
+    ParameterCallbackHandle, Publisher, QoSProfile, RclrsError, Subscription,
+};
+
+/// A change of the parameters of a node, as published on the `/parameter_events` topic.
+///
+/// Receive the events of any node using a [`ParameterEventHandler`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParameterEvent {
+    /// The fully qualified name of the node whose parameters changed.
+    pub node: String,
+    /// The time of the change in nanoseconds, according to the clock of the node.
+    pub stamp: i64,
+    /// Parameters that were declared, or set for the first time.
+    pub new_parameters: Vec<Parameter>,
+    /// Parameters whose value changed.
+    pub changed_parameters: Vec<Parameter>,
+    /// Parameters that were undeclared. Their value is always `None`.
+    pub deleted_parameters: Vec<Parameter>,
+}
+
+impl ParameterEvent {
+    /// Returns the new or changed parameter with the given name, if the event contains it.
+    pub fn get_parameter(&self, name: &str) -> Option<&Parameter> {
+        self.new_parameters
+            .iter()
+            .chain(&self.changed_parameters)
+            .find(|parameter| &*parameter.name == name)
+    }
+}
+
+impl From<RmwParameterEvent> for ParameterEvent {
+    fn from(msg: RmwParameterEvent) -> Self {
+        Self {
+            node: msg.node.to_string(),
+            stamp: i64::from(msg.stamp.sec) * 1_000_000_000 + i64::from(msg.stamp.nanosec),
+            new_parameters: from_rmw_parameters(msg.new_parameters),
+            changed_parameters: from_rmw_parameters(msg.changed_parameters),
+            deleted_parameters: from_rmw_parameters(msg.deleted_parameters),
+        }
+    }
+}
+
+fn from_rmw_parameters(parameters: Sequence<RmwParameter>) -> Vec<Parameter> {
+    parameters
+        .into_iter()
+        .map(|parameter| Parameter {
+            name: parameter.name.to_string().into(),
+            // Parameters that are not set cannot be converted and are reported as `None`.
+            value: parameter.value.try_into().ok(),
+        })
+        .collect()
+}
+
+pub(super) fn to_rmw_parameters(parameters: &[Parameter]) -> Sequence<RmwParameter> {
+    parameters
+        .iter()
+        .map(|parameter| RmwParameter {
+            name: parameter.name.as_ref().into(),
+            value: parameter.value.clone().map(Into::into).unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Publishes the parameter changes of a node on the `/parameter_events` topic.
+pub(crate) struct ParameterEventPublisher {
+    publisher: Publisher<RmwParameterEvent>,
+    clock: Clock,
+    fully_qualified_name: String,
+    logger: Logger,
+}
+
+impl std::fmt::Debug for ParameterEventPublisher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParameterEventPublisher")
+            .field("node", &self.fully_qualified_name)
+            .finish()
+    }
+}
+
+impl ParameterEventPublisher {
+    pub(crate) fn new(node: &Node) -> Result<Self, RclrsError> {
+        let publisher =
+            node.create_publisher("/parameter_events".qos(QoSProfile::parameter_events_default()))?;
+        Ok(Self {
+            publisher,
+            clock: node.get_clock(),
+            fully_qualified_name: node.fully_qualified_name(),
+            logger: node.logger().clone(),
+        })
+    }
+
+    /// Publishes an event for the given changes, unless there are none.
+    pub(crate) fn publish(&self, new: &[Parameter], changed: &[Parameter], deleted: &[Parameter]) {
+        if new.is_empty() && changed.is_empty() && deleted.is_empty() {
+            return;
+        }
+        let nsec = self.clock.now().nsec;
+        let event = RmwParameterEvent {
+            stamp: RmwTime {
+                sec: (nsec / 1_000_000_000) as i32,
+                nanosec: (nsec % 1_000_000_000) as u32,
+            },
+            node: self.fully_qualified_name.as_str().into(),
+            new_parameters: to_rmw_parameters(new),
+            changed_parameters: to_rmw_parameters(changed),
+            deleted_parameters: to_rmw_parameters(deleted),
+        };
+        // A failure to publish the event does not undo the parameter change.
+        if let Err(err) = self.publisher.publish(event) {
+            log_error!(&self.logger, "Failed to publish a parameter event: {err}");
+        }
+    }
+}
+
+type ParameterEventCallback = Box<dyn FnMut(&ParameterEvent) + Send>;
+type RemoteParameterCallback = Box<dyn FnMut(&Parameter) + Send>;
+
+#[derive(Default)]
+struct ParameterEventCallbacks {
+    next_id: usize,
+    event_callbacks: Vec<(usize, ParameterEventCallback)>,
+    parameter_callbacks: Vec<(usize, Arc<str>, String, RemoteParameterCallback)>,
+}
+
+impl ParameterEventCallbacks {
+    fn next_id(&mut self) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn dispatch(&mut self, event: &ParameterEvent) {
+        for (_, parameter_name, node_name, callback) in &mut self.parameter_callbacks {
+            if event.node != *node_name {
+                continue;
+            }
+            if let Some(parameter) = event.get_parameter(parameter_name) {
+                callback(parameter);
+            }
+        }
+        for (_, callback) in &mut self.event_callbacks {
+            callback(event);
+        }
+    }
+}
+
+impl CallbackRegistry for ParameterEventCallbacks {
+    fn remove(&mut self, id: usize) {
+        self.event_callbacks.retain(|(i, _)| *i != id);
+        self.parameter_callbacks.retain(|(i, _, _, _)| *i != id);
+    }
+}
+
+/// Receives the parameter events of all nodes, and runs callbacks for them.
+///
+/// Create a parameter event handler using [`NodeState::create_parameter_event_handler`][1].
+///
+/// The callbacks are run by the executor of the node that created the handler, and stay
+/// registered until their [`ParameterCallbackHandle`] is dropped.
+///
+/// [1]: crate::NodeState::create_parameter_event_handler
+pub struct ParameterEventHandler {
+    callbacks: Arc<Mutex<ParameterEventCallbacks>>,
+    fully_qualified_name: String,
+    namespace: String,
+    // Only kept to keep receiving events.
+    #[allow(dead_code)]
+    subscription: Subscription<RmwParameterEvent>,
+}
+
+impl std::fmt::Debug for ParameterEventHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParameterEventHandler")
+            .field("node", &self.fully_qualified_name)
+            .finish()
+    }
+}
+
+impl ParameterEventHandler {
+    pub(crate) fn new(node: &Node) -> Result<Self, RclrsError> {
+        let callbacks = Arc::new(Mutex::new(ParameterEventCallbacks::default()));
+        let subscription = {
+            let callbacks = Arc::clone(&callbacks);
+            node.create_subscription(
+                "/parameter_events".qos(QoSProfile::parameter_events_default()),
+                move |msg: RmwParameterEvent| {
+                    callbacks.lock().unwrap().dispatch(&msg.into());
+                },
+            )?
+        };
+        Ok(Self {
+            callbacks,
+            fully_qualified_name: node.fully_qualified_name(),
+            namespace: node.namespace(),
+            subscription,
+        })
+    }
+
+    /// Registers a callback that is called for every parameter event of any node.
+    pub fn add_parameter_event_callback(
+        &self,
+        callback: impl FnMut(&ParameterEvent) + Send + 'static,
+    ) -> ParameterCallbackHandle {
+        let mut callbacks = self.callbacks.lock().unwrap();
+        let id = callbacks.next_id();
+        callbacks.event_callbacks.push((id, Box::new(callback)));
+        ParameterCallbackHandle::new(id, Arc::downgrade(&self.callbacks) as _)
+    }
+
+    /// Registers a callback that is called whenever a parameter of a node is declared or set.
+    ///
+    /// The `node_name` is resolved like a node name in a topic name: an empty name refers to
+    /// the node that created this handler, and a relative name is resolved in the namespace
+    /// of that node.
+    pub fn add_parameter_callback(
+        &self,
+        parameter_name: impl Into<Arc<str>>,
+        node_name: &str,
+        callback: impl FnMut(&Parameter) + Send + 'static,
+    ) -> ParameterCallbackHandle {
+        let node_name = self.resolve_node_name(node_name);
+        let mut callbacks = self.callbacks.lock().unwrap();
+        let id = callbacks.next_id();
+        callbacks.parameter_callbacks.push((
+            id,
+            parameter_name.into(),
+            node_name,
+            Box::new(callback),
+        ));
+        ParameterCallbackHandle::new(id, Arc::downgrade(&self.callbacks) as _)
+    }
+
+    fn resolve_node_name(&self, node_name: &str) -> String {
+        if node_name.is_empty() {
+            self.fully_qualified_name.clone()
+        } else {
+            resolve_node_name(&self.namespace, node_name)
+        }
+    }
+}
+
+/// Resolves a relative node name in the given namespace, and returns absolute node names
+/// unchanged.
+pub(super) fn resolve_node_name(namespace: &str, node_name: &str) -> String {
+    if node_name.starts_with('/') {
+        node_name.to_owned()
+    } else if namespace.ends_with('/') {
+        format!("{namespace}{node_name}")
+    } else {
+        format!("{namespace}/{node_name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    #[test]
+    fn test_parameter_event_handler() {
+        let mut executor = Context::default().create_basic_executor();
+        let listener = executor
+            .create_node(NodeOptions::new("listener").namespace("/param_events_test"))
+            .unwrap();
+        let talker = executor
+            .create_node(NodeOptions::new("talker").namespace("/param_events_test"))
+            .unwrap();
+        let handler = listener.create_parameter_event_handler().unwrap();
+
+        let values = Arc::new(Mutex::new(Vec::new()));
+        let _parameter_callback = handler.add_parameter_callback("speed", "talker", {
+            let values = Arc::clone(&values);
+            move |parameter| values.lock().unwrap().push(parameter.value.clone())
+        });
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let _event_callback = handler.add_parameter_event_callback({
+            let events = Arc::clone(&events);
+            move |event| events.lock().unwrap().push(event.clone())
+        });
+
+        let speed = talker
+            .declare_parameter("speed")
+            .default(0)
+            .mandatory()
+            .unwrap();
+        // Keep changing the parameter until the subscription was matched and received an event
+        for i in 1..100 {
+            speed.set(i).unwrap();
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+            if !values.lock().unwrap().is_empty() {
+                break;
+            }
+        }
+        let last_value = values.lock().unwrap().last().cloned().flatten();
+        assert_eq!(last_value, Some(ParameterValue::Integer(speed.get())));
+
+        drop(speed);
+        for _ in 0..100 {
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+            if events
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|event| !event.deleted_parameters.is_empty())
+            {
+                break;
+            }
+        }
+        let events = events.lock().unwrap();
+        let deleted = events
+            .iter()
+            .find(|event| !event.deleted_parameters.is_empty())
+            .unwrap();
+        assert_eq!(deleted.node, "/param_events_test/talker");
+        assert_eq!(&*deleted.deleted_parameters[0].name, "speed");
+        assert_eq!(deleted.deleted_parameters[0].value, None);
+    }
+}
@@ -41,6 +41,35 @@ pub struct ParameterRanges {
 }
 
 impl ParameterRanges {
+    /// Returns the range that applies to integer values, if any.
+    pub fn integer(&self) -> Option<&ParameterRange<i64>> {
+        self.integer.as_ref()
+    }
+
+    /// Returns the range that applies to floating point values, if any.
+    pub fn float(&self) -> Option<&ParameterRange<f64>> {
+        self.float.as_ref()
+    }
+
+    /// The inverse of [`Self::to_descriptor_ranges`], where unbounded limits and a zero step
+    /// are converted back to `None`.
+    pub(crate) fn from_descriptor_ranges(
+        integer_range: &[IntegerRange],
+        floating_point_range: &[FloatingPointRange],
+    ) -> Self {
+        let integer = integer_range.first().map(|range| ParameterRange {
+            lower: (range.from_value != i64::MIN).then_some(range.from_value),
+            upper: (range.to_value != i64::MAX).then_some(range.to_value),
+            step: (range.step != 0).then(|| range.step.try_into().unwrap_or(i64::MAX)),
+        });
+        let float = floating_point_range.first().map(|range| ParameterRange {
+            lower: (range.from_value != f64::NEG_INFINITY).then_some(range.from_value),
+            upper: (range.to_value != f64::INFINITY).then_some(range.to_value),
+            step: (range.step != 0.0).then_some(range.step),
+        });
+        Self { float, integer }
+    }
+
     pub(crate) fn to_descriptor_ranges(
         &self,
     ) -> (
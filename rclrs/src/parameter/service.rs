@@ -6,7 +6,7 @@ use std::{
 use crate::vendor::rcl_interfaces::{msg::rmw::*, srv::rmw::*};
 use rosidl_runtime_rs::Sequence;
 
-use super::ParameterMap;
+use super::{Parameter, ParameterCallbacks, ParameterMap};
 use crate::{
     parameter::{DeclaredValue, ParameterKind, ParameterStorage},
     rmw_request_id_t, IntoPrimitiveOptions, Node, QoSProfile, RclrsError, Service,
@@ -174,7 +174,17 @@ fn list_parameters(req: ListParameters_Request, map: &ParameterMap) -> ListParam
     }
 }
 
-fn set_parameters(req: SetParameters_Request, map: &mut ParameterMap) -> SetParameters_Response {
+/// Sets the requested parameters one by one and returns the response, together with the
+/// parameters that were changed.
+///
+/// The on-set callbacks run without the parameter map being locked, so that they can
+/// access parameters.
+fn set_parameters(
+    req: SetParameters_Request,
+    map: &Mutex<ParameterMap>,
+) -> (SetParameters_Response, Vec<Parameter>) {
+    let callbacks = Arc::clone(&map.lock().unwrap().callbacks);
+    let mut changed = Vec::new();
     let results = req
         .parameters
         .into_iter()
@@ -185,28 +195,58 @@ fn set_parameters(req: SetParameters_Request, map: &mut ParameterMap) -> SetPara
                     reason: "Failed parsing into UTF-8".into(),
                 };
             };
-            match map.validate_parameter_setting(name, param.value) {
-                Ok(value) => {
-                    map.store_parameter(name.into(), value);
-                    SetParametersResult {
-                        successful: true,
-                        reason: Default::default(),
+            let value = match map
+                .lock()
+                .unwrap()
+                .validate_parameter_setting(name, param.value)
+            {
+                Ok(value) => value,
+                Err(e) => {
+                    return SetParametersResult {
+                        successful: false,
+                        reason: e.into(),
                     }
                 }
-                Err(e) => SetParametersResult {
+            };
+            let parameter = Parameter {
+                name: name.into(),
+                value: Some(value.clone()),
+            };
+            if let Err(reason) =
+                ParameterCallbacks::validate(&callbacks, std::slice::from_ref(&parameter))
+            {
+                return SetParametersResult {
                     successful: false,
-                    reason: e.into(),
-                },
+                    reason: reason.as_str().into(),
+                };
+            }
+            let mut map = map.lock().unwrap();
+            if map.store_parameter(parameter.name.clone(), value) {
+                map.publish_event(std::slice::from_ref(&parameter), &[], &[]);
+            } else {
+                map.publish_event(&[], std::slice::from_ref(&parameter), &[]);
+            }
+            changed.push(parameter);
+            SetParametersResult {
+                successful: true,
+                reason: Default::default(),
             }
         })
         .collect();
-    SetParameters_Response { results }
+    (SetParameters_Response { results }, changed)
 }
 
+/// Sets all the requested parameters if all of them are valid and returns the response,
+/// together with the parameters that were changed.
+///
+/// The on-set callbacks run without the parameter map being locked, so that they can
+/// access parameters.
 fn set_parameters_atomically(
     req: SetParametersAtomically_Request,
-    map: &mut ParameterMap,
-) -> SetParametersAtomically_Response {
+    map: &Mutex<ParameterMap>,
+) -> (SetParametersAtomically_Response, Vec<Parameter>) {
+    let callbacks = Arc::clone(&map.lock().unwrap().callbacks);
+    let storage = map.lock().unwrap();
     let results = req
         .parameters
         .into_iter()
@@ -214,27 +254,58 @@ fn set_parameters_atomically(
             let Ok(name) = param.name.to_cstr().to_str() else {
                 return Err("Failed parsing into UTF-8".into());
             };
-            let value = map.validate_parameter_setting(name, param.value)?;
-            Ok((name.into(), value))
+            let value = storage.validate_parameter_setting(name, param.value)?;
+            Ok(Parameter {
+                name: name.into(),
+                value: Some(value),
+            })
         })
-        .collect::<Result<Vec<_>, _>>();
+        .collect::<Result<Vec<_>, rosidl_runtime_rs::String>>();
+    drop(storage);
+    let results = results.and_then(|parameters| {
+        ParameterCallbacks::validate(&callbacks, &parameters)
+            .map_err(|reason| rosidl_runtime_rs::String::from(reason.as_str()))?;
+        Ok(parameters)
+    });
     // Check if there was any error and update parameters accordingly
-    let result = match results {
-        Ok(results) => {
-            for (name, value) in results.into_iter() {
-                map.store_parameter(name, value);
+    match results {
+        Ok(parameters) => {
+            let mut map = map.lock().unwrap();
+            let (mut new, mut changed) = (Vec::new(), Vec::new());
+            for parameter in &parameters {
+                // Values that are received through the service are never unset.
+                if let Some(value) = &parameter.value {
+                    if map.store_parameter(parameter.name.clone(), value.clone()) {
+                        new.push(parameter.clone());
+                    } else {
+                        changed.push(parameter.clone());
+                    }
+                }
             }
-            SetParametersResult {
+            map.publish_event(&new, &changed, &[]);
+            let result = SetParametersResult {
                 successful: true,
                 reason: Default::default(),
-            }
+            };
+            (SetParametersAtomically_Response { result }, parameters)
         }
-        Err(reason) => SetParametersResult {
-            successful: false,
-            reason,
-        },
-    };
-    SetParametersAtomically_Response { result }
+        Err(reason) => {
+            let result = SetParametersResult {
+                successful: false,
+                reason,
+            };
+            (SetParametersAtomically_Response { result }, Vec::new())
+        }
+    }
+}
+
+/// Runs the post-set callbacks for the parameters that were changed by a service request.
+///
+/// The parameter map is not locked while running the callbacks, so that they can access
+/// parameters.
+fn notify_post_set(map: &Mutex<ParameterMap>, changed: &[Parameter]) {
+    let callbacks = Arc::clone(&map.lock().unwrap().callbacks);
+    ParameterCallbacks::notify(&callbacks, changed);
 }
 
 impl ParameterService {
@@ -281,16 +352,18 @@ impl ParameterService {
         let set_parameters_service = node.create_service(
             (fqn.clone() + "/set_parameters").qos(QoSProfile::parameter_services_default()),
             move |_req_id: &rmw_request_id_t, req: SetParameters_Request| {
-                let mut map = map.lock().unwrap();
-                set_parameters(req, &mut map)
+                let (response, changed) = set_parameters(req, &map);
+                notify_post_set(&map, &changed);
+                response
             },
         )?;
         let set_parameters_atomically_service = node.create_service(
             (fqn.clone() + "/set_parameters_atomically")
                 .qos(QoSProfile::parameter_services_default()),
             move |_req_id: &rmw_request_id_t, req: SetParametersAtomically_Request| {
-                let mut map = parameter_map.lock().unwrap();
-                set_parameters_atomically(req, &mut map)
+                let (response, changed) = set_parameters_atomically(req, &parameter_map);
+                notify_post_set(&parameter_map, &changed);
+                response
             },
         )?;
         Ok(Self {
@@ -1,17 +1,24 @@
 use std::{
     borrow::Cow,
     ffi::{CStr, CString},
+    future::Future,
     marker::PhantomData,
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, MutexGuard, Weak},
+    time::Duration,
 };
 
+use futures::future::{self, Either};
+
 use rosidl_runtime_rs::{Message, RmwMessage};
 
 use crate::{
     error::{RclrsError, ToResult},
     qos::QoSProfile,
+    qos_supports_intra_process,
     rcl_bindings::*,
-    IntoPrimitiveOptions, NodeHandle, ENTITY_LIFECYCLE_MUTEX,
+    wait_for_graph_condition, wait_for_graph_condition_async, ContextHandle, IntoPrimitiveOptions,
+    IntraProcessPublisher, Node, NodeHandle, NodeState, PublisherGid, SerializedMessage,
+    ENTITY_LIFECYCLE_MUTEX,
 };
 
 mod loaned_message;
@@ -26,9 +33,164 @@ unsafe impl Send for rcl_publisher_t {}
 /// [dropped after][1] the `rcl_publisher_t`.
 ///
 /// [1]: <https://doc.rust-lang.org/reference/destructors.html>
-struct PublisherHandle {
+pub struct PublisherHandle {
     rcl_publisher: Mutex<rcl_publisher_t>,
     node_handle: Arc<NodeHandle>,
+    /// The node is only needed to wait for graph changes, so it is not kept alive by
+    /// the publisher. Otherwise, the publishers that a node owns would keep it alive.
+    node: Weak<NodeState>,
+}
+
+impl PublisherHandle {
+    /// Creates a publisher for messages of the given type support.
+    ///
+    /// Node and namespace changes are always applied _before_ topic remapping.
+    pub(crate) fn new(
+        node: &Node,
+        type_support_ptr: *const rosidl_message_type_support_t,
+        options: PublisherOptions<'_>,
+    ) -> Result<Self, RclrsError> {
+        let node_handle = Arc::clone(&node.handle);
+        let PublisherOptions { topic, qos, .. } = options;
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut rcl_publisher = unsafe { rcl_get_zero_initialized_publisher() };
+        let topic_c_string = CString::new(topic).map_err(|err| RclrsError::StringContainsNul {
+            err,
+            s: topic.into(),
+        })?;
+
+        // SAFETY: No preconditions for this function.
+        let mut publisher_options = unsafe { rcl_publisher_get_default_options() };
+        publisher_options.qos = qos.into();
+
+        {
+            let rcl_node = node_handle.rcl_node.lock().unwrap();
+            let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+            unsafe {
+                // SAFETY:
+                // * The rcl_publisher is zero-initialized as mandated by this function.
+                // * The rcl_node is kept alive by the NodeHandle because it is a dependency of the publisher.
+                // * The topic name and the options are copied by this function, so they can be dropped afterwards.
+                // * The entity lifecycle mutex is locked to protect against the risk of global
+                //   variables in the rmw implementation being unsafely modified during cleanup.
+                rcl_publisher_init(
+                    &mut rcl_publisher,
+                    &*rcl_node,
+                    type_support_ptr,
+                    topic_c_string.as_ptr(),
+                    &publisher_options,
+                )
+                .ok()?;
+            }
+        }
+
+        Ok(Self {
+            rcl_publisher: Mutex::new(rcl_publisher),
+            node_handle,
+            node: Arc::downgrade(node),
+        })
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_publisher_t> {
+        self.rcl_publisher.lock().unwrap()
+    }
+
+    /// See [`PublisherState::topic_name`].
+    pub(crate) fn topic_name(&self) -> String {
+        // SAFETY: No preconditions for the functions called.
+        // The unsafe variables created get converted to safe types before being returned
+        unsafe {
+            let raw_topic_pointer = rcl_publisher_get_topic_name(&*self.lock());
+            CStr::from_ptr(raw_topic_pointer)
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+
+    /// See [`PublisherState::get_subscription_count`].
+    pub(crate) fn get_subscription_count(&self) -> Result<usize, RclrsError> {
+        let mut subscription_count = 0;
+        // SAFETY: No preconditions for the function called.
+        unsafe {
+            rcl_publisher_get_subscription_count(&*self.lock(), &mut subscription_count).ok()?
+        };
+        Ok(subscription_count)
+    }
+
+    /// See [`PublisherState::wait_for_subscribers`].
+    pub(crate) fn wait_for_subscribers(
+        &self,
+        n: usize,
+        timeout: Option<Duration>,
+    ) -> Result<bool, RclrsError> {
+        wait_for_graph_condition(&self.node_handle, timeout, || {
+            Ok(self.get_subscription_count()? >= n)
+        })
+    }
+
+    /// See [`PublisherState::wait_for_subscribers_async`].
+    ///
+    /// The `subscription_count` returns the number of subscriptions of this publisher,
+    /// from a clone of the publisher that the future can keep.
+    pub(crate) fn wait_for_subscribers_async(
+        &self,
+        n: usize,
+        timeout: Option<Duration>,
+        subscription_count: impl Fn() -> Result<usize, RclrsError> + Send + 'static,
+    ) -> impl Future<Output = Result<bool, RclrsError>> + Send + 'static {
+        match self.node.upgrade() {
+            Some(node) => Either::Left(wait_for_graph_condition_async(&node, timeout, move || {
+                Ok(subscription_count()? >= n)
+            })),
+            // Without the node, the graph can't be observed any more.
+            None => Either::Right(future::ready(Ok(false))),
+        }
+    }
+
+    pub(crate) fn context_handle(&self) -> &Arc<ContextHandle> {
+        &self.node_handle.context_handle
+    }
+
+    /// Returns the identifier of the publisher, which is also reported in the
+    /// [`MessageInfo`][1] of the messages it sends.
+    ///
+    /// [1]: crate::MessageInfo
+    pub(crate) fn gid(&self) -> Result<PublisherGid, RclrsError> {
+        let mut gid = rmw_gid_t {
+            data: [0; RMW_GID_STORAGE_SIZE],
+            implementation_identifier: std::ptr::null(),
+        };
+        unsafe {
+            // SAFETY: The rmw publisher is kept alive by the rcl publisher, which is locked
+            // for the duration of this call.
+            let rcl_publisher = self.lock();
+            rmw_get_gid_for_publisher(rcl_publisher_get_rmw_handle(&*rcl_publisher), &mut gid)
+                .ok()?;
+        }
+        Ok(PublisherGid {
+            data: gid.data,
+            implementation_identifier: gid.implementation_identifier,
+        })
+    }
+
+    /// See [`PublisherState::publish_serialized`].
+    pub(crate) fn publish_serialized(&self, bytes: &[u8]) -> Result<(), RclrsError> {
+        // The message only borrows the bytes, so it must not be finalized.
+        let serialized_message = rmw_serialized_message_t {
+            buffer: bytes.as_ptr() as *mut u8,
+            buffer_length: bytes.len(),
+            buffer_capacity: bytes.len(),
+            // SAFETY: No preconditions for this function.
+            allocator: unsafe { rcutils_get_default_allocator() },
+        };
+        unsafe {
+            // SAFETY: The serialized message is only read by this function, and does not
+            // need to be valid beyond the duration of this function call.
+            // The third argument is explictly allowed to be NULL.
+            rcl_publish_serialized_message(&*self.lock(), &serialized_message, std::ptr::null_mut())
+                .ok()
+        }
+    }
 }
 
 impl Drop for PublisherHandle {
@@ -43,6 +205,12 @@ impl Drop for PublisherHandle {
     }
 }
 
+/// Trait to be implemented by all kinds of publishers.
+pub trait PublisherBase: Send + Sync {
+    /// Internal function to get a reference to the `rcl` handle.
+    fn handle(&self) -> &PublisherHandle;
+}
+
 /// Struct for sending messages of type `T`.
 ///
 /// Create a publisher using [`Node::create_publisher`][1].
@@ -78,6 +246,9 @@ where
     // it is global data in the type support library.
     type_support_ptr: *const rosidl_message_type_support_t,
     message: PhantomData<T>,
+    /// Set if the publisher hands its messages directly to subscriptions in the same context.
+    /// This is dropped before the handle, so that the publisher is unregistered first.
+    intra_process: Option<IntraProcessPublisher>,
     handle: PublisherHandle,
 }
 
@@ -96,54 +267,36 @@ where
     ///
     /// Node and namespace changes are always applied _before_ topic remapping.
     pub(crate) fn new<'a>(
-        node_handle: Arc<NodeHandle>,
+        node: &Node,
         options: impl Into<PublisherOptions<'a>>,
     ) -> Result<Self, RclrsError>
     where
         T: Message,
     {
-        let PublisherOptions { topic, qos } = options.into();
-        // SAFETY: Getting a zero-initialized value is always safe.
-        let mut rcl_publisher = unsafe { rcl_get_zero_initialized_publisher() };
+        let options = options.into();
+        let use_intra_process = options
+            .intra_process
+            .unwrap_or(node.use_intra_process_comms())
+            && qos_supports_intra_process(&options.qos);
+        let qos = options.qos;
         let type_support_ptr =
             <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t;
-        let topic_c_string = CString::new(topic).map_err(|err| RclrsError::StringContainsNul {
-            err,
-            s: topic.into(),
-        })?;
-
-        // SAFETY: No preconditions for this function.
-        let mut publisher_options = unsafe { rcl_publisher_get_default_options() };
-        publisher_options.qos = qos.into();
-
-        {
-            let rcl_node = node_handle.rcl_node.lock().unwrap();
-            let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
-            unsafe {
-                // SAFETY:
-                // * The rcl_publisher is zero-initialized as mandated by this function.
-                // * The rcl_node is kept alive by the NodeHandle because it is a dependency of the publisher.
-                // * The topic name and the options are copied by this function, so they can be dropped afterwards.
-                // * The entity lifecycle mutex is locked to protect against the risk of global
-                //   variables in the rmw implementation being unsafely modified during cleanup.
-                rcl_publisher_init(
-                    &mut rcl_publisher,
-                    &*rcl_node,
-                    type_support_ptr,
-                    topic_c_string.as_ptr(),
-                    &publisher_options,
-                )
-                .ok()?;
-            }
-        }
-
+        let handle = PublisherHandle::new(node, type_support_ptr, options)?;
+        let intra_process = if use_intra_process {
+            Some(IntraProcessPublisher::new(
+                Arc::clone(handle.context_handle()),
+                handle.gid()?,
+                handle.topic_name(),
+                qos,
+            ))
+        } else {
+            None
+        };
         Ok(Self {
             type_support_ptr,
             message: PhantomData,
-            handle: PublisherHandle {
-                rcl_publisher: Mutex::new(rcl_publisher),
-                node_handle,
-            },
+            intra_process,
+            handle,
         })
     }
 
@@ -152,29 +305,46 @@ where
     /// This returns the topic name after remapping, so it is not necessarily the
     /// topic name which was used when creating the publisher.
     pub fn topic_name(&self) -> String {
-        // SAFETY: No preconditions for the functions called.
-        // The unsafe variables created get converted to safe types before being returned
-        unsafe {
-            let raw_topic_pointer =
-                rcl_publisher_get_topic_name(&*self.handle.rcl_publisher.lock().unwrap());
-            CStr::from_ptr(raw_topic_pointer)
-                .to_string_lossy()
-                .into_owned()
-        }
+        self.handle.topic_name()
     }
 
     /// Returns the number of subscriptions of the publisher.
     pub fn get_subscription_count(&self) -> Result<usize, RclrsError> {
-        let mut subscription_count = 0;
-        // SAFETY: No preconditions for the function called.
-        unsafe {
-            rcl_publisher_get_subscription_count(
-                &*self.handle.rcl_publisher.lock().unwrap(),
-                &mut subscription_count,
-            )
-            .ok()?
-        };
-        Ok(subscription_count)
+        self.handle.get_subscription_count()
+    }
+
+    /// Blocks until at least `n` subscriptions are matched with this publisher.
+    ///
+    /// Returns `true` once they are matched, and `false` if the timeout elapsed or the
+    /// context shut down before that. A timeout of `None` waits indefinitely.
+    ///
+    /// Messages that are published before a subscription is matched are usually not
+    /// received by it, so this is useful before publishing the first message, e.g. in tests.
+    pub fn wait_for_subscribers(
+        &self,
+        n: usize,
+        timeout: Option<Duration>,
+    ) -> Result<bool, RclrsError> {
+        self.handle.wait_for_subscribers(n, timeout)
+    }
+
+    /// Returns a future that completes once at least `n` subscriptions are matched with
+    /// this publisher.
+    ///
+    /// The matches are checked again whenever the executor of the publisher's node
+    /// notices that the ROS graph changed, so the executor must be [spinning][1] while
+    /// the future is awaited. The output is the same as for
+    /// [`PublisherState::wait_for_subscribers`].
+    ///
+    /// [1]: crate::Executor::spin
+    pub fn wait_for_subscribers_async(
+        self: &Arc<Self>,
+        n: usize,
+        timeout: Option<Duration>,
+    ) -> impl Future<Output = Result<bool, RclrsError>> + Send + 'static {
+        let publisher = Arc::clone(self);
+        self.handle
+            .wait_for_subscribers_async(n, timeout, move || publisher.get_subscription_count())
     }
 
     /// Publishes a message.
@@ -192,10 +362,43 @@ where
     ///
     /// Calling `publish()` is a potentially blocking call, see [this issue][1] for details.
     ///
+    /// If the publisher uses [intra-process communication][2], the message is handed
+    /// to the subscriptions in the same context directly. It only goes through the
+    /// middleware as well if the middleware has matched more subscriptions than those,
+    /// i.e. there are subscriptions in other contexts or processes. The subscriptions in
+    /// the same context discard the copies that arrive through the middleware.
+    ///
     /// [1]: https://github.com/ros2/ros2/issues/255
+    /// [2]: PublisherOptions::intra_process
     pub fn publish<'a, M: MessageCow<'a, T>>(&self, message: M) -> Result<(), RclrsError> {
-        let rmw_message = T::into_rmw_message(message.into_cow());
-        let rcl_publisher = &mut *self.handle.rcl_publisher.lock().unwrap();
+        let message = message.into_cow();
+        let Some(intra_process) = &self.intra_process else {
+            return self.publish_rmw(message);
+        };
+        let subscriptions = intra_process.subscriptions::<T>();
+        if subscriptions.is_empty() {
+            return self.publish_rmw(message);
+        }
+        if self.has_other_subscriptions(subscriptions.len())? {
+            self.publish_rmw(Cow::Borrowed(&*message))?;
+        }
+        intra_process.deliver(&subscriptions, message.into_owned());
+        Ok(())
+    }
+
+    /// Returns true if the middleware has matched subscriptions besides the given number
+    /// of intra-process subscriptions, which then need the message from the middleware.
+    ///
+    /// The intra-process subscriptions are matched by the middleware too, since each of
+    /// them also has a subscription there.
+    fn has_other_subscriptions(&self, intra_process_count: usize) -> Result<bool, RclrsError> {
+        Ok(self.handle.get_subscription_count()? > intra_process_count)
+    }
+
+    /// Publishes a message through the middleware.
+    fn publish_rmw(&self, message: Cow<'_, T>) -> Result<(), RclrsError> {
+        let rmw_message = T::into_rmw_message(message);
+        let rcl_publisher = &mut *self.handle.lock();
         unsafe {
             // SAFETY: The message type is guaranteed to match the publisher type by the type system.
             // The message does not need to be valid beyond the duration of this function call.
@@ -208,6 +411,41 @@ where
             .ok()
         }
     }
+
+    /// Publishes a message that is already serialized, e.g. by [`SerializedMessage::serialize`][1].
+    ///
+    /// The bytes are passed to the middleware as they are, so they must be a valid serialized
+    /// message of type `T` in the encoding of the RMW implementation, typically CDR.
+    ///
+    /// If the publisher uses [intra-process communication][2], the subscriptions in the same
+    /// context receive a deserialized copy of the message.
+    ///
+    /// [1]: crate::SerializedMessage::serialize
+    /// [2]: PublisherOptions::intra_process
+    pub fn publish_serialized(&self, bytes: &[u8]) -> Result<(), RclrsError> {
+        let Some(intra_process) = &self.intra_process else {
+            return self.handle.publish_serialized(bytes);
+        };
+        let subscriptions = intra_process.subscriptions::<T>();
+        if subscriptions.is_empty() {
+            return self.handle.publish_serialized(bytes);
+        }
+        let message = SerializedMessage::from_bytes(bytes).deserialize::<T>()?;
+        if self.has_other_subscriptions(subscriptions.len())? {
+            self.handle.publish_serialized(bytes)?;
+        }
+        intra_process.deliver(&subscriptions, message);
+        Ok(())
+    }
+}
+
+impl<T> PublisherBase for PublisherState<T>
+where
+    T: Message,
+{
+    fn handle(&self) -> &PublisherHandle {
+        &self.handle
+    }
 }
 
 impl<T> PublisherState<T>
@@ -248,12 +486,8 @@ where
         let mut msg_ptr = std::ptr::null_mut();
         unsafe {
             // SAFETY: msg_ptr contains a null ptr as expected by this function.
-            rcl_borrow_loaned_message(
-                &*self.handle.rcl_publisher.lock().unwrap(),
-                self.type_support_ptr,
-                &mut msg_ptr,
-            )
-            .ok()?;
+            rcl_borrow_loaned_message(&*self.handle.lock(), self.type_support_ptr, &mut msg_ptr)
+                .ok()?;
         }
         Ok(LoanedMessage {
             publisher: self,
@@ -263,7 +497,7 @@ where
 
     /// Returns true if message loans are possible, false otherwise.
     pub fn can_loan_messages(&self) -> bool {
-        unsafe { rcl_publisher_can_loan_messages(&*self.handle.rcl_publisher.lock().unwrap()) }
+        unsafe { rcl_publisher_can_loan_messages(&*self.handle.lock()) }
     }
 }
 
@@ -278,6 +512,18 @@ pub struct PublisherOptions<'a> {
     pub topic: &'a str,
     /// The quality of service settings for the publisher.
     pub qos: QoSProfile,
+    /// Whether the publisher hands its messages directly to subscriptions in the same
+    /// [`Context`][1] instead of sending them through the middleware. If this is `None`,
+    /// the setting of the node is used, see [`IntoNodeOptions::use_intra_process_comms`][2].
+    ///
+    /// Only subscriptions that use intra-process communication themselves receive messages
+    /// this way. Publishers with transient local durability always use the middleware, so
+    /// that late-joining subscriptions receive the messages that were published earlier,
+    /// and so do dynamic and generic publishers.
+    ///
+    /// [1]: crate::Context
+    /// [2]: crate::IntoNodeOptions::use_intra_process_comms
+    pub intra_process: Option<bool>,
 }
 
 impl<'a> PublisherOptions<'a> {
@@ -286,8 +532,15 @@ impl<'a> PublisherOptions<'a> {
         Self {
             topic,
             qos: QoSProfile::topics_default(),
+            intra_process: None,
         }
     }
+
+    /// Sets whether the publisher uses intra-process communication.
+    pub fn intra_process(mut self, enable: bool) -> Self {
+        self.intra_process = Some(enable);
+        self
+    }
 }
 
 impl<'a, T: IntoPrimitiveOptions<'a>> From<T> for PublisherOptions<'a> {
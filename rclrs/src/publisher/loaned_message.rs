@@ -54,7 +54,7 @@ where
             unsafe {
                 // SAFETY: These two pointers are valid, and the msg_ptr is not used afterwards.
                 rcl_return_loaned_message_from_publisher(
-                    &*self.publisher.handle.rcl_publisher.lock().unwrap(),
+                    &*self.publisher.handle.lock(),
                     self.msg_ptr as *mut _,
                 )
                 .ok()
@@ -75,11 +75,22 @@ where
     T: RmwMessage,
 {
     /// Publishes the loaned message, falling back to regular publishing if needed.
+    ///
+    /// If the publisher uses [intra-process communication][1], the subscriptions in the
+    /// same context receive a copy of the message.
+    ///
+    /// [1]: crate::PublisherOptions::intra_process
     pub fn publish(mut self) -> Result<(), RclrsError> {
+        // The loan is returned by publishing, so the copy must be made beforehand.
+        let publisher = self.publisher;
+        let intra_process_delivery = publisher.intra_process.as_ref().and_then(|intra_process| {
+            let subscriptions = intra_process.subscriptions::<T>();
+            (!subscriptions.is_empty()).then(|| (intra_process, subscriptions, (*self).clone()))
+        });
         unsafe {
             // SAFETY: These two pointers are valid, and the msg_ptr is not used afterwards.
             rcl_publish_loaned_message(
-                &*self.publisher.handle.rcl_publisher.lock().unwrap(),
+                &*self.publisher.handle.lock(),
                 self.msg_ptr as *mut _,
                 std::ptr::null_mut(),
             )
@@ -88,6 +99,9 @@ where
         // Set the msg_ptr to null, as a signal to the drop impl that this
         // loan was already returned.
         self.msg_ptr = std::ptr::null_mut();
+        if let Some((intra_process, subscriptions, message)) = intra_process_delivery {
+            intra_process.deliver(&subscriptions, message);
+        }
         Ok(())
     }
 }
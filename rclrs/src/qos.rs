@@ -127,6 +127,33 @@ pub enum QoSDuration {
     Custom(Duration),
 }
 
+/// A QoS policy, as reported by an [incompatible QoS event][1].
+///
+/// [1]: crate::IncompatibleQoSStatus
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum QoSPolicyKind {
+    /// The policy is not known.
+    Invalid,
+    /// The durability policy.
+    Durability,
+    /// The deadline.
+    Deadline,
+    /// The liveliness policy.
+    Liveliness,
+    /// The reliability policy.
+    Reliability,
+    /// The history policy.
+    History,
+    /// The lifespan.
+    Lifespan,
+    /// The history depth.
+    Depth,
+    /// The liveliness lease duration.
+    LivelinessLeaseDuration,
+    /// Whether ROS namespace conventions are avoided.
+    AvoidRosNamespaceConventions,
+}
+
 /// A Quality of Service profile.
 ///
 /// See [docs.ros.org][1] on Quality of Service settings in general.
@@ -206,6 +233,56 @@ impl From<QoSProfile> for rmw_qos_profile_t {
     }
 }
 
+impl From<&rmw_qos_profile_t> for QoSProfile {
+    fn from(qos: &rmw_qos_profile_t) -> Self {
+        let depth = qos.depth.try_into().unwrap_or(u32::MAX);
+        // Policies that rclrs does not support, such as the "best available" policies of
+        // newer distros, fall back to the system default.
+        Self {
+            history: match qos.history {
+                rmw_qos_history_policy_t::RMW_QOS_POLICY_HISTORY_KEEP_LAST => {
+                    QoSHistoryPolicy::KeepLast { depth }
+                }
+                rmw_qos_history_policy_t::RMW_QOS_POLICY_HISTORY_KEEP_ALL => {
+                    QoSHistoryPolicy::KeepAll
+                }
+                _ => QoSHistoryPolicy::SystemDefault { depth },
+            },
+            reliability: match qos.reliability {
+                rmw_qos_reliability_policy_t::RMW_QOS_POLICY_RELIABILITY_RELIABLE => {
+                    QoSReliabilityPolicy::Reliable
+                }
+                rmw_qos_reliability_policy_t::RMW_QOS_POLICY_RELIABILITY_BEST_EFFORT => {
+                    QoSReliabilityPolicy::BestEffort
+                }
+                _ => QoSReliabilityPolicy::SystemDefault,
+            },
+            durability: match qos.durability {
+                rmw_qos_durability_policy_t::RMW_QOS_POLICY_DURABILITY_TRANSIENT_LOCAL => {
+                    QoSDurabilityPolicy::TransientLocal
+                }
+                rmw_qos_durability_policy_t::RMW_QOS_POLICY_DURABILITY_VOLATILE => {
+                    QoSDurabilityPolicy::Volatile
+                }
+                _ => QoSDurabilityPolicy::SystemDefault,
+            },
+            deadline: (&qos.deadline).into(),
+            lifespan: (&qos.lifespan).into(),
+            liveliness: match qos.liveliness {
+                rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_AUTOMATIC => {
+                    QoSLivelinessPolicy::Automatic
+                }
+                rmw_qos_liveliness_policy_t::RMW_QOS_POLICY_LIVELINESS_MANUAL_BY_TOPIC => {
+                    QoSLivelinessPolicy::ManualByTopic
+                }
+                _ => QoSLivelinessPolicy::SystemDefault,
+            },
+            liveliness_lease: (&qos.liveliness_lease_duration).into(),
+            avoid_ros_namespace_conventions: qos.avoid_ros_namespace_conventions,
+        }
+    }
+}
+
 impl QoSProfile {
     /// Sets the QoS profile history to [QoSHistoryPolicy::KeepLast] with the specified depth.
     pub fn keep_last(mut self, depth: u32) -> Self {
@@ -380,6 +457,41 @@ impl From<QoSDuration> for rmw_time_t {
     }
 }
 
+impl From<&rmw_time_t> for QoSDuration {
+    fn from(time: &rmw_time_t) -> Self {
+        match (time.sec, time.nsec) {
+            // See RMW_DURATION_DEFAULT
+            (0, 0) => QoSDuration::SystemDefault,
+            // See RMW_DURATION_INFINITE
+            (9223372036, 854775807) => QoSDuration::Infinite,
+            (sec, nsec) => {
+                QoSDuration::Custom(Duration::from_secs(sec) + Duration::from_nanos(nsec))
+            }
+        }
+    }
+}
+
+impl From<rmw_qos_policy_kind_t> for QoSPolicyKind {
+    fn from(kind: rmw_qos_policy_kind_t) -> Self {
+        match kind {
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_INVALID => Self::Invalid,
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_DURABILITY => Self::Durability,
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_DEADLINE => Self::Deadline,
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_LIVELINESS => Self::Liveliness,
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_RELIABILITY => Self::Reliability,
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_HISTORY => Self::History,
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_LIFESPAN => Self::Lifespan,
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_DEPTH => Self::Depth,
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_LIVELINESS_LEASE_DURATION => {
+                Self::LivelinessLeaseDuration
+            }
+            rmw_qos_policy_kind_t::RMW_QOS_POLICY_AVOID_ROS_NAMESPACE_CONVENTIONS => {
+                Self::AvoidRosNamespaceConventions
+            }
+        }
+    }
+}
+
 /// Equivalent to `rmw_qos_profile_sensor_data` from the [`rmw` package][1].
 ///
 /// [1]: https://github.com/ros2/rmw/blob/master/rmw/include/rmw/qos_profiles.h
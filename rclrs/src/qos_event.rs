@@ -0,0 +1,558 @@
+use std::{
+    ffi::c_void,
+    mem::MaybeUninit,
+    sync::{atomic::AtomicBool, Arc, Mutex, MutexGuard},
+};
+
+use crate::{
+    error::ToResult, rcl_bindings::*, CallbackGroup, PublisherBase, QoSPolicyKind, RclrsError,
+    SubscriptionBase, ENTITY_LIFECYCLE_MUTEX,
+};
+
+// SAFETY: The functions accessing this type, including drop(), shouldn't care about the thread
+// they are running in. Therefore, this type can be safely sent to another thread.
+unsafe impl Send for rcl_event_t {}
+
+/// The status of a deadline missed event.
+///
+/// A publisher misses its deadline when it does not publish a message within the
+/// [deadline][1] of its QoS profile. A subscription misses its deadline when it does not
+/// receive a message within the deadline.
+///
+/// [1]: crate::QoSProfile::deadline
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DeadlineMissedStatus {
+    /// The total number of missed deadlines.
+    pub total_count: i32,
+    /// The number of missed deadlines since the last event.
+    pub total_count_change: i32,
+}
+
+/// The status of a liveliness lost event of a publisher.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LivelinessLostStatus {
+    /// The total number of times that the publisher failed to assert its liveliness
+    /// within the [lease duration][1].
+    ///
+    /// [1]: crate::QoSProfile::liveliness_lease
+    pub total_count: i32,
+    /// The change of `total_count` since the last event.
+    pub total_count_change: i32,
+}
+
+/// The status of a liveliness changed event of a subscription.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LivelinessChangedStatus {
+    /// The number of matched publishers that are currently alive.
+    pub alive_count: i32,
+    /// The number of matched publishers that are currently not alive.
+    pub not_alive_count: i32,
+    /// The change of `alive_count` since the last event.
+    pub alive_count_change: i32,
+    /// The change of `not_alive_count` since the last event.
+    pub not_alive_count_change: i32,
+}
+
+/// The status of an incompatible QoS event.
+///
+/// This event happens when a publisher and a subscription on the same topic are not
+/// matched because their QoS profiles are incompatible.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IncompatibleQoSStatus {
+    /// The total number of entities that were found to be incompatible.
+    pub total_count: i32,
+    /// The change of `total_count` since the last event.
+    pub total_count_change: i32,
+    /// The policy that was incompatible the last time an incompatibility was found.
+    pub last_policy_kind: QoSPolicyKind,
+}
+
+/// The status of a message lost event of a subscription.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MessageLostStatus {
+    /// The total number of messages that were lost.
+    pub total_count: usize,
+    /// The change of `total_count` since the last event.
+    pub total_count_change: usize,
+}
+
+/// The status of a matched event.
+///
+/// This event happens when a publisher or subscription is matched with, or unmatched
+/// from, a subscription or publisher on the same topic.
+#[cfg(not(ros_distro = "humble"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MatchedStatus {
+    /// The total number of times that an entity was matched.
+    pub total_count: usize,
+    /// The change of `total_count` since the last event.
+    pub total_count_change: usize,
+    /// The number of entities that are currently matched.
+    pub current_count: usize,
+    /// The change of `current_count` since the last event.
+    pub current_count_change: i32,
+}
+
+impl From<rmw_offered_deadline_missed_status_t> for DeadlineMissedStatus {
+    fn from(status: rmw_offered_deadline_missed_status_t) -> Self {
+        Self {
+            total_count: status.total_count,
+            total_count_change: status.total_count_change,
+        }
+    }
+}
+
+impl From<rmw_requested_deadline_missed_status_t> for DeadlineMissedStatus {
+    fn from(status: rmw_requested_deadline_missed_status_t) -> Self {
+        Self {
+            total_count: status.total_count,
+            total_count_change: status.total_count_change,
+        }
+    }
+}
+
+impl From<rmw_liveliness_lost_status_t> for LivelinessLostStatus {
+    fn from(status: rmw_liveliness_lost_status_t) -> Self {
+        Self {
+            total_count: status.total_count,
+            total_count_change: status.total_count_change,
+        }
+    }
+}
+
+impl From<rmw_liveliness_changed_status_t> for LivelinessChangedStatus {
+    fn from(status: rmw_liveliness_changed_status_t) -> Self {
+        Self {
+            alive_count: status.alive_count,
+            not_alive_count: status.not_alive_count,
+            alive_count_change: status.alive_count_change,
+            not_alive_count_change: status.not_alive_count_change,
+        }
+    }
+}
+
+impl From<rmw_qos_incompatible_event_status_t> for IncompatibleQoSStatus {
+    fn from(status: rmw_qos_incompatible_event_status_t) -> Self {
+        Self {
+            total_count: status.total_count,
+            total_count_change: status.total_count_change,
+            last_policy_kind: status.last_policy_kind.into(),
+        }
+    }
+}
+
+impl From<rmw_message_lost_status_t> for MessageLostStatus {
+    fn from(status: rmw_message_lost_status_t) -> Self {
+        Self {
+            total_count: status.total_count,
+            total_count_change: status.total_count_change,
+        }
+    }
+}
+
+#[cfg(not(ros_distro = "humble"))]
+impl From<rmw_matched_status_t> for MatchedStatus {
+    fn from(status: rmw_matched_status_t) -> Self {
+        Self {
+            total_count: status.total_count,
+            total_count_change: status.total_count_change,
+            current_count: status.current_count,
+            current_count_change: status.current_count_change,
+        }
+    }
+}
+
+/// A callback for a QoS event of a publisher.
+///
+/// The variant determines which event is handled.
+/// Pass this to [`NodeState::create_publisher_event_handler`][1].
+///
+/// [1]: crate::NodeState::create_publisher_event_handler
+pub enum PublisherEventCallback {
+    /// The publisher did not publish a message within its deadline.
+    OfferedDeadlineMissed(Box<dyn FnMut(DeadlineMissedStatus) + Send>),
+    /// The publisher did not assert its liveliness within its lease duration.
+    LivelinessLost(Box<dyn FnMut(LivelinessLostStatus) + Send>),
+    /// A subscription requested a QoS profile that the publisher does not offer.
+    OfferedIncompatibleQoS(Box<dyn FnMut(IncompatibleQoSStatus) + Send>),
+    /// The publisher was matched with, or unmatched from, a subscription.
+    #[cfg(not(ros_distro = "humble"))]
+    Matched(Box<dyn FnMut(MatchedStatus) + Send>),
+}
+
+/// A callback for a QoS event of a subscription.
+///
+/// The variant determines which event is handled.
+/// Pass this to [`NodeState::create_subscription_event_handler`][1].
+///
+/// [1]: crate::NodeState::create_subscription_event_handler
+pub enum SubscriptionEventCallback {
+    /// The subscription did not receive a message within its deadline.
+    RequestedDeadlineMissed(Box<dyn FnMut(DeadlineMissedStatus) + Send>),
+    /// A matched publisher became alive or not alive.
+    LivelinessChanged(Box<dyn FnMut(LivelinessChangedStatus) + Send>),
+    /// A publisher offered a QoS profile that is incompatible with the one of the subscription.
+    RequestedIncompatibleQoS(Box<dyn FnMut(IncompatibleQoSStatus) + Send>),
+    /// Messages were lost before the subscription received them.
+    MessageLost(Box<dyn FnMut(MessageLostStatus) + Send>),
+    /// The subscription was matched with, or unmatched from, a publisher.
+    #[cfg(not(ros_distro = "humble"))]
+    Matched(Box<dyn FnMut(MatchedStatus) + Send>),
+}
+
+impl PublisherEventCallback {
+    fn event_type(&self) -> rcl_publisher_event_type_t {
+        match self {
+            Self::OfferedDeadlineMissed(_) => {
+                rcl_publisher_event_type_t::RCL_PUBLISHER_OFFERED_DEADLINE_MISSED
+            }
+            Self::LivelinessLost(_) => rcl_publisher_event_type_t::RCL_PUBLISHER_LIVELINESS_LOST,
+            Self::OfferedIncompatibleQoS(_) => {
+                rcl_publisher_event_type_t::RCL_PUBLISHER_OFFERED_INCOMPATIBLE_QOS
+            }
+            #[cfg(not(ros_distro = "humble"))]
+            Self::Matched(_) => rcl_publisher_event_type_t::RCL_PUBLISHER_MATCHED,
+        }
+    }
+}
+
+impl SubscriptionEventCallback {
+    fn event_type(&self) -> rcl_subscription_event_type_t {
+        match self {
+            Self::RequestedDeadlineMissed(_) => {
+                rcl_subscription_event_type_t::RCL_SUBSCRIPTION_REQUESTED_DEADLINE_MISSED
+            }
+            Self::LivelinessChanged(_) => {
+                rcl_subscription_event_type_t::RCL_SUBSCRIPTION_LIVELINESS_CHANGED
+            }
+            Self::RequestedIncompatibleQoS(_) => {
+                rcl_subscription_event_type_t::RCL_SUBSCRIPTION_REQUESTED_INCOMPATIBLE_QOS
+            }
+            Self::MessageLost(_) => rcl_subscription_event_type_t::RCL_SUBSCRIPTION_MESSAGE_LOST,
+            #[cfg(not(ros_distro = "humble"))]
+            Self::Matched(_) => rcl_subscription_event_type_t::RCL_SUBSCRIPTION_MATCHED,
+        }
+    }
+}
+
+enum AnyEventCallback {
+    Publisher(PublisherEventCallback),
+    Subscription(SubscriptionEventCallback),
+}
+
+/// The publisher or subscription that an event belongs to.
+enum EventSource {
+    Publisher(Arc<dyn PublisherBase>),
+    Subscription(Arc<dyn SubscriptionBase>),
+}
+
+/// Manage the lifecycle of an `rcl_event_t`, including managing its dependency
+/// on the publisher or subscription by ensuring that this dependency is
+/// [dropped after][1] the `rcl_event_t`.
+///
+/// [1]: <https://doc.rust-lang.org/reference/destructors.html>
+pub struct QoSEventHandle {
+    rcl_event: Mutex<rcl_event_t>,
+    #[allow(dead_code)]
+    source: EventSource,
+    pub(crate) in_use_by_wait_set: Arc<AtomicBool>,
+}
+
+impl QoSEventHandle {
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_event_t> {
+        self.rcl_event.lock().unwrap()
+    }
+}
+
+impl Drop for QoSEventHandle {
+    fn drop(&mut self) {
+        let rcl_event = self.rcl_event.get_mut().unwrap();
+        let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+        // SAFETY: The publisher or subscription of the event is still alive, since it is
+        // only dropped after this. The entity lifecycle mutex is locked to protect against
+        // the risk of global variables in the rmw implementation being unsafely modified
+        // during cleanup.
+        unsafe {
+            rcl_event_fini(rcl_event);
+        }
+    }
+}
+
+/// Struct for handling a QoS event of a publisher or subscription, such as a missed
+/// deadline or an incompatible QoS profile.
+///
+/// Create an event handler using [`Node::create_publisher_event_handler`][1] or
+/// [`Node::create_subscription_event_handler`][2].
+///
+/// The callback is run by the node's executor while it is [spinning][3]. Each event
+/// handler keeps its publisher or subscription alive, and stops handling events once it
+/// is dropped.
+///
+/// Not all RMW implementations support all events. Creating an event handler for an
+/// unsupported event returns an error.
+///
+/// [1]: crate::NodeState::create_publisher_event_handler
+/// [2]: crate::NodeState::create_subscription_event_handler
+/// [3]: crate::Executor::spin
+pub type QoSEventHandler = Arc<QoSEventHandlerState>;
+
+/// The inner state of a [`QoSEventHandler`].
+///
+/// This is public so that you can choose to create a [`Weak`][1] reference to it
+/// if you want to be able to refer to a [`QoSEventHandler`] in a non-owning way.
+///
+/// [1]: std::sync::Weak
+pub struct QoSEventHandlerState {
+    pub(crate) handle: Arc<QoSEventHandle>,
+    callback: Mutex<AnyEventCallback>,
+    pub(crate) callback_group: CallbackGroup,
+}
+
+impl QoSEventHandlerState {
+    /// Creates a handler for an event of a publisher.
+    pub(crate) fn new_for_publisher(
+        publisher: Arc<dyn PublisherBase>,
+        callback: PublisherEventCallback,
+        callback_group: CallbackGroup,
+    ) -> Result<Self, RclrsError> {
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut rcl_event = unsafe { rcl_get_zero_initialized_event() };
+        {
+            let rcl_publisher = publisher.handle().lock();
+            let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+            unsafe {
+                // SAFETY:
+                // * The rcl_event is zero-initialized as mandated by this function.
+                // * The rcl_publisher is kept alive by the QoSEventHandle because it is a
+                //   dependency of the event.
+                // * The entity lifecycle mutex is locked to protect against the risk of global
+                //   variables in the rmw implementation being unsafely modified during cleanup.
+                rcl_publisher_event_init(&mut rcl_event, &*rcl_publisher, callback.event_type())
+                    .ok()?;
+            }
+        }
+        Ok(Self::new(
+            rcl_event,
+            EventSource::Publisher(publisher),
+            AnyEventCallback::Publisher(callback),
+            callback_group,
+        ))
+    }
+
+    /// Creates a handler for an event of a subscription.
+    pub(crate) fn new_for_subscription(
+        subscription: Arc<dyn SubscriptionBase>,
+        callback: SubscriptionEventCallback,
+        callback_group: CallbackGroup,
+    ) -> Result<Self, RclrsError> {
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut rcl_event = unsafe { rcl_get_zero_initialized_event() };
+        {
+            let rcl_subscription = subscription.handle().lock();
+            let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+            unsafe {
+                // SAFETY:
+                // * The rcl_event is zero-initialized as mandated by this function.
+                // * The rcl_subscription is kept alive by the QoSEventHandle because it is a
+                //   dependency of the event.
+                // * The entity lifecycle mutex is locked to protect against the risk of global
+                //   variables in the rmw implementation being unsafely modified during cleanup.
+                rcl_subscription_event_init(
+                    &mut rcl_event,
+                    &*rcl_subscription,
+                    callback.event_type(),
+                )
+                .ok()?;
+            }
+        }
+        Ok(Self::new(
+            rcl_event,
+            EventSource::Subscription(subscription),
+            AnyEventCallback::Subscription(callback),
+            callback_group,
+        ))
+    }
+
+    fn new(
+        rcl_event: rcl_event_t,
+        source: EventSource,
+        callback: AnyEventCallback,
+        callback_group: CallbackGroup,
+    ) -> Self {
+        let handle = Arc::new(QoSEventHandle {
+            rcl_event: Mutex::new(rcl_event),
+            source,
+            in_use_by_wait_set: Arc::new(AtomicBool::new(false)),
+        });
+        Self {
+            handle,
+            callback: Mutex::new(callback),
+            callback_group,
+        }
+    }
+
+    /// Takes the status of the event, if there is a new one, and runs the callback with it.
+    pub(crate) fn execute(&self) -> Result<(), RclrsError> {
+        let mut callback = self.callback.lock().unwrap();
+        match &mut *callback {
+            AnyEventCallback::Publisher(callback) => match callback {
+                PublisherEventCallback::OfferedDeadlineMissed(callback) => {
+                    self.take_and_call::<rmw_offered_deadline_missed_status_t, _, _>(callback)
+                }
+                PublisherEventCallback::LivelinessLost(callback) => {
+                    self.take_and_call::<rmw_liveliness_lost_status_t, _, _>(callback)
+                }
+                PublisherEventCallback::OfferedIncompatibleQoS(callback) => {
+                    self.take_and_call::<rmw_qos_incompatible_event_status_t, _, _>(callback)
+                }
+                #[cfg(not(ros_distro = "humble"))]
+                PublisherEventCallback::Matched(callback) => {
+                    self.take_and_call::<rmw_matched_status_t, _, _>(callback)
+                }
+            },
+            AnyEventCallback::Subscription(callback) => match callback {
+                SubscriptionEventCallback::RequestedDeadlineMissed(callback) => {
+                    self.take_and_call::<rmw_requested_deadline_missed_status_t, _, _>(callback)
+                }
+                SubscriptionEventCallback::LivelinessChanged(callback) => {
+                    self.take_and_call::<rmw_liveliness_changed_status_t, _, _>(callback)
+                }
+                SubscriptionEventCallback::RequestedIncompatibleQoS(callback) => {
+                    self.take_and_call::<rmw_qos_incompatible_event_status_t, _, _>(callback)
+                }
+                SubscriptionEventCallback::MessageLost(callback) => {
+                    self.take_and_call::<rmw_message_lost_status_t, _, _>(callback)
+                }
+                #[cfg(not(ros_distro = "humble"))]
+                SubscriptionEventCallback::Matched(callback) => {
+                    self.take_and_call::<rmw_matched_status_t, _, _>(callback)
+                }
+            },
+        }
+    }
+
+    /// Takes the status of the event as the rmw type `S`, which must be the status type
+    /// of the event, and runs the callback with it.
+    fn take_and_call<S, T, F>(&self, mut callback: F) -> Result<(), RclrsError>
+    where
+        T: From<S>,
+        F: FnMut(T),
+    {
+        let mut status = MaybeUninit::<S>::uninit();
+        let result = unsafe {
+            // SAFETY: The status type matches the event type, which was chosen by the
+            // same callback variant when the event was created.
+            rcl_take_event(&*self.handle.lock(), status.as_mut_ptr() as *mut c_void).ok()
+        };
+        match result {
+            Ok(()) => {
+                // SAFETY: The status was filled in by rcl_take_event.
+                let status = unsafe { status.assume_init() };
+                callback(status.into());
+                Ok(())
+            }
+            // Spurious wakeup – this may happen even when the wait set indicated readiness.
+            Err(err) if err.is_take_failed() => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_helpers::*, *};
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+    use test_msgs::msg::Empty;
+
+    #[test]
+    fn traits() {
+        assert_send::<QoSEventHandlerState>();
+        assert_sync::<QoSEventHandlerState>();
+    }
+
+    /// Spins until the condition holds, or fails after a timeout.
+    fn spin_until(executor: &mut Executor, mut condition: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !condition() {
+            assert!(Instant::now() < deadline, "timed out waiting for the event");
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+    }
+
+    #[test]
+    fn test_incompatible_qos_events() -> Result<(), RclrsError> {
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_qos_events_{}", line!()))?;
+        let topic = format!("test_qos_events_topic_{}", line!());
+
+        let publisher = node.create_publisher::<Empty>(
+            topic
+                .as_str()
+                .qos(QoSProfile::topics_default().best_effort()),
+        )?;
+        let subscription = node.create_subscription(
+            topic.as_str().qos(QoSProfile::topics_default().reliable()),
+            |_: Empty| {},
+        )?;
+
+        let offered = Arc::new(Mutex::new(None));
+        let _publisher_events = {
+            let offered = Arc::clone(&offered);
+            node.create_publisher_event_handler(
+                &publisher,
+                PublisherEventCallback::OfferedIncompatibleQoS(Box::new(move |status| {
+                    *offered.lock().unwrap() = Some(status);
+                })),
+            )?
+        };
+        let requested = Arc::new(Mutex::new(None));
+        let _subscription_events = {
+            let requested = Arc::clone(&requested);
+            node.create_subscription_event_handler(
+                &subscription,
+                SubscriptionEventCallback::RequestedIncompatibleQoS(Box::new(move |status| {
+                    *requested.lock().unwrap() = Some(status);
+                })),
+            )?
+        };
+
+        spin_until(&mut executor, || {
+            offered.lock().unwrap().is_some() && requested.lock().unwrap().is_some()
+        });
+        for status in [offered, requested] {
+            let status = status.lock().unwrap().unwrap();
+            assert!(status.total_count >= 1);
+            assert_eq!(status.last_policy_kind, QoSPolicyKind::Reliability);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_deadline_missed_event() -> Result<(), RclrsError> {
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_qos_events_{}", line!()))?;
+        let topic = format!("test_qos_events_topic_{}", line!());
+        let qos = QoSProfile::topics_default().deadline_duration(Duration::from_millis(50));
+
+        let publisher = node.create_publisher::<Empty>(topic.as_str().qos(qos))?;
+        let subscription = node.create_subscription(topic.as_str().qos(qos), |_: Empty| {})?;
+        let missed = Arc::new(Mutex::new(0));
+        let _events = {
+            let missed = Arc::clone(&missed);
+            node.create_subscription_event_handler(
+                &subscription,
+                SubscriptionEventCallback::RequestedDeadlineMissed(Box::new(move |status| {
+                    *missed.lock().unwrap() = status.total_count;
+                })),
+            )?
+        };
+
+        // The deadline is only monitored after the first message was received.
+        publisher.publish(Empty::default())?;
+        spin_until(&mut executor, || *missed.lock().unwrap() > 0);
+        Ok(())
+    }
+}
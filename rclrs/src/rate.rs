@@ -0,0 +1,141 @@
+use std::{future::Future, time::Duration};
+
+use crate::Clock;
+
+/// Runs a loop at a fixed frequency, according to the time of a [`Clock`].
+///
+/// Create a rate that uses the clock of a node with [`NodeState::create_rate`][1], or
+/// with [`Rate::new`] for any other clock. When the node uses sim time, the rate follows
+/// the simulated time.
+///
+/// ```
+/// # use rclrs::*;
+/// # use std::time::Duration;
+/// let mut rate = Rate::new(Clock::steady(), Duration::from_millis(10));
+/// for _ in 0..3 {
+///     // Do some work
+///     rate.sleep();
+/// }
+/// ```
+///
+/// [1]: crate::NodeState::create_rate
+#[derive(Debug)]
+pub struct Rate {
+    clock: Clock,
+    period: Duration,
+    // The time in nanoseconds at which the last period started.
+    last_wakeup: i64,
+}
+
+impl Rate {
+    /// Creates a rate with the given period, which starts now.
+    pub fn new(clock: Clock, period: Duration) -> Self {
+        let last_wakeup = clock.now().nsec;
+        Self {
+            clock,
+            period,
+            last_wakeup,
+        }
+    }
+
+    /// Returns the period of this rate.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Restarts the current period at the current time.
+    pub fn reset(&mut self) {
+        self.last_wakeup = self.clock.now().nsec;
+    }
+
+    /// Blocks the current thread until the current period is over.
+    ///
+    /// If the period is already over, this returns immediately. If the loop fell behind
+    /// by more than a whole period, the next period starts now instead of trying to catch up.
+    ///
+    /// Returns `false` if the sleep was interrupted, see [`Clock::sleep_until`].
+    pub fn sleep(&mut self) -> bool {
+        match self.next_wakeup() {
+            Some(wakeup) => self.clock.sleep_until_nsec(wakeup),
+            None => true,
+        }
+    }
+
+    /// Returns a future that completes when the current period is over.
+    ///
+    /// This behaves like [`Rate::sleep`], without blocking the current thread.
+    pub fn sleep_async(&mut self) -> impl Future<Output = bool> + Send + 'static {
+        let sleep = self
+            .next_wakeup()
+            .map(|wakeup| self.clock.sleep_until_nsec_async(wakeup));
+        async move {
+            match sleep {
+                Some(sleep) => sleep.await,
+                None => true,
+            }
+        }
+    }
+
+    /// Advances to the next period and returns the time at which it starts, or `None`
+    /// if it has already started.
+    fn next_wakeup(&mut self) -> Option<i64> {
+        // Periods that don't fit into the time of a clock never end.
+        let period = i64::try_from(self.period.as_nanos()).unwrap_or(i64::MAX);
+        let now = self.clock.now().nsec;
+        if now < self.last_wakeup {
+            // The time jumped back, e.g. because a rosbag that provides the sim time looped.
+            self.last_wakeup = now;
+        }
+        let wakeup = self.last_wakeup.saturating_add(period);
+        if wakeup > now {
+            self.last_wakeup = wakeup;
+            Some(wakeup)
+        } else {
+            // Start the next period now if the loop fell behind by more than a period.
+            self.last_wakeup = if now - wakeup > period { now } else { wakeup };
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn rate_sleeps_for_period() {
+        let start = Instant::now();
+        let mut rate = Rate::new(Clock::steady(), Duration::from_millis(20));
+        for _ in 0..3 {
+            assert!(rate.sleep());
+        }
+        assert!(start.elapsed() >= Duration::from_millis(60));
+    }
+
+    #[test]
+    fn rate_follows_ros_time() {
+        let (clock, source) = Clock::with_source();
+        source.set_ros_time_override(1_000_000_000);
+        let mut rate = Rate::new(clock, Duration::from_secs(1));
+        let sleeper = std::thread::spawn(move || rate.sleep());
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(!sleeper.is_finished());
+        source.set_ros_time_override(2_000_000_000);
+        assert!(sleeper.join().unwrap());
+    }
+
+    #[test]
+    fn rate_sleep_async() {
+        let start = Instant::now();
+        let mut rate = Rate::new(Clock::steady(), Duration::from_millis(20));
+        assert!(futures::executor::block_on(rate.sleep_async()));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn rate_saturates_long_periods() {
+        let mut rate = Rate::new(Clock::steady(), Duration::MAX);
+        assert_eq!(rate.next_wakeup(), Some(i64::MAX));
+    }
+}
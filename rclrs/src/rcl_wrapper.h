@@ -1,5 +1,7 @@
 #include <rcl/graph.h>
 #include <rcl/rcl.h>
+#include <rcl_action/rcl_action.h>
+#include <rcl_lifecycle/rcl_lifecycle.h>
 #include <rcl_yaml_param_parser/parser.h>
 #include <rcutils/error_handling.h>
 #include <rcl/logging.h>
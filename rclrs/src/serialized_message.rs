@@ -0,0 +1,220 @@
+use std::{borrow::Cow, ffi::c_void, fmt, ops::Deref};
+
+use rosidl_runtime_rs::{Message, RmwMessage};
+
+use crate::{error::ToResult, rcl_bindings::*, RclrsError};
+
+/// A message in its serialized form, i.e. CDR-encoded bytes as sent by the middleware.
+///
+/// Serialized messages can be published with [`PublisherState::publish_serialized`][1] and
+/// received with [`NodeState::create_serialized_subscription`][2], which avoids converting
+/// the message from and to its Rust type when it is only forwarded or recorded.
+///
+/// Use [`SerializedMessage::serialize`] and [`SerializedMessage::deserialize`] to convert
+/// between a [`Message`] and its serialized form.
+///
+/// ```
+/// # use rclrs::*;
+/// let message = test_msgs::msg::BasicTypes {
+///     int32_value: 42,
+///     ..Default::default()
+/// };
+/// let serialized = SerializedMessage::serialize(&message)?;
+/// let deserialized: test_msgs::msg::BasicTypes = serialized.deserialize()?;
+/// assert_eq!(deserialized, message);
+/// # Ok::<(), RclrsError>(())
+/// ```
+///
+/// [1]: crate::PublisherState::publish_serialized
+/// [2]: crate::NodeState::create_serialized_subscription
+pub struct SerializedMessage {
+    rmw_serialized_message: rmw_serialized_message_t,
+}
+
+// SAFETY: The serialized message owns its buffer, and the allocator is thread-safe.
+unsafe impl Send for SerializedMessage {}
+// SAFETY: The buffer is only modified through a mutable reference.
+unsafe impl Sync for SerializedMessage {}
+
+impl Drop for SerializedMessage {
+    fn drop(&mut self) {
+        // SAFETY: The message was initialized with rcutils_uint8_array_init.
+        unsafe {
+            rcutils_uint8_array_fini(&mut self.rmw_serialized_message);
+        }
+    }
+}
+
+impl Default for SerializedMessage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for SerializedMessage {
+    fn clone(&self) -> Self {
+        Self::from_bytes(self)
+    }
+}
+
+impl fmt::Debug for SerializedMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SerializedMessage")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl PartialEq for SerializedMessage {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for SerializedMessage {}
+
+impl Deref for SerializedMessage {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl AsRef<[u8]> for SerializedMessage {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl From<&[u8]> for SerializedMessage {
+    fn from(bytes: &[u8]) -> Self {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl SerializedMessage {
+    /// Creates an empty serialized message.
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an empty serialized message with a buffer of the given capacity.
+    pub fn with_capacity(capacity: usize) -> Self {
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut rmw_serialized_message = unsafe { rcutils_get_zero_initialized_uint8_array() };
+        // SAFETY: The array is zero-initialized, and the allocator is copied into it.
+        unsafe {
+            rcutils_uint8_array_init(
+                &mut rmw_serialized_message,
+                capacity,
+                &rcutils_get_default_allocator(),
+            )
+            .ok()
+            // Like a Vec, treat a failed allocation as unrecoverable.
+            .expect("Failed to allocate a serialized message");
+        }
+        Self {
+            rmw_serialized_message,
+        }
+    }
+
+    /// Creates a serialized message that contains a copy of the given bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut serialized = Self::with_capacity(bytes.len());
+        if !bytes.is_empty() {
+            // SAFETY: The buffer has a capacity of at least the number of bytes.
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    bytes.as_ptr(),
+                    serialized.rmw_serialized_message.buffer,
+                    bytes.len(),
+                );
+            }
+        }
+        serialized.rmw_serialized_message.buffer_length = bytes.len();
+        serialized
+    }
+
+    /// Returns the serialized bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        if self.rmw_serialized_message.buffer.is_null() {
+            return &[];
+        }
+        // SAFETY: The buffer contains buffer_length initialized bytes.
+        unsafe {
+            std::slice::from_raw_parts(
+                self.rmw_serialized_message.buffer,
+                self.rmw_serialized_message.buffer_length,
+            )
+        }
+    }
+
+    /// Serializes a message.
+    pub fn serialize<T: Message>(message: &T) -> Result<Self, RclrsError> {
+        let rmw_message = T::into_rmw_message(Cow::Borrowed(message));
+        let type_support =
+            <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t;
+        let mut serialized = Self::new();
+        // SAFETY: The message has the type of the type support, and the serialized
+        // message is initialized, so it can be resized by this function.
+        unsafe {
+            rmw_serialize(
+                rmw_message.as_ref() as *const <T as Message>::RmwMsg as *const c_void,
+                type_support,
+                &mut serialized.rmw_serialized_message,
+            )
+            .ok()?;
+        }
+        Ok(serialized)
+    }
+
+    /// Deserializes the message, which must have been serialized from a message of type `T`.
+    pub fn deserialize<T: Message>(&self) -> Result<T, RclrsError> {
+        let mut rmw_message = <T as Message>::RmwMsg::default();
+        let type_support =
+            <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t;
+        // SAFETY: The message has the type of the type support. The serialized message
+        // is only read.
+        unsafe {
+            rmw_deserialize(
+                &self.rmw_serialized_message,
+                type_support,
+                &mut rmw_message as *mut <T as Message>::RmwMsg as *mut c_void,
+            )
+            .ok()?;
+        }
+        Ok(T::from_rmw_message(rmw_message))
+    }
+
+    pub(crate) fn as_rmw_mut(&mut self) -> &mut rmw_serialized_message_t {
+        &mut self.rmw_serialized_message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+    use test_msgs::msg;
+
+    #[test]
+    fn traits() {
+        assert_send::<SerializedMessage>();
+        assert_sync::<SerializedMessage>();
+    }
+
+    #[test]
+    fn serialized_message_round_trip() {
+        let message = msg::Strings {
+            string_value: "Hello".into(),
+            ..Default::default()
+        };
+        let serialized = SerializedMessage::serialize(&message).unwrap();
+        assert!(!serialized.is_empty());
+        assert_eq!(serialized.clone(), serialized);
+        let copy = SerializedMessage::from_bytes(&serialized);
+        assert_eq!(copy.deserialize::<msg::Strings>().unwrap(), message);
+        assert!(SerializedMessage::new().is_empty());
+    }
+}
@@ -4,15 +4,25 @@ use std::{
     sync::{atomic::AtomicBool, Arc, Mutex, MutexGuard},
 };
 
+use futures::future::BoxFuture;
 use rosidl_runtime_rs::Message;
 
 use crate::{
     error::{RclReturnCode, ToResult},
+    log_error,
     rcl_bindings::*,
-    IntoPrimitiveOptions, MessageCow, Node, NodeHandle, QoSProfile, RclrsError,
-    ENTITY_LIFECYCLE_MUTEX,
+    CallbackGroup, Clock, IntoPrimitiveOptions, MessageCow, Node, NodeHandle, QoSProfile,
+    RclrsError, ENTITY_LIFECYCLE_MUTEX,
 };
 
+mod responder;
+mod service_info;
+pub use responder::*;
+pub use service_info::*;
+
+#[cfg(not(ros_distro = "humble"))]
+use crate::ServiceIntrospectionState;
+
 // SAFETY: The functions accessing this type, including drop(), shouldn't care about the thread
 // they are running in. Therefore, this type can be safely sent to another thread.
 unsafe impl Send for rcl_service_t {}
@@ -25,6 +35,9 @@ unsafe impl Send for rcl_service_t {}
 pub struct ServiceHandle {
     rcl_service: Mutex<rcl_service_t>,
     node_handle: Arc<NodeHandle>,
+    /// The clock that service introspection events are stamped with. rcl keeps a pointer
+    /// to it for as long as introspection is enabled, so it is kept alive here.
+    introspection_clock: Mutex<Option<Clock>>,
     pub(crate) in_use_by_wait_set: Arc<AtomicBool>,
 }
 
@@ -32,6 +45,18 @@ impl ServiceHandle {
     pub(crate) fn lock(&self) -> MutexGuard<rcl_service_t> {
         self.rcl_service.lock().unwrap()
     }
+
+    /// Returns the clock of service introspection, if it is enabled.
+    ///
+    /// rcl reads this clock when taking requests and sending responses, so it must be
+    /// locked for those calls, after the service itself.
+    fn introspection_clock(&self) -> Option<Arc<Mutex<rcl_clock_t>>> {
+        self.introspection_clock
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|clock| Arc::clone(clock.get_rcl_clock()))
+    }
 }
 
 impl Drop for ServiceHandle {
@@ -53,12 +78,26 @@ impl Drop for ServiceHandle {
 pub trait ServiceBase: Send + Sync {
     /// Internal function to get a reference to the `rcl` handle.
     fn handle(&self) -> &ServiceHandle;
+    /// Returns the callback group that the callback belongs to.
+    fn callback_group(&self) -> &CallbackGroup;
     /// Tries to take a new request and run the callback with it.
     fn execute(&self) -> Result<(), RclrsError>;
 }
 
-type ServiceCallback<Request, Response> =
-    Box<dyn Fn(&rmw_request_id_t, Request) -> Response + 'static + Send>;
+/// An enum capturing the possible function signatures for service callbacks.
+pub enum AnyServiceCallback<Request, Response> {
+    /// A callback that returns the response right away.
+    Regular(Box<dyn Fn(&rmw_request_id_t, Request) -> Response + 'static + Send>),
+    /// An async callback. The response is sent once the returned future has completed,
+    /// which is run by the executor of the node.
+    #[allow(clippy::type_complexity)]
+    Async(Box<dyn Fn(&rmw_request_id_t, Request) -> BoxFuture<'static, Response> + 'static + Send>),
+    /// A callback that receives a [`ServiceResponder`] instead of returning the response.
+    /// The response is sent whenever the responder is used, which may be after the
+    /// callback has returned.
+    #[allow(clippy::type_complexity)]
+    Deferred(Box<dyn Fn(ServiceInfo, Request, ServiceResponder<Response>) + 'static + Send>),
+}
 
 /// Provide a service that can respond to requests sent by ROS service clients.
 ///
@@ -92,10 +131,10 @@ where
 {
     pub(crate) handle: Arc<ServiceHandle>,
     /// The callback function that runs when a request was received.
-    pub callback: Mutex<ServiceCallback<T::Request, T::Response>>,
-    /// Ensure the parent node remains alive as long as the subscription is held.
-    /// This implementation will change in the future.
-    #[allow(unused)]
+    pub callback: Mutex<AnyServiceCallback<T::Request, T::Response>>,
+    callback_group: CallbackGroup,
+    /// Ensure the parent node remains alive as long as the service is held.
+    /// The node also runs the futures of async callbacks.
     node: Node,
 }
 
@@ -104,18 +143,21 @@ where
     T: rosidl_runtime_rs::Service,
 {
     /// Creates a new service.
-    pub(crate) fn new<'a, F>(
+    pub(crate) fn new<'a>(
         node: &Node,
         options: impl Into<ServiceOptions<'a>>,
-        callback: F,
+        callback: AnyServiceCallback<T::Request, T::Response>,
     ) -> Result<Self, RclrsError>
     // This uses pub(crate) visibility to avoid instantiating this struct outside
     // [`Node::create_service`], see the struct's documentation for the rationale
     where
         T: rosidl_runtime_rs::Service,
-        F: Fn(&rmw_request_id_t, T::Request) -> T::Response + 'static + Send,
     {
-        let ServiceOptions { name, qos } = options.into();
+        let ServiceOptions {
+            name,
+            qos,
+            callback_group,
+        } = options.into();
         // SAFETY: Getting a zero-initialized value is always safe.
         let mut rcl_service = unsafe { rcl_get_zero_initialized_service() };
         let type_support = <T as rosidl_runtime_rs::Service>::get_type_support()
@@ -154,13 +196,16 @@ where
         let handle = Arc::new(ServiceHandle {
             rcl_service: Mutex::new(rcl_service),
             node_handle: Arc::clone(&node.handle),
+            introspection_clock: Mutex::new(None),
             in_use_by_wait_set: Arc::new(AtomicBool::new(false)),
         });
 
         Ok(Self {
             handle,
             node: Arc::clone(node),
-            callback: Mutex::new(Box::new(callback)),
+            callback: Mutex::new(callback),
+            callback_group: callback_group
+                .unwrap_or_else(|| Arc::clone(node.default_callback_group())),
         })
     }
 
@@ -195,6 +240,10 @@ where
             <<T as rosidl_runtime_rs::Service>::Request as rosidl_runtime_rs::Message>::RmwMsg;
         let mut request_out = RmwMsg::<T>::default();
         let handle = &*self.handle.lock();
+        let introspection_clock = self.handle.introspection_clock();
+        let _introspection_clock = introspection_clock
+            .as_ref()
+            .map(|clock| clock.lock().unwrap());
         unsafe {
             // SAFETY: The three pointers are valid/initialized
             rcl_take_request(
@@ -206,6 +255,104 @@ where
         .ok()?;
         Ok((T::Request::from_rmw_message(request_out), request_id_out))
     }
+
+    /// Fetches a new request, together with the [`ServiceInfo`] sent along with it.
+    ///
+    /// Like [`take_request`][1], but also returns the timestamps of the request.
+    ///
+    /// [1]: Self::take_request
+    pub fn take_request_with_info(&self) -> Result<(T::Request, ServiceInfo), RclrsError> {
+        let mut service_info_out = rmw_service_info_t {
+            source_timestamp: 0,
+            received_timestamp: 0,
+            request_id: rmw_request_id_t {
+                writer_guid: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                sequence_number: 0,
+            },
+        };
+        type RmwMsg<T> =
+            <<T as rosidl_runtime_rs::Service>::Request as rosidl_runtime_rs::Message>::RmwMsg;
+        let mut request_out = RmwMsg::<T>::default();
+        let handle = &*self.handle.lock();
+        let introspection_clock = self.handle.introspection_clock();
+        let _introspection_clock = introspection_clock
+            .as_ref()
+            .map(|clock| clock.lock().unwrap());
+        unsafe {
+            // SAFETY: The three pointers are valid/initialized
+            rcl_take_request_with_info(
+                handle,
+                &mut service_info_out,
+                &mut request_out as *mut RmwMsg<T> as *mut _,
+            )
+        }
+        .ok()?;
+        Ok((
+            T::Request::from_rmw_message(request_out),
+            ServiceInfo::from_rmw_service_info(&service_info_out),
+        ))
+    }
+
+    /// Configures [service introspection][1] for this service, which publishes an event
+    /// for every request and response it receives or sends.
+    ///
+    /// The events are published with the given QoS profile and are stamped with the
+    /// given clock, which is usually the clock of the node. The service keeps a clone of
+    /// the clock while introspection is enabled. Passing
+    /// [`ServiceIntrospectionState::Off`] stops publishing events.
+    ///
+    /// [1]: ServiceIntrospectionState
+    #[cfg(not(ros_distro = "humble"))]
+    pub fn configure_introspection(
+        &self,
+        clock: &Clock,
+        qos: QoSProfile,
+        state: ServiceIntrospectionState,
+    ) -> Result<(), RclrsError> {
+        let type_support = <T as rosidl_runtime_rs::Service>::get_type_support()
+            as *const rosidl_service_type_support_t;
+        let mut rcl_service = self.handle.lock();
+        let mut introspection_clock = self.handle.introspection_clock.lock().unwrap();
+        let mut rcl_node = self.handle.node_handle.rcl_node.lock().unwrap();
+        let mut configure = |state: ServiceIntrospectionState| {
+            // SAFETY: No preconditions for this function.
+            let mut publisher_options = unsafe { rcl_publisher_get_default_options() };
+            publisher_options.qos = qos.into();
+            let mut rcl_clock = clock.get_rcl_clock().lock().unwrap();
+            let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+            unsafe {
+                // SAFETY:
+                // * The service, node and clock are valid and locked for the duration of
+                //   this call.
+                // * rcl keeps using the clock afterwards, which is why a clone of it is kept
+                //   in the handle while introspection is enabled.
+                // * The type support matches the one that the service was created with.
+                // * The entity lifecycle mutex is locked, since this creates or destroys the
+                //   publisher of the service events.
+                rcl_service_configure_service_introspection(
+                    &mut *rcl_service,
+                    &mut *rcl_node,
+                    &mut *rcl_clock,
+                    type_support,
+                    publisher_options,
+                    state.into(),
+                )
+                .ok()
+            }
+        };
+        // rcl keeps the clock that introspection was enabled with until it is turned off,
+        // so it needs to be turned off to switch to a different clock.
+        let switches_clock = introspection_clock
+            .as_ref()
+            .is_some_and(|current| !Arc::ptr_eq(current.get_rcl_clock(), clock.get_rcl_clock()));
+        if switches_clock {
+            configure(ServiceIntrospectionState::Off)?;
+            *introspection_clock = None;
+        }
+        configure(state)?;
+        *introspection_clock = (state != ServiceIntrospectionState::Off).then(|| clock.clone());
+        Ok(())
+    }
 }
 
 /// `ServiceOptions are used by [`Node::create_service`][1] to initialize a
@@ -217,6 +364,9 @@ pub struct ServiceOptions<'a> {
     pub name: &'a str,
     /// The quality of service profile for the service.
     pub qos: QoSProfile,
+    /// The callback group of the service. If this is `None`, the default callback
+    /// group of the node is used.
+    pub callback_group: Option<CallbackGroup>,
 }
 
 impl<'a> ServiceOptions<'a> {
@@ -225,6 +375,7 @@ impl<'a> ServiceOptions<'a> {
         Self {
             name,
             qos: QoSProfile::services_default(),
+            callback_group: None,
         }
     }
 }
@@ -234,6 +385,7 @@ impl<'a, T: IntoPrimitiveOptions<'a>> From<T> for ServiceOptions<'a> {
         let primitive = value.into_primitive_options();
         let mut options = Self::new(primitive.name);
         primitive.apply_to(&mut options.qos);
+        options.callback_group = primitive.callback_group.cloned();
         options
     }
 }
@@ -246,9 +398,13 @@ where
         &self.handle
     }
 
+    fn callback_group(&self) -> &CallbackGroup {
+        &self.callback_group
+    }
+
     fn execute(&self) -> Result<(), RclrsError> {
-        let (req, mut req_id) = match self.take_request() {
-            Ok((req, req_id)) => (req, req_id),
+        let (req, info) = match self.take_request_with_info() {
+            Ok((req, info)) => (req, info),
             Err(RclrsError::RclError {
                 code: RclReturnCode::ServiceTakeFailed,
                 ..
@@ -259,21 +415,62 @@ where
             }
             Err(e) => return Err(e),
         };
-        let res = (*self.callback.lock().unwrap())(&req_id, req);
-        let rmw_message = <T::Response as Message>::into_rmw_message(res.into_cow());
-        let handle = &*self.handle.lock();
-        unsafe {
-            // SAFETY: The response type is guaranteed to match the service type by the type system.
-            rcl_send_response(
-                handle,
-                &mut req_id,
-                rmw_message.as_ref() as *const <T::Response as Message>::RmwMsg as *mut _,
-            )
+        let mut req_id = info.request_id();
+        match &*self.callback.lock().unwrap() {
+            AnyServiceCallback::Regular(cb) => {
+                let res = cb(&req_id, req);
+                send_response(&self.handle, &mut req_id, res)
+            }
+            AnyServiceCallback::Async(cb) => {
+                let response = cb(&req_id, req);
+                let handle = Arc::clone(&self.handle);
+                let logger = self.node.logger().clone();
+                self.node.task_queue.spawn(async move {
+                    let res = response.await;
+                    if let Err(err) = send_response(&handle, &mut req_id, res) {
+                        log_error!(&logger, "Failed to send a service response: {err}");
+                    }
+                });
+                Ok(())
+            }
+            AnyServiceCallback::Deferred(cb) => {
+                let responder = ServiceResponder::new(
+                    Arc::clone(&self.handle),
+                    req_id,
+                    self.node.logger().clone(),
+                );
+                cb(info, req, responder);
+                Ok(())
+            }
         }
-        .ok()
     }
 }
 
+fn send_response<Response>(
+    handle: &ServiceHandle,
+    req_id: &mut rmw_request_id_t,
+    res: Response,
+) -> Result<(), RclrsError>
+where
+    Response: Message,
+{
+    let rmw_message = <Response as Message>::into_rmw_message(res.into_cow());
+    let rcl_service = &*handle.lock();
+    let introspection_clock = handle.introspection_clock();
+    let _introspection_clock = introspection_clock
+        .as_ref()
+        .map(|clock| clock.lock().unwrap());
+    unsafe {
+        // SAFETY: The response type is guaranteed to match the service type by the type system.
+        rcl_send_response(
+            rcl_service,
+            req_id,
+            rmw_message.as_ref() as *const <Response as Message>::RmwMsg as *mut _,
+        )
+    }
+    .ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -323,4 +520,103 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn async_service_awaits_another_service() -> Result<(), RclrsError> {
+        use crate::{Context, CreateBasicExecutor, SpinOptions};
+        use std::{sync::atomic::Ordering, time::Duration};
+        use test_msgs::srv;
+
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node("test_async_service")?;
+        let _inner_service =
+            node.create_service::<srv::Empty, _>("async_inner_service", |_, request| request)?;
+        let inner_client = node.create_client::<srv::Empty>("async_inner_service")?;
+        let _outer_service = node.create_async_service::<srv::Empty, _, _>(
+            "async_outer_service",
+            move |_, request| {
+                let inner_client = Arc::clone(&inner_client);
+                async move { inner_client.call_async(&request).await.unwrap() }
+            },
+        )?;
+        let outer_client = node.create_client::<srv::Empty>("async_outer_service")?;
+
+        let done = Arc::new(AtomicBool::new(false));
+        let done_inner = Arc::clone(&done);
+        node.spawn(async move {
+            outer_client
+                .call_async(&srv::Empty_Request::default())
+                .await
+                .unwrap();
+            done_inner.store(true, Ordering::Release);
+        });
+
+        for _ in 0..100 {
+            if done.load(Ordering::Acquire) {
+                break;
+            }
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+        assert!(done.load(Ordering::Acquire));
+
+        Ok(())
+    }
+
+    #[test]
+    fn deferred_service_responds_from_another_thread() -> Result<(), RclrsError> {
+        use crate::{Context, CreateBasicExecutor, SpinOptions};
+        use std::{sync::atomic::Ordering, time::Duration};
+        use test_msgs::srv;
+
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_deferred_service_{}", line!()))?;
+        let service_name = format!("deferred_service_{}", line!());
+        let responders = Arc::new(Mutex::new(Vec::new()));
+        let responders_inner = Arc::clone(&responders);
+        let _service = node.create_deferred_service::<srv::Empty, _>(
+            service_name.as_str(),
+            move |info, _request, responder| {
+                assert_eq!(
+                    info.request_id().sequence_number,
+                    responder.request_id().sequence_number
+                );
+                responders_inner.lock().unwrap().push(responder);
+            },
+        )?;
+        let client = node.create_client::<srv::Empty>(service_name.as_str())?;
+
+        let done = Arc::new(AtomicBool::new(false));
+        let done_inner = Arc::clone(&done);
+        node.spawn(async move {
+            client
+                .call_async(&srv::Empty_Request::default())
+                .await
+                .unwrap();
+            done_inner.store(true, Ordering::Release);
+        });
+
+        for _ in 0..100 {
+            if !responders.lock().unwrap().is_empty() {
+                break;
+            }
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+        // The callback has returned, but no response has been sent yet.
+        let responder = responders.lock().unwrap().pop().unwrap();
+        executor.spin(SpinOptions::default().timeout(Duration::from_millis(50)));
+        assert!(!done.load(Ordering::Acquire));
+
+        std::thread::spawn(move || responder.send(srv::Empty_Response::default()))
+            .join()
+            .unwrap()?;
+        for _ in 0..100 {
+            if done.load(Ordering::Acquire) {
+                break;
+            }
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+        assert!(done.load(Ordering::Acquire));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,73 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use rosidl_runtime_rs::Message;
+
+use crate::{log_warn, rcl_bindings::*, Logger, RclrsError, ServiceHandle};
+
+use super::send_response;
+
+/// Sends the response to a single request received by a deferred service.
+///
+/// A responder is passed to the callback of a service created with
+/// [`NodeState::create_deferred_service`][1]. It can be stored and completed later,
+/// e.g. from another thread or an async task, so that long-running requests do not
+/// block the executor.
+///
+/// Each responder can send exactly one response. If it is dropped without sending one,
+/// the client never receives a response to its request.
+///
+/// [1]: crate::NodeState::create_deferred_service
+pub struct ServiceResponder<Response> {
+    handle: Arc<ServiceHandle>,
+    request_id: rmw_request_id_t,
+    logger: Logger,
+    sent: bool,
+    _response: PhantomData<fn(Response)>,
+}
+
+impl<Response> ServiceResponder<Response>
+where
+    Response: Message,
+{
+    pub(crate) fn new(
+        handle: Arc<ServiceHandle>,
+        request_id: rmw_request_id_t,
+        logger: Logger,
+    ) -> Self {
+        Self {
+            handle,
+            request_id,
+            logger,
+            sent: false,
+            _response: PhantomData,
+        }
+    }
+
+    /// Returns the ID of the request that this responder answers.
+    pub fn request_id(&self) -> &rmw_request_id_t {
+        &self.request_id
+    }
+
+    /// Sends the response to the client.
+    ///
+    /// This works even if the [`Service`][1] itself has been dropped in the meantime.
+    ///
+    /// [1]: crate::Service
+    pub fn send(mut self, response: Response) -> Result<(), RclrsError> {
+        self.sent = true;
+        send_response(&self.handle, &mut self.request_id, response)
+    }
+}
+
+impl<Response> Drop for ServiceResponder<Response> {
+    fn drop(&mut self) {
+        if !self.sent {
+            log_warn!(
+                &self.logger,
+                "A service responder was dropped without sending a response. \
+                The client will not receive a response to request {}.",
+                self.request_id.sequence_number,
+            );
+        }
+    }
+}
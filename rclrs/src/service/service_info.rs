@@ -0,0 +1,81 @@
+use std::time::{Duration, SystemTime};
+
+use crate::rcl_bindings::*;
+
+/// Additional information about a request received by a service.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServiceInfo {
+    /// Time when the request was sent by the client.
+    ///
+    /// The `rmw` layer does not specify the exact point at which the RMW implementation
+    /// must take the timestamp, but it should be taken consistently at the same point in the
+    /// process of sending a request.
+    pub source_timestamp: Option<SystemTime>,
+    /// Time when the request was received by the service.
+    ///
+    /// The `rmw` layer does not specify the exact point at which the RMW implementation
+    /// must take the timestamp, but it should be taken consistently at the same point in the
+    /// process of receiving a request.
+    pub received_timestamp: Option<SystemTime>,
+    /// The GUID of the client's request writer, which identifies the client that sent
+    /// the request.
+    pub writer_guid: [u8; 16],
+    /// Sequence number of the request, set by the client.
+    ///
+    /// Together with the writer GUID, it uniquely identifies the request.
+    pub sequence_number: i64,
+}
+
+impl ServiceInfo {
+    pub(crate) fn from_rmw_service_info(rmw_service_info: &rmw_service_info_t) -> Self {
+        let to_system_time = |ts: i64| match ts {
+            0 => None,
+            ts if ts < 0 => Some(SystemTime::UNIX_EPOCH - Duration::from_nanos(ts.unsigned_abs())),
+            ts => Some(SystemTime::UNIX_EPOCH + Duration::from_nanos(ts.unsigned_abs())),
+        };
+        Self {
+            source_timestamp: to_system_time(rmw_service_info.source_timestamp),
+            received_timestamp: to_system_time(rmw_service_info.received_timestamp),
+            writer_guid: rmw_service_info.request_id.writer_guid.map(|b| b as u8),
+            sequence_number: rmw_service_info.request_id.sequence_number,
+        }
+    }
+
+    /// Returns the request ID, which is what regular service callbacks receive.
+    pub fn request_id(&self) -> rmw_request_id_t {
+        rmw_request_id_t {
+            writer_guid: self.writer_guid.map(|b| b as _),
+            sequence_number: self.sequence_number,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn service_info_round_trips_request_id() {
+        let rmw_service_info = rmw_service_info_t {
+            source_timestamp: -1_000_000_000,
+            received_timestamp: 0,
+            request_id: rmw_request_id_t {
+                writer_guid: [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16],
+                sequence_number: 42,
+            },
+        };
+        let service_info = ServiceInfo::from_rmw_service_info(&rmw_service_info);
+        assert_eq!(
+            service_info.source_timestamp,
+            Some(SystemTime::UNIX_EPOCH - Duration::from_secs(1))
+        );
+        assert_eq!(service_info.received_timestamp, None);
+
+        let request_id = service_info.request_id();
+        assert_eq!(
+            request_id.writer_guid,
+            rmw_service_info.request_id.writer_guid
+        );
+        assert_eq!(request_id.sequence_number, 42);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::rcl_bindings::*;
+
+/// How much a [`Service`][1] or [`Client`][2] reveals about its requests and responses
+/// through service introspection.
+///
+/// When introspection is enabled, the service or client publishes an event on the
+/// `<service name>/_service_event` topic whenever it sends or receives a request or a
+/// response. These events can be displayed with `ros2 service echo`.
+///
+/// Introspection is configured with [`ServiceState::configure_introspection`][3] and
+/// [`ClientState::configure_introspection`][4]. It is not available in ROS 2 Humble.
+///
+/// [1]: crate::Service
+/// [2]: crate::Client
+/// [3]: crate::ServiceState::configure_introspection
+/// [4]: crate::ClientState::configure_introspection
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ServiceIntrospectionState {
+    /// No service events are published.
+    #[default]
+    Off,
+    /// Service events are published, but only with metadata such as the kind of
+    /// event, the time stamp and the sequence number.
+    Metadata,
+    /// Service events are published with metadata and the content of the request or
+    /// response.
+    Contents,
+}
+
+impl From<ServiceIntrospectionState> for rcl_service_introspection_state_t {
+    fn from(state: ServiceIntrospectionState) -> Self {
+        match state {
+            ServiceIntrospectionState::Off => {
+                rcl_service_introspection_state_t::RCL_SERVICE_INTROSPECTION_OFF
+            }
+            ServiceIntrospectionState::Metadata => {
+                rcl_service_introspection_state_t::RCL_SERVICE_INTROSPECTION_METADATA
+            }
+            ServiceIntrospectionState::Contents => {
+                rcl_service_introspection_state_t::RCL_SERVICE_INTROSPECTION_CONTENTS
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
+    use test_msgs::srv;
+
+    #[test]
+    fn test_service_introspection_publishes_events() -> Result<(), RclrsError> {
+        let executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_service_introspection_{}", line!()))?;
+        let service_name = format!("/test_service_introspection_service_{}", line!());
+        let event_topic = format!("{service_name}/_service_event");
+
+        let service = node.create_service::<srv::Empty, _>(service_name.as_str(), |_, _| {
+            srv::Empty_Response::default()
+        })?;
+        let client = node.create_client::<srv::Empty>(service_name.as_str())?;
+        assert_eq!(node.count_publishers(&event_topic)?, 0);
+
+        let clock = node.get_clock();
+        service.configure_introspection(
+            &clock,
+            QoSProfile::system_default(),
+            ServiceIntrospectionState::Contents,
+        )?;
+        client.configure_introspection(
+            &clock,
+            QoSProfile::system_default(),
+            ServiceIntrospectionState::Metadata,
+        )?;
+        let wait_for_publishers = |count: usize| -> Result<(), RclrsError> {
+            let start = Instant::now();
+            while node.count_publishers(&event_topic)? != count {
+                assert!(start.elapsed() < Duration::from_secs(10));
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Ok(())
+        };
+        wait_for_publishers(2)?;
+
+        service.configure_introspection(
+            &clock,
+            QoSProfile::system_default(),
+            ServiceIntrospectionState::Off,
+        )?;
+        client.configure_introspection(
+            &clock,
+            QoSProfile::system_default(),
+            ServiceIntrospectionState::Off,
+        )?;
+        wait_for_publishers(0)
+    }
+
+    #[test]
+    fn test_service_introspection_keeps_clock() -> Result<(), RclrsError> {
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_service_introspection_{}", line!()))?;
+        let service_name = format!("/test_service_introspection_service_{}", line!());
+
+        let service = node.create_service::<srv::Empty, _>(service_name.as_str(), |_, _| {
+            srv::Empty_Response::default()
+        })?;
+        let client = node.create_client::<srv::Empty>(service_name.as_str())?;
+        // The clocks are dropped right away, so the service and the client need to keep
+        // them for stamping the events.
+        service.configure_introspection(
+            &Clock::system(),
+            QoSProfile::system_default(),
+            ServiceIntrospectionState::Contents,
+        )?;
+        client.configure_introspection(
+            &Clock::steady(),
+            QoSProfile::system_default(),
+            ServiceIntrospectionState::Contents,
+        )?;
+        // Switching to another clock while introspection is enabled.
+        client.configure_introspection(
+            &Clock::system(),
+            QoSProfile::system_default(),
+            ServiceIntrospectionState::Metadata,
+        )?;
+
+        let done = Arc::new(AtomicBool::new(false));
+        let done_inner = Arc::clone(&done);
+        node.spawn(async move {
+            client
+                .call_async(&srv::Empty_Request::default())
+                .await
+                .unwrap();
+            done_inner.store(true, Ordering::Release);
+        });
+        let start = Instant::now();
+        while !done.load(Ordering::Acquire) {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+        Ok(())
+    }
+}
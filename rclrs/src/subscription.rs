@@ -1,7 +1,9 @@
 use std::{
-    ffi::{CStr, CString},
+    ffi::{c_void, CStr, CString},
+    future::Future,
     marker::PhantomData,
     sync::{atomic::AtomicBool, Arc, Mutex, MutexGuard},
+    time::Duration,
 };
 
 use rosidl_runtime_rs::{Message, RmwMessage};
@@ -9,14 +11,19 @@ use rosidl_runtime_rs::{Message, RmwMessage};
 use crate::{
     error::{RclReturnCode, ToResult},
     qos::QoSProfile,
+    qos_supports_intra_process,
     rcl_bindings::*,
-    IntoPrimitiveOptions, Node, NodeHandle, RclrsError, ENTITY_LIFECYCLE_MUTEX,
+    wait_for_graph_condition, wait_for_graph_condition_async, CallbackGroup, ContextHandle,
+    GuardCondition, IntoPrimitiveOptions, IntraProcessBuffer, Node, NodeHandle, RclrsError,
+    SerializedMessage, ENTITY_LIFECYCLE_MUTEX,
 };
 
 mod callback;
+mod content_filter;
 mod message_info;
 mod readonly_loaned_message;
 pub use callback::*;
+pub use content_filter::*;
 pub use message_info::*;
 pub use readonly_loaned_message::*;
 
@@ -36,9 +43,210 @@ pub struct SubscriptionHandle {
 }
 
 impl SubscriptionHandle {
+    /// Creates a subscription for messages of the given type support.
+    pub(crate) fn new(
+        node_handle: Arc<NodeHandle>,
+        type_support: *const rosidl_message_type_support_t,
+        topic: &str,
+        qos: QoSProfile,
+        content_filter: Option<&ContentFilter>,
+    ) -> Result<Self, RclrsError> {
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut rcl_subscription = unsafe { rcl_get_zero_initialized_subscription() };
+        let topic_c_string = CString::new(topic).map_err(|err| RclrsError::StringContainsNul {
+            err,
+            s: topic.into(),
+        })?;
+
+        // SAFETY: No preconditions for this function.
+        let mut rcl_subscription_options = unsafe { rcl_subscription_get_default_options() };
+        rcl_subscription_options.qos = qos.into();
+        if let Some(content_filter) = content_filter {
+            let c_strings = content_filter.to_c_strings()?;
+            unsafe {
+                // SAFETY: The strings are copied by this function, so they can be dropped
+                // afterwards. The allocated copies are freed by rcl_subscription_options_fini.
+                rcl_subscription_options_set_content_filter_options(
+                    c_strings.expression.as_ptr(),
+                    c_strings.parameter_ptrs.len(),
+                    c_strings.parameter_ptrs.as_ptr() as *mut _,
+                    &mut rcl_subscription_options,
+                )
+                .ok()?;
+            }
+        }
+
+        let init_result = {
+            let rcl_node = node_handle.rcl_node.lock().unwrap();
+            let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+            unsafe {
+                // SAFETY:
+                // * The rcl_subscription is zero-initialized as mandated by this function.
+                // * The rcl_node is kept alive by the NodeHandle because it is a dependency of the subscription.
+                // * The topic name and the options are copied by this function, so they can be dropped afterwards.
+                // * The entity lifecycle mutex is locked to protect against the risk of global
+                //   variables in the rmw implementation being unsafely modified during cleanup.
+                rcl_subscription_init(
+                    &mut rcl_subscription,
+                    &*rcl_node,
+                    type_support,
+                    topic_c_string.as_ptr(),
+                    &rcl_subscription_options,
+                )
+                .ok()
+            }
+        };
+        // SAFETY: The options were initialized above, and the subscription holds its own
+        // copy of the content filter. This only fails for invalid arguments.
+        unsafe {
+            rcl_subscription_options_fini(&mut rcl_subscription_options);
+        }
+        init_result?;
+
+        Ok(Self {
+            rcl_subscription: Mutex::new(rcl_subscription),
+            node_handle,
+            in_use_by_wait_set: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
     pub(crate) fn lock(&self) -> MutexGuard<rcl_subscription_t> {
         self.rcl_subscription.lock().unwrap()
     }
+
+    /// See [`SubscriptionState::get_publisher_count`].
+    pub(crate) fn get_publisher_count(&self) -> Result<usize, RclrsError> {
+        let mut publisher_count = 0;
+        // SAFETY: No preconditions for the function called.
+        unsafe { rcl_subscription_get_publisher_count(&*self.lock(), &mut publisher_count).ok()? };
+        Ok(publisher_count)
+    }
+
+    /// See [`SubscriptionState::wait_for_publishers`].
+    pub(crate) fn wait_for_publishers(
+        &self,
+        n: usize,
+        timeout: Option<Duration>,
+    ) -> Result<bool, RclrsError> {
+        wait_for_graph_condition(&self.node_handle, timeout, || {
+            Ok(self.get_publisher_count()? >= n)
+        })
+    }
+
+    /// See [`SubscriptionState::is_content_filter_enabled`].
+    pub(crate) fn is_content_filter_enabled(&self) -> bool {
+        // SAFETY: No preconditions for the function called.
+        unsafe { rcl_subscription_is_cft_enabled(&*self.lock()) }
+    }
+
+    /// See [`SubscriptionState::set_content_filter`].
+    pub(crate) fn set_content_filter(
+        &self,
+        content_filter: &ContentFilter,
+    ) -> Result<(), RclrsError> {
+        let c_strings = content_filter.to_c_strings()?;
+        let rcl_subscription = self.lock();
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut options = unsafe { rcl_get_zero_initialized_subscription_content_filter_options() };
+        unsafe {
+            // SAFETY: The options are zero-initialized as mandated by this function, and
+            // the strings are copied by it.
+            rcl_subscription_content_filter_options_init(
+                &*rcl_subscription,
+                c_strings.expression.as_ptr(),
+                c_strings.parameter_ptrs.len(),
+                c_strings.parameter_ptrs.as_ptr() as *mut _,
+                &mut options,
+            )
+            .ok()?;
+        }
+        // SAFETY: The options were initialized above.
+        let result = unsafe { rcl_subscription_set_content_filter(&*rcl_subscription, &options) };
+        // SAFETY: The options were initialized with the same subscription.
+        unsafe {
+            rcl_subscription_content_filter_options_fini(&*rcl_subscription, &mut options).ok()?;
+        }
+        result.ok()
+    }
+
+    /// See [`SubscriptionState::get_content_filter`].
+    pub(crate) fn get_content_filter(&self) -> Result<ContentFilter, RclrsError> {
+        let rcl_subscription = self.lock();
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut options = unsafe { rcl_get_zero_initialized_subscription_content_filter_options() };
+        unsafe {
+            // SAFETY: The options are zero-initialized as mandated by this function.
+            rcl_subscription_get_content_filter(&*rcl_subscription, &mut options).ok()?;
+        }
+        // SAFETY: The options were filled in by rcl above.
+        let content_filter =
+            unsafe { ContentFilter::from_rmw(&options.rmw_subscription_content_filter_options) };
+        // SAFETY: The options were initialized for the same subscription.
+        unsafe {
+            rcl_subscription_content_filter_options_fini(&*rcl_subscription, &mut options).ok()?;
+        }
+        Ok(content_filter)
+    }
+
+    pub(crate) fn context_handle(&self) -> &Arc<ContextHandle> {
+        &self.node_handle.context_handle
+    }
+
+    /// See [`SubscriptionState::topic_name`].
+    pub(crate) fn topic_name(&self) -> String {
+        // SAFETY: No preconditions for the function used
+        // The unsafe variables get converted to safe types before being returned
+        unsafe {
+            let raw_topic_pointer = rcl_subscription_get_topic_name(&*self.lock());
+            CStr::from_ptr(raw_topic_pointer)
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+
+    /// Takes a message into the given buffer, which must be a message of the type support
+    /// that the subscription was created with.
+    ///
+    /// # Safety
+    ///
+    /// The message pointer must point to a valid, initialized message of the right type.
+    pub(crate) unsafe fn take_raw(
+        &self,
+        rmw_message: *mut c_void,
+    ) -> Result<MessageInfo, RclrsError> {
+        let mut message_info = rmw_get_zero_initialized_message_info();
+        // SAFETY: The first two pointers are valid/initialized, and do not need to be valid
+        // beyond the function call.
+        // The latter two pointers are explicitly allowed to be NULL.
+        rcl_take(
+            &*self.lock(),
+            rmw_message,
+            &mut message_info,
+            std::ptr::null_mut(),
+        )
+        .ok()?;
+        Ok(MessageInfo::from_rmw_message_info(&message_info))
+    }
+
+    /// Takes a message without deserializing it.
+    pub(crate) fn take_serialized(
+        &self,
+        serialized_message: &mut SerializedMessage,
+    ) -> Result<MessageInfo, RclrsError> {
+        let mut message_info = unsafe { rmw_get_zero_initialized_message_info() };
+        unsafe {
+            // SAFETY: The serialized message is initialized, so it can be resized by this function.
+            // The last pointer is explicitly allowed to be NULL.
+            rcl_take_serialized_message(
+                &*self.lock(),
+                serialized_message.as_rmw_mut(),
+                &mut message_info,
+                std::ptr::null_mut(),
+            )
+            .ok()?;
+        }
+        Ok(MessageInfo::from_rmw_message_info(&message_info))
+    }
 }
 
 impl Drop for SubscriptionHandle {
@@ -58,8 +266,20 @@ impl Drop for SubscriptionHandle {
 pub trait SubscriptionBase: Send + Sync {
     /// Internal function to get a reference to the `rcl` handle.
     fn handle(&self) -> &SubscriptionHandle;
+    /// Returns the callback group that the callback belongs to.
+    fn callback_group(&self) -> &CallbackGroup;
     /// Tries to take a new message and run the callback with it.
     fn execute(&self) -> Result<(), RclrsError>;
+    /// Returns the guard condition that is triggered when the subscription receives a
+    /// message through intra-process communication.
+    ///
+    /// If this is `Some`, the guard condition is added to a [`WaitSet`][1] together with
+    /// the subscription.
+    ///
+    /// [1]: crate::WaitSet
+    fn intra_process_guard_condition(&self) -> Option<&Arc<GuardCondition>> {
+        None
+    }
 }
 
 /// Struct for receiving messages of type `T`.
@@ -96,10 +316,12 @@ where
     pub(crate) handle: Arc<SubscriptionHandle>,
     /// The callback function that runs when a message was received.
     pub callback: Mutex<AnySubscriptionCallback<T>>,
+    callback_group: CallbackGroup,
     /// Ensure the parent node remains alive as long as the subscription is held.
-    /// This implementation will change in the future.
-    #[allow(unused)]
+    /// The node also runs the futures of async callbacks.
     node: Node,
+    /// Set if the subscription receives messages directly from publishers in the same context.
+    intra_process: Option<Arc<IntraProcessBuffer<T>>>,
     message: PhantomData<T>,
 }
 
@@ -108,61 +330,64 @@ where
     T: Message,
 {
     /// Creates a new subscription.
-    pub(crate) fn new<'a, Args>(
+    pub(crate) fn new<'a>(
         node: &Node,
         options: impl Into<SubscriptionOptions<'a>>,
-        callback: impl SubscriptionCallback<T, Args>,
+        callback: AnySubscriptionCallback<T>,
     ) -> Result<Self, RclrsError>
     // This uses pub(crate) visibility to avoid instantiating this struct outside
     // [`Node::create_subscription`], see the struct's documentation for the rationale
     where
         T: Message,
     {
-        let SubscriptionOptions { topic, qos } = options.into();
-        // SAFETY: Getting a zero-initialized value is always safe.
-        let mut rcl_subscription = unsafe { rcl_get_zero_initialized_subscription() };
+        let SubscriptionOptions {
+            topic,
+            qos,
+            callback_group,
+            content_filter,
+            intra_process,
+        } = options.into();
+        // Loaned and serialized messages can only be taken from the middleware.
+        let use_intra_process = intra_process.unwrap_or(node.use_intra_process_comms())
+            && qos_supports_intra_process(&qos)
+            && content_filter.is_none()
+            && !matches!(
+                callback,
+                AnySubscriptionCallback::Loaned(_)
+                    | AnySubscriptionCallback::LoanedWithMessageInfo(_)
+                    | AnySubscriptionCallback::Serialized(_)
+            );
         let type_support =
             <T as Message>::RmwMsg::get_type_support() as *const rosidl_message_type_support_t;
-        let topic_c_string = CString::new(topic).map_err(|err| RclrsError::StringContainsNul {
-            err,
-            s: topic.into(),
-        })?;
-
-        // SAFETY: No preconditions for this function.
-        let mut rcl_subscription_options = unsafe { rcl_subscription_get_default_options() };
-        rcl_subscription_options.qos = qos.into();
-
-        {
-            let rcl_node = node.handle.rcl_node.lock().unwrap();
-            let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
-            unsafe {
-                // SAFETY:
-                // * The rcl_subscription is zero-initialized as mandated by this function.
-                // * The rcl_node is kept alive by the NodeHandle because it is a dependency of the subscription.
-                // * The topic name and the options are copied by this function, so they can be dropped afterwards.
-                // * The entity lifecycle mutex is locked to protect against the risk of global
-                //   variables in the rmw implementation being unsafely modified during cleanup.
-                rcl_subscription_init(
-                    &mut rcl_subscription,
-                    &*rcl_node,
-                    type_support,
-                    topic_c_string.as_ptr(),
-                    &rcl_subscription_options,
-                )
-                .ok()?;
-            }
-        }
-
-        let handle = Arc::new(SubscriptionHandle {
-            rcl_subscription: Mutex::new(rcl_subscription),
-            node_handle: Arc::clone(&node.handle),
-            in_use_by_wait_set: Arc::new(AtomicBool::new(false)),
-        });
+        let handle = Arc::new(SubscriptionHandle::new(
+            Arc::clone(&node.handle),
+            type_support,
+            topic,
+            qos,
+            content_filter.as_ref(),
+        )?);
+        let intra_process = if use_intra_process {
+            let buffer = Arc::new(IntraProcessBuffer::new(
+                Arc::clone(handle.context_handle()),
+                &qos,
+            ));
+            handle.context_handle().intra_process.add_subscription(
+                handle.topic_name(),
+                qos,
+                &buffer,
+            );
+            Some(buffer)
+        } else {
+            None
+        };
 
         Ok(Self {
             handle,
-            callback: Mutex::new(callback.into_callback()),
+            callback: Mutex::new(callback),
+            callback_group: callback_group
+                .unwrap_or_else(|| Arc::clone(node.default_callback_group())),
             node: Arc::clone(node),
+            intra_process,
             message: PhantomData,
         })
     }
@@ -172,14 +397,77 @@ where
     /// This returns the topic name after remapping, so it is not necessarily the
     /// topic name which was used when creating the subscription.
     pub fn topic_name(&self) -> String {
-        // SAFETY: No preconditions for the function used
-        // The unsafe variables get converted to safe types before being returned
-        unsafe {
-            let raw_topic_pointer = rcl_subscription_get_topic_name(&*self.handle.lock());
-            CStr::from_ptr(raw_topic_pointer)
-                .to_string_lossy()
-                .into_owned()
-        }
+        self.handle.topic_name()
+    }
+
+    /// Returns the number of publishers that are matched with this subscription.
+    pub fn get_publisher_count(&self) -> Result<usize, RclrsError> {
+        self.handle.get_publisher_count()
+    }
+
+    /// Blocks until at least `n` publishers are matched with this subscription.
+    ///
+    /// Returns `true` once they are matched, and `false` if the timeout elapsed or the
+    /// context shut down before that. A timeout of `None` waits indefinitely.
+    pub fn wait_for_publishers(
+        &self,
+        n: usize,
+        timeout: Option<Duration>,
+    ) -> Result<bool, RclrsError> {
+        self.handle.wait_for_publishers(n, timeout)
+    }
+
+    /// Returns a future that completes once at least `n` publishers are matched with
+    /// this subscription.
+    ///
+    /// The matches are checked again whenever the executor of the subscription's node
+    /// notices that the ROS graph changed, so the executor must be [spinning][1] while
+    /// the future is awaited. The output is the same as for
+    /// [`SubscriptionState::wait_for_publishers`].
+    ///
+    /// [1]: crate::Executor::spin
+    pub fn wait_for_publishers_async(
+        self: &Arc<Self>,
+        n: usize,
+        timeout: Option<Duration>,
+    ) -> impl Future<Output = Result<bool, RclrsError>> + Send + 'static {
+        let subscription = Arc::clone(self);
+        wait_for_graph_condition_async(&self.node, timeout, move || {
+            Ok(subscription.get_publisher_count()? >= n)
+        })
+    }
+
+    /// Returns whether the middleware applies a [content filter][1] to the messages of this
+    /// subscription.
+    ///
+    /// This is `false` if no filter is set, or if the RMW implementation does not support
+    /// content filters, in which case all messages are received.
+    ///
+    /// [1]: ContentFilter
+    pub fn is_content_filter_enabled(&self) -> bool {
+        self.handle.is_content_filter_enabled()
+    }
+
+    /// Replaces the [content filter][1] of this subscription.
+    ///
+    /// An empty expression removes the filter. This fails with
+    /// [`RclReturnCode::Unsupported`][2] if the RMW implementation does not support content
+    /// filters.
+    ///
+    /// [1]: ContentFilter
+    /// [2]: crate::RclReturnCode::Unsupported
+    pub fn set_content_filter(&self, content_filter: &ContentFilter) -> Result<(), RclrsError> {
+        self.handle.set_content_filter(content_filter)
+    }
+
+    /// Returns the [content filter][1] of this subscription.
+    ///
+    /// This fails if no filter is set or the RMW implementation does not support content
+    /// filters.
+    ///
+    /// [1]: ContentFilter
+    pub fn get_content_filter(&self) -> Result<ContentFilter, RclrsError> {
+        self.handle.get_content_filter()
     }
 
     /// Fetches a new message.
@@ -187,7 +475,11 @@ where
     /// When there is no new message, this will return a
     /// [`SubscriptionTakeFailed`][1].
     ///
+    /// If the subscription uses [intra-process communication][2], messages from publishers
+    /// in the same context are returned before those from the middleware.
+    ///
     /// [1]: crate::RclrsError
+    /// [2]: SubscriptionOptions::intra_process
     //
     // ```text
     // +-------------+
@@ -205,6 +497,9 @@ where
     // +-------------+
     // ```
     pub fn take(&self) -> Result<(T, MessageInfo), RclrsError> {
+        if let Some(taken) = self.take_intra_process() {
+            return Ok(taken);
+        }
         let mut rmw_message = <T as Message>::RmwMsg::default();
         let message_info = self.take_inner(&mut rmw_message)?;
         Ok((T::from_rmw_message(rmw_message), message_info))
@@ -214,6 +509,9 @@ where
     ///
     /// This can be more efficient for messages containing large arrays.
     pub fn take_boxed(&self) -> Result<(Box<T>, MessageInfo), RclrsError> {
+        if let Some((message, message_info)) = self.take_intra_process() {
+            return Ok((Box::new(message), message_info));
+        }
         let mut rmw_message = Box::<<T as Message>::RmwMsg>::default();
         let message_info = self.take_inner(&mut *rmw_message)?;
         // TODO: This will still use the stack in general. Change signature of
@@ -227,21 +525,39 @@ where
         &self,
         rmw_message: &mut <T as Message>::RmwMsg,
     ) -> Result<MessageInfo, RclrsError> {
-        let mut message_info = unsafe { rmw_get_zero_initialized_message_info() };
-        let rcl_subscription = &mut *self.handle.lock();
-        unsafe {
-            // SAFETY: The first two pointers are valid/initialized, and do not need to be valid
-            // beyond the function call.
-            // The latter two pointers are explicitly allowed to be NULL.
-            rcl_take(
-                rcl_subscription,
-                rmw_message as *mut <T as Message>::RmwMsg as *mut _,
-                &mut message_info,
-                std::ptr::null_mut(),
-            )
-            .ok()?
-        };
-        Ok(MessageInfo::from_rmw_message_info(&message_info))
+        loop {
+            // SAFETY: The message has the type of the subscription, as guaranteed by the type system.
+            let message_info = unsafe {
+                self.handle
+                    .take_raw(rmw_message as *mut <T as Message>::RmwMsg as *mut _)
+            }?;
+            // Messages of intra-process publishers were already delivered directly.
+            let delivered = self.intra_process.is_some()
+                && self
+                    .handle
+                    .context_handle()
+                    .intra_process
+                    .is_intra_process_publisher(&message_info.publisher_gid);
+            if !delivered {
+                return Ok(message_info);
+            }
+        }
+    }
+
+    fn take_intra_process(&self) -> Option<(T, MessageInfo)> {
+        self.intra_process.as_ref()?.pop()
+    }
+
+    /// Fetches a new message without deserializing it.
+    ///
+    /// When there is no new message, this will return a
+    /// [`SubscriptionTakeFailed`][1].
+    ///
+    /// [1]: crate::RclrsError
+    pub fn take_serialized(&self) -> Result<(SerializedMessage, MessageInfo), RclrsError> {
+        let mut serialized_message = SerializedMessage::new();
+        let message_info = self.handle.take_serialized(&mut serialized_message)?;
+        Ok((serialized_message, message_info))
     }
 
     /// Obtains a read-only handle to a message owned by the middleware.
@@ -290,6 +606,29 @@ pub struct SubscriptionOptions<'a> {
     pub topic: &'a str,
     /// The quality of service settings for the subscription.
     pub qos: QoSProfile,
+    /// The callback group of the subscription. If this is `None`, the default callback
+    /// group of the node is used.
+    pub callback_group: Option<CallbackGroup>,
+    /// A filter that the middleware applies to the messages, if it supports
+    /// [content filters][1].
+    ///
+    /// [1]: ContentFilter
+    pub content_filter: Option<ContentFilter>,
+    /// Whether the subscription receives messages directly from publishers in the same
+    /// [`Context`][1] instead of through the middleware. If this is `None`, the setting of
+    /// the node is used, see [`IntoNodeOptions::use_intra_process_comms`][2].
+    ///
+    /// Only publishers that use intra-process communication themselves deliver messages
+    /// this way. Messages are queued up to the depth of the QoS history, like in the
+    /// middleware.
+    ///
+    /// Subscriptions with transient local durability, a content filter, or a callback that
+    /// takes loaned or serialized messages always use the middleware, and so do dynamic and
+    /// generic subscriptions.
+    ///
+    /// [1]: crate::Context
+    /// [2]: crate::IntoNodeOptions::use_intra_process_comms
+    pub intra_process: Option<bool>,
 }
 
 impl<'a> SubscriptionOptions<'a> {
@@ -298,8 +637,23 @@ impl<'a> SubscriptionOptions<'a> {
         Self {
             topic,
             qos: QoSProfile::topics_default(),
+            callback_group: None,
+            content_filter: None,
+            intra_process: None,
         }
     }
+
+    /// Sets the filter that the middleware applies to the messages.
+    pub fn content_filter(mut self, content_filter: ContentFilter) -> Self {
+        self.content_filter = Some(content_filter);
+        self
+    }
+
+    /// Sets whether the subscription uses intra-process communication.
+    pub fn intra_process(mut self, enable: bool) -> Self {
+        self.intra_process = Some(enable);
+        self
+    }
 }
 
 impl<'a, T: IntoPrimitiveOptions<'a>> From<T> for SubscriptionOptions<'a> {
@@ -307,6 +661,7 @@ impl<'a, T: IntoPrimitiveOptions<'a>> From<T> for SubscriptionOptions<'a> {
         let primitive = value.into_primitive_options();
         let mut options = Self::new(primitive.name);
         primitive.apply_to(&mut options.qos);
+        options.callback_group = primitive.callback_group.cloned();
         options
     }
 }
@@ -319,6 +674,10 @@ where
         &self.handle
     }
 
+    fn callback_group(&self) -> &CallbackGroup {
+        &self.callback_group
+    }
+
     fn execute(&self) -> Result<(), RclrsError> {
         let evaluate = || {
             match &mut *self.callback.lock().unwrap() {
@@ -346,6 +705,14 @@ where
                     let (msg, msg_info) = self.take_loaned()?;
                     cb(msg, msg_info)
                 }
+                AnySubscriptionCallback::Serialized(cb) => {
+                    let (msg, msg_info) = self.take_serialized()?;
+                    cb(msg, msg_info)
+                }
+                AnySubscriptionCallback::Async(cb) => {
+                    let (msg, _) = self.take()?;
+                    self.node.task_queue.spawn(cb(msg));
+                }
             }
             Ok(())
         };
@@ -364,6 +731,12 @@ where
             other => other,
         }
     }
+
+    fn intra_process_guard_condition(&self) -> Option<&Arc<GuardCondition>> {
+        self.intra_process
+            .as_ref()
+            .map(|buffer| &buffer.guard_condition)
+    }
 }
 
 #[cfg(test)]
@@ -478,4 +851,159 @@ mod tests {
             assert!(start_time.elapsed() < std::time::Duration::from_secs(10));
         }
     }
+
+    #[test]
+    fn test_serialized_publish_and_take() {
+        use crate::*;
+        use std::sync::Mutex;
+
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor
+            .create_node(&format!("test_serialized_publish_and_take_{}", line!()))
+            .unwrap();
+        let qos = QoSProfile::default().keep_all().reliable();
+
+        let received_serialized = Arc::new(Mutex::new(Vec::new()));
+        let _serialized_subscription = node
+            .create_serialized_subscription::<msg::Strings, _>("serialized_topic".qos(qos), {
+                let received_serialized = Arc::clone(&received_serialized);
+                move |msg: SerializedMessage, _info: MessageInfo| {
+                    received_serialized.lock().unwrap().push(msg)
+                }
+            })
+            .unwrap();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let _subscription = node
+            .create_subscription::<msg::Strings, _>("serialized_topic".qos(qos), {
+                let received = Arc::clone(&received);
+                move |msg: msg::Strings| received.lock().unwrap().push(msg)
+            })
+            .unwrap();
+        let publisher = node
+            .create_publisher::<msg::Strings>("serialized_topic".qos(qos))
+            .unwrap();
+
+        let message = msg::Strings {
+            string_value: "Hello".into(),
+            ..Default::default()
+        };
+        publisher.publish(&message).unwrap();
+        let serialized = SerializedMessage::serialize(&message).unwrap();
+        publisher.publish_serialized(&serialized).unwrap();
+
+        let start_time = std::time::Instant::now();
+        while received_serialized.lock().unwrap().len() < 2 || received.lock().unwrap().len() < 2 {
+            executor.spin(SpinOptions::spin_once().timeout(std::time::Duration::from_millis(10)));
+            assert!(start_time.elapsed() < std::time::Duration::from_secs(10));
+        }
+        for serialized in received_serialized.lock().unwrap().iter() {
+            assert_eq!(serialized.deserialize::<msg::Strings>().unwrap(), message);
+        }
+        for received_message in received.lock().unwrap().iter() {
+            assert_eq!(received_message, &message);
+        }
+    }
+
+    #[test]
+    fn test_wait_for_matched_publishers_and_subscriptions() {
+        use crate::*;
+        use std::{sync::Mutex, time::Duration};
+
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor
+            .create_node(&format!("test_wait_for_matched_{}", line!()))
+            .unwrap();
+        let topic = format!("test_wait_for_matched_topic_{}", line!());
+
+        let subscription = node
+            .create_subscription::<msg::Empty, _>(topic.as_str(), |_: msg::Empty| {})
+            .unwrap();
+        assert_eq!(subscription.get_publisher_count().unwrap(), 0);
+        assert!(!subscription
+            .wait_for_publishers(1, Some(Duration::from_millis(50)))
+            .unwrap());
+
+        // The future is woken up by the graph change when the publisher is created.
+        let matched = Arc::new(Mutex::new(None));
+        let matched_inner = Arc::clone(&matched);
+        let wait_for_publishers = subscription.wait_for_publishers_async(1, None);
+        node.spawn(async move {
+            *matched_inner.lock().unwrap() = Some(wait_for_publishers.await);
+        });
+        executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        assert!(matched.lock().unwrap().is_none());
+
+        let publisher = node.create_publisher::<msg::Empty>(topic.as_str()).unwrap();
+        assert!(publisher
+            .wait_for_subscribers(1, Some(Duration::from_secs(10)))
+            .unwrap());
+        let start_time = std::time::Instant::now();
+        while matched.lock().unwrap().is_none() {
+            assert!(start_time.elapsed() < Duration::from_secs(10));
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        }
+        assert_eq!(matched.lock().unwrap().take(), Some(Ok(true)));
+        assert_eq!(subscription.get_publisher_count().unwrap(), 1);
+        assert_eq!(publisher.get_subscription_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_content_filtered_subscription() {
+        use crate::*;
+        use std::{sync::Mutex, time::Duration};
+
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor
+            .create_node(&format!("test_content_filter_{}", line!()))
+            .unwrap();
+        let topic = format!("test_content_filter_topic_{}", line!());
+        let qos = QoSProfile::default().keep_all().reliable();
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let options = SubscriptionOptions::from(topic.as_str().qos(qos))
+            .content_filter(ContentFilter::new("int32_value > %0", ["1"]));
+        let subscription = node
+            .create_subscription::<msg::BasicTypes, _>(options, {
+                let received = Arc::clone(&received);
+                move |msg: msg::BasicTypes| received.lock().unwrap().push(msg.int32_value)
+            })
+            .unwrap();
+        if !subscription.is_content_filter_enabled() {
+            // The RMW implementation does not support content filters.
+            return;
+        }
+        assert_eq!(
+            subscription.get_content_filter().unwrap(),
+            ContentFilter::new("int32_value > %0", ["1"])
+        );
+
+        let publisher = node
+            .create_publisher::<msg::BasicTypes>(topic.as_str().qos(qos))
+            .unwrap();
+        assert!(publisher
+            .wait_for_subscribers(1, Some(Duration::from_secs(10)))
+            .unwrap());
+        for int32_value in 0..4 {
+            publisher
+                .publish(msg::BasicTypes {
+                    int32_value,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        let start_time = std::time::Instant::now();
+        while received.lock().unwrap().len() < 2 {
+            executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+            assert!(start_time.elapsed() < Duration::from_secs(10));
+        }
+        assert_eq!(*received.lock().unwrap(), [2, 3]);
+
+        subscription
+            .set_content_filter(&ContentFilter::new("int32_value < %0", ["1"]))
+            .unwrap();
+        assert_eq!(
+            subscription.get_content_filter().unwrap().expression,
+            "int32_value < %0"
+        );
+    }
 }
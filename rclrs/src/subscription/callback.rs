@@ -1,7 +1,8 @@
+use futures::future::BoxFuture;
 use rosidl_runtime_rs::Message;
 
 use super::MessageInfo;
-use crate::ReadOnlyLoanedMessage;
+use crate::{ReadOnlyLoanedMessage, SerializedMessage};
 
 /// A trait for allowed callbacks for subscriptions.
 ///
@@ -37,6 +38,16 @@ where
     /// A callback with the loaned message and the message info as arguments.
     #[allow(clippy::type_complexity)]
     LoanedWithMessageInfo(Box<dyn for<'a> FnMut(ReadOnlyLoanedMessage<'a, T>, MessageInfo) + Send>),
+    /// A callback with the serialized message and the message info as arguments.
+    ///
+    /// This is created by [`NodeState::create_serialized_subscription`][1].
+    ///
+    /// [1]: crate::NodeState::create_serialized_subscription
+    Serialized(Box<dyn FnMut(SerializedMessage, MessageInfo) + Send>),
+    /// An async callback with only the message as an argument.
+    ///
+    /// The returned future is run by the executor of the node.
+    Async(Box<dyn FnMut(T) -> BoxFuture<'static, ()> + Send>),
 }
 
 // We need one implementation per arity. This was inspired by Bevy's systems.
@@ -0,0 +1,100 @@
+use std::{
+    ffi::{c_char, CStr, CString},
+    slice,
+};
+
+use crate::{rcl_bindings::*, RclrsError};
+
+/// A filter that is applied to the messages of a subscription by the middleware.
+///
+/// The expression uses the SQL-like syntax of DDS content-filtered topics, e.g.
+/// `"data > %0 AND id = %1"`, where `%n` refers to the `n`-th parameter. Messages that do
+/// not match the filter are dropped before they reach the subscription, which saves
+/// deserializing and processing them.
+///
+/// Not every RMW implementation supports content filters. Use
+/// [`SubscriptionState::is_content_filter_enabled`][1] to check whether the filter is
+/// applied by the middleware.
+///
+/// [1]: crate::SubscriptionState::is_content_filter_enabled
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ContentFilter {
+    /// The filter expression, e.g. `"data > %0"`.
+    ///
+    /// An empty expression removes the filter.
+    pub expression: String,
+    /// The parameters that are substituted for `%0`, `%1`, … in the expression.
+    ///
+    /// Strings have to be quoted, e.g. `"'hello'"`.
+    pub parameters: Vec<String>,
+}
+
+impl ContentFilter {
+    /// Creates a filter with the given expression and parameters.
+    pub fn new(
+        expression: impl Into<String>,
+        parameters: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            expression: expression.into(),
+            parameters: parameters.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Converts the expression and parameters into C strings.
+    ///
+    /// The pointers of the parameters are only valid as long as the returned strings exist.
+    pub(crate) fn to_c_strings(&self) -> Result<ContentFilterCStrings, RclrsError> {
+        let to_c_string = |s: &str| {
+            CString::new(s).map_err(|err| RclrsError::StringContainsNul { err, s: s.into() })
+        };
+        let expression = to_c_string(&self.expression)?;
+        let parameters = self
+            .parameters
+            .iter()
+            .map(|parameter| to_c_string(parameter))
+            .collect::<Result<Vec<_>, _>>()?;
+        let parameter_ptrs = parameters.iter().map(|s| s.as_ptr()).collect();
+        Ok(ContentFilterCStrings {
+            expression,
+            _parameters: parameters,
+            parameter_ptrs,
+        })
+    }
+
+    /// Copies the filter out of the options that were filled in by `rcl`.
+    ///
+    /// # Safety
+    ///
+    /// The options must have been initialized by `rcl`.
+    pub(crate) unsafe fn from_rmw(options: &rmw_subscription_content_filter_options_t) -> Self {
+        let expression = if options.filter_expression.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(options.filter_expression)
+                .to_string_lossy()
+                .into_owned()
+        };
+        let parameters = &options.expression_parameters;
+        let parameters = if parameters.data.is_null() {
+            Vec::new()
+        } else {
+            slice::from_raw_parts(parameters.data, parameters.size)
+                .iter()
+                .map(|parameter| CStr::from_ptr(*parameter).to_string_lossy().into_owned())
+                .collect()
+        };
+        Self {
+            expression,
+            parameters,
+        }
+    }
+}
+
+/// The C strings of a [`ContentFilter`], which are passed to `rcl`.
+pub(crate) struct ContentFilterCStrings {
+    pub(crate) expression: CString,
+    // Owns the strings that the pointers refer to.
+    _parameters: Vec<CString>,
+    pub(crate) parameter_ptrs: Vec<*const c_char>,
+}
@@ -1,25 +1,23 @@
 use crate::{
     clock::{Clock, ClockSource, ClockType},
     vendor::rosgraph_msgs::msg::Clock as ClockMsg,
-    IntoPrimitiveOptions, Node, NodeState, QoSProfile, ReadOnlyParameter, Subscription,
-    QOS_PROFILE_CLOCK,
+    ContextHandle, IntoPrimitiveOptions, MandatoryParameter, Node, NodeState,
+    ParameterCallbackHandle, QoSProfile, Subscription, QOS_PROFILE_CLOCK,
 };
-use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::sync::{Arc, Mutex, Weak};
 
 /// Time source for a node that drives the attached clock.
 /// If the node's `use_sim_time` parameter is set to `true`, the `TimeSource` will subscribe
 /// to the `/clock` topic and drive the attached clock
 pub(crate) struct TimeSource {
     node: Mutex<Weak<NodeState>>,
-    clock: RwLock<Clock>,
+    clock: Clock,
     clock_source: Arc<Mutex<Option<ClockSource>>>,
-    requested_clock_type: ClockType,
     clock_qos: QoSProfile,
     clock_subscription: Mutex<Option<Subscription<ClockMsg>>>,
     last_received_time: Arc<Mutex<Option<i64>>>,
-    // TODO(luca) Make this parameter editable when we have parameter callbacks implemented and can
-    // safely change clock type at runtime
-    use_sim_time: Mutex<Option<ReadOnlyParameter<bool>>>,
+    use_sim_time: Mutex<Option<MandatoryParameter<bool>>>,
+    use_sim_time_callback: Mutex<Option<ParameterCallbackHandle>>,
 }
 
 /// A builder for creating a [`TimeSource`][1].
@@ -54,21 +52,24 @@ impl TimeSourceBuilder {
         self
     }
 
-    /// Builds the `TimeSource` and attaches the provided `Node` and `Clock`.
-    pub(crate) fn build(self) -> TimeSource {
+    /// Builds the `TimeSource` for a node that belongs to the given context.
+    pub(crate) fn build(self, context: &Arc<ContextHandle>) -> TimeSource {
         let clock = match self.clock_type {
-            ClockType::RosTime | ClockType::SystemTime => Clock::system(),
+            // The clock keeps using system time until ROS time is enabled through a source.
+            ClockType::RosTime => Clock::with_source().0,
+            ClockType::SystemTime => Clock::system(),
             ClockType::SteadyTime => Clock::steady(),
-        };
+        }
+        .with_context(context);
         TimeSource {
             node: Mutex::new(Weak::new()),
-            clock: RwLock::new(clock),
+            clock,
             clock_source: Arc::new(Mutex::new(None)),
-            requested_clock_type: self.clock_type,
             clock_qos: self.clock_qos,
             clock_subscription: Mutex::new(None),
             last_received_time: Arc::new(Mutex::new(None)),
             use_sim_time: Mutex::new(None),
+            use_sim_time_callback: Mutex::new(None),
         }
     }
 }
@@ -81,41 +82,47 @@ impl TimeSource {
 
     /// Returns the clock that this TimeSource is controlling.
     pub(crate) fn get_clock(&self) -> Clock {
-        self.clock.read().unwrap().clone()
+        self.clock.clone()
     }
 
     /// Attaches the given node to to the `TimeSource`, using its interface to read the
     /// `use_sim_time` parameter and create the clock subscription.
     pub(crate) fn attach_node(&self, node: &Node) {
-        // TODO(luca) Make this parameter editable and register a parameter callback
-        // that calls set_ros_time(bool) once parameter callbacks are implemented.
         let param = node
             .declare_parameter("use_sim_time")
             .default(false)
-            .read_only()
+            .mandatory()
             .unwrap();
         *self.node.lock().unwrap() = Arc::downgrade(node);
         self.set_ros_time_enable(param.get());
+        let weak_node = Arc::downgrade(node);
+        let callback = param.add_post_set_callback(move |use_sim_time| {
+            if let Some(node) = weak_node.upgrade() {
+                node.time_source.set_ros_time_enable(use_sim_time);
+            }
+        });
         *self.use_sim_time.lock().unwrap() = Some(param);
+        *self.use_sim_time_callback.lock().unwrap() = Some(callback);
     }
 
+    // The clock is never replaced, so that jump callbacks that were added to it are notified
+    // when ROS time is activated or deactivated.
     fn set_ros_time_enable(&self, enable: bool) {
-        if matches!(self.requested_clock_type, ClockType::RosTime) {
-            let mut clock = self.clock.write().unwrap();
-            if enable && matches!(clock.clock_type(), ClockType::SystemTime) {
-                let (new_clock, mut clock_source) = Clock::with_source();
-                if let Some(last_received_time) = *self.last_received_time.lock().unwrap() {
-                    Self::update_clock(&mut clock_source, last_received_time);
-                }
-                *clock = new_clock;
-                *self.clock_source.lock().unwrap() = Some(clock_source);
-                *self.clock_subscription.lock().unwrap() = Some(self.create_clock_sub());
-            }
-            if !enable && matches!(clock.clock_type(), ClockType::RosTime) {
-                *clock = Clock::system();
-                *self.clock_source.lock().unwrap() = None;
-                *self.clock_subscription.lock().unwrap() = None;
+        let mut clock_source = self.clock_source.lock().unwrap();
+        if enable && clock_source.is_none() {
+            // Only a clock of type `ClockType::RosTime` has a source.
+            let Some(mut source) = self.clock.create_source() else {
+                return;
+            };
+            if let Some(last_received_time) = *self.last_received_time.lock().unwrap() {
+                Self::update_clock(&mut source, last_received_time);
             }
+            *clock_source = Some(source);
+            *self.clock_subscription.lock().unwrap() = Some(self.create_clock_sub());
+        }
+        if !enable && clock_source.is_some() {
+            *clock_source = None;
+            *self.clock_subscription.lock().unwrap() = None;
         }
     }
 
@@ -150,6 +157,7 @@ impl TimeSource {
 #[cfg(test)]
 mod tests {
     use crate::*;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn time_source_default_clock() {
@@ -180,4 +188,41 @@ mod tests {
         // Default sim time value should be 0 (no message received)
         assert_eq!(node.get_clock().now().nsec, 0);
     }
+
+    #[test]
+    fn time_source_toggle_sim_time() {
+        let node = Context::default()
+            .create_basic_executor()
+            .create_node(&format!("time_source_test_node_{}", line!()))
+            .unwrap();
+        let clock = node.get_clock();
+        assert!(matches!(clock.clock_type(), ClockType::RosTime));
+        assert!(clock.now().nsec > 0);
+
+        let clock_changes = Arc::new(Mutex::new(Vec::new()));
+        let threshold = JumpThreshold {
+            on_clock_change: true,
+            ..Default::default()
+        };
+        let _handle = clock
+            .add_jump_callback(threshold, |_| {}, {
+                let clock_changes = Arc::clone(&clock_changes);
+                move |jump| clock_changes.lock().unwrap().push(jump.clock_change)
+            })
+            .unwrap();
+
+        let parameters = node.use_undeclared_parameters();
+        parameters.set("use_sim_time", true).unwrap();
+        // The clock of the node is the same, but now uses sim time
+        assert_eq!(clock.now().nsec, 0);
+        parameters.set("use_sim_time", false).unwrap();
+        assert!(clock.now().nsec > 0);
+        assert_eq!(
+            *clock_changes.lock().unwrap(),
+            vec![
+                ClockChange::RosTimeActivated,
+                ClockChange::RosTimeDeactivated
+            ]
+        );
+    }
 }
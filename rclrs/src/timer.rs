@@ -0,0 +1,475 @@
+use std::{
+    sync::{atomic::AtomicBool, Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use crate::{
+    error::{RclReturnCode, ToResult},
+    rcl_bindings::*,
+    CallbackGroup, Clock, ContextHandle, RclrsError, ENTITY_LIFECYCLE_MUTEX,
+};
+
+// SAFETY: The functions accessing this type, including drop(), shouldn't care about the thread
+// they are running in. Therefore, this type can be safely sent to another thread.
+unsafe impl Send for rcl_timer_t {}
+
+/// Manage the lifecycle of an `rcl_timer_t`, including managing its dependencies
+/// on `rcl_clock_t` and `rcl_context_t` by ensuring that these dependencies are
+/// [dropped after][1] the `rcl_timer_t`.
+///
+/// [1]: <https://doc.rust-lang.org/reference/destructors.html>
+pub struct TimerHandle {
+    rcl_timer: Mutex<rcl_timer_t>,
+    clock: Clock,
+    context_handle: Arc<ContextHandle>,
+    pub(crate) in_use_by_wait_set: Arc<AtomicBool>,
+}
+
+impl TimerHandle {
+    pub(crate) fn lock(&self) -> MutexGuard<rcl_timer_t> {
+        self.rcl_timer.lock().unwrap()
+    }
+}
+
+impl Drop for TimerHandle {
+    fn drop(&mut self) {
+        let rcl_timer = self.rcl_timer.get_mut().unwrap();
+        let _context_lock = self.context_handle.rcl_context.lock().unwrap();
+        // Timers driven by ROS time register a jump callback on their clock, which
+        // gets removed again during finalization.
+        let _clock_lock = self.clock.get_rcl_clock().lock().unwrap();
+        let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+        // SAFETY: The entity lifecycle mutex is locked to protect against the risk of
+        // global variables in the rmw implementation being unsafely modified during cleanup.
+        unsafe {
+            rcl_timer_fini(rcl_timer);
+        }
+    }
+}
+
+/// A callback that can be triggered when a [`Timer`] elapses.
+///
+/// The timer that triggered the callback is passed in, so that the callback can
+/// e.g. [cancel][1] the timer or [change its period][2].
+///
+/// [1]: TimerState::cancel
+/// [2]: TimerState::set_period
+pub type TimerCallback = Box<dyn FnMut(&TimerState) + Send>;
+
+/// `TimerOptions` are used by [`Node::create_timer`][1] and [`Node::create_wall_timer`][2]
+/// to initialize a [`Timer`].
+///
+/// A plain [`Duration`] can be passed in wherever `TimerOptions` are expected, which
+/// creates a timer with that period in the default callback group of the node.
+///
+/// [1]: crate::NodeState::create_timer
+/// [2]: crate::NodeState::create_wall_timer
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct TimerOptions<'a> {
+    /// The period of the timer.
+    pub period: Duration,
+    /// The [`CallbackGroup`] that the callback of the timer will belong to.
+    /// If this is `None`, the default callback group of the node is used.
+    pub callback_group: Option<&'a CallbackGroup>,
+}
+
+impl<'a> TimerOptions<'a> {
+    /// Initialize a new [`TimerOptions`] with default settings.
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            callback_group: None,
+        }
+    }
+
+    /// Set the [`CallbackGroup`] that the callback of the timer will belong to.
+    pub fn callback_group(mut self, callback_group: &'a CallbackGroup) -> Self {
+        self.callback_group = Some(callback_group);
+        self
+    }
+}
+
+impl From<Duration> for TimerOptions<'_> {
+    fn from(period: Duration) -> Self {
+        Self::new(period)
+    }
+}
+
+/// Struct for periodically triggering a callback.
+///
+/// Create a timer using [`Node::create_timer`][1] or [`Node::create_wall_timer`][2].
+///
+/// The timer measures time with a [`Clock`]. Timers created with [`Node::create_timer`][1]
+/// use the clock of the node, which means that they follow simulated time when the
+/// `use_sim_time` parameter is enabled. Timers created with [`Node::create_wall_timer`][2]
+/// use a steady clock and are not affected by simulated time.
+///
+/// The callback is run by the node's executor while it is [spinning][3].
+///
+/// [1]: crate::NodeState::create_timer
+/// [2]: crate::NodeState::create_wall_timer
+/// [3]: crate::Executor::spin
+pub type Timer = Arc<TimerState>;
+
+/// The inner state of a [`Timer`].
+///
+/// This is public so that you can choose to create a [`Weak`][1] reference to it
+/// if you want to be able to refer to a [`Timer`] in a non-owning way. It is
+/// generally recommended to manage the `TimerState` inside of an [`Arc`],
+/// and [`Timer`] is provided as a convenience alias for that.
+///
+/// The public API of the [`Timer`] type is implemented via `TimerState`.
+///
+/// [1]: std::sync::Weak
+pub struct TimerState {
+    pub(crate) handle: Arc<TimerHandle>,
+    /// The callback function that runs when the timer elapses.
+    callback: Mutex<TimerCallback>,
+    pub(crate) callback_group: CallbackGroup,
+}
+
+impl TimerState {
+    /// Creates a new timer.
+    pub(crate) fn new(
+        context_handle: &Arc<ContextHandle>,
+        clock: Clock,
+        period: Duration,
+        callback: TimerCallback,
+        callback_group: CallbackGroup,
+    ) -> Result<Self, RclrsError> {
+        // This uses pub(crate) visibility to avoid instantiating this struct outside
+        // [`Node::create_timer`], see the struct's documentation for the rationale
+        let period = duration_to_nanoseconds(period)?;
+        // SAFETY: Getting a zero-initialized value is always safe.
+        let mut rcl_timer = unsafe { rcl_get_zero_initialized_timer() };
+
+        {
+            let mut rcl_context = context_handle.rcl_context.lock().unwrap();
+            let mut rcl_clock = clock.get_rcl_clock().lock().unwrap();
+            let _lifecycle_lock = ENTITY_LIFECYCLE_MUTEX.lock().unwrap();
+            unsafe {
+                // SAFETY:
+                // * The rcl_timer is zero-initialized as mandated by this function.
+                // * The rcl_clock is kept alive by the TimerHandle because it is a dependency of the timer.
+                //   It is stored behind an Arc, so its address does not change while the timer exists.
+                // * The rcl_context is kept alive by the TimerHandle because it is a dependency of the timer.
+                // * No C callback is passed in, the callback is run by the executor instead.
+                // * The entity lifecycle mutex is locked to protect against the risk of global
+                //   variables in the rmw implementation being unsafely modified during cleanup.
+                #[cfg(ros_distro = "humble")]
+                rcl_timer_init(
+                    &mut rcl_timer,
+                    &mut *rcl_clock,
+                    &mut *rcl_context,
+                    period,
+                    None,
+                    rcutils_get_default_allocator(),
+                )
+                .ok()?;
+                #[cfg(not(ros_distro = "humble"))]
+                rcl_timer_init2(
+                    &mut rcl_timer,
+                    &mut *rcl_clock,
+                    &mut *rcl_context,
+                    period,
+                    None,
+                    rcutils_get_default_allocator(),
+                    true,
+                )
+                .ok()?;
+            }
+        }
+
+        let handle = Arc::new(TimerHandle {
+            rcl_timer: Mutex::new(rcl_timer),
+            clock,
+            context_handle: Arc::clone(context_handle),
+            in_use_by_wait_set: Arc::new(AtomicBool::new(false)),
+        });
+
+        Ok(Self {
+            handle,
+            callback: Mutex::new(callback),
+            callback_group,
+        })
+    }
+
+    /// Returns the clock that this timer uses to measure time.
+    pub fn clock(&self) -> &Clock {
+        &self.handle.clock
+    }
+
+    /// Returns the period of the timer.
+    pub fn get_period(&self) -> Result<Duration, RclrsError> {
+        let mut period = 0;
+        unsafe {
+            // SAFETY: The rcl_timer is valid and the out-parameter is a valid pointer.
+            rcl_timer_get_period(&*self.handle.lock(), &mut period).ok()?;
+        }
+        // The period was set from a Duration, so it is never negative.
+        Ok(Duration::from_nanos(period as u64))
+    }
+
+    /// Changes the period of the timer.
+    ///
+    /// The new period takes effect after the next time the timer elapses, or after
+    /// the timer is [reset][1].
+    ///
+    /// [1]: TimerState::reset
+    pub fn set_period(&self, period: Duration) -> Result<(), RclrsError> {
+        let new_period = duration_to_nanoseconds(period)?;
+        let mut old_period = 0;
+        unsafe {
+            // SAFETY: The rcl_timer is valid and the out-parameter is a valid pointer.
+            rcl_timer_exchange_period(&*self.handle.lock(), new_period, &mut old_period).ok()
+        }
+    }
+
+    /// Cancels the timer.
+    ///
+    /// A canceled timer will not trigger its callback until it is [reset][1].
+    ///
+    /// [1]: TimerState::reset
+    pub fn cancel(&self) -> Result<(), RclrsError> {
+        unsafe {
+            // SAFETY: The rcl_timer is valid.
+            rcl_timer_cancel(&mut *self.handle.lock()).ok()
+        }
+    }
+
+    /// Checks whether the timer is canceled.
+    pub fn is_canceled(&self) -> Result<bool, RclrsError> {
+        let mut is_canceled = false;
+        unsafe {
+            // SAFETY: The rcl_timer is valid and the out-parameter is a valid pointer.
+            rcl_timer_is_canceled(&*self.handle.lock(), &mut is_canceled).ok()?;
+        }
+        Ok(is_canceled)
+    }
+
+    /// Resets the timer.
+    ///
+    /// The time until the next call is set back to the full period, and the timer
+    /// is un-canceled if it was canceled before.
+    pub fn reset(&self) -> Result<(), RclrsError> {
+        let mut rcl_timer = self.handle.lock();
+        let _clock_lock = self.handle.clock.get_rcl_clock().lock().unwrap();
+        unsafe {
+            // SAFETY: The rcl_timer is valid, and the clock mutex is locked because the
+            // current time is read from the clock.
+            rcl_timer_reset(&mut *rcl_timer).ok()
+        }
+    }
+
+    /// Checks whether the timer has elapsed and its callback is due to be run.
+    pub fn is_ready(&self) -> Result<bool, RclrsError> {
+        let rcl_timer = self.handle.lock();
+        let _clock_lock = self.handle.clock.get_rcl_clock().lock().unwrap();
+        let mut is_ready = false;
+        unsafe {
+            // SAFETY: The rcl_timer is valid and the out-parameter is a valid pointer. The
+            // clock mutex is locked because the current time is read from the clock.
+            rcl_timer_is_ready(&*rcl_timer, &mut is_ready).ok()?;
+        }
+        Ok(is_ready)
+    }
+
+    /// Returns the time in nanoseconds until the timer elapses next.
+    ///
+    /// The result is negative if the timer is overdue, i.e. if it has elapsed but its
+    /// callback has not been run yet.
+    ///
+    /// If the timer is canceled, a [`TimerCanceled`][1] error is returned.
+    ///
+    /// [1]: crate::RclReturnCode::TimerCanceled
+    pub fn time_until_next_call(&self) -> Result<i64, RclrsError> {
+        let rcl_timer = self.handle.lock();
+        let _clock_lock = self.handle.clock.get_rcl_clock().lock().unwrap();
+        let mut time_until_next_call = 0;
+        unsafe {
+            // SAFETY: The rcl_timer is valid and the out-parameter is a valid pointer. The
+            // clock mutex is locked because the current time is read from the clock.
+            rcl_timer_get_time_until_next_call(&*rcl_timer, &mut time_until_next_call).ok()?;
+        }
+        Ok(time_until_next_call)
+    }
+
+    /// Returns the time in nanoseconds since the timer last elapsed, or since it was
+    /// created or [reset][1] if it has not elapsed yet.
+    ///
+    /// [1]: TimerState::reset
+    pub fn time_since_last_call(&self) -> Result<i64, RclrsError> {
+        let rcl_timer = self.handle.lock();
+        let _clock_lock = self.handle.clock.get_rcl_clock().lock().unwrap();
+        let mut time_since_last_call = 0;
+        unsafe {
+            // SAFETY: The rcl_timer is valid and the out-parameter is a valid pointer. The
+            // clock mutex is locked because the current time is read from the clock.
+            rcl_timer_get_time_since_last_call(&*rcl_timer, &mut time_since_last_call).ok()?;
+        }
+        Ok(time_since_last_call)
+    }
+
+    /// Marks the timer as called, if it is ready, and runs the callback.
+    ///
+    /// A timer that is not ready (e.g. because it was canceled after the wait set
+    /// indicated that it is ready) is skipped, since this is not an error.
+    pub(crate) fn execute(&self) -> Result<(), RclrsError> {
+        if !self.is_ready()? {
+            return Ok(());
+        }
+
+        let call_result = {
+            let mut rcl_timer = self.handle.lock();
+            let _clock_lock = self.handle.clock.get_rcl_clock().lock().unwrap();
+            unsafe {
+                // SAFETY: The rcl_timer is valid, and the clock mutex is locked because the
+                // current time is read from the clock. No C callback was registered, so this
+                // only updates the time of the last call.
+                rcl_timer_call(&mut *rcl_timer).ok()
+            }
+        };
+
+        match call_result {
+            Ok(()) => {}
+            Err(RclrsError::RclError {
+                code: RclReturnCode::TimerCanceled,
+                ..
+            }) => {
+                // The timer was canceled in the meantime, so there is nothing to do.
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        }
+
+        (*self.callback.lock().unwrap())(self);
+        Ok(())
+    }
+}
+
+impl PartialEq for TimerState {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.handle, &other.handle)
+    }
+}
+
+impl Eq for TimerState {}
+
+fn duration_to_nanoseconds(duration: Duration) -> Result<i64, RclrsError> {
+    i64::try_from(duration.as_nanos()).map_err(|_| RclrsError::RclError {
+        code: RclReturnCode::InvalidArgument,
+        msg: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_helpers::*, *};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn traits() {
+        assert_send::<TimerState>();
+        assert_sync::<TimerState>();
+    }
+
+    #[test]
+    fn test_timer_period_and_cancel() -> Result<(), RclrsError> {
+        let executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_timer_period_and_cancel_{}", line!()))?;
+
+        let timer = node.create_wall_timer(Duration::from_millis(100), |_| {})?;
+        assert_eq!(timer.get_period()?, Duration::from_millis(100));
+        assert!(!timer.is_canceled()?);
+        assert!(timer.time_until_next_call()? <= 100_000_000);
+
+        timer.set_period(Duration::from_millis(50))?;
+        assert_eq!(timer.get_period()?, Duration::from_millis(50));
+
+        timer.cancel()?;
+        assert!(timer.is_canceled()?);
+        assert!(timer.time_until_next_call().is_err());
+
+        timer.reset()?;
+        assert!(!timer.is_canceled()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_timer_callback_group() -> Result<(), RclrsError> {
+        let executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_timer_callback_group_{}", line!()))?;
+
+        let timer = node.create_wall_timer(Duration::from_millis(100), |_| {})?;
+        assert!(Arc::ptr_eq(
+            &timer.callback_group,
+            node.default_callback_group()
+        ));
+
+        let group = node.create_callback_group(CallbackGroupType::Reentrant);
+        let timer = node.create_timer(
+            TimerOptions::new(Duration::from_millis(100)).callback_group(&group),
+            |_| {},
+        )?;
+        assert!(Arc::ptr_eq(&timer.callback_group, &group));
+        Ok(())
+    }
+
+    #[test]
+    fn test_timer_fires_while_spinning() -> Result<(), RclrsError> {
+        let mut executor = Context::default().create_basic_executor();
+        let node = executor.create_node(&format!("test_timer_fires_{}", line!()))?;
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let inner_count = Arc::clone(&count);
+        let _timer = node.create_wall_timer(Duration::from_millis(1), move |timer| {
+            if inner_count.fetch_add(1, Ordering::AcqRel) + 1 >= 3 {
+                timer.cancel().unwrap();
+            }
+        })?;
+
+        let start_time = std::time::Instant::now();
+        while count.load(Ordering::Acquire) < 3 {
+            for err in executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10))) {
+                assert!(err.is_timeout(), "{err}");
+            }
+            assert!(start_time.elapsed() < Duration::from_secs(10));
+        }
+
+        // The timer canceled itself, so spinning further must not trigger it again.
+        executor.spin(SpinOptions::spin_once().timeout(Duration::from_millis(10)));
+        assert_eq!(count.load(Ordering::Acquire), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_timer_follows_ros_time_override() -> Result<(), RclrsError> {
+        let (clock, source) = Clock::with_source();
+        let context = Context::default();
+        let count = Arc::new(AtomicUsize::new(0));
+        let inner_count = Arc::clone(&count);
+        let timer = TimerState::new(
+            &context.handle,
+            clock,
+            Duration::from_secs(1),
+            Box::new(move |_| {
+                inner_count.fetch_add(1, Ordering::AcqRel);
+            }),
+            Arc::new(CallbackGroupState::new(
+                CallbackGroupType::MutuallyExclusive,
+            )),
+        )?;
+
+        assert!(!timer.is_ready()?);
+        source.set_ros_time_override(2_000_000_000);
+        assert!(timer.is_ready()?);
+        timer.execute()?;
+        assert_eq!(count.load(Ordering::Acquire), 1);
+
+        Ok(())
+    }
+}
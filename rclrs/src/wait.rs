@@ -20,7 +20,9 @@ use std::{sync::Arc, time::Duration, vec::Vec};
 use crate::{
     error::{to_rclrs_result, RclReturnCode, RclrsError, ToResult},
     rcl_bindings::*,
-    ClientBase, Context, ContextHandle, Node, ServiceBase, SubscriptionBase,
+    ActionClientBase, ActionClientReady, ActionServerBase, ActionServerReady, ClientBase, Context,
+    ContextHandle, GraphWatcherState, Node, QoSEventHandlerState, ServiceBase, SubscriptionBase,
+    TimerState,
 };
 
 mod exclusivity_guard;
@@ -50,6 +52,12 @@ pub struct WaitSet {
     // The guard conditions that are currently registered in the wait set.
     guard_conditions: Vec<ExclusivityGuard<Arc<GuardCondition>>>,
     services: Vec<ExclusivityGuard<Arc<dyn ServiceBase>>>,
+    timers: Vec<ExclusivityGuard<Arc<TimerState>>>,
+    action_servers: Vec<ExclusivityGuard<Arc<dyn ActionServerBase>>>,
+    action_clients: Vec<ExclusivityGuard<Arc<dyn ActionClientBase>>>,
+    events: Vec<ExclusivityGuard<Arc<QoSEventHandlerState>>>,
+    // The graph watchers, whose guard conditions are part of `guard_conditions`.
+    graph_watchers: Vec<Arc<GraphWatcherState>>,
     handle: WaitSetHandle,
 }
 
@@ -63,6 +71,206 @@ pub struct ReadyEntities {
     pub guard_conditions: Vec<Arc<GuardCondition>>,
     /// A list of services that have potentially received requests.
     pub services: Vec<Arc<dyn ServiceBase>>,
+    /// A list of timers that have potentially elapsed.
+    pub timers: Vec<Arc<TimerState>>,
+    /// A list of action servers, together with the parts of them that are ready.
+    pub action_servers: Vec<(Arc<dyn ActionServerBase>, ActionServerReady)>,
+    /// A list of action clients, together with the parts of them that are ready.
+    pub action_clients: Vec<(Arc<dyn ActionClientBase>, ActionClientReady)>,
+    /// A list of QoS event handlers whose events have potentially occurred.
+    pub events: Vec<Arc<QoSEventHandlerState>>,
+    /// A list of graph watchers whose graph has potentially changed.
+    pub graph_watchers: Vec<Arc<GraphWatcherState>>,
+}
+
+/// A collection of waitable entities, which can be turned into a [`WaitSet`] that
+/// fits them exactly.
+#[derive(Default)]
+pub(crate) struct WaitableEntities {
+    pub(crate) subscriptions: Vec<Arc<dyn SubscriptionBase>>,
+    pub(crate) clients: Vec<Arc<dyn ClientBase>>,
+    pub(crate) guard_conditions: Vec<Arc<GuardCondition>>,
+    pub(crate) services: Vec<Arc<dyn ServiceBase>>,
+    pub(crate) timers: Vec<Arc<TimerState>>,
+    pub(crate) action_servers: Vec<Arc<dyn ActionServerBase>>,
+    pub(crate) action_clients: Vec<Arc<dyn ActionClientBase>>,
+    pub(crate) events: Vec<Arc<QoSEventHandlerState>>,
+    pub(crate) graph_watchers: Vec<Arc<GraphWatcherState>>,
+}
+
+impl WaitableEntities {
+    /// Collects all waitable entities of the node that have not been dropped yet.
+    pub(crate) fn from_node(node: &Node) -> Self {
+        Self {
+            subscriptions: node.live_subscriptions(),
+            clients: node.live_clients(),
+            guard_conditions: node.live_guard_conditions(),
+            services: node.live_services(),
+            timers: node.live_timers(),
+            action_servers: node.live_action_servers(),
+            action_clients: node.live_action_clients(),
+            events: node.live_events(),
+            graph_watchers: node.live_graph_watchers(),
+        }
+    }
+
+    /// Moves all entities of `other` into this collection.
+    pub(crate) fn extend(&mut self, other: Self) {
+        self.subscriptions.extend(other.subscriptions);
+        self.clients.extend(other.clients);
+        self.guard_conditions.extend(other.guard_conditions);
+        self.services.extend(other.services);
+        self.timers.extend(other.timers);
+        self.action_servers.extend(other.action_servers);
+        self.action_clients.extend(other.action_clients);
+        self.events.extend(other.events);
+        self.graph_watchers.extend(other.graph_watchers);
+    }
+
+    /// Creates a new wait set and adds all entities to it.
+    pub(crate) fn into_wait_set(self, context: &Context) -> Result<WaitSet, RclrsError> {
+        // Graph watchers of the same node share their guard condition.
+        let mut graph_guard_conditions: Vec<&Arc<GuardCondition>> = Vec::new();
+        for graph_watcher in &self.graph_watchers {
+            if !graph_guard_conditions
+                .iter()
+                .any(|guard_condition| Arc::ptr_eq(guard_condition, &graph_watcher.guard_condition))
+            {
+                graph_guard_conditions.push(&graph_watcher.guard_condition);
+            }
+        }
+        let intra_process_guard_conditions = self
+            .subscriptions
+            .iter()
+            .filter(|subscription| subscription.intra_process_guard_condition().is_some())
+            .count();
+        let mut count = WaitableCount {
+            subscriptions: self.subscriptions.len(),
+            guard_conditions: self.guard_conditions.len()
+                + graph_guard_conditions.len()
+                + intra_process_guard_conditions,
+            timers: self.timers.len(),
+            clients: self.clients.len(),
+            services: self.services.len(),
+        };
+        for action_server in &self.action_servers {
+            count.add_action_server(action_server.as_ref())?;
+        }
+        for action_client in &self.action_clients {
+            count.add_action_client(action_client.as_ref())?;
+        }
+        let mut wait_set = WaitSet::new(
+            count.subscriptions,
+            count.guard_conditions,
+            count.timers,
+            count.clients,
+            count.services,
+            self.events.len(),
+            context,
+        )?;
+
+        for subscription in self.subscriptions {
+            wait_set.add_subscription(subscription)?;
+        }
+
+        for client in self.clients {
+            wait_set.add_client(client)?;
+        }
+
+        for guard_condition in self.guard_conditions {
+            wait_set.add_guard_condition(guard_condition)?;
+        }
+
+        for service in self.services {
+            wait_set.add_service(service)?;
+        }
+
+        for timer in self.timers {
+            wait_set.add_timer(timer)?;
+        }
+
+        for action_server in self.action_servers {
+            wait_set.add_action_server(action_server)?;
+        }
+
+        for action_client in self.action_clients {
+            wait_set.add_action_client(action_client)?;
+        }
+
+        for event in self.events {
+            wait_set.add_event(event)?;
+        }
+
+        for graph_watcher in self.graph_watchers {
+            wait_set.add_graph_watcher(graph_watcher)?;
+        }
+        Ok(wait_set)
+    }
+}
+
+/// The number of each kind of rcl entity that a wait set needs to hold.
+///
+/// Action servers and clients are composed of several services, clients, subscriptions
+/// and timers, which all occupy their own slot in the wait set.
+#[derive(Clone, Copy, Debug, Default)]
+struct WaitableCount {
+    subscriptions: usize,
+    guard_conditions: usize,
+    timers: usize,
+    clients: usize,
+    services: usize,
+}
+
+impl WaitableCount {
+    fn add_action_server(
+        &mut self,
+        action_server: &dyn ActionServerBase,
+    ) -> Result<(), RclrsError> {
+        let mut count = Self::default();
+        unsafe {
+            // SAFETY: The action server is valid, and all out-parameters point to valid memory.
+            rcl_action_server_wait_set_get_num_entities(
+                &*action_server.handle().lock(),
+                &mut count.subscriptions,
+                &mut count.guard_conditions,
+                &mut count.timers,
+                &mut count.clients,
+                &mut count.services,
+            )
+            .ok()?;
+        }
+        self.add(count);
+        Ok(())
+    }
+
+    fn add_action_client(
+        &mut self,
+        action_client: &dyn ActionClientBase,
+    ) -> Result<(), RclrsError> {
+        let mut count = Self::default();
+        unsafe {
+            // SAFETY: The action client is valid, and all out-parameters point to valid memory.
+            rcl_action_client_wait_set_get_num_entities(
+                &*action_client.handle().lock(),
+                &mut count.subscriptions,
+                &mut count.guard_conditions,
+                &mut count.timers,
+                &mut count.clients,
+                &mut count.services,
+            )
+            .ok()?;
+        }
+        self.add(count);
+        Ok(())
+    }
+
+    fn add(&mut self, other: Self) {
+        self.subscriptions += other.subscriptions;
+        self.guard_conditions += other.guard_conditions;
+        self.timers += other.timers;
+        self.clients += other.clients;
+        self.services += other.services;
+    }
 }
 
 impl Drop for rcl_wait_set_t {
@@ -123,6 +331,11 @@ impl WaitSet {
             guard_conditions: Vec::new(),
             clients: Vec::new(),
             services: Vec::new(),
+            timers: Vec::new(),
+            action_servers: Vec::new(),
+            action_clients: Vec::new(),
+            events: Vec::new(),
+            graph_watchers: Vec::new(),
             handle: WaitSetHandle {
                 rcl_wait_set,
                 context_handle: Arc::clone(&context.handle),
@@ -134,39 +347,10 @@ impl WaitSet {
     ///
     /// The wait set is sized to fit the node exactly, so there is no capacity for adding other entities.
     pub fn new_for_node(node: &Node) -> Result<Self, RclrsError> {
-        let live_subscriptions = node.live_subscriptions();
-        let live_clients = node.live_clients();
-        let live_guard_conditions = node.live_guard_conditions();
-        let live_services = node.live_services();
         let ctx = Context {
             handle: Arc::clone(&node.handle.context_handle),
         };
-        let mut wait_set = WaitSet::new(
-            live_subscriptions.len(),
-            live_guard_conditions.len(),
-            0,
-            live_clients.len(),
-            live_services.len(),
-            0,
-            &ctx,
-        )?;
-
-        for live_subscription in &live_subscriptions {
-            wait_set.add_subscription(live_subscription.clone())?;
-        }
-
-        for live_client in &live_clients {
-            wait_set.add_client(live_client.clone())?;
-        }
-
-        for live_guard_condition in &live_guard_conditions {
-            wait_set.add_guard_condition(live_guard_condition.clone())?;
-        }
-
-        for live_service in &live_services {
-            wait_set.add_service(live_service.clone())?;
-        }
-        Ok(wait_set)
+        WaitableEntities::from_node(node).into_wait_set(&ctx)
     }
 
     /// Removes all entities from the wait set.
@@ -178,6 +362,11 @@ impl WaitSet {
         self.guard_conditions.clear();
         self.clients.clear();
         self.services.clear();
+        self.timers.clear();
+        self.action_servers.clear();
+        self.action_clients.clear();
+        self.events.clear();
+        self.graph_watchers.clear();
         // This cannot fail – the rcl_wait_set_clear function only checks that the input handle is
         // valid, which it always is in our case. Hence, only debug_assert instead of returning
         // Result.
@@ -188,6 +377,10 @@ impl WaitSet {
 
     /// Adds a subscription to the wait set.
     ///
+    /// A subscription that uses [intra-process communication][3] also occupies a guard
+    /// condition in the wait set, which is triggered when it receives a message directly
+    /// from a publisher.
+    ///
     /// # Errors
     /// - If the subscription was already added to this wait set or another one,
     ///   [`AlreadyAddedToWaitSet`][1] will be returned
@@ -196,6 +389,7 @@ impl WaitSet {
     ///
     /// [1]: crate::RclrsError
     /// [2]: crate::RclReturnCode
+    /// [3]: crate::SubscriptionOptions::intra_process
     pub fn add_subscription(
         &mut self,
         subscription: Arc<dyn SubscriptionBase>,
@@ -216,6 +410,9 @@ impl WaitSet {
         }
         .ok()?;
         self.subscriptions.push(exclusive_subscription);
+        if let Some(guard_condition) = subscription.intra_process_guard_condition() {
+            self.add_guard_condition(Arc::clone(guard_condition))?;
+        }
         Ok(())
     }
 
@@ -311,6 +508,170 @@ impl WaitSet {
         Ok(())
     }
 
+    /// Adds a timer to the wait set.
+    ///
+    /// The wait set will become ready as soon as the timer elapses, so the timeout
+    /// passed to [`WaitSet::wait`] is effectively shortened to the time until the
+    /// next call of the timer.
+    ///
+    /// # Errors
+    /// - If the timer was already added to this wait set or another one,
+    ///   [`AlreadyAddedToWaitSet`][1] will be returned
+    /// - If the number of timers in the wait set is larger than the
+    ///   capacity set in [`WaitSet::new`], [`WaitSetFull`][2] will be returned
+    ///
+    /// [1]: crate::RclrsError
+    /// [2]: crate::RclReturnCode
+    pub fn add_timer(&mut self, timer: Arc<TimerState>) -> Result<(), RclrsError> {
+        let exclusive_timer = ExclusivityGuard::new(
+            Arc::clone(&timer),
+            Arc::clone(&timer.handle.in_use_by_wait_set),
+        )?;
+        unsafe {
+            // SAFETY: I'm not sure if it's required, but the timer pointer will remain valid
+            // for as long as the wait set exists, because it's stored in self.timers.
+            // Passing in a null pointer for the third argument is explicitly allowed.
+            rcl_wait_set_add_timer(
+                &mut self.handle.rcl_wait_set,
+                &*timer.handle.lock() as *const _,
+                core::ptr::null_mut(),
+            )
+        }
+        .ok()?;
+        self.timers.push(exclusive_timer);
+        Ok(())
+    }
+
+    /// Adds an action server to the wait set.
+    ///
+    /// An action server occupies several services and a timer in the wait set, which
+    /// need to be accounted for in the capacities passed to [`WaitSet::new`].
+    ///
+    /// # Errors
+    /// - If the action server was already added to this wait set or another one,
+    ///   [`AlreadyAddedToWaitSet`][1] will be returned
+    /// - If the wait set does not have enough capacity left for the entities of the
+    ///   action server, [`WaitSetFull`][2] will be returned
+    ///
+    /// [1]: crate::RclrsError
+    /// [2]: crate::RclReturnCode
+    pub fn add_action_server(
+        &mut self,
+        action_server: Arc<dyn ActionServerBase>,
+    ) -> Result<(), RclrsError> {
+        let exclusive_action_server = ExclusivityGuard::new(
+            Arc::clone(&action_server),
+            Arc::clone(&action_server.handle().in_use_by_wait_set),
+        )?;
+        unsafe {
+            // SAFETY: The action server will remain valid for as long as the wait set exists,
+            // because it's stored in self.action_servers.
+            // Passing in a null pointer for the index argument is explicitly allowed.
+            rcl_action_wait_set_add_action_server(
+                &mut self.handle.rcl_wait_set,
+                &*action_server.handle().lock(),
+                core::ptr::null_mut(),
+            )
+        }
+        .ok()?;
+        self.action_servers.push(exclusive_action_server);
+        Ok(())
+    }
+
+    /// Adds an action client to the wait set.
+    ///
+    /// An action client occupies several clients and subscriptions in the wait set, which
+    /// need to be accounted for in the capacities passed to [`WaitSet::new`].
+    ///
+    /// # Errors
+    /// - If the action client was already added to this wait set or another one,
+    ///   [`AlreadyAddedToWaitSet`][1] will be returned
+    /// - If the wait set does not have enough capacity left for the entities of the
+    ///   action client, [`WaitSetFull`][2] will be returned
+    ///
+    /// [1]: crate::RclrsError
+    /// [2]: crate::RclReturnCode
+    pub fn add_action_client(
+        &mut self,
+        action_client: Arc<dyn ActionClientBase>,
+    ) -> Result<(), RclrsError> {
+        let exclusive_action_client = ExclusivityGuard::new(
+            Arc::clone(&action_client),
+            Arc::clone(&action_client.handle().in_use_by_wait_set),
+        )?;
+        unsafe {
+            // SAFETY: The action client will remain valid for as long as the wait set exists,
+            // because it's stored in self.action_clients.
+            // Passing in null pointers for the index arguments is explicitly allowed.
+            rcl_action_wait_set_add_action_client(
+                &mut self.handle.rcl_wait_set,
+                &*action_client.handle().lock(),
+                core::ptr::null_mut(),
+                core::ptr::null_mut(),
+            )
+        }
+        .ok()?;
+        self.action_clients.push(exclusive_action_client);
+        Ok(())
+    }
+
+    /// Adds a QoS event handler to the wait set.
+    ///
+    /// # Errors
+    /// - If the event handler was already added to this wait set or another one,
+    ///   [`AlreadyAddedToWaitSet`][1] will be returned
+    /// - If the number of events in the wait set is larger than the
+    ///   capacity set in [`WaitSet::new`], [`WaitSetFull`][2] will be returned
+    ///
+    /// [1]: crate::RclrsError
+    /// [2]: crate::RclReturnCode
+    pub fn add_event(&mut self, event: Arc<QoSEventHandlerState>) -> Result<(), RclrsError> {
+        let exclusive_event = ExclusivityGuard::new(
+            Arc::clone(&event),
+            Arc::clone(&event.handle.in_use_by_wait_set),
+        )?;
+        unsafe {
+            // SAFETY: The event will remain valid for as long as the wait set exists,
+            // because it's stored in self.events.
+            // Passing in a null pointer for the third argument is explicitly allowed.
+            rcl_wait_set_add_event(
+                &mut self.handle.rcl_wait_set,
+                &*event.handle.lock(),
+                core::ptr::null_mut(),
+            )
+        }
+        .ok()?;
+        self.events.push(exclusive_event);
+        Ok(())
+    }
+
+    /// Adds a graph watcher to the wait set.
+    ///
+    /// This adds the graph guard condition of the watcher's node, unless another watcher of
+    /// the same node was already added, so it occupies at most one guard condition per node.
+    ///
+    /// # Errors
+    /// - If the graph guard condition of the node is used by another wait set,
+    ///   [`AlreadyAddedToWaitSet`][1] will be returned
+    /// - If the number of guard conditions in the wait set is larger than the
+    ///   capacity set in [`WaitSet::new`], [`WaitSetFull`][2] will be returned
+    ///
+    /// [1]: crate::RclrsError
+    /// [2]: crate::RclReturnCode
+    pub fn add_graph_watcher(
+        &mut self,
+        graph_watcher: Arc<GraphWatcherState>,
+    ) -> Result<(), RclrsError> {
+        let guard_condition_added = self.guard_conditions.iter().any(|guard_condition| {
+            Arc::ptr_eq(&guard_condition.waitable, &graph_watcher.guard_condition)
+        });
+        if !guard_condition_added {
+            self.add_guard_condition(Arc::clone(&graph_watcher.guard_condition))?;
+        }
+        self.graph_watchers.push(graph_watcher);
+        Ok(())
+    }
+
     /// Blocks until the wait set is ready, or until the timeout has been exceeded.
     ///
     /// If the timeout is `None` then this function will block indefinitely until
@@ -365,19 +726,12 @@ impl WaitSet {
             clients: Vec::new(),
             guard_conditions: Vec::new(),
             services: Vec::new(),
+            timers: Vec::new(),
+            action_servers: Vec::new(),
+            action_clients: Vec::new(),
+            events: Vec::new(),
+            graph_watchers: Vec::new(),
         };
-        for (i, subscription) in self.subscriptions.iter().enumerate() {
-            // SAFETY: The `subscriptions` entry is an array of pointers, and this dereferencing is
-            // equivalent to
-            // https://github.com/ros2/rcl/blob/35a31b00a12f259d492bf53c0701003bd7f1745c/rcl/include/rcl/wait.h#L419
-            let wait_set_entry = unsafe { *self.handle.rcl_wait_set.subscriptions.add(i) };
-            if !wait_set_entry.is_null() {
-                ready_entities
-                    .subscriptions
-                    .push(Arc::clone(&subscription.waitable));
-            }
-        }
-
         for (i, client) in self.clients.iter().enumerate() {
             // SAFETY: The `clients` entry is an array of pointers, and this dereferencing is
             // equivalent to
@@ -400,6 +754,30 @@ impl WaitSet {
             }
         }
 
+        for (i, subscription) in self.subscriptions.iter().enumerate() {
+            // SAFETY: The `subscriptions` entry is an array of pointers, and this dereferencing is
+            // equivalent to
+            // https://github.com/ros2/rcl/blob/35a31b00a12f259d492bf53c0701003bd7f1745c/rcl/include/rcl/wait.h#L419
+            let wait_set_entry = unsafe { *self.handle.rcl_wait_set.subscriptions.add(i) };
+            // The subscription is also ready when it received intra-process messages.
+            let intra_process_ready = subscription
+                .waitable
+                .intra_process_guard_condition()
+                .is_some_and(|intra_process_guard_condition| {
+                    ready_entities
+                        .guard_conditions
+                        .iter()
+                        .any(|guard_condition| {
+                            Arc::ptr_eq(guard_condition, intra_process_guard_condition)
+                        })
+                });
+            if !wait_set_entry.is_null() || intra_process_ready {
+                ready_entities
+                    .subscriptions
+                    .push(Arc::clone(&subscription.waitable));
+            }
+        }
+
         for (i, service) in self.services.iter().enumerate() {
             // SAFETY: The `services` entry is an array of pointers, and this dereferencing is
             // equivalent to
@@ -409,6 +787,85 @@ impl WaitSet {
                 ready_entities.services.push(Arc::clone(&service.waitable));
             }
         }
+
+        for (i, timer) in self.timers.iter().enumerate() {
+            // SAFETY: The `timers` entry is an array of pointers, and this dereferencing is
+            // equivalent to
+            // https://github.com/ros2/rcl/blob/35a31b00a12f259d492bf53c0701003bd7f1745c/rcl/include/rcl/wait.h#L419
+            let wait_set_entry = unsafe { *self.handle.rcl_wait_set.timers.add(i) };
+            if !wait_set_entry.is_null() {
+                ready_entities.timers.push(Arc::clone(&timer.waitable));
+            }
+        }
+
+        for action_server in &self.action_servers {
+            let mut ready = ActionServerReady::default();
+            unsafe {
+                // SAFETY: The action server was added to this wait set, which has just been
+                // waited on, and all out-parameters point to valid memory.
+                rcl_action_server_wait_set_get_entities_ready(
+                    &self.handle.rcl_wait_set,
+                    &*action_server.waitable.handle().lock(),
+                    &mut ready.goal_request,
+                    &mut ready.cancel_request,
+                    &mut ready.result_request,
+                    &mut ready.goal_expired,
+                )
+                .ok()?;
+            }
+            if ready.any() {
+                ready_entities
+                    .action_servers
+                    .push((Arc::clone(&action_server.waitable), ready));
+            }
+        }
+
+        for action_client in &self.action_clients {
+            let mut ready = ActionClientReady::default();
+            unsafe {
+                // SAFETY: The action client was added to this wait set, which has just been
+                // waited on, and all out-parameters point to valid memory.
+                rcl_action_client_wait_set_get_entities_ready(
+                    &self.handle.rcl_wait_set,
+                    &*action_client.waitable.handle().lock(),
+                    &mut ready.feedback,
+                    &mut ready.status,
+                    &mut ready.goal_response,
+                    &mut ready.cancel_response,
+                    &mut ready.result_response,
+                )
+                .ok()?;
+            }
+            if ready.any() {
+                ready_entities
+                    .action_clients
+                    .push((Arc::clone(&action_client.waitable), ready));
+            }
+        }
+
+        for (i, event) in self.events.iter().enumerate() {
+            // SAFETY: The `events` entry is an array of pointers, and this dereferencing is
+            // equivalent to
+            // https://github.com/ros2/rcl/blob/35a31b00a12f259d492bf53c0701003bd7f1745c/rcl/include/rcl/wait.h#L419
+            let wait_set_entry = unsafe { *self.handle.rcl_wait_set.events.add(i) };
+            if !wait_set_entry.is_null() {
+                ready_entities.events.push(Arc::clone(&event.waitable));
+            }
+        }
+
+        for graph_watcher in &self.graph_watchers {
+            let graph_changed = ready_entities
+                .guard_conditions
+                .iter()
+                .any(|guard_condition| {
+                    Arc::ptr_eq(guard_condition, &graph_watcher.guard_condition)
+                });
+            if graph_changed {
+                ready_entities
+                    .graph_watchers
+                    .push(Arc::clone(graph_watcher));
+            }
+        }
         Ok(ready_entities)
     }
 }
@@ -440,4 +897,29 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn timer_in_wait_set_readies() -> Result<(), RclrsError> {
+        let context = Context::default();
+
+        let timer = Arc::new(TimerState::new(
+            &context.handle,
+            crate::Clock::steady(),
+            Duration::from_millis(1),
+            Box::new(|_| {}),
+            Arc::new(crate::CallbackGroupState::new(
+                crate::CallbackGroupType::MutuallyExclusive,
+            )),
+        )?);
+
+        let mut wait_set = WaitSet::new(0, 0, 1, 0, 0, 0, &context)?;
+        wait_set.add_timer(Arc::clone(&timer))?;
+
+        // The timeout is much longer than the timer period, so the wait set must
+        // be woken up by the timer.
+        let readies = wait_set.wait(Some(Duration::from_secs(10)))?;
+        assert!(readies.timers.contains(&timer));
+
+        Ok(())
+    }
 }
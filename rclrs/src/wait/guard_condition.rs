@@ -1,6 +1,6 @@
 use std::sync::{atomic::AtomicBool, Arc, Mutex};
 
-use crate::{rcl_bindings::*, Context, ContextHandle, RclrsError, ToResult};
+use crate::{rcl_bindings::*, Context, ContextHandle, NodeHandle, RclrsError, ToResult};
 
 /// A waitable entity used for waking up a wait set manually.
 ///
@@ -61,10 +61,16 @@ pub(crate) struct GuardConditionHandle {
     /// Keep the context alive for the whole lifecycle of the guard condition
     #[allow(dead_code)]
     pub(crate) context_handle: Arc<ContextHandle>,
+    /// The node that owns the guard condition, if this is the graph guard condition of a node.
+    /// Such a guard condition is finalized by its node, not by this handle.
+    pub(crate) node_handle: Option<Arc<NodeHandle>>,
 }
 
 impl Drop for GuardCondition {
     fn drop(&mut self) {
+        if self.handle.node_handle.is_some() {
+            return;
+        }
         unsafe {
             // SAFETY: No precondition for this function (besides passing in a valid guard condition)
             rcl_guard_condition_fini(&mut *self.handle.rcl_guard_condition.lock().unwrap());
@@ -133,12 +139,41 @@ impl GuardCondition {
             handle: GuardConditionHandle {
                 rcl_guard_condition,
                 context_handle,
+                node_handle: None,
             },
             callback,
             in_use_by_wait_set: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// Wraps the guard condition that the node triggers whenever the ROS graph changes.
+    ///
+    /// Only one such wrapper should exist per node at a time, see
+    /// [`NodeState::graph_guard_condition`][1].
+    ///
+    /// [1]: crate::NodeState::graph_guard_condition
+    pub(crate) fn new_for_graph(node_handle: Arc<NodeHandle>) -> Self {
+        let rcl_guard_condition = {
+            let rcl_node = node_handle.rcl_node.lock().unwrap();
+            // SAFETY: The node is valid, so it returns a pointer to its initialized graph
+            // guard condition. The guard condition only consists of pointers to its
+            // implementation, which is owned by the node, so it can be copied. The node
+            // handle is kept alive by this guard condition, so the implementation outlives
+            // the copy.
+            unsafe { std::ptr::read(rcl_node_get_graph_guard_condition(&*rcl_node)) }
+        };
+
+        Self {
+            handle: GuardConditionHandle {
+                rcl_guard_condition: Mutex::new(rcl_guard_condition),
+                context_handle: Arc::clone(&node_handle.context_handle),
+                node_handle: Some(node_handle),
+            },
+            callback: None,
+            in_use_by_wait_set: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
     /// Triggers this guard condition, activating the wait set, and calling the optionally assigned callback.
     pub fn trigger(&self) -> Result<(), RclrsError> {
         unsafe {
@@ -1,8 +1,9 @@
 #!/usr/bin/env python3
 # This script produces the `vendor` module inside `rclrs` by copying the
-# generated code for the `rosgraph_msgs`, `rcl_interfaces`, and `action_msgs`
-# packages and their dependencies `builtin_interfaces` and
-# `unique_identifier_msgs` and adjusting the submodule paths in the code.
+# generated code for the `rosgraph_msgs`, `rcl_interfaces`, `action_msgs`,
+# `lifecycle_msgs`, and `composition_interfaces` packages and their
+# dependencies `builtin_interfaces` and `unique_identifier_msgs` and adjusting
+# the submodule paths in the code.
 # If these packages, or the `rosidl_generator_rs`, get changed, you can
 # update the `vendor` module by running this script.
 # The purpose is to avoid an external dependency on these message packages,
@@ -16,6 +17,8 @@ import subprocess
 vendored_packages = [
   "action_msgs",
   "builtin_interfaces",
+  "composition_interfaces",
+  "lifecycle_msgs",
   "rcl_interfaces",
   "rosgraph_msgs",
   "unique_identifier_msgs",
@@ -52,7 +55,8 @@ def main():
     src = args.install_base / pkg / 'share' / pkg / 'rust' / 'src'
     dst = vendor_dir / pkg
     dst.mkdir(parents=True)
-    copy_adjusted(pkg, src / 'msg.rs', dst / 'msg.rs')
+    if (src / 'msg.rs').is_file():
+      copy_adjusted(pkg, src / 'msg.rs', dst / 'msg.rs')
     if (src / 'srv.rs').is_file():
       copy_adjusted(pkg, src / 'srv.rs', dst / 'srv.rs')
     if (src / 'action.rs').is_file():